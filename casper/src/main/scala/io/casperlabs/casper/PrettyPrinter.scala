@@ -26,6 +26,7 @@ object PrettyPrinter extends ByteStringPrettyPrinter {
       s"Insert(${ks.map(buildString).mkString(",")})"
     case Transform.TransformInstance.Failure(_)  => "TransformFailure"
     case Transform.TransformInstance.Identity(_) => "Read"
+    case Transform.TransformInstance.Delete(_)   => "Delete"
     case Transform.TransformInstance.Write(TransformWrite(mv)) =>
       mv match {
         case None    => "Write(Nothing)"
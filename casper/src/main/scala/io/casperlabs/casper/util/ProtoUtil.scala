@@ -632,7 +632,9 @@ object ProtoUtil {
         payment = payment,
         gasPrice = GAS_PRICE,
         authorizationKeys = keyHashes,
-        deployHash = d.deployHash
+        deployHash = d.deployHash,
+        timestamp = d.getHeader.timestamp,
+        ttlMillis = d.getHeader.ttlMillis
       )
     }
   }
@@ -0,0 +1,76 @@
+//! Functions for hashing and verifying signatures natively on the host, which is considerably
+//! cheaper than doing so in Wasm.
+
+use casperlabs_types::api_error;
+
+use crate::{ext_ffi, unwrap_or_revert::UnwrapOrRevert};
+
+/// The length in bytes of the digests returned by [`blake2b`] and [`sha256`].
+pub const DIGEST_LENGTH: usize = 32;
+
+/// Returns the blake2b-256 digest of `input`.
+pub fn blake2b(input: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut digest = [0u8; DIGEST_LENGTH];
+    let ret = unsafe {
+        ext_ffi::blake2b(
+            input.as_ptr(),
+            input.len(),
+            digest.as_mut_ptr(),
+            digest.len(),
+        )
+    };
+    api_error::result_from(ret).unwrap_or_revert();
+    digest
+}
+
+/// Returns the sha2-256 digest of `input`.
+pub fn sha256(input: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut digest = [0u8; DIGEST_LENGTH];
+    let ret = unsafe {
+        ext_ffi::sha256(
+            input.as_ptr(),
+            input.len(),
+            digest.as_mut_ptr(),
+            digest.len(),
+        )
+    };
+    api_error::result_from(ret).unwrap_or_revert();
+    digest
+}
+
+/// Returns whether `signature` is a valid ed25519 signature of `message` by `public_key`.
+///
+/// The public key is expected to be 32 bytes and the signature 64 bytes; malformed values are
+/// treated as an invalid signature.
+pub fn verify_ed25519(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let result = unsafe {
+        ext_ffi::verify_ed25519(
+            public_key.as_ptr(),
+            public_key.len(),
+            message.as_ptr(),
+            message.len(),
+            signature.as_ptr(),
+            signature.len(),
+        )
+    };
+    result != 0
+}
+
+/// Returns whether `signature` is a valid secp256k1 signature of the sha2-256 digest of `message`
+/// by `public_key`.
+///
+/// The public key may be compressed or uncompressed, and the signature is expected in 64-byte
+/// compact form; malformed values are treated as an invalid signature.
+pub fn verify_secp256k1(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let result = unsafe {
+        ext_ffi::verify_secp256k1(
+            public_key.as_ptr(),
+            public_key.len(),
+            message.as_ptr(),
+            message.len(),
+            signature.as_ptr(),
+            signature.len(),
+        )
+    };
+    result != 0
+}
@@ -276,6 +276,17 @@ pub fn remove_key(name: &str) {
     unsafe { ext_ffi::remove_key(name_ptr, name_size) }
 }
 
+/// Emits an event with the given `name` and `value`.
+///
+/// Events are returned to the client in the deploy's result, in the order in which they were
+/// emitted.  If execution of the deploy fails, its events are discarded.  The gas charged grows
+/// with the size of the event.
+pub fn emit_event(name: &str, value: CLValue) {
+    let (name_ptr, name_size, _bytes) = contract_api::to_ptr(name);
+    let (value_ptr, value_size, _bytes2) = contract_api::to_ptr(value);
+    unsafe { ext_ffi::emit_event(name_ptr, name_size, value_ptr, value_size) }
+}
+
 /// Returns the named keys of the current context.
 ///
 /// The current context is either the caller's account or a stored contract depending on whether the
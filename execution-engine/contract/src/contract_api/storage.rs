@@ -116,6 +116,27 @@ pub fn add_local<K: ToBytes, V: CLTyped + ToBytes>(key: K, value: V) {
     }
 }
 
+/// Removes the value under `uref` from the global state.
+pub fn remove(uref: URef) {
+    let key = Key::from(uref);
+    let (key_ptr, key_size, _bytes) = contract_api::to_ptr(key);
+
+    unsafe {
+        ext_ffi::remove(key_ptr, key_size);
+    }
+}
+
+/// Removes the value under `key` from the context-local partition of global state.
+///
+/// `key` must serialize to the address of a [`URef`] with write access held by the caller.
+pub fn remove_local<K: ToBytes>(key: K) {
+    let (key_ptr, key_size, _bytes) = contract_api::to_ptr(key);
+
+    unsafe {
+        ext_ffi::remove_local(key_ptr, key_size);
+    }
+}
+
 /// Returns a new unforgeable pointer, where the value is initialized to `init`.
 pub fn new_uref<T: CLTyped + ToBytes>(init: T) -> URef {
     let uref_non_null_ptr = contract_api::alloc_bytes(UREF_SERIALIZED_LENGTH);
@@ -77,6 +77,26 @@ extern "C" {
     pub fn add(key_ptr: *const u8, key_size: usize, value_ptr: *const u8, value_size: usize);
     ///
     pub fn add_local(key_ptr: *const u8, key_size: usize, value_ptr: *const u8, value_size: usize);
+    /// This function removes the value under the provided key (read via de-serializing the bytes
+    /// in wasm memory from offset `key_ptr` to `key_ptr + key_size`) from the global state. This
+    /// function will cause a `Trap` if the key fails to de-serialize or if writing to that key is
+    /// not permitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_ptr` - pointer to bytes representing the key to remove
+    /// * `key_size` - size of the key (in bytes)
+    pub fn remove(key_ptr: *const u8, key_size: usize);
+    /// The bytes in wasm memory from offset `key_ptr` to `key_ptr + key_size`
+    /// will be used together with the current context’s seed to form a local key.
+    /// This function removes the value under that local key from the global state.  The bytes
+    /// must be the address of a URef with write access held by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_ptr` - pointer to bytes representing the user-defined key to remove
+    /// * `key_size` - size of the key (in bytes)
+    pub fn remove_local(key_ptr: *const u8, key_size: usize);
     /// This function causes the runtime to generate a new [`casperlabs_types::uref::URef`], with
     /// the provided value stored under it in the global state. The new
     /// [`casperlabs_types::uref::URef`] is written (in serialized form) to the wasm linear
@@ -627,6 +647,88 @@ extern "C" {
         urefs_size: usize,
     ) -> i32;
 
+    /// Emits an event with the given name and value.  Events are returned in the deploy's result
+    /// in the order they were emitted, unless execution of the deploy fails.  This function will
+    /// cause a `Trap` if the bytes in wasm memory cannot be de-serialized into a `String` and a
+    /// `CLValue` respectively.
+    ///
+    /// # Arguments
+    ///
+    /// * `name_ptr` - pointer to serialized event name
+    /// * `name_size` - size of serialized event name
+    /// * `value_ptr` - pointer to serialized event value
+    /// * `value_size` - size of serialized event value
+    pub fn emit_event(
+        name_ptr: *const u8,
+        name_size: usize,
+        value_ptr: *const u8,
+        value_size: usize,
+    );
+
+    /// Hashes the given bytes with blake2b-256 and writes the 32-byte digest into the given
+    /// buffer.  Returns `ApiError::BufferTooSmall` if the buffer is shorter than the digest.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_ptr` - pointer to bytes to hash
+    /// * `in_size` - size of bytes to hash
+    /// * `out_ptr` - pointer to buffer for the digest
+    /// * `out_size` - size of buffer for the digest
+    pub fn blake2b(in_ptr: *const u8, in_size: usize, out_ptr: *mut u8, out_size: usize) -> i32;
+
+    /// Hashes the given bytes with sha2-256 and writes the 32-byte digest into the given buffer.
+    /// Returns `ApiError::BufferTooSmall` if the buffer is shorter than the digest.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_ptr` - pointer to bytes to hash
+    /// * `in_size` - size of bytes to hash
+    /// * `out_ptr` - pointer to buffer for the digest
+    /// * `out_size` - size of buffer for the digest
+    pub fn sha256(in_ptr: *const u8, in_size: usize, out_ptr: *mut u8, out_size: usize) -> i32;
+
+    /// Returns `1` if the given signature is a valid ed25519 signature of the given message by
+    /// the given public key, or `0` otherwise.  A malformed public key or signature is treated as
+    /// an invalid signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key_ptr` - pointer to 32-byte public key
+    /// * `public_key_size` - size of public key
+    /// * `message_ptr` - pointer to signed message
+    /// * `message_size` - size of signed message
+    /// * `signature_ptr` - pointer to 64-byte signature
+    /// * `signature_size` - size of signature
+    pub fn verify_ed25519(
+        public_key_ptr: *const u8,
+        public_key_size: usize,
+        message_ptr: *const u8,
+        message_size: usize,
+        signature_ptr: *const u8,
+        signature_size: usize,
+    ) -> i32;
+
+    /// Returns `1` if the given signature is a valid secp256k1 signature of the sha2-256 digest of
+    /// the given message by the given public key, or `0` otherwise.  A malformed public key or
+    /// signature is treated as an invalid signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `public_key_ptr` - pointer to compressed or uncompressed public key
+    /// * `public_key_size` - size of public key
+    /// * `message_ptr` - pointer to signed message
+    /// * `message_size` - size of signed message
+    /// * `signature_ptr` - pointer to 64-byte compact signature
+    /// * `signature_size` - size of signature
+    pub fn verify_secp256k1(
+        public_key_ptr: *const u8,
+        public_key_size: usize,
+        message_ptr: *const u8,
+        message_size: usize,
+        signature_ptr: *const u8,
+        signature_size: usize,
+    ) -> i32;
+
     /// Prints data directly to stanadard output on the host.
     ///
     /// # Arguments
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use contract::{contract_api::runtime, ext_ffi, unwrap_or_revert::UnwrapOrRevert};
+use types::{bytesrepr::ToBytes, CLValue, Key};
+
+const ARG_NAME: &str = "name";
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let name: String = runtime::get_named_arg(ARG_NAME);
+    let account = Key::Account(runtime::get_caller());
+    // Adds a named key to the caller's account directly, rather than through `put_key`
+    let key_bytes = account.to_bytes().unwrap_or_revert();
+    let value_bytes = CLValue::from_t((name, account))
+        .unwrap_or_revert()
+        .to_bytes()
+        .unwrap_or_revert();
+    unsafe {
+        ext_ffi::add(
+            key_bytes.as_ptr(),
+            key_bytes.len(),
+            value_bytes.as_ptr(),
+            value_bytes.len(),
+        );
+    }
+}
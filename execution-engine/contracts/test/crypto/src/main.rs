@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+use contract::{
+    contract_api::{crypto, runtime},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use types::{ApiError, CLValue};
+
+const ARG_ALGORITHM: &str = "algorithm";
+const ARG_PUBLIC_KEY: &str = "public_key";
+const ARG_MESSAGE: &str = "message";
+const ARG_SIGNATURE: &str = "signature";
+const ALGORITHM_ED25519: &str = "ed25519";
+const ALGORITHM_SECP256K1: &str = "secp256k1";
+const EVENT_BLAKE2B: &str = "blake2b";
+const EVENT_SHA256: &str = "sha256";
+const EVENT_VALID: &str = "valid";
+
+#[repr(u16)]
+enum Error {
+    UnknownAlgorithm = 0,
+}
+
+impl Into<ApiError> for Error {
+    fn into(self) -> ApiError {
+        ApiError::User(self as u16)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let algorithm: String = runtime::get_named_arg(ARG_ALGORITHM);
+    let public_key: Vec<u8> = runtime::get_named_arg(ARG_PUBLIC_KEY);
+    let message: Vec<u8> = runtime::get_named_arg(ARG_MESSAGE);
+    let signature: Vec<u8> = runtime::get_named_arg(ARG_SIGNATURE);
+
+    let blake2b = crypto::blake2b(&message);
+    runtime::emit_event(EVENT_BLAKE2B, CLValue::from_t(blake2b).unwrap_or_revert());
+
+    let sha256 = crypto::sha256(&message);
+    runtime::emit_event(EVENT_SHA256, CLValue::from_t(sha256).unwrap_or_revert());
+
+    let valid = match algorithm.as_str() {
+        ALGORITHM_ED25519 => crypto::verify_ed25519(&public_key, &message, &signature),
+        ALGORITHM_SECP256K1 => crypto::verify_secp256k1(&public_key, &message, &signature),
+        _ => runtime::revert(Error::UnknownAlgorithm),
+    };
+    runtime::emit_event(EVENT_VALID, CLValue::from_t(valid).unwrap_or_revert());
+}
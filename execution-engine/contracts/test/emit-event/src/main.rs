@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::string::String;
+
+use contract::{contract_api::runtime, unwrap_or_revert::UnwrapOrRevert};
+use types::{ApiError, CLValue};
+
+const ARG_VALUE: &str = "value";
+const ARG_REVERT: &str = "revert";
+const EVENT_STARTED: &str = "started";
+const EVENT_VALUE: &str = "value";
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let value: String = runtime::get_named_arg(ARG_VALUE);
+    let revert: bool = runtime::get_named_arg(ARG_REVERT);
+
+    runtime::emit_event(EVENT_STARTED, CLValue::from_t(()).unwrap_or_revert());
+    runtime::emit_event(EVENT_VALUE, CLValue::from_t(value).unwrap_or_revert());
+
+    if revert {
+        runtime::revert(ApiError::User(0));
+    }
+}
@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use contract::contract_api::{runtime, storage};
+
+const ARG_VALUE: &str = "value";
+const KEY_NAME: &str = "value";
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let value: Vec<u8> = runtime::get_named_arg(ARG_VALUE);
+    let uref = storage::new_uref(value.clone());
+    storage::write(uref, value);
+    runtime::put_key(KEY_NAME, uref.into());
+}
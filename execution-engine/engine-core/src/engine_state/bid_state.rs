@@ -0,0 +1,72 @@
+use engine_shared::{
+    newtypes::{Blake2bHash, CorrelationId},
+    stored_value::StoredValue,
+    TypeMismatch,
+};
+use engine_storage::global_state::StateReader;
+pub use proof_of_stake::{Queue, QueueEntry, Stakes};
+use types::{Key, ProtocolVersion};
+
+use crate::{execution, tracking_copy::TrackingCopy};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BidStateResult {
+    RootNotFound,
+    Success {
+        /// The stakes of the bonded validators.
+        stakes: Stakes,
+        /// The pending bonding requests, oldest first.
+        bonding_queue: Queue,
+        /// The pending unbonding requests, oldest first.
+        unbonding_queue: Queue,
+    },
+}
+
+/// A request for the validator bids held by the proof-of-stake contract under a given state hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidStateRequest {
+    state_hash: Blake2bHash,
+    protocol_version: ProtocolVersion,
+}
+
+impl BidStateRequest {
+    pub fn new(state_hash: Blake2bHash, protocol_version: ProtocolVersion) -> Self {
+        BidStateRequest {
+            state_hash,
+            protocol_version,
+        }
+    }
+
+    pub fn state_hash(&self) -> Blake2bHash {
+        self.state_hash
+    }
+
+    /// The protocol version whose proof-of-stake contract holds the bids.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+}
+
+/// Reads the bonding or unbonding queue stored under `key` by the proof-of-stake contract.  A
+/// queue which has never been written is empty.
+pub(crate) fn read_queue<R>(
+    tracking_copy: &mut TrackingCopy<R>,
+    correlation_id: CorrelationId,
+    key: [u8; 32],
+) -> Result<Queue, execution::Error>
+where
+    R: StateReader<Key, StoredValue>,
+    R::Error: Into<execution::Error>,
+{
+    match tracking_copy
+        .get(correlation_id, &Key::Hash(key))
+        .map_err(Into::into)?
+    {
+        Some(StoredValue::CLValue(cl_value)) => Ok(cl_value.into_t()?),
+        Some(other) => Err(execution::Error::TypeMismatch(TypeMismatch::new(
+            "CLValue".to_string(),
+            other.type_name(),
+        ))),
+        None => Ok(Queue::default()),
+    }
+}
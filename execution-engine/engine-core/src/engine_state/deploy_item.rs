@@ -1,6 +1,6 @@
-use std::collections::BTreeSet;
+use std::{cmp, collections::BTreeSet};
 
-use types::account::AccountHash;
+use types::{account::AccountHash, BlockTime};
 
 use crate::{engine_state::executable_deploy_item::ExecutableDeployItem, DeployHash};
 
@@ -15,10 +15,16 @@ pub struct DeployItem {
     pub gas_price: GasPrice,
     pub authorization_keys: BTreeSet<AccountHash>,
     pub deploy_hash: DeployHash,
+    /// The time at which the deploy was created, in milliseconds.
+    pub timestamp: u64,
+    /// The time to live of the deploy, in milliseconds.  A value of `0` means the deploy has no
+    /// TTL of its own, and so expires after the maximum TTL.
+    pub ttl_millis: u32,
 }
 
 impl DeployItem {
     /// Creates a [`DeployItem`].
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         address: AccountHash,
         session: ExecutableDeployItem,
@@ -26,6 +32,8 @@ impl DeployItem {
         gas_price: GasPrice,
         authorization_keys: BTreeSet<AccountHash>,
         deploy_hash: DeployHash,
+        timestamp: u64,
+        ttl_millis: u32,
     ) -> Self {
         DeployItem {
             address,
@@ -34,6 +42,67 @@ impl DeployItem {
             gas_price,
             authorization_keys,
             deploy_hash,
+            timestamp,
+            ttl_millis,
         }
     }
+
+    /// Returns whether the deploy's TTL has passed at `blocktime`.  A deploy without a TTL, or
+    /// with one longer than `max_ttl_millis`, expires after `max_ttl_millis`.
+    pub fn is_expired(&self, blocktime: BlockTime, max_ttl_millis: u32) -> bool {
+        let ttl_millis = if self.ttl_millis == 0 {
+            max_ttl_millis
+        } else {
+            cmp::min(self.ttl_millis, max_ttl_millis)
+        };
+        let blocktime: u64 = blocktime.into();
+        let expires_at = self.timestamp.saturating_add(u64::from(ttl_millis));
+        blocktime > expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deploy_item(timestamp: u64, ttl_millis: u32) -> DeployItem {
+        let module_bytes = ExecutableDeployItem::ModuleBytes {
+            module_bytes: vec![],
+            args: vec![],
+        };
+        DeployItem::new(
+            AccountHash::new([1; 32]),
+            module_bytes.clone(),
+            module_bytes,
+            1,
+            BTreeSet::new(),
+            [2; 32],
+            timestamp,
+            ttl_millis,
+        )
+    }
+
+    const MAX_TTL_MILLIS: u32 = 10_000;
+
+    #[test]
+    fn should_expire_after_ttl() {
+        let deploy_item = deploy_item(1_000, 500);
+        assert!(!deploy_item.is_expired(BlockTime::new(0), MAX_TTL_MILLIS));
+        assert!(!deploy_item.is_expired(BlockTime::new(1_500), MAX_TTL_MILLIS));
+        assert!(deploy_item.is_expired(BlockTime::new(1_501), MAX_TTL_MILLIS));
+    }
+
+    #[test]
+    fn should_expire_after_max_ttl_without_ttl() {
+        let deploy_item = deploy_item(1_000, 0);
+        assert!(!deploy_item.is_expired(BlockTime::new(11_000), MAX_TTL_MILLIS));
+        assert!(deploy_item.is_expired(BlockTime::new(11_001), MAX_TTL_MILLIS));
+    }
+
+    #[test]
+    fn should_expire_after_max_ttl_with_longer_ttl() {
+        let deploy_item = deploy_item(1_000, MAX_TTL_MILLIS * 2);
+        assert!(!deploy_item.is_expired(BlockTime::new(11_000), MAX_TTL_MILLIS));
+        assert!(deploy_item.is_expired(BlockTime::new(11_001), MAX_TTL_MILLIS));
+    }
 }
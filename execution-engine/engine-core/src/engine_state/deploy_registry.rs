@@ -0,0 +1,49 @@
+//! The registry of executed deploys, kept in global state so that no deploy can be executed twice.
+//!
+//! Each executed deploy has an entry under a [`Key::Hash`] derived from its deploy hash, holding
+//! the block time at which it was executed.  As no deploy lives longer than
+//! [`EngineConfig::max_ttl_millis`](super::EngineConfig::max_ttl_millis), an entry can be pruned
+//! once the block time is later than the one it holds plus that maximum, provided the maximum is
+//! never raised.
+
+use engine_shared::{newtypes::Blake2bHash, stored_value::StoredValue};
+use types::{BlockTime, CLValue, Key};
+
+use crate::DeployHash;
+
+/// Prefixed to a deploy hash before hashing it, so that registry keys can't collide with the
+/// addresses of contracts created by the deploy.
+const DEPLOY_REGISTRY_PREFIX: &[u8] = b"deploy_registry";
+
+/// Returns the key of the registry entry for the given deploy.
+pub fn deploy_registry_key(deploy_hash: &DeployHash) -> Key {
+    let mut preimage = DEPLOY_REGISTRY_PREFIX.to_vec();
+    preimage.extend_from_slice(deploy_hash);
+    Key::Hash(Blake2bHash::new(&preimage).value())
+}
+
+/// Returns the registry entry recording a deploy executed at `blocktime`.
+pub fn deploy_registry_value(blocktime: BlockTime) -> StoredValue {
+    let blocktime: u64 = blocktime.into();
+    // from_t for u64 is assumed to never panic
+    StoredValue::CLValue(CLValue::from_t(blocktime).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_derive_distinct_keys_from_deploy_hashes() {
+        let deploy_hash = [1; 32];
+        assert_eq!(
+            deploy_registry_key(&deploy_hash),
+            deploy_registry_key(&deploy_hash)
+        );
+        assert_ne!(deploy_registry_key(&deploy_hash), Key::Hash(deploy_hash));
+        assert_ne!(
+            deploy_registry_key(&deploy_hash),
+            deploy_registry_key(&[2; 32])
+        );
+    }
+}
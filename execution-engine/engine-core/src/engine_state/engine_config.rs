@@ -1,9 +1,36 @@
+use super::module_cache::DEFAULT_MODULE_CACHE_CAPACITY;
+
+const DEFAULT_EXECUTION_THREADS: usize = 1;
+/// The default session gas limit of a dry run for an estimate.
+pub const DEFAULT_ESTIMATE_GAS_LIMIT: u64 = 1_000_000_000;
+/// The default maximum time to live of a deploy, one day in milliseconds.
+pub const DEFAULT_MAX_TTL_MILLIS: u32 = 24 * 60 * 60 * 1_000;
+
 /// The runtime configuration of the execution engine
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone)]
 pub struct EngineConfig {
     // feature flags go here
     use_system_contracts: bool,
     enable_bonding: bool,
+    execution_threads: usize,
+    trace_host_calls: bool,
+    module_cache_capacity: usize,
+    estimate_gas_limit: u64,
+    max_ttl_millis: u32,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            use_system_contracts: false,
+            enable_bonding: false,
+            execution_threads: DEFAULT_EXECUTION_THREADS,
+            trace_host_calls: false,
+            module_cache_capacity: DEFAULT_MODULE_CACHE_CAPACITY,
+            estimate_gas_limit: DEFAULT_ESTIMATE_GAS_LIMIT,
+            max_ttl_millis: DEFAULT_MAX_TTL_MILLIS,
+        }
+    }
 }
 
 impl EngineConfig {
@@ -29,4 +56,68 @@ impl EngineConfig {
         self.enable_bonding = enable_bonding;
         self
     }
+
+    /// The number of threads used to execute the deploys of execute requests.
+    pub fn execution_threads(self) -> usize {
+        self.execution_threads
+    }
+
+    /// Sets the number of threads used to execute the deploys of execute requests.  The threads
+    /// are started once by [`EngineState::new`](super::EngineState::new) and shared by all
+    /// requests.  A value of `0` is treated as `1`, i.e. deploys are executed one after another on
+    /// the thread handling the request.
+    pub fn with_execution_threads(mut self, execution_threads: usize) -> EngineConfig {
+        self.execution_threads = execution_threads;
+        self
+    }
+
+    /// Whether every host function invoked by Wasm code is recorded in the execution results.
+    pub fn trace_host_calls(self) -> bool {
+        self.trace_host_calls
+    }
+
+    /// Enables recording of the host functions invoked during execution.  Intended for debugging
+    /// deploys, as recording slows down execution.
+    pub fn with_trace_host_calls(mut self, trace_host_calls: bool) -> EngineConfig {
+        self.trace_host_calls = trace_host_calls;
+        self
+    }
+
+    /// The capacity in bytes of the cache of deserialized stored contract modules.
+    pub fn module_cache_capacity(self) -> usize {
+        self.module_cache_capacity
+    }
+
+    /// Sets the capacity in bytes of the cache of deserialized stored contract modules.  A value of
+    /// `0` disables the cache.
+    pub fn with_module_cache_capacity(mut self, module_cache_capacity: usize) -> EngineConfig {
+        self.module_cache_capacity = module_cache_capacity;
+        self
+    }
+
+    /// The gas limit of the session code when a deploy is dry run for an estimate.
+    pub fn estimate_gas_limit(self) -> u64 {
+        self.estimate_gas_limit
+    }
+
+    /// Sets the gas limit of the session code when a deploy is dry run for an estimate, which
+    /// bounds how long an estimate can take.
+    pub fn with_estimate_gas_limit(mut self, estimate_gas_limit: u64) -> EngineConfig {
+        self.estimate_gas_limit = estimate_gas_limit;
+        self
+    }
+
+    /// The maximum time to live of a deploy, in milliseconds.
+    pub fn max_ttl_millis(self) -> u32 {
+        self.max_ttl_millis
+    }
+
+    /// Sets the maximum time to live of a deploy, in milliseconds.  A deploy without a TTL, or
+    /// with a longer one, expires after this maximum, so entries of the deploy registry older than
+    /// it are no longer needed to reject replays.  It must be at least the maximum TTL of the
+    /// chain, so that no deploy accepted by the node is rejected as expired.
+    pub fn with_max_ttl_millis(mut self, max_ttl_millis: u32) -> EngineConfig {
+        self.max_ttl_millis = max_ttl_millis;
+        self
+    }
 }
@@ -27,6 +27,8 @@ pub enum Error {
     Exec(execution::Error),
     #[fail(display = "Storage error: {}", _0)]
     Storage(engine_storage::error::Error),
+    #[fail(display = "Storage error: {}", _0)]
+    FileStorage(engine_storage::error::file::Error),
     #[fail(display = "Authorization failure: not authorized.")]
     Authorization,
     #[fail(display = "Insufficient payment")]
@@ -47,6 +49,10 @@ pub enum Error {
     InvalidUpgradeResult,
     #[fail(display = "Unsupported deploy item variant: {}", _0)]
     InvalidDeployItemVariant(String),
+    #[fail(display = "Deploy has already been executed")]
+    DeployReplay,
+    #[fail(display = "Deploy has expired")]
+    DeployExpired,
 }
 
 impl From<engine_wasm_prep::PreprocessingError> for Error {
@@ -78,6 +84,12 @@ impl From<engine_storage::error::Error> for Error {
     }
 }
 
+impl From<engine_storage::error::file::Error> for Error {
+    fn from(error: engine_storage::error::file::Error) -> Self {
+        Error::FileStorage(error)
+    }
+}
+
 impl From<bytesrepr::Error> for Error {
     fn from(error: bytesrepr::Error) -> Self {
         Error::Serialization(error)
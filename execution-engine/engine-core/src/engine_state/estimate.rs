@@ -0,0 +1,100 @@
+use engine_shared::{gas::Gas, motes::Motes, newtypes::Blake2bHash};
+use types::ProtocolVersion;
+
+use super::{deploy_item::DeployItem, execution_result::ExecutionResult, CONV_RATE};
+
+/// A request to execute a single deploy against a state without committing its effects, in order
+/// to find out how much gas it uses.
+#[derive(Debug)]
+pub struct EstimateRequest {
+    pub parent_state_hash: Blake2bHash,
+    pub block_time: u64,
+    pub deploy_item: DeployItem,
+    pub protocol_version: ProtocolVersion,
+}
+
+impl EstimateRequest {
+    pub fn new(
+        parent_state_hash: Blake2bHash,
+        block_time: u64,
+        deploy_item: DeployItem,
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        EstimateRequest {
+            parent_state_hash,
+            block_time,
+            deploy_item,
+            protocol_version,
+        }
+    }
+}
+
+/// The gas used by each phase of a deploy.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct PhaseCosts {
+    pub(crate) payment: Gas,
+    pub(crate) session: Gas,
+    pub(crate) finalize: Gas,
+}
+
+impl PhaseCosts {
+    /// The amount which pays for the payment and session phases.
+    pub(crate) fn minimum_payment(&self) -> Motes {
+        Motes::from_gas(self.payment + self.session, CONV_RATE).expect("motes overflow")
+    }
+}
+
+#[derive(Debug)]
+pub struct EstimateResult {
+    execution_result: ExecutionResult,
+    payment_cost: Gas,
+    session_cost: Gas,
+    finalize_cost: Gas,
+    minimum_payment: Option<Motes>,
+}
+
+impl EstimateResult {
+    pub(crate) fn new(
+        execution_result: ExecutionResult,
+        costs: PhaseCosts,
+        minimum_payment: Option<Motes>,
+    ) -> Self {
+        EstimateResult {
+            execution_result,
+            payment_cost: costs.payment,
+            session_cost: costs.session,
+            finalize_cost: costs.finalize,
+            minimum_payment,
+        }
+    }
+
+    /// The result of executing the deploy with a session gas limit which does not depend on its
+    /// payment, and paying the minimum payment if one was found.  Its effects have not been
+    /// committed.
+    pub fn execution_result(&self) -> &ExecutionResult {
+        &self.execution_result
+    }
+
+    pub fn take_execution_result(self) -> ExecutionResult {
+        self.execution_result
+    }
+
+    pub fn payment_cost(&self) -> Gas {
+        self.payment_cost
+    }
+
+    pub fn session_cost(&self) -> Gas {
+        self.session_cost
+    }
+
+    /// The gas used to finalize payment, which is not charged to the deploy.
+    pub fn finalize_cost(&self) -> Gas {
+        self.finalize_cost
+    }
+
+    /// The least amount which the payment code must pay for the session code to be given enough
+    /// gas to run to completion, or `None` if the deploy failed.
+    pub fn minimum_payment(&self) -> Option<Motes> {
+        self.minimum_payment
+    }
+}
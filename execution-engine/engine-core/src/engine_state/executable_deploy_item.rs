@@ -2,9 +2,9 @@ use super::error;
 use crate::execution;
 use engine_shared::account::Account;
 use types::{
-    bytesrepr,
+    bytesrepr::{self, ToBytes},
     contracts::{ContractVersion, DEFAULT_ENTRY_POINT_NAME},
-    ContractHash, ContractPackageHash, Key, RuntimeArgs,
+    CLValue, ContractHash, ContractPackageHash, Key, NamedArg, RuntimeArgs,
 };
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -78,6 +78,34 @@ impl ExecutableDeployItem {
         }
     }
 
+    /// Returns a copy of this item with the value of its argument called `name` replaced by
+    /// `value`, or `None` if it has no such argument.
+    pub(crate) fn with_arg(
+        &self,
+        name: &str,
+        value: CLValue,
+    ) -> Result<Option<ExecutableDeployItem>, bytesrepr::Error> {
+        let mut item = self.clone();
+        let args = match &mut item {
+            ExecutableDeployItem::ModuleBytes { args, .. }
+            | ExecutableDeployItem::StoredContractByHash { args, .. }
+            | ExecutableDeployItem::StoredContractByName { args, .. }
+            | ExecutableDeployItem::StoredVersionedContractByHash { args, .. }
+            | ExecutableDeployItem::StoredVersionedContractByName { args, .. }
+            | ExecutableDeployItem::Transfer { args } => args,
+        };
+        let mut named_args: Vec<NamedArg> = bytesrepr::deserialize(args.clone())?;
+        match named_args
+            .iter_mut()
+            .find(|named_arg| named_arg.name() == name)
+        {
+            Some(named_arg) => *named_arg = NamedArg::new(name.to_string(), value),
+            None => return Ok(None),
+        }
+        *args = RuntimeArgs::from(named_args).to_bytes()?;
+        Ok(Some(item))
+    }
+
     pub fn entry_point_name(&self) -> &str {
         match self {
             ExecutableDeployItem::ModuleBytes { .. } | ExecutableDeployItem::Transfer { .. } => {
@@ -11,6 +11,9 @@ pub struct ExecuteRequest {
     pub block_time: u64,
     pub deploys: Vec<Result<DeployItem, ExecutionResult>>,
     pub protocol_version: ProtocolVersion,
+    /// Whether each deploy is executed on top of the effects of the deploys preceding it, rather
+    /// than against the parent state.
+    pub chained: bool,
 }
 
 impl ExecuteRequest {
@@ -19,12 +22,14 @@ impl ExecuteRequest {
         block_time: u64,
         deploys: Vec<Result<DeployItem, ExecutionResult>>,
         protocol_version: ProtocolVersion,
+        chained: bool,
     ) -> Self {
         Self {
             parent_state_hash,
             block_time,
             deploys,
             protocol_version,
+            chained,
         }
     }
 
@@ -40,6 +45,7 @@ impl Default for ExecuteRequest {
             block_time: 0,
             deploys: vec![],
             protocol_version: Default::default(),
+            chained: false,
         }
     }
 }
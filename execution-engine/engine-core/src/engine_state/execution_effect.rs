@@ -1,16 +1,66 @@
+use std::collections::BTreeSet;
+
 use engine_shared::{additive_map::AdditiveMap, transform::Transform};
-use types::Key;
+use types::{CLValue, Key};
 
 use super::op::Op;
 
+/// An event emitted by a contract via the `emit_event` host function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub value: CLValue,
+}
+
+impl Event {
+    pub fn new(name: String, value: CLValue) -> Self {
+        Event { name, value }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ExecutionEffect {
     pub ops: AdditiveMap<Key, Op>,
     pub transforms: AdditiveMap<Key, Transform>,
+    /// Every key read, including keys which were not found in global state.  Keys which were
+    /// only added to are not included, as adds commute.
+    pub reads: BTreeSet<Key>,
+    /// The events emitted during execution, in the order in which they were emitted.
+    pub events: Vec<Event>,
 }
 
 impl ExecutionEffect {
     pub fn new(ops: AdditiveMap<Key, Op>, transforms: AdditiveMap<Key, Transform>) -> Self {
-        ExecutionEffect { ops, transforms }
+        ExecutionEffect {
+            ops,
+            transforms,
+            reads: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Returns the keys which are modified by this effect.
+    pub fn write_set(&self) -> BTreeSet<Key> {
+        self.transforms
+            .iter()
+            .filter_map(|(key, transform)| match transform {
+                Transform::Identity => None,
+                _ => Some(*key),
+            })
+            .collect()
+    }
+
+    /// Returns the effect of executing `self` followed by `other`, where `other` was executed on
+    /// top of the state produced by `self`.
+    pub fn then(mut self, other: ExecutionEffect) -> Self {
+        for (key, op) in other.ops {
+            self.ops.insert_add(key, op);
+        }
+        for (key, transform) in other.transforms {
+            self.transforms.insert_add(key, transform);
+        }
+        self.reads.extend(other.reads);
+        self.events.extend(other.events);
+        self
     }
 }
@@ -1,16 +1,25 @@
-use super::{error, execution_effect::ExecutionEffect, op::Op, CONV_RATE};
+use std::collections::BTreeSet;
+
+use super::{
+    deploy_registry, error, execution_effect::ExecutionEffect, execution_trace::ExecutionTrace,
+    op::Op, CONV_RATE,
+};
 use engine_shared::{
     additive_map::AdditiveMap, gas::Gas, motes::Motes, newtypes::CorrelationId,
     stored_value::StoredValue, transform::Transform,
 };
 use engine_storage::global_state::StateReader;
-use types::{bytesrepr::FromBytes, CLTyped, CLValue, Key};
+use types::{bytesrepr::FromBytes, BlockTime, CLTyped, CLValue, Key};
+
+use crate::DeployHash;
 
 fn make_payment_error_effects(
     max_payment_cost: Motes,
     account_main_purse_balance: Motes,
     account_main_purse: Key,
     rewards_purse: Key,
+    deploy_hash: DeployHash,
+    blocktime: BlockTime,
 ) -> ExecutionEffect {
     let mut ops = AdditiveMap::new();
     let mut transforms = AdditiveMap::new();
@@ -35,6 +44,14 @@ fn make_payment_error_effects(
         Transform::AddUInt512(max_payment_cost.value()),
     );
 
+    // The deploy was paid for, so it is registered as executed
+    let registry_key = deploy_registry::deploy_registry_key(&deploy_hash);
+    ops.insert(registry_key, Op::Write);
+    transforms.insert(
+        registry_key,
+        Transform::Write(deploy_registry::deploy_registry_value(blocktime)),
+    );
+
     ExecutionEffect::new(ops, transforms)
 }
 
@@ -45,9 +62,16 @@ pub enum ExecutionResult {
         error: error::Error,
         effect: ExecutionEffect,
         cost: Gas,
+        /// The host calls made during execution, if tracing is enabled
+        trace: ExecutionTrace,
     },
     /// Execution was finished successfully
-    Success { effect: ExecutionEffect, cost: Gas },
+    Success {
+        effect: ExecutionEffect,
+        cost: Gas,
+        /// The host calls made during execution, if tracing is enabled
+        trace: ExecutionTrace,
+    },
 }
 
 pub enum ForcedTransferResult {
@@ -66,6 +90,7 @@ impl ExecutionResult {
             error,
             effect: Default::default(),
             cost: Gas::default(),
+            trace: Default::default(),
         }
     }
 
@@ -106,28 +131,84 @@ impl ExecutionResult {
         }
     }
 
+    /// The host calls made during execution, which is empty unless tracing is enabled.
+    pub fn trace(&self) -> &ExecutionTrace {
+        match self {
+            ExecutionResult::Failure { trace, .. } => trace,
+            ExecutionResult::Success { trace, .. } => trace,
+        }
+    }
+
     pub fn with_cost(self, cost: Gas) -> Self {
         match self {
-            ExecutionResult::Failure { error, effect, .. } => ExecutionResult::Failure {
+            ExecutionResult::Failure {
+                error,
+                effect,
+                trace,
+                ..
+            } => ExecutionResult::Failure {
                 error,
                 effect,
                 cost,
+                trace,
+            },
+            ExecutionResult::Success { effect, trace, .. } => ExecutionResult::Success {
+                effect,
+                cost,
+                trace,
             },
-            ExecutionResult::Success { effect, .. } => ExecutionResult::Success { effect, cost },
         }
     }
 
     pub fn with_effect(self, effect: ExecutionEffect) -> Self {
         match self {
-            ExecutionResult::Failure { error, cost, .. } => ExecutionResult::Failure {
+            ExecutionResult::Failure {
+                error, cost, trace, ..
+            } => ExecutionResult::Failure {
+                error,
+                effect,
+                cost,
+                trace,
+            },
+            ExecutionResult::Success { cost, trace, .. } => ExecutionResult::Success {
+                effect,
+                cost,
+                trace,
+            },
+        }
+    }
+
+    pub fn with_trace(self, trace: ExecutionTrace) -> Self {
+        match self {
+            ExecutionResult::Failure {
                 error,
                 effect,
                 cost,
+                ..
+            } => ExecutionResult::Failure {
+                error,
+                effect,
+                cost,
+                trace,
+            },
+            ExecutionResult::Success { effect, cost, .. } => ExecutionResult::Success {
+                effect,
+                cost,
+                trace,
             },
-            ExecutionResult::Success { cost, .. } => ExecutionResult::Success { effect, cost },
         }
     }
 
+    /// Adds `reads` to the keys read during execution.
+    pub fn with_reads(mut self, reads: BTreeSet<Key>) -> Self {
+        match &mut self {
+            ExecutionResult::Failure { effect, .. } | ExecutionResult::Success { effect, .. } => {
+                effect.reads.extend(reads)
+            }
+        }
+        self
+    }
+
     pub fn as_error(&self) -> Option<&error::Error> {
         match self {
             ExecutionResult::Failure { error, .. } => Some(error),
@@ -179,18 +260,23 @@ impl ExecutionResult {
         account_main_purse_balance: Motes,
         account_main_purse: Key,
         rewards_purse: Key,
+        deploy_hash: DeployHash,
+        blocktime: BlockTime,
     ) -> ExecutionResult {
         let effect = make_payment_error_effects(
             max_payment_cost,
             account_main_purse_balance,
             account_main_purse,
             rewards_purse,
+            deploy_hash,
+            blocktime,
         );
         let cost = Gas::from_motes(max_payment_cost, CONV_RATE).unwrap_or_default();
         ExecutionResult::Failure {
             error,
             effect,
             cost,
+            trace: Default::default(),
         }
     }
 
@@ -214,6 +300,7 @@ pub struct ExecutionResultBuilder {
     payment_execution_result: Option<ExecutionResult>,
     session_execution_result: Option<ExecutionResult>,
     finalize_execution_result: Option<ExecutionResult>,
+    reads: BTreeSet<Key>,
 }
 
 impl Default for ExecutionResultBuilder {
@@ -222,6 +309,7 @@ impl Default for ExecutionResultBuilder {
             payment_execution_result: None,
             session_execution_result: None,
             finalize_execution_result: None,
+            reads: BTreeSet::new(),
         }
     }
 }
@@ -252,6 +340,13 @@ impl ExecutionResultBuilder {
         self
     }
 
+    /// Adds keys read outside of the effects of the execution results, e.g. while validating the
+    /// deploy, or by session code whose effects are discarded.
+    pub fn add_reads(&mut self, reads: &BTreeSet<Key>) -> &mut ExecutionResultBuilder {
+        self.reads.extend(reads.iter().cloned());
+        self
+    }
+
     pub fn total_cost(&self) -> Gas {
         let payment_cost = self
             .payment_execution_result
@@ -274,18 +369,25 @@ impl ExecutionResultBuilder {
         let cost = self.total_cost();
         let mut ops = AdditiveMap::new();
         let mut transforms = AdditiveMap::new();
+        let mut reads = self.reads;
+        let mut trace = ExecutionTrace::new();
+        let mut events = Vec::new();
 
         let mut ret: ExecutionResult = ExecutionResult::Success {
             effect: Default::default(),
             cost,
+            trace: Default::default(),
         };
 
         match self.payment_execution_result {
             Some(result) => {
+                reads.extend(result.effect().reads.iter().cloned());
+                trace.extend(result.trace().iter().cloned());
                 if result.is_failure() {
-                    return Ok(result);
+                    return Ok(result.with_reads(reads));
                 } else {
                     Self::add_effects(&mut ops, &mut transforms, result.effect());
+                    events.extend(result.effect().events.iter().cloned());
                 }
             }
             None => return Err(ExecutionResultBuilderError::MissingPaymentExecutionResult),
@@ -295,10 +397,13 @@ impl ExecutionResultBuilder {
         // exec error
         match self.session_execution_result {
             Some(result) => {
+                reads.extend(result.effect().reads.iter().cloned());
+                trace.extend(result.trace().iter().cloned());
                 if result.is_failure() {
                     ret = result.with_cost(cost);
                 } else {
                     Self::add_effects(&mut ops, &mut transforms, result.effect());
+                    events.extend(result.effect().events.iter().cloned());
                 }
             }
             None => return Err(ExecutionResultBuilderError::MissingSessionExecutionResult),
@@ -306,6 +411,7 @@ impl ExecutionResultBuilder {
 
         match self.finalize_execution_result {
             Some(result) => {
+                trace.extend(result.trace().iter().cloned());
                 if result.is_failure() {
                     // payment_code_spec_5_a: Finalization Error should only ever be raised here
                     return Ok(ExecutionResult::precondition_failure(
@@ -313,15 +419,22 @@ impl ExecutionResultBuilder {
                     ));
                 } else {
                     Self::add_effects(&mut ops, &mut transforms, result.effect());
+                    events.extend(result.effect().events.iter().cloned());
                 }
             }
             None => return Err(ExecutionResultBuilderError::MissingFinalizeExecutionResult),
         }
 
         // Remove redundant writes to allow more opportunity to commute
-        let reduced_effect = Self::reduce_identity_writes(ops, transforms, reader, correlation_id);
-
-        Ok(ret.with_effect(reduced_effect))
+        let mut reduced_effect =
+            Self::reduce_identity_writes(ops, transforms, reader, correlation_id);
+        // Events emitted by session code which failed are discarded along with its other effects
+        reduced_effect.events = events;
+
+        Ok(ret
+            .with_effect(reduced_effect)
+            .with_reads(reads)
+            .with_trace(trace))
     }
 
     fn add_effects(
@@ -0,0 +1,20 @@
+use engine_shared::gas::Gas;
+
+/// A record of a single host function invoked by Wasm code while tracing is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostCall {
+    /// The name of the host function, as used in its metrics.
+    pub name: &'static str,
+    /// The raw Wasm arguments passed to the host function.
+    pub args: String,
+    /// The value returned to the Wasm code, or the trap raised by the host function.
+    pub result: String,
+    pub gas_before: Gas,
+    pub gas_after: Gas,
+    /// The number of contract calls between the deploy and the code which made this call, i.e. `0`
+    /// for calls made directly by the payment or session code.
+    pub call_depth: usize,
+}
+
+/// The host calls made during execution, in the order in which they were made.
+pub type ExecutionTrace = Vec<HostCall>;
@@ -6,7 +6,12 @@ use rand::{
     Rng,
 };
 
-use engine_shared::{motes::Motes, newtypes::Blake2bHash, TypeMismatch};
+use engine_shared::{
+    host_function_costs::{HostFunctionCosts, HOST_FUNCTION_COSTS_SERIALIZED_LENGTH},
+    motes::Motes,
+    newtypes::Blake2bHash,
+    TypeMismatch,
+};
 use engine_storage::global_state::CommitResult;
 use engine_wasm_prep::wasm_costs::WasmCosts;
 use types::{account::AccountHash, bytesrepr, Key, ProtocolVersion, U512};
@@ -188,6 +193,7 @@ pub struct ExecConfig {
     standard_payment_installer_bytes: Vec<u8>,
     accounts: Vec<GenesisAccount>,
     wasm_costs: WasmCosts,
+    host_function_costs: HostFunctionCosts,
 }
 
 impl ExecConfig {
@@ -197,6 +203,7 @@ impl ExecConfig {
         standard_payment_installer_bytes: Vec<u8>,
         accounts: Vec<GenesisAccount>,
         wasm_costs: WasmCosts,
+        host_function_costs: HostFunctionCosts,
     ) -> ExecConfig {
         ExecConfig {
             mint_installer_bytes,
@@ -204,6 +211,7 @@ impl ExecConfig {
             standard_payment_installer_bytes,
             accounts,
             wasm_costs,
+            host_function_costs,
         }
     }
     pub fn mint_installer_bytes(&self) -> &[u8] {
@@ -222,6 +230,10 @@ impl ExecConfig {
         self.wasm_costs
     }
 
+    pub fn host_function_costs(&self) -> HostFunctionCosts {
+        self.host_function_costs
+    }
+
     pub fn get_bonded_validators(&self) -> impl Iterator<Item = (AccountHash, Motes)> + '_ {
         let zero = Motes::zero();
         self.accounts.iter().filter_map(move |genesis_account| {
@@ -272,14 +284,23 @@ impl Distribution<ExecConfig> for Standard {
             max_stack_height: rng.gen(),
             opcodes_mul: rng.gen(),
             opcodes_div: rng.gen(),
+            storage_per_byte: rng.gen(),
         };
 
+        let host_function_costs_bytes = iter::repeat(())
+            .map(|_| rng.gen())
+            .take(HOST_FUNCTION_COSTS_SERIALIZED_LENGTH)
+            .collect();
+        let host_function_costs = bytesrepr::deserialize(host_function_costs_bytes)
+            .expect("should deserialize host function costs");
+
         ExecConfig {
             mint_installer_bytes,
             proof_of_stake_installer_bytes,
             standard_payment_installer_bytes,
             accounts,
             wasm_costs,
+            host_function_costs,
         }
     }
 }
@@ -1,14 +1,20 @@
+pub mod bid_state;
 pub mod deploy_item;
+pub mod deploy_registry;
 pub mod engine_config;
 mod error;
+pub mod estimate;
 pub mod executable_deploy_item;
 pub mod execute_request;
 pub mod execution_effect;
 pub mod execution_result;
+pub mod execution_trace;
 pub mod genesis;
+pub mod module_cache;
 pub mod op;
 pub mod query;
 pub mod run_genesis_request;
+pub mod scan;
 pub mod system_contract_cache;
 mod transfer;
 pub mod upgrade;
@@ -16,18 +22,24 @@ pub mod utils;
 
 use std::{
     cell::RefCell,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    cmp,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     rc::Rc,
 };
 
 use log::{debug, warn};
 use num_traits::Zero;
 use parity_wasm::elements::Module;
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
 
 use engine_shared::{
     account::Account,
     additive_map::AdditiveMap,
     gas::Gas,
+    host_function_costs::HostFunctionCosts,
     motes::Motes,
     newtypes::{Blake2bHash, CorrelationId},
     stored_value::StoredValue,
@@ -38,14 +50,15 @@ use engine_storage::{
     protocol_data::ProtocolData,
 };
 use engine_wasm_prep::{wasm_costs::WasmCosts, Preprocessor};
+use proof_of_stake::Stakes;
 use types::{
     account::AccountHash,
     bytesrepr::{self, ToBytes},
     contracts::{NamedKeys, ENTRY_POINT_NAME_INSTALL, UPGRADE_ENTRY_POINT_NAME},
     runtime_args,
-    system_contract_errors::mint,
+    system_contract_errors::{self, mint},
     system_contract_type::PROOF_OF_STAKE,
-    AccessRights, BlockTime, Contract, ContractHash, ContractPackage, ContractPackageHash,
+    AccessRights, BlockTime, CLValue, Contract, ContractHash, ContractPackage, ContractPackageHash,
     ContractVersionKey, EntryPoint, EntryPointType, Key, Phase, ProtocolVersion, RuntimeArgs, URef,
     U512,
 };
@@ -57,15 +70,19 @@ pub use self::{
 };
 use crate::{
     engine_state::{
+        bid_state::{BidStateRequest, BidStateResult},
         deploy_item::DeployItem,
         error::Error::MissingSystemContract,
+        estimate::{EstimateRequest, EstimateResult, PhaseCosts},
         executable_deploy_item::ExecutableDeployItem,
         execute_request::ExecuteRequest,
         execution_result::{ExecutionResult, ForcedTransferResult},
         genesis::{
             ExecConfig, GenesisAccount, GenesisResult, POS_PAYMENT_PURSE, POS_REWARDS_PURSE,
         },
+        module_cache::ModuleCache,
         query::{QueryRequest, QueryResult},
+        scan::{ScanRequest, ScanResult},
         system_contract_cache::SystemContractCache,
         transfer::TransferTargetMode,
         upgrade::{UpgradeConfig, UpgradeResult},
@@ -73,7 +90,8 @@ use crate::{
     execution::{
         self, AddressGenerator, AddressGeneratorBuilder, DirectSystemContractCall, Executor,
     },
-    tracking_copy::{TrackingCopy, TrackingCopyExt},
+    runtime,
+    tracking_copy::{AddResult, SharedReadCache, TrackingCopy, TrackingCopyExt},
 };
 
 // TODO?: MAX_PAYMENT && CONV_RATE values are currently arbitrary w/ real values
@@ -85,11 +103,22 @@ pub const SYSTEM_ACCOUNT_ADDR: AccountHash = AccountHash::new([0u8; 32]);
 
 const GENESIS_INITIAL_BLOCKTIME: u64 = 0;
 const ARG_AMOUNT: &str = "amount";
+/// The number of times an estimate is refined by paying the previous estimate of the minimum
+/// payment.
+const MAX_ESTIMATE_REFINEMENTS: usize = 8;
+const EXECUTION_POOL_EXPECT: &str = "should start execution threads";
+/// The maximum estimated heap size of the values read from the global state which are shared by
+/// the deploys of a single execute request: 16 MiB.
+const SHARED_READ_CACHE_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Debug)]
 pub struct EngineState<S> {
     config: EngineConfig,
     system_contract_cache: SystemContractCache,
+    module_cache: ModuleCache,
+    /// The threads on which the deploys of execute requests are executed, if there is more than
+    /// one.
+    maybe_execution_pool: Option<ThreadPool>,
     state: S,
 }
 
@@ -126,13 +155,31 @@ where
 {
     pub fn new(state: S, config: EngineConfig) -> EngineState<S> {
         let system_contract_cache = Default::default();
+        let module_cache = ModuleCache::new(config.module_cache_capacity());
+        let maybe_execution_pool = if config.execution_threads() > 1 {
+            let execution_pool = ThreadPoolBuilder::new()
+                .num_threads(config.execution_threads())
+                .thread_name(|index| format!("execution-{}", index))
+                .build()
+                .expect(EXECUTION_POOL_EXPECT);
+            Some(execution_pool)
+        } else {
+            None
+        };
         EngineState {
             config,
             system_contract_cache,
+            module_cache,
+            maybe_execution_pool,
             state,
         }
     }
 
+    /// The cache of deserialized stored contract modules shared by all executions.
+    pub fn module_cache(&self) -> &ModuleCache {
+        &self.module_cache
+    }
+
     pub fn config(&self) -> &EngineConfig {
         &self.config
     }
@@ -147,6 +194,16 @@ where
         }
     }
 
+    pub fn host_function_costs(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Option<HostFunctionCosts>, Error> {
+        match self.get_protocol_data(protocol_version)? {
+            Some(protocol_data) => Ok(Some(*protocol_data.host_function_costs())),
+            None => Ok(None),
+        }
+    }
+
     pub fn get_protocol_data(
         &self,
         protocol_version: ProtocolVersion,
@@ -166,13 +223,14 @@ where
         ee_config: &ExecConfig,
     ) -> Result<GenesisResult, Error> {
         // Preliminaries
-        let executor = Executor::new(self.config);
+        let executor = Executor::new(self.config, ModuleCache::clone(&self.module_cache));
         let blocktime = BlockTime::new(GENESIS_INITIAL_BLOCKTIME);
         let gas_limit = Gas::new(std::u64::MAX.into());
         let phase = Phase::System;
 
         let initial_root_hash = self.state.empty_root();
         let wasm_costs = ee_config.wasm_costs();
+        let host_function_costs = ee_config.host_function_costs();
         let preprocessor = Preprocessor::new(wasm_costs);
 
         // Spec #3: Create "virtual system account" object.
@@ -352,6 +410,7 @@ where
         // Spec #2: Associate given CostTable with given ProtocolVersion.
         let protocol_data = ProtocolData::new(
             wasm_costs,
+            host_function_costs,
             mint_hash,
             proof_of_stake_hash,
             standard_payment_hash,
@@ -535,9 +594,16 @@ where
             None => *current_protocol_data.wasm_costs(),
         };
 
+        // 3.1.1.1.1.7 resolve host function CostTable for new protocol version
+        let new_host_function_costs = match upgrade_config.host_function_costs() {
+            Some(new_host_function_costs) => new_host_function_costs,
+            None => *current_protocol_data.host_function_costs(),
+        };
+
         // 3.1.2.2 persist wasm CostTable
         let mut new_protocol_data = ProtocolData::new(
             new_wasm_costs,
+            new_host_function_costs,
             current_protocol_data.mint(),
             current_protocol_data.proof_of_stake(),
             current_protocol_data.standard_payment(),
@@ -616,7 +682,7 @@ where
                 let tracking_copy = Rc::clone(&tracking_copy);
                 let system_contract_cache = SystemContractCache::clone(&self.system_contract_cache);
 
-                let executor = Executor::new(self.config);
+                let executor = Executor::new(self.config, ModuleCache::clone(&self.module_cache));
 
                 let result: BTreeMap<ContractHash, ContractHash> = executor.exec_wasm_direct(
                     upgrade_installer_module,
@@ -673,6 +739,22 @@ where
         }
     }
 
+    /// Returns a `TrackingCopy` of the state at `hash`, which shares the values it reads from the
+    /// global state with the other tracking copies using `shared_read_cache`.
+    fn tracking_copy_with_shared_cache(
+        &self,
+        hash: Blake2bHash,
+        shared_read_cache: SharedReadCache,
+    ) -> Result<Option<TrackingCopy<S::Reader>>, Error> {
+        match self.state.checkout(hash).map_err(Into::into)? {
+            Some(reader) => Ok(Some(TrackingCopy::with_shared_cache(
+                reader,
+                shared_read_cache,
+            ))),
+            None => Ok(None),
+        }
+    }
+
     pub fn run_query(
         &self,
         correlation_id: CorrelationId,
@@ -691,65 +773,488 @@ where
             .into())
     }
 
+    /// As `run_query()`, but a successful result also holds proofs of the values read while
+    /// following the query path.
+    pub fn run_query_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        query_request: QueryRequest,
+    ) -> Result<QueryResult, Error> {
+        let tracking_copy = match self.tracking_copy(query_request.state_hash())? {
+            Some(tracking_copy) => tracking_copy,
+            None => return Ok(QueryResult::RootNotFound),
+        };
+
+        Ok(tracking_copy
+            .query_with_proof(correlation_id, query_request.key(), query_request.path())
+            .map_err(|err| Error::Exec(err.into()))?
+            .into())
+    }
+
+    /// Reads a page of the entries within the range of `scan_request`.  Follow
+    /// `ScanResult::Success::next_request` to read the rest of the range.
+    pub fn run_scan(
+        &self,
+        correlation_id: CorrelationId,
+        scan_request: ScanRequest,
+    ) -> Result<ScanResult, Error> {
+        let reader = match self
+            .state
+            .checkout(scan_request.state_hash())
+            .map_err(Into::into)?
+        {
+            Some(reader) => reader,
+            None => return Ok(ScanResult::RootNotFound),
+        };
+
+        let entries = reader
+            .read_range(correlation_id, scan_request.range(), scan_request.limit())
+            .map_err(Into::into)?;
+
+        let next_request = match entries.last() {
+            Some((last_key, _)) if entries.len() == scan_request.limit() => {
+                Some(scan_request.next(last_key))
+            }
+            _ => None,
+        };
+
+        Ok(ScanResult::Success {
+            entries,
+            next_request,
+        })
+    }
+
+    /// Reads the stakes of the bonded validators and the pending bonding and unbonding requests
+    /// held by the proof-of-stake contract of `bid_state_request`'s protocol version.
+    pub fn get_bid_state(
+        &self,
+        correlation_id: CorrelationId,
+        bid_state_request: BidStateRequest,
+    ) -> Result<BidStateResult, Error> {
+        let mut tracking_copy = match self.tracking_copy(bid_state_request.state_hash())? {
+            Some(tracking_copy) => tracking_copy,
+            None => return Ok(BidStateResult::RootNotFound),
+        };
+
+        let protocol_version = bid_state_request.protocol_version();
+        let protocol_data = match self.get_protocol_data(protocol_version)? {
+            Some(protocol_data) => protocol_data,
+            None => return Err(Error::InvalidProtocolVersion(protocol_version)),
+        };
+
+        let proof_of_stake =
+            tracking_copy.get_contract(correlation_id, protocol_data.proof_of_stake())?;
+        let stakes = Stakes::from_strings(proof_of_stake.named_keys().keys().map(String::as_str))
+            .map_err(|error| {
+            Error::Exec(execution::Error::from(system_contract_errors::Error::from(
+                error,
+            )))
+        })?;
+
+        let bonding_queue =
+            bid_state::read_queue(&mut tracking_copy, correlation_id, runtime::BONDING_KEY)?;
+        let unbonding_queue =
+            bid_state::read_queue(&mut tracking_copy, correlation_id, runtime::UNBONDING_KEY)?;
+
+        Ok(BidStateResult::Success {
+            stakes,
+            bonding_queue,
+            unbonding_queue,
+        })
+    }
+
+    /// Executes each of the deploys of `exec_request` against its parent state, on the pool of
+    /// [`EngineConfig::execution_threads`] threads shared by all execute requests.
+    ///
+    /// Each deploy is executed against its own `TrackingCopy` of the parent state, so the results
+    /// do not depend on the order of execution, and are returned in the order of the deploys.
+    ///
+    /// If the request is [`chained`](ExecuteRequest::chained), the deploys are instead executed
+    /// one after another, each on top of the effects of those preceding it.
     pub fn run_execute(
         &self,
         correlation_id: CorrelationId,
         mut exec_request: ExecuteRequest,
-    ) -> Result<Vec<ExecutionResult>, RootNotFound> {
+    ) -> Result<Vec<ExecutionResult>, RootNotFound>
+    where
+        S: Sync,
+    {
         // TODO: do not unwrap
         let wasm_costs = self
             .wasm_costs(exec_request.protocol_version)
             .unwrap()
             .unwrap();
-        let executor = Executor::new(self.config);
+        let executor = Executor::new(self.config, ModuleCache::clone(&self.module_cache));
         let preprocessor = Preprocessor::new(wasm_costs);
 
-        let mut results = Vec::new();
+        let protocol_version = exec_request.protocol_version;
+        let parent_state_hash = exec_request.parent_state_hash;
+        let blocktime = BlockTime::new(exec_request.block_time);
+        let deploy_items = exec_request.take_deploys();
 
-        for deploy_item in exec_request.take_deploys() {
-            let result = match deploy_item {
-                Err(exec_result) => Ok(exec_result),
-                Ok(deploy_item) => match deploy_item.session {
-                    ExecutableDeployItem::Transfer { .. } => self.transfer(
-                        correlation_id,
-                        &executor,
-                        &preprocessor,
-                        exec_request.protocol_version,
-                        exec_request.parent_state_hash,
-                        BlockTime::new(exec_request.block_time),
-                        deploy_item,
-                    ),
-                    _ => self.deploy(
-                        correlation_id,
-                        &executor,
-                        &preprocessor,
-                        exec_request.protocol_version,
-                        exec_request.parent_state_hash,
-                        BlockTime::new(exec_request.block_time),
-                        deploy_item,
-                    ),
-                },
+        if exec_request.chained {
+            return self.run_execute_chained(
+                correlation_id,
+                &executor,
+                &preprocessor,
+                protocol_version,
+                parent_state_hash,
+                blocktime,
+                deploy_items,
+            );
+        }
+
+        // The deploys are all executed against the parent state, and so can't see each other's
+        // entries in the deploy registry.  Any deploy with the same hash as an earlier one in the
+        // request is rejected as a replay here instead.
+        let mut deploy_hashes = HashSet::new();
+        let deploy_items: Vec<_> = deploy_items
+            .into_iter()
+            .map(|deploy_item| match deploy_item {
+                Ok(deploy_item) if !deploy_hashes.insert(deploy_item.deploy_hash) => {
+                    Err(ExecutionResult::precondition_failure(Error::DeployReplay))
+                }
+                deploy_item => deploy_item,
+            })
+            .collect();
+
+        // The values read by one of the deploys can be reused by the others.
+        let shared_read_cache = SharedReadCache::new(SHARED_READ_CACHE_SIZE);
+        let execute = |deploy_item| {
+            self.execute_deploy_item(
+                correlation_id,
+                &executor,
+                &preprocessor,
+                protocol_version,
+                parent_state_hash,
+                blocktime,
+                deploy_item,
+                &shared_read_cache,
+            )
+        };
+
+        match &self.maybe_execution_pool {
+            Some(execution_pool) if deploy_items.len() > 1 => {
+                execution_pool.install(|| deploy_items.into_par_iter().map(execute).collect())
+            }
+            _ => deploy_items.into_iter().map(execute).collect(),
+        }
+    }
+
+    /// Executes `deploy_items` in order, each against a fork of a `TrackingCopy` of the parent
+    /// state to which the effects of the preceding deploys have been applied.
+    #[allow(clippy::too_many_arguments)]
+    fn run_execute_chained(
+        &self,
+        correlation_id: CorrelationId,
+        executor: &Executor,
+        preprocessor: &Preprocessor,
+        protocol_version: ProtocolVersion,
+        parent_state_hash: Blake2bHash,
+        blocktime: BlockTime,
+        deploy_items: Vec<Result<DeployItem, ExecutionResult>>,
+    ) -> Result<Vec<ExecutionResult>, RootNotFound> {
+        let mut results = Vec::with_capacity(deploy_items.len());
+        // Checked out on the first deploy which gets as far as needing it, in the same order in
+        // which the preconditions of an unchained deploy are checked.
+        let mut chain: Option<TrackingCopy<S::Reader>> = None;
+
+        for deploy_item in deploy_items {
+            let deploy_item = match deploy_item {
+                Ok(deploy_item) => deploy_item,
+                Err(exec_result) => {
+                    results.push(exec_result);
+                    continue;
+                }
             };
-            match result {
-                Ok(result) => results.push(result),
-                Err(error) => {
-                    return Err(error);
+
+            let protocol_data = match self.deploy_protocol_data(protocol_version) {
+                Ok(protocol_data) => protocol_data,
+                Err(failure) => {
+                    results.push(failure);
+                    continue;
                 }
             };
+
+            let chain = match chain {
+                Some(ref mut chain) => chain,
+                None => match self.tracking_copy(parent_state_hash) {
+                    Err(error) => {
+                        results.push(ExecutionResult::precondition_failure(error));
+                        continue;
+                    }
+                    Ok(None) => return Err(RootNotFound::new(parent_state_hash)),
+                    Ok(Some(tracking_copy)) => chain.get_or_insert(tracking_copy),
+                },
+            };
+
+            let tracking_copy = Rc::new(RefCell::new(chain.fork()));
+            let result = match deploy_item.session {
+                ExecutableDeployItem::Transfer { .. } => self.transfer_with_tracking_copy(
+                    correlation_id,
+                    executor,
+                    preprocessor,
+                    protocol_version,
+                    protocol_data,
+                    tracking_copy,
+                    blocktime,
+                    deploy_item,
+                )?,
+                _ => self.deploy_with_tracking_copy(
+                    correlation_id,
+                    executor,
+                    preprocessor,
+                    protocol_version,
+                    protocol_data,
+                    tracking_copy,
+                    blocktime,
+                    deploy_item,
+                    None,
+                )?,
+            };
+
+            let result = match chain.apply_effect(correlation_id, result.effect()) {
+                Ok(AddResult::Success) => result,
+                Ok(add_result) => panic!(
+                    "effect of a deploy should apply to the state it was executed on: {:?}",
+                    add_result
+                ),
+                Err(error) => ExecutionResult::precondition_failure(Error::Exec(error.into())),
+            };
+            results.push(result);
         }
 
         Ok(results)
     }
 
-    pub fn get_module(
+    /// Executes the deploy of `estimate_request` without committing its effects, and with a
+    /// session gas limit of [`EngineConfig::estimate_gas_limit`] which does not depend on the
+    /// amount it pays, reporting the gas used by each phase.
+    ///
+    /// The cost of the payment code may depend on the amount it pays, so if the payment code takes
+    /// an `amount` argument, the deploy is executed again paying the estimated minimum payment
+    /// until the estimate settles.  The result reported is that of the last execution.
+    pub fn run_estimate(
+        &self,
+        correlation_id: CorrelationId,
+        estimate_request: EstimateRequest,
+    ) -> Result<EstimateResult, RootNotFound> {
+        let EstimateRequest {
+            parent_state_hash,
+            block_time,
+            deploy_item,
+            protocol_version,
+        } = estimate_request;
+        let blocktime = BlockTime::new(block_time);
+
+        let (mut execution_result, mut costs) = self.dry_run(
+            correlation_id,
+            protocol_version,
+            parent_state_hash,
+            blocktime,
+            deploy_item.clone(),
+        )?;
+        if !execution_result.is_success() {
+            return Ok(EstimateResult::new(execution_result, costs, None));
+        }
+
+        // Transfers run no payment code of their own.
+        if let ExecutableDeployItem::Transfer { .. } = deploy_item.session {
+            let minimum_payment = costs.minimum_payment();
+            return Ok(EstimateResult::new(
+                execution_result,
+                costs,
+                Some(minimum_payment),
+            ));
+        }
+
+        let mut payment = costs.minimum_payment();
+        for _ in 0..MAX_ESTIMATE_REFINEMENTS {
+            let payment_arg = match CLValue::from_t(payment.value()) {
+                Ok(payment_arg) => payment_arg,
+                Err(_) => break,
+            };
+            let payment_code = match deploy_item.payment.with_arg(ARG_AMOUNT, payment_arg) {
+                Ok(Some(payment_code)) => payment_code,
+                // Without an amount to pay, the cost of the payment code does not depend on it.
+                Ok(None) | Err(_) => {
+                    return Ok(EstimateResult::new(execution_result, costs, Some(payment)));
+                }
+            };
+            let refined_deploy_item = DeployItem {
+                payment: payment_code,
+                ..deploy_item.clone()
+            };
+            let (refined_result, refined_costs) = self.dry_run(
+                correlation_id,
+                protocol_version,
+                parent_state_hash,
+                blocktime,
+                refined_deploy_item,
+            )?;
+            execution_result = refined_result;
+            costs = refined_costs;
+            if !execution_result.is_success() {
+                break;
+            }
+            let minimum_payment = costs.minimum_payment();
+            if minimum_payment == payment {
+                return Ok(EstimateResult::new(execution_result, costs, Some(payment)));
+            }
+            payment = minimum_payment;
+        }
+
+        // No payment was found to cover its own cost.
+        Ok(EstimateResult::new(execution_result, costs, None))
+    }
+
+    /// Executes `deploy_item` against the state at `parent_state_hash` as if its payment were
+    /// unlimited, without committing its effects, and returns the gas used by each phase.
+    fn dry_run(
         &self,
-        tracking_copy: Rc<RefCell<TrackingCopy<<S as StateProvider>::Reader>>>,
+        correlation_id: CorrelationId,
+        protocol_version: ProtocolVersion,
+        parent_state_hash: Blake2bHash,
+        blocktime: BlockTime,
+        deploy_item: DeployItem,
+    ) -> Result<(ExecutionResult, PhaseCosts), RootNotFound> {
+        let mut costs = PhaseCosts::default();
+
+        let protocol_data = match self.deploy_protocol_data(protocol_version) {
+            Ok(protocol_data) => protocol_data,
+            Err(failure) => return Ok((failure, costs)),
+        };
+
+        let tracking_copy = match self.tracking_copy(parent_state_hash) {
+            Err(error) => {
+                let failure = ExecutionResult::precondition_failure(error);
+                return Ok((failure, costs));
+            }
+            Ok(None) => return Err(RootNotFound::new(parent_state_hash)),
+            Ok(Some(tracking_copy)) => Rc::new(RefCell::new(tracking_copy)),
+        };
+
+        let executor = Executor::new(self.config, ModuleCache::clone(&self.module_cache));
+        let preprocessor = Preprocessor::new(*protocol_data.wasm_costs());
+
+        let execution_result = match deploy_item.session {
+            ExecutableDeployItem::Transfer { .. } => {
+                let execution_result = self.transfer_with_tracking_copy(
+                    correlation_id,
+                    &executor,
+                    &preprocessor,
+                    protocol_version,
+                    protocol_data,
+                    tracking_copy,
+                    blocktime,
+                    deploy_item,
+                )?;
+                costs.session = execution_result.cost();
+                execution_result
+            }
+            _ => self.deploy_with_tracking_copy(
+                correlation_id,
+                &executor,
+                &preprocessor,
+                protocol_version,
+                protocol_data,
+                tracking_copy,
+                blocktime,
+                deploy_item,
+                Some(&mut costs),
+            )?,
+        };
+
+        Ok((execution_result, costs))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_deploy_item(
+        &self,
+        correlation_id: CorrelationId,
+        executor: &Executor,
+        preprocessor: &Preprocessor,
+        protocol_version: ProtocolVersion,
+        parent_state_hash: Blake2bHash,
+        blocktime: BlockTime,
+        deploy_item: Result<DeployItem, ExecutionResult>,
+        shared_read_cache: &SharedReadCache,
+    ) -> Result<ExecutionResult, RootNotFound> {
+        let deploy_item = match deploy_item {
+            Err(exec_result) => return Ok(exec_result),
+            Ok(deploy_item) => deploy_item,
+        };
+
+        // Preconditions are checked in the same order as by `deploy` and `transfer`.
+        let protocol_data = match self.deploy_protocol_data(protocol_version) {
+            Ok(protocol_data) => protocol_data,
+            Err(failure) => return Ok(failure),
+        };
+
+        let shared_read_cache = SharedReadCache::clone(shared_read_cache);
+        let tracking_copy =
+            match self.tracking_copy_with_shared_cache(parent_state_hash, shared_read_cache) {
+                Err(error) => return Ok(ExecutionResult::precondition_failure(error)),
+                Ok(None) => return Err(RootNotFound::new(parent_state_hash)),
+                Ok(Some(tracking_copy)) => Rc::new(RefCell::new(tracking_copy)),
+            };
+
+        match deploy_item.session {
+            ExecutableDeployItem::Transfer { .. } => self.transfer_with_tracking_copy(
+                correlation_id,
+                executor,
+                preprocessor,
+                protocol_version,
+                protocol_data,
+                tracking_copy,
+                blocktime,
+                deploy_item,
+            ),
+            _ => self.deploy_with_tracking_copy(
+                correlation_id,
+                executor,
+                preprocessor,
+                protocol_version,
+                protocol_data,
+                tracking_copy,
+                blocktime,
+                deploy_item,
+                None,
+            ),
+        }
+    }
+
+    /// Returns the protocol data a deploy is executed with, or the precondition failure of such a
+    /// deploy if there is none for `protocol_version`.
+    fn deploy_protocol_data(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> Result<ProtocolData, ExecutionResult> {
+        match self.state.get_protocol_data(protocol_version) {
+            Ok(Some(protocol_data)) => Ok(protocol_data),
+            Ok(None) => {
+                let error = Error::InvalidProtocolVersion(protocol_version);
+                Err(ExecutionResult::precondition_failure(error))
+            }
+            Err(error) => Err(ExecutionResult::precondition_failure(Error::Exec(
+                error.into(),
+            ))),
+        }
+    }
+
+    pub fn get_module<R>(
+        &self,
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
         deploy_item: &ExecutableDeployItem,
         account: &Account,
         correlation_id: CorrelationId,
         preprocessor: &Preprocessor,
         protocol_version: &ProtocolVersion,
-    ) -> Result<GetModuleResult, error::Error> {
+    ) -> Result<GetModuleResult, error::Error>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
         let (contract_package, contract, base_key) = match deploy_item {
             ExecutableDeployItem::ModuleBytes { module_bytes, .. } => {
                 let module = preprocessor.preprocess(&module_bytes)?;
@@ -837,11 +1342,23 @@ where
                 error::Error::Exec(execution::Error::NoSuchMethod(entry_point_name.to_owned()))
             })?;
 
+        // The Wasm is read even when its module is cached, so that it is part of the deploy's
+        // reads.
+        let contract_wasm_hash = contract.contract_wasm_hash();
         let contract_wasm = tracking_copy
             .borrow_mut()
-            .get_contract_wasm(correlation_id, contract.contract_wasm_hash())?;
+            .get_contract_wasm(correlation_id, contract_wasm_hash)?;
 
-        let module = engine_wasm_prep::deserialize(contract_wasm.bytes())?;
+        let wasm_costs = *preprocessor.wasm_costs();
+        let module = match self.module_cache.get(contract_wasm_hash, wasm_costs) {
+            Some(module) => module,
+            None => {
+                let module = engine_wasm_prep::deserialize(contract_wasm.bytes())?;
+                self.module_cache
+                    .insert(contract_wasm_hash, wasm_costs, module.clone());
+                module
+            }
+        };
 
         match entry_point.entry_point_type() {
             EntryPointType::Session => Ok(GetModuleResult::Session {
@@ -859,13 +1376,17 @@ where
         }
     }
 
-    fn get_module_from_contract_hash(
+    fn get_module_from_contract_hash<R>(
         &self,
-        tracking_copy: Rc<RefCell<TrackingCopy<<S as StateProvider>::Reader>>>,
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
         contract_hash: ContractHash,
         correlation_id: CorrelationId,
         protocol_version: &ProtocolVersion,
-    ) -> Result<Module, error::Error> {
+    ) -> Result<Module, error::Error>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
         let contract = tracking_copy
             .borrow_mut()
             .get_contract(correlation_id, contract_hash)?;
@@ -889,13 +1410,17 @@ where
         Ok(module)
     }
 
-    fn get_authorized_account(
+    fn get_authorized_account<R>(
         &self,
         correlation_id: CorrelationId,
         account_hash: AccountHash,
         authorization_keys: &BTreeSet<AccountHash>,
-        tracking_copy: Rc<RefCell<TrackingCopy<<S as StateProvider>::Reader>>>,
-    ) -> Result<Account, Error> {
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
+    ) -> Result<Account, Error>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
         let account: Account = match tracking_copy
             .borrow_mut()
             .get_account(correlation_id, account_hash)
@@ -919,6 +1444,36 @@ where
         Ok(account)
     }
 
+    /// Checks that `deploy_item` has neither expired nor already been executed.
+    fn check_deploy_registry<R>(
+        &self,
+        correlation_id: CorrelationId,
+        deploy_item: &DeployItem,
+        blocktime: BlockTime,
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
+    ) -> Result<(), Error>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
+        if deploy_item.is_expired(blocktime, self.config.max_ttl_millis()) {
+            return Err(Error::DeployExpired);
+        }
+
+        let registry_key = deploy_registry::deploy_registry_key(&deploy_item.deploy_hash);
+        match tracking_copy
+            .borrow_mut()
+            .read(correlation_id, &registry_key)
+        {
+            Ok(None) => Ok(()),
+            Ok(Some(_)) => Err(Error::DeployReplay),
+            Err(error) => {
+                let exec_error: execution::Error = error.into();
+                Err(exec_error.into())
+            }
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn transfer(
         &self,
@@ -930,17 +1485,9 @@ where
         blocktime: BlockTime,
         deploy_item: DeployItem,
     ) -> Result<ExecutionResult, RootNotFound> {
-        let protocol_data = match self.state.get_protocol_data(protocol_version) {
-            Ok(Some(protocol_data)) => protocol_data,
-            Ok(None) => {
-                let error = Error::InvalidProtocolVersion(protocol_version);
-                return Ok(ExecutionResult::precondition_failure(error));
-            }
-            Err(error) => {
-                return Ok(ExecutionResult::precondition_failure(Error::Exec(
-                    error.into(),
-                )));
-            }
+        let protocol_data = match self.deploy_protocol_data(protocol_version) {
+            Ok(protocol_data) => protocol_data,
+            Err(failure) => return Ok(failure),
         };
 
         let tracking_copy = match self.tracking_copy(prestate_hash) {
@@ -949,6 +1496,43 @@ where
             Ok(Some(tracking_copy)) => Rc::new(RefCell::new(tracking_copy)),
         };
 
+        self.transfer_with_tracking_copy(
+            correlation_id,
+            executor,
+            preprocessor,
+            protocol_version,
+            protocol_data,
+            tracking_copy,
+            blocktime,
+            deploy_item,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_with_tracking_copy<R>(
+        &self,
+        correlation_id: CorrelationId,
+        executor: &Executor,
+        preprocessor: &Preprocessor,
+        protocol_version: ProtocolVersion,
+        protocol_data: ProtocolData,
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
+        blocktime: BlockTime,
+        deploy_item: DeployItem,
+    ) -> Result<ExecutionResult, RootNotFound>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
+        if let Err(error) = self.check_deploy_registry(
+            correlation_id,
+            &deploy_item,
+            blocktime,
+            Rc::clone(&tracking_copy),
+        ) {
+            return Ok(ExecutionResult::precondition_failure(error));
+        }
+
         let base_key = Key::Account(deploy_item.address);
 
         let account_public_key = match base_key.into_account() {
@@ -1008,6 +1592,11 @@ where
             Err(error) => return Ok(ExecutionResult::precondition_failure(error.into())),
         };
 
+        tracking_copy.borrow_mut().write(
+            deploy_registry::deploy_registry_key(&deploy_item.deploy_hash),
+            deploy_registry::deploy_registry_value(blocktime),
+        );
+
         let mut runtime_args_builder = TransferRuntimeArgsBuilder::new(input_runtime_args);
         match runtime_args_builder.transfer_target_mode(correlation_id, Rc::clone(&tracking_copy)) {
             Ok(mode) => match mode {
@@ -1054,6 +1643,7 @@ where
                     error,
                     effect: Default::default(),
                     cost: Gas::default(),
+                    trace: Default::default(),
                 });
             }
         }
@@ -1066,6 +1656,7 @@ where
                         error,
                         effect: Default::default(),
                         cost: Gas::default(),
+                        trace: Default::default(),
                     });
                 }
             };
@@ -1109,17 +1700,9 @@ where
 
         // Obtain current protocol data for given version
         // do this first, as there is no reason to proceed if protocol version is invalid
-        let protocol_data = match self.state.get_protocol_data(protocol_version) {
-            Ok(Some(protocol_data)) => protocol_data,
-            Ok(None) => {
-                let error = Error::InvalidProtocolVersion(protocol_version);
-                return Ok(ExecutionResult::precondition_failure(error));
-            }
-            Err(error) => {
-                return Ok(ExecutionResult::precondition_failure(Error::Exec(
-                    error.into(),
-                )));
-            }
+        let protocol_data = match self.deploy_protocol_data(protocol_version) {
+            Ok(protocol_data) => protocol_data,
+            Err(failure) => return Ok(failure),
         };
 
         // Create tracking copy (which functions as a deploy context)
@@ -1131,6 +1714,52 @@ where
             Ok(Some(tracking_copy)) => Rc::new(RefCell::new(tracking_copy)),
         };
 
+        self.deploy_with_tracking_copy(
+            correlation_id,
+            executor,
+            preprocessor,
+            protocol_version,
+            protocol_data,
+            tracking_copy,
+            blocktime,
+            deploy_item,
+            None,
+        )
+    }
+
+    /// Executes `deploy_item` against `tracking_copy`.
+    ///
+    /// If `dry_run_costs` is given, the deploy is executed as if its payment were unlimited: the
+    /// session gas limit is [`EngineConfig::estimate_gas_limit`] rather than depending on the
+    /// amount paid, and finalization charges no more than was paid.  The gas used by each phase
+    /// is recorded in `dry_run_costs`.
+    #[allow(clippy::too_many_arguments)]
+    fn deploy_with_tracking_copy<R>(
+        &self,
+        correlation_id: CorrelationId,
+        executor: &Executor,
+        preprocessor: &Preprocessor,
+        protocol_version: ProtocolVersion,
+        protocol_data: ProtocolData,
+        tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
+        blocktime: BlockTime,
+        deploy_item: DeployItem,
+        mut dry_run_costs: Option<&mut PhaseCosts>,
+    ) -> Result<ExecutionResult, RootNotFound>
+    where
+        R: StateReader<Key, StoredValue>,
+        R::Error: Into<execution::Error>,
+    {
+        // Reject deploys which have expired or have already been executed
+        if let Err(error) = self.check_deploy_registry(
+            correlation_id,
+            &deploy_item,
+            blocktime,
+            Rc::clone(&tracking_copy),
+        ) {
+            return Ok(ExecutionResult::precondition_failure(error));
+        }
+
         let base_key = Key::Account(deploy_item.address);
 
         // Get addr bytes from `address` (which is actually a Key)
@@ -1280,6 +1909,13 @@ where
             ));
         }
 
+        // All preconditions are met, so the deploy is registered as executed, whether or not
+        // execution succeeds
+        tracking_copy.borrow_mut().write(
+            deploy_registry::deploy_registry_key(&deploy_hash),
+            deploy_registry::deploy_registry_value(blocktime),
+        );
+
         // Finalization is executed by system account (currently genesis account)
         // payment_code_spec_5: system executes finalization
         let system_account = Account::new(
@@ -1460,11 +2096,13 @@ where
                     Ok(()) => ExecutionResult::Success {
                         effect: runtime.context().effect(),
                         cost: runtime.context().gas_counter(),
+                        trace: runtime.trace(),
                     },
                     Err(error) => ExecutionResult::Failure {
                         error: error.into(),
                         effect: effects_snapshot,
                         cost: runtime.context().gas_counter(),
+                        trace: runtime.trace(),
                     },
                 }
             }
@@ -1505,7 +2143,19 @@ where
             }
         };
 
-        if let Some(forced_transfer) = payment_result.check_forced_transfer(payment_purse_balance) {
+        if let Some(ref mut costs) = dry_run_costs {
+            costs.payment = payment_result_cost;
+        }
+
+        // A dry run is only stopped by a failure of the payment code, not by its paying too little.
+        let forced_transfer_balance = if dry_run_costs.is_some() {
+            Motes::new(U512::max_value())
+        } else {
+            payment_purse_balance
+        };
+
+        if let Some(forced_transfer) = payment_result.check_forced_transfer(forced_transfer_balance)
+        {
             // Get rewards purse balance key
             // payment_code_spec_6: system contract validity
             let rewards_purse_balance_key: Key = {
@@ -1540,7 +2190,10 @@ where
                 account_main_purse_balance,
                 account_main_purse_balance_key,
                 rewards_purse_balance_key,
-            ));
+                deploy_hash,
+                blocktime,
+            )
+            .with_reads(tracking_copy.borrow().reads().clone()));
         }
 
         execution_result_builder.set_payment_execution_result(payment_result);
@@ -1595,9 +2248,12 @@ where
             // payment code execution) * conv_rate, yes session
             // session_code_spec_1: gas limit = ((balance of PoS payment purse) / conv_rate)
             // - (gas spent during payment execution)
-            let session_gas_limit: Gas = Gas::from_motes(payment_purse_balance, CONV_RATE)
-                .unwrap_or_default()
-                - payment_result_cost;
+            let session_gas_limit: Gas = if dry_run_costs.is_some() {
+                Gas::new(U512::from(self.config.estimate_gas_limit()))
+            } else {
+                Gas::from_motes(payment_purse_balance, CONV_RATE).unwrap_or_default()
+                    - payment_result_cost
+            };
             let system_contract_cache = SystemContractCache::clone(&self.system_contract_cache);
 
             executor.exec(
@@ -1622,6 +2278,9 @@ where
         };
         debug!("Session result: {:?}", session_result);
 
+        // The session's reads are kept even if its effects are not.
+        execution_result_builder.add_reads(session_tracking_copy.borrow().reads());
+
         let post_session_rc = if session_result.is_failure() {
             // If session code fails we do not include its effects,
             // so we start again from the post-payment state.
@@ -1632,6 +2291,9 @@ where
 
         // NOTE: session_code_spec_3: (do not include session execution effects in
         // results) is enforced in execution_result_builder.build()
+        if let Some(ref mut costs) = dry_run_costs {
+            costs.session = session_result.cost();
+        }
         execution_result_builder.set_session_execution_result(session_result);
 
         // payment_code_spec_5: run finalize process
@@ -1641,7 +2303,10 @@ where
 
             let proof_of_stake_args = {
                 //((gas spent during payment code execution) + (gas spent during session code execution)) * conv_rate
-                let finalize_cost_motes: Motes = Motes::from_gas(execution_result_builder.total_cost(), CONV_RATE).expect("motes overflow");
+                let mut finalize_cost_motes: Motes = Motes::from_gas(execution_result_builder.total_cost(), CONV_RATE).expect("motes overflow");
+                if dry_run_costs.is_some() {
+                    finalize_cost_motes = cmp::min(finalize_cost_motes, payment_purse_balance);
+                }
                 const ARG_AMOUNT: &str = "amount";
                 const ARG_ACCOUNT_KEY: &str = "account";
                 runtime_args! {
@@ -1686,8 +2351,14 @@ where
             )
         };
 
+        if let Some(costs) = dry_run_costs {
+            costs.finalize = finalize_result.cost();
+        }
         execution_result_builder.set_finalize_execution_result(finalize_result);
 
+        // Include the reads made while validating the deploy and executing payment.
+        execution_result_builder.add_reads(tracking_copy.borrow().reads());
+
         // We panic here to indicate that the builder was not used properly.
         let ret = execution_result_builder
             .build(tracking_copy.borrow().reader(), correlation_id)
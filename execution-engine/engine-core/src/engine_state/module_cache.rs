@@ -0,0 +1,438 @@
+//! A cache of deserialized Wasm modules of stored contracts, keyed by the hash of their
+//! [`ContractWasm`](types::ContractWasm) and the [`WasmCosts`] they were instrumented with.
+//!
+//! Contract Wasm is content-addressed, so a cached module never goes stale.  It is only evicted to
+//! keep the cache within its capacity.
+//!
+//! The modules of the system contracts are kept apart in the
+//! [`SystemContractCache`](super::system_contract_cache::SystemContractCache): they are keyed by
+//! contract hash, as their module is a do-nothing stand-in unless system contracts are executed
+//! as Wasm, and they are used by every deploy, so they are never evicted.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug, Formatter},
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use parity_wasm::elements::{
+    BrTableData, CodeSection, DataSection, ElementSection, ExportSection, GlobalSection,
+    ImportSection, Instruction, Module, RelocSection, Section, Type, TypeSection,
+};
+
+use engine_shared::{logging::log_metric, newtypes::CorrelationId};
+use engine_wasm_prep::wasm_costs::WasmCosts;
+use types::ContractWasmHash;
+
+/// The default capacity of a [`ModuleCache`], in bytes: 64 MiB.
+pub const DEFAULT_MODULE_CACHE_CAPACITY: usize = 64 * 1024 * 1024;
+
+const MODULE_CACHE_HITS: &str = "module_cache_hits";
+const MODULE_CACHE_MISSES: &str = "module_cache_misses";
+const MODULE_CACHE_HIT_RATE: &str = "module_cache_hit_rate";
+const GAUGE_METRIC_KEY: &str = "gauge";
+
+type CacheKey = (ContractWasmHash, WasmCosts);
+
+/// Returns the size of the elements of a slice.
+fn slice_size<T>(elements: &[T]) -> usize {
+    elements.len() * mem::size_of::<T>()
+}
+
+/// Returns an estimate of the memory used by `instructions`.  Each takes
+/// `size_of::<Instruction>()` bytes, several times the size of its encoding in Wasm.
+fn instructions_size(instructions: &[Instruction]) -> usize {
+    slice_size(instructions)
+        + instructions
+            .iter()
+            .map(|instruction| match instruction {
+                Instruction::BrTable(data) => {
+                    mem::size_of::<BrTableData>() + slice_size(&data.table)
+                }
+                _ => 0,
+            })
+            .sum::<usize>()
+}
+
+fn type_section_size(section: &TypeSection) -> usize {
+    slice_size(section.types())
+        + section
+            .types()
+            .iter()
+            .map(|Type::Function(function_type)| slice_size(function_type.params()))
+            .sum::<usize>()
+}
+
+fn import_section_size(section: &ImportSection) -> usize {
+    slice_size(section.entries())
+        + section
+            .entries()
+            .iter()
+            .map(|entry| entry.module().len() + entry.field().len())
+            .sum::<usize>()
+}
+
+fn global_section_size(section: &GlobalSection) -> usize {
+    slice_size(section.entries())
+        + section
+            .entries()
+            .iter()
+            .map(|entry| instructions_size(entry.init_expr().code()))
+            .sum::<usize>()
+}
+
+fn export_section_size(section: &ExportSection) -> usize {
+    slice_size(section.entries())
+        + section
+            .entries()
+            .iter()
+            .map(|entry| entry.field().len())
+            .sum::<usize>()
+}
+
+fn element_section_size(section: &ElementSection) -> usize {
+    slice_size(section.entries())
+        + section
+            .entries()
+            .iter()
+            .map(|segment| {
+                slice_size(segment.members())
+                    + segment
+                        .offset()
+                        .as_ref()
+                        .map_or(0, |offset| instructions_size(offset.code()))
+            })
+            .sum::<usize>()
+}
+
+fn code_section_size(section: &CodeSection) -> usize {
+    slice_size(section.bodies())
+        + section
+            .bodies()
+            .iter()
+            .map(|body| slice_size(body.locals()) + instructions_size(body.code().elements()))
+            .sum::<usize>()
+}
+
+fn data_section_size(section: &DataSection) -> usize {
+    slice_size(section.entries())
+        + section
+            .entries()
+            .iter()
+            .map(|segment| {
+                segment.value().len()
+                    + segment
+                        .offset()
+                        .as_ref()
+                        .map_or(0, |offset| instructions_size(offset.code()))
+            })
+            .sum::<usize>()
+}
+
+fn reloc_section_size(section: &RelocSection) -> usize {
+    section.name().len() + slice_size(section.entries())
+}
+
+/// Returns an estimate of the memory used by a deserialized module: the sizes of its sections and
+/// of the heap allocations they own.  Allocator overhead and the names in a parsed name section
+/// are not included.
+fn estimated_size(module: &Module) -> usize {
+    mem::size_of::<Module>()
+        + slice_size(module.sections())
+        + module
+            .sections()
+            .iter()
+            .map(|section| match section {
+                Section::Unparsed { payload, .. } => payload.len(),
+                Section::Custom(section) => section.name().len() + section.payload().len(),
+                Section::Type(section) => type_section_size(section),
+                Section::Import(section) => import_section_size(section),
+                Section::Function(section) => slice_size(section.entries()),
+                Section::Table(section) => slice_size(section.entries()),
+                Section::Memory(section) => slice_size(section.entries()),
+                Section::Global(section) => global_section_size(section),
+                Section::Export(section) => export_section_size(section),
+                Section::Element(section) => element_section_size(section),
+                Section::Code(section) => code_section_size(section),
+                Section::Data(section) => data_section_size(section),
+                Section::Reloc(section) => reloc_section_size(section),
+                Section::Start(_) | Section::DataCount(_) | Section::Name(_) => 0,
+            })
+            .sum::<usize>()
+}
+
+struct CacheEntry {
+    module: Module,
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// The keys of the cached entries, keyed by when they were last used.
+    recency: BTreeMap<u64, CacheKey>,
+    next_use: u64,
+    size: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// A size-bounded, thread-safe cache of deserialized contract modules, which evicts the least
+/// recently used modules once it is full.
+///
+/// Clones share the same underlying cache.
+#[derive(Clone)]
+pub struct ModuleCache {
+    capacity: usize,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl ModuleCache {
+    /// Creates an empty cache holding at most roughly `capacity` bytes of modules.
+    pub fn new(capacity: usize) -> Self {
+        ModuleCache {
+            capacity,
+            state: Default::default(),
+        }
+    }
+
+    /// Returns the capacity of the cache in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the estimated size in bytes of the cached modules.
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().size
+    }
+
+    /// Returns the number of cached modules.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of lookups which found a cached module, and the number which did not.
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        let state = self.state.lock().unwrap();
+        (state.hits, state.misses)
+    }
+
+    /// Returns a copy of the module cached for the given Wasm hash and costs, marking it as
+    /// recently used.
+    pub fn get(
+        &self,
+        contract_wasm_hash: ContractWasmHash,
+        wasm_costs: WasmCosts,
+    ) -> Option<Module> {
+        let key = (contract_wasm_hash, wasm_costs);
+        let mut state = self.state.lock().unwrap();
+        let next_use = state.next_use;
+        let (module, last_used) = match state.entries.get_mut(&key) {
+            Some(entry) => (
+                entry.module.clone(),
+                mem::replace(&mut entry.last_used, next_use),
+            ),
+            None => {
+                state.misses += 1;
+                return None;
+            }
+        };
+        state.recency.remove(&last_used);
+        state.recency.insert(next_use, key);
+        state.next_use += 1;
+        state.hits += 1;
+        Some(module)
+    }
+
+    /// Caches `module` for the given Wasm hash and costs, evicting the least recently used modules
+    /// as needed.
+    ///
+    /// A module too large to fit in the cache at all is not cached.
+    pub fn insert(
+        &self,
+        contract_wasm_hash: ContractWasmHash,
+        wasm_costs: WasmCosts,
+        module: Module,
+    ) {
+        let key = (contract_wasm_hash, wasm_costs);
+        let size = estimated_size(&module);
+        if size > self.capacity {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.remove(&key);
+        while state.size + size > self.capacity {
+            let least_recently_used = match state.recency.values().next() {
+                Some(key) => *key,
+                None => break,
+            };
+            state.remove(&least_recently_used);
+        }
+        let last_used = state.next_use;
+        state.next_use += 1;
+        state.recency.insert(last_used, key);
+        state.size += size;
+        state.entries.insert(
+            key,
+            CacheEntry {
+                module,
+                size,
+                last_used,
+            },
+        );
+    }
+
+    /// Logs the number of cache hits and misses so far, and the resulting hit rate.
+    pub fn log_metrics(&self, correlation_id: CorrelationId, tag: &str) {
+        let (hits, misses) = self.hits_and_misses();
+        let lookups = hits + misses;
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            hits as f64 / lookups as f64
+        };
+        log_metric(
+            correlation_id,
+            MODULE_CACHE_HITS,
+            tag,
+            GAUGE_METRIC_KEY,
+            hits as f64,
+        );
+        log_metric(
+            correlation_id,
+            MODULE_CACHE_MISSES,
+            tag,
+            GAUGE_METRIC_KEY,
+            misses as f64,
+        );
+        log_metric(
+            correlation_id,
+            MODULE_CACHE_HIT_RATE,
+            tag,
+            GAUGE_METRIC_KEY,
+            hit_rate,
+        );
+    }
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        ModuleCache::new(DEFAULT_MODULE_CACHE_CAPACITY)
+    }
+}
+
+impl Debug for ModuleCache {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ModuleCache")
+            .field("capacity", &self.capacity)
+            .field("size", &self.size())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_wasm::elements::{FuncBody, Instructions};
+
+    use super::*;
+
+    const INSTRUCTION_COUNT: usize = 1000;
+
+    fn module() -> Module {
+        let instructions = Instructions::new(vec![Instruction::Nop; INSTRUCTION_COUNT]);
+        let body = FuncBody::new(Vec::new(), instructions);
+        Module::new(vec![Section::Code(CodeSection::with_bodies(vec![body]))])
+    }
+
+    fn module_size() -> usize {
+        estimated_size(&module())
+    }
+
+    fn insert(cache: &ModuleCache, hash_byte: u8) -> ContractWasmHash {
+        let hash = [hash_byte; 32];
+        cache.insert(hash, WasmCosts::default(), module());
+        hash
+    }
+
+    #[test]
+    fn estimates_size_of_deserialized_code() {
+        // Each `nop` is encoded in a single byte, but takes far more once deserialized.
+        let serialized_length = parity_wasm::serialize(module()).unwrap().len();
+        assert!(module_size() >= INSTRUCTION_COUNT * mem::size_of::<Instruction>());
+        assert!(module_size() > 4 * serialized_length);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_modules() {
+        let cache = ModuleCache::new(3 * module_size());
+        let hash_0 = insert(&cache, 0);
+        let hash_1 = insert(&cache, 1);
+        let hash_2 = insert(&cache, 2);
+        assert_eq!(cache.len(), 3);
+
+        // Using the oldest module makes the next oldest the one to be evicted.
+        assert!(cache.get(hash_0, WasmCosts::default()).is_some());
+        let hash_3 = insert(&cache, 3);
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.size(), 3 * module_size());
+        assert!(cache.get(hash_1, WasmCosts::default()).is_none());
+        for hash in &[hash_0, hash_2, hash_3] {
+            assert!(cache.get(*hash, WasmCosts::default()).is_some());
+        }
+    }
+
+    #[test]
+    fn does_not_cache_modules_larger_than_capacity() {
+        let cache = ModuleCache::new(module_size() - 1);
+        let hash = insert(&cache, 0);
+        assert!(cache.get(hash, WasmCosts::default()).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn keys_modules_by_wasm_costs() {
+        let cache = ModuleCache::new(3 * module_size());
+        let hash = insert(&cache, 0);
+        let other_costs = WasmCosts {
+            regular: WasmCosts::default().regular + 1,
+            ..WasmCosts::default()
+        };
+        assert!(cache.get(hash, other_costs).is_none());
+        assert!(cache.get(hash, WasmCosts::default()).is_some());
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = ModuleCache::new(3 * module_size());
+        assert_eq!(cache.hits_and_misses(), (0, 0));
+        let hash = insert(&cache, 0);
+        assert!(cache.get([1; 32], WasmCosts::default()).is_none());
+        assert!(cache.get(hash, WasmCosts::default()).is_some());
+        assert!(cache.get(hash, WasmCosts::default()).is_some());
+        assert_eq!(cache.hits_and_misses(), (2, 1));
+    }
+
+    #[test]
+    fn shares_state_between_clones() {
+        let cache = ModuleCache::new(3 * module_size());
+        let hash = insert(&cache.clone(), 0);
+        assert!(cache.get(hash, WasmCosts::default()).is_some());
+    }
+}
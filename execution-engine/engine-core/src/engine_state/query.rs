@@ -1,4 +1,5 @@
 use engine_shared::{newtypes::Blake2bHash, stored_value::StoredValue};
+use engine_storage::trie::TrieMerkleProof;
 use types::Key;
 
 use crate::tracking_copy::TrackingCopyQueryResult;
@@ -7,7 +8,12 @@ pub enum QueryResult {
     RootNotFound,
     ValueNotFound(String),
     CircularReference(String),
-    Success(StoredValue),
+    Success {
+        value: StoredValue,
+        /// Proofs of the values read while following the query path.  Only made by
+        /// `EngineState::run_query_with_proof()`.
+        proofs: Vec<TrieMerkleProof<Key, StoredValue>>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,7 +52,9 @@ impl From<TrackingCopyQueryResult> for QueryResult {
             TrackingCopyQueryResult::CircularReference(message) => {
                 QueryResult::CircularReference(message)
             }
-            TrackingCopyQueryResult::Success(value) => QueryResult::Success(value),
+            TrackingCopyQueryResult::Success { value, proofs } => {
+                QueryResult::Success { value, proofs }
+            }
         }
     }
 }
@@ -0,0 +1,65 @@
+use engine_shared::{newtypes::Blake2bHash, stored_value::StoredValue};
+use engine_storage::global_state::KeyRange;
+use types::{bytesrepr::ToBytes, Key};
+
+/// The largest page of entries returned by a single scan.
+pub const MAX_SCAN_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    RootNotFound,
+    Success {
+        /// Entries within the requested range, in ascending order of their serialized keys.
+        entries: Vec<(Key, StoredValue)>,
+        /// The request for the next page, or `None` if the range has been exhausted.
+        next_request: Option<ScanRequest>,
+    },
+}
+
+/// A request for a page of the entries in global state whose serialized keys are within a range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanRequest {
+    state_hash: Blake2bHash,
+    range: KeyRange,
+    limit: usize,
+}
+
+impl ScanRequest {
+    /// Creates a request for up to `limit` entries within `range`.  `limit` is capped at
+    /// [`MAX_SCAN_LIMIT`].
+    pub fn new(state_hash: Blake2bHash, range: KeyRange, limit: usize) -> Self {
+        let limit = limit.min(MAX_SCAN_LIMIT);
+        ScanRequest {
+            state_hash,
+            range,
+            limit,
+        }
+    }
+
+    /// Creates a request for up to `limit` entries whose serialized keys start with `prefix`.
+    pub fn with_prefix(state_hash: Blake2bHash, prefix: &[u8], limit: usize) -> Self {
+        ScanRequest::new(state_hash, KeyRange::with_prefix(prefix), limit)
+    }
+
+    pub fn state_hash(&self) -> Blake2bHash {
+        self.state_hash
+    }
+
+    pub fn range(&self) -> &KeyRange {
+        &self.range
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the request for the page following one whose last entry was under `last_key`.
+    pub(crate) fn next(&self, last_key: &Key) -> Self {
+        let last_key_bytes = last_key.to_bytes().expect("should serialize key");
+        ScanRequest {
+            state_hash: self.state_hash,
+            range: self.range.after(&last_key_bytes),
+            limit: self.limit,
+        }
+    }
+}
@@ -7,7 +7,10 @@ use parity_wasm::elements::Module;
 
 use types::ContractHash;
 
-/// A cache of deserialized contracts.
+/// A cache of the deserialized modules of the system contracts, keyed by contract hash.
+///
+/// Unlike the [`ModuleCache`](super::module_cache::ModuleCache) of stored contracts, nothing is
+/// ever evicted, as the system contracts are used by every deploy.
 #[derive(Clone, Default, Debug)]
 pub struct SystemContractCache(Arc<RwLock<HashMap<ContractHash, Module>>>);
 
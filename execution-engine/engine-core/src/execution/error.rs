@@ -16,6 +16,8 @@ pub enum Error {
     Interpreter(String),
     #[fail(display = "Storage error: {}", _0)]
     Storage(engine_storage::error::Error),
+    #[fail(display = "Storage error: {}", _0)]
+    FileStorage(engine_storage::error::file::Error),
     #[fail(display = "Serialization error: {}", _0)]
     BytesRepr(bytesrepr::Error),
     #[fail(display = "Named key {} not found", _0)]
@@ -126,6 +128,12 @@ impl From<engine_storage::error::Error> for Error {
     }
 }
 
+impl From<engine_storage::error::file::Error> for Error {
+    fn from(e: engine_storage::error::file::Error) -> Self {
+        Error::FileStorage(e)
+    }
+}
+
 impl From<bytesrepr::Error> for Error {
     fn from(e: bytesrepr::Error) -> Self {
         Error::BytesRepr(e)
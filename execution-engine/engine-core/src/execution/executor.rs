@@ -17,7 +17,7 @@ use types::{
 use crate::{
     engine_state::{
         execution_effect::ExecutionEffect, execution_result::ExecutionResult,
-        system_contract_cache::SystemContractCache, EngineConfig,
+        module_cache::ModuleCache, system_contract_cache::SystemContractCache, EngineConfig,
     },
     execution::{address_generator::AddressGenerator, Error},
     runtime::{
@@ -51,11 +51,15 @@ macro_rules! on_fail_charge {
                     error: exec_err.into(),
                     effect: Default::default(),
                     cost: $cost,
+                    trace: Default::default(),
                 };
             }
         }
     };
     ($fn:expr, $cost:expr, $effect:expr) => {
+        on_fail_charge!($fn, $cost, $effect, Default::default())
+    };
+    ($fn:expr, $cost:expr, $effect:expr, $trace:expr) => {
         match $fn {
             Ok(res) => res,
             Err(e) => {
@@ -65,6 +69,7 @@ macro_rules! on_fail_charge {
                     error: exec_err.into(),
                     effect: $effect,
                     cost: $cost,
+                    trace: $trace,
                 };
             }
         }
@@ -73,12 +78,16 @@ macro_rules! on_fail_charge {
 
 pub struct Executor {
     config: EngineConfig,
+    module_cache: ModuleCache,
 }
 
 #[allow(clippy::too_many_arguments)]
 impl Executor {
-    pub fn new(config: EngineConfig) -> Self {
-        Executor { config }
+    pub fn new(config: EngineConfig, module_cache: ModuleCache) -> Self {
+        Executor {
+            config,
+            module_cache,
+        }
     }
 
     pub fn config(&self) -> EngineConfig {
@@ -156,7 +165,14 @@ impl Executor {
             protocol_data,
         );
 
-        let mut runtime = Runtime::new(self.config, system_contract_cache, memory, module, context);
+        let mut runtime = Runtime::new(
+            self.config,
+            system_contract_cache,
+            ModuleCache::clone(&self.module_cache),
+            memory,
+            module,
+            context,
+        );
 
         let accounts_access_rights = {
             let keys: Vec<Key> = account.named_keys().values().cloned().collect();
@@ -182,6 +198,7 @@ impl Executor {
                         return ExecutionResult::Success {
                             effect: runtime.context().effect(),
                             cost: runtime.context().gas_counter(),
+                            trace: runtime.trace(),
                         };
                     }
                     Err(error) => {
@@ -189,6 +206,7 @@ impl Executor {
                             error: error.into(),
                             effect: effects_snapshot,
                             cost: runtime.context().gas_counter(),
+                            trace: runtime.trace(),
                         };
                     }
                 }
@@ -204,6 +222,7 @@ impl Executor {
                         return ExecutionResult::Success {
                             effect: runtime.context().effect(),
                             cost: runtime.context().gas_counter(),
+                            trace: runtime.trace(),
                         };
                     }
                     Err(error) => {
@@ -211,6 +230,7 @@ impl Executor {
                             error: error.into(),
                             effect: effects_snapshot,
                             cost: runtime.context().gas_counter(),
+                            trace: runtime.trace(),
                         };
                     }
                 }
@@ -220,12 +240,14 @@ impl Executor {
         on_fail_charge!(
             instance.invoke_export(entry_point_name, &[], &mut runtime),
             runtime.context().gas_counter(),
-            effects_snapshot
+            effects_snapshot,
+            runtime.trace()
         );
 
         ExecutionResult::Success {
             effect: runtime.context().effect(),
             cost: runtime.context().gas_counter(),
+            trace: runtime.trace(),
         }
     }
 
@@ -314,6 +336,7 @@ impl Executor {
                     effect: effect_snapshot.clone(),
                     cost: gas_counter,
                     error: e.into(),
+                    trace: Default::default(),
                 }
                 .take_without_ret::<T>();
             })
@@ -374,6 +397,7 @@ impl Executor {
         let runtime_context = runtime.context();
 
         let cost = runtime_context.gas_counter();
+        let trace = runtime.trace();
 
         let effect = if revert_effect {
             effect_snapshot
@@ -386,8 +410,13 @@ impl Executor {
                 error: error.into(),
                 effect,
                 cost,
+                trace,
+            },
+            None => ExecutionResult::Success {
+                effect,
+                cost,
+                trace,
             },
-            None => ExecutionResult::Success { effect, cost },
         };
 
         match maybe_ret {
@@ -540,6 +569,7 @@ impl Executor {
         let runtime = Runtime::new(
             self.config,
             system_contract_cache,
+            ModuleCache::clone(&self.module_cache),
             memory,
             module,
             runtime_context,
@@ -602,12 +632,14 @@ impl DirectSystemContractCall {
                 Ok(ret) => ExecutionResult::Success {
                     effect: runtime.context().effect(),
                     cost: runtime.context().gas_counter(),
+                    trace: runtime.trace(),
                 }
                 .take_with_ret(ret),
                 Err(error) => ExecutionResult::Failure {
                     error: Error::CLValue(error).into(),
                     effect: execution_effect,
                     cost: runtime.context().gas_counter(),
+                    trace: runtime.trace(),
                 }
                 .take_without_ret(),
             },
@@ -615,6 +647,7 @@ impl DirectSystemContractCall {
                 error: error.into(),
                 effect: execution_effect,
                 cost: runtime.context().gas_counter(),
+                trace: runtime.trace(),
             }
             .take_without_ret(),
         }
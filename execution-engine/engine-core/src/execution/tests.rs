@@ -16,6 +16,7 @@ fn on_fail_charge_test_helper<T>(
     ExecutionResult::Success {
         effect: Default::default(),
         cost: success_cost,
+        trace: Default::default(),
     }
 }
 
@@ -58,6 +59,7 @@ fn on_fail_charge_with_action() {
         ExecutionResult::Success {
             effect: Default::default(),
             cost: Gas::default(),
+            trace: Default::default(),
         }
     };
     match f() {
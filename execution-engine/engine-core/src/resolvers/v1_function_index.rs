@@ -49,6 +49,13 @@ pub enum FunctionIndex {
     RemoveContractUserGroupIndex,
     ExtendContractUserGroupURefsIndex,
     RemoveContractUserGroupURefsIndex,
+    RemoveFuncIndex,
+    RemoveLocalFuncIndex,
+    EmitEventIndex,
+    Blake2bIndex,
+    Sha256Index,
+    VerifyEd25519Index,
+    VerifySecp256k1Index,
 }
 
 impl Into<usize> for FunctionIndex {
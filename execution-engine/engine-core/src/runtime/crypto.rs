@@ -0,0 +1,133 @@
+//! Native implementations of the cryptographic host functions.
+
+use std::convert::TryFrom;
+
+use ed25519_dalek::Verifier;
+use lazy_static::lazy_static;
+use secp256k1::{Secp256k1, VerifyOnly};
+use sha2::{Digest, Sha256};
+
+use engine_shared::newtypes::Blake2bHash;
+
+/// The length in bytes of the digests produced by the hash host functions.
+pub(super) const DIGEST_LENGTH: usize = 32;
+
+lazy_static! {
+    static ref SECP256K1_VERIFIER: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
+}
+
+pub(super) fn blake2b(input: &[u8]) -> [u8; DIGEST_LENGTH] {
+    Blake2bHash::new(input).value()
+}
+
+pub(super) fn sha256(input: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut ret = [0u8; DIGEST_LENGTH];
+    ret.copy_from_slice(&Sha256::digest(input));
+    ret
+}
+
+/// Returns whether `signature` is a valid ed25519 signature of `message` by `public_key`.  A
+/// malformed public key or signature is treated as an invalid signature.
+pub(super) fn verify_ed25519(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    public_key.verify(message, &signature).is_ok()
+}
+
+/// Returns whether `signature` is a valid secp256k1 signature of the sha2-256 digest of `message`
+/// by `public_key`.  The public key may be compressed or uncompressed, and the signature is
+/// expected in 64-byte compact form.  A malformed public key or signature is treated as an invalid
+/// signature.
+pub(super) fn verify_secp256k1(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let public_key = match secp256k1::PublicKey::from_slice(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match secp256k1::Signature::from_compact(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    // Can't fail, as the digest has the required length
+    let message = secp256k1::Message::from_slice(&sha256(message)).unwrap();
+    SECP256K1_VERIFIER
+        .verify(&message, &signature, &public_key)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test 1 from RFC 8032, section 7.1
+    const ED25519_PUBLIC_KEY: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const ED25519_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    fn decode(hex: &str) -> Vec<u8> {
+        base16::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn should_hash_with_blake2b() {
+        assert_eq!(
+            blake2b(b"").to_vec(),
+            decode("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+        );
+    }
+
+    #[test]
+    fn should_hash_with_sha256() {
+        assert_eq!(
+            sha256(b"abc").to_vec(),
+            decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[test]
+    fn should_verify_ed25519_signature() {
+        let public_key = decode(ED25519_PUBLIC_KEY);
+        let signature = decode(ED25519_SIGNATURE);
+        assert!(verify_ed25519(&public_key, b"", &signature));
+        assert!(!verify_ed25519(&public_key, b"x", &signature));
+        assert!(!verify_ed25519(&public_key[1..], b"", &signature));
+        assert!(!verify_ed25519(&public_key, b"", &signature[1..]));
+    }
+
+    #[test]
+    fn should_verify_secp256k1_signature() {
+        let secp = Secp256k1::new();
+        let secret_key = secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key);
+        let message = b"Hello, world!";
+        let digest = secp256k1::Message::from_slice(&sha256(message)).unwrap();
+        let signature = secp.sign(&digest, &secret_key).serialize_compact();
+
+        assert!(verify_secp256k1(
+            &public_key.serialize(),
+            message,
+            &signature
+        ));
+        assert!(verify_secp256k1(
+            &public_key.serialize_uncompressed(),
+            message,
+            &signature
+        ));
+        assert!(!verify_secp256k1(&public_key.serialize(), b"x", &signature));
+        assert!(!verify_secp256k1(
+            &public_key.serialize()[1..],
+            message,
+            &signature
+        ));
+        assert!(!verify_secp256k1(
+            &public_key.serialize(),
+            message,
+            &signature[1..]
+        ));
+    }
+}
@@ -13,8 +13,13 @@ use types::{
 use engine_shared::{gas::Gas, stored_value::StoredValue};
 use engine_storage::global_state::StateReader;
 
-use super::{args::Args, scoped_instrumenter::ScopedInstrumenter, Error, Runtime};
-use crate::resolvers::v1_function_index::FunctionIndex;
+use super::{
+    args::Args,
+    crypto,
+    scoped_instrumenter::{self, ScopedInstrumenter},
+    Error, Runtime,
+};
+use crate::{engine_state::execution_trace::HostCall, resolvers::v1_function_index::FunctionIndex};
 
 impl<'a, R> Externals for Runtime<'a, R>
 where
@@ -27,13 +32,58 @@ where
         args: RuntimeArgs,
     ) -> Result<Option<RuntimeValue>, Trap> {
         let func = FunctionIndex::try_from(index).expect("unknown function index");
+
+        let (trace, name) = match (
+            self.trace.clone(),
+            scoped_instrumenter::host_function_name(func),
+        ) {
+            (Some(trace), Some(name)) => (trace, name),
+            _ => return self.invoke_host_function(func, args),
+        };
+
+        // The call is recorded before it is made so that any calls made by a called contract
+        // follow it in the trace.
+        let gas_before = self.context.gas_counter();
+        let position = trace.borrow().len();
+        trace.borrow_mut().push(HostCall {
+            name,
+            args: format!("{:?}", args.as_ref()),
+            result: String::new(),
+            gas_before,
+            gas_after: gas_before,
+            call_depth: self.call_depth,
+        });
+
+        let result = self.invoke_host_function(func, args);
+
+        let mut trace = trace.borrow_mut();
+        let host_call = &mut trace[position];
+        host_call.result = format!("{:?}", result);
+        host_call.gas_after = self.context.gas_counter();
+
+        result
+    }
+}
+
+impl<'a, R> Runtime<'a, R>
+where
+    R: StateReader<Key, StoredValue>,
+    R::Error: Into<Error>,
+{
+    fn invoke_host_function(
+        &mut self,
+        func: FunctionIndex,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
         let mut scoped_instrumenter = ScopedInstrumenter::new(func);
+        let host_function_costs = *self.context.protocol_data().host_function_costs();
         match func {
             FunctionIndex::ReadFuncIndex => {
                 // args(0) = pointer to key in Wasm memory
                 // args(1) = size of key in Wasm memory
                 // args(2) = pointer to output size (output param)
                 let (key_ptr, key_size, output_size_ptr) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.read_value, &[key_size])?;
                 let ret = self.read(key_ptr, key_size, output_size_ptr)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
             }
@@ -43,6 +93,7 @@ where
                 // args(1) = size of key in Wasm memory
                 // args(2) = pointer to output size (output param)
                 let (key_ptr, key_size, output_size_ptr): (_, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.read_value_local, &[key_size])?;
                 scoped_instrumenter.add_property("key_size", key_size);
                 let ret = self.read_local(key_ptr, key_size, output_size_ptr)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
@@ -52,6 +103,7 @@ where
                 // args(0) = pointer to amount of keys (output)
                 // args(1) = pointer to amount of serialized bytes (output)
                 let (total_keys_ptr, result_size_ptr) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.load_named_keys, &[])?;
                 let ret = self.load_named_keys(
                     total_keys_ptr,
                     result_size_ptr,
@@ -66,6 +118,7 @@ where
                 // args(2) = pointer to value
                 // args(3) = size of value
                 let (key_ptr, key_size, value_ptr, value_size): (_, _, _, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.write, &[key_size, value_size])?;
                 scoped_instrumenter.add_property("value_size", value_size);
                 self.write(key_ptr, key_size, value_ptr, value_size)?;
                 Ok(None)
@@ -78,18 +131,146 @@ where
                 // args(3) = size of value
                 let (key_bytes_ptr, key_bytes_size, value_ptr, value_size): (_, u32, _, u32) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.write_local,
+                    &[key_bytes_size, value_size],
+                )?;
                 scoped_instrumenter.add_property("key_bytes_size", key_bytes_size);
                 scoped_instrumenter.add_property("value_size", value_size);
                 self.write_local(key_bytes_ptr, key_bytes_size, value_ptr, value_size)?;
                 Ok(None)
             }
 
+            FunctionIndex::RemoveFuncIndex => {
+                // args(0) = pointer to key in Wasm memory
+                // args(1) = size of key
+                let (key_ptr, key_size) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.remove, &[key_size])?;
+                self.remove(key_ptr, key_size)?;
+                Ok(None)
+            }
+
+            FunctionIndex::RemoveLocalFuncIndex => {
+                // args(0) = pointer to key in Wasm memory
+                // args(1) = size of key
+                let (key_bytes_ptr, key_bytes_size): (_, u32) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.remove_local,
+                    &[key_bytes_size],
+                )?;
+                scoped_instrumenter.add_property("key_bytes_size", key_bytes_size);
+                self.remove_local(key_bytes_ptr, key_bytes_size)?;
+                Ok(None)
+            }
+
+            FunctionIndex::EmitEventIndex => {
+                // args(0) = pointer to event name in Wasm memory
+                // args(1) = size of event name
+                // args(2) = pointer to event value
+                // args(3) = size of event value
+                let (name_ptr, name_size, value_ptr, value_size): (_, u32, _, u32) =
+                    Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.emit_event,
+                    &[name_size, value_size],
+                )?;
+                scoped_instrumenter.add_property("name_size", name_size);
+                scoped_instrumenter.add_property("value_size", value_size);
+                self.emit_event(name_ptr, name_size, value_ptr, value_size)?;
+                Ok(None)
+            }
+
+            FunctionIndex::Blake2bIndex => {
+                // args(0) = pointer to input in Wasm memory
+                // args(1) = size of input
+                // args(2) = pointer to output buffer
+                // args(3) = size of output buffer
+                let (in_ptr, in_size, out_ptr, out_size): (_, u32, _, _) = Args::parse(args)?;
+                scoped_instrumenter.add_property("in_size", in_size);
+                let cost = host_function_costs.blake2b;
+                let ret = self.hash(cost, crypto::blake2b, in_ptr, in_size, out_ptr, out_size)?;
+                Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
+            }
+
+            FunctionIndex::Sha256Index => {
+                // args(0) = pointer to input in Wasm memory
+                // args(1) = size of input
+                // args(2) = pointer to output buffer
+                // args(3) = size of output buffer
+                let (in_ptr, in_size, out_ptr, out_size): (_, u32, _, _) = Args::parse(args)?;
+                scoped_instrumenter.add_property("in_size", in_size);
+                let cost = host_function_costs.sha256;
+                let ret = self.hash(cost, crypto::sha256, in_ptr, in_size, out_ptr, out_size)?;
+                Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
+            }
+
+            FunctionIndex::VerifyEd25519Index => {
+                // args(0) = pointer to public key in Wasm memory
+                // args(1) = size of public key
+                // args(2) = pointer to message
+                // args(3) = size of message
+                // args(4) = pointer to signature
+                // args(5) = size of signature
+                let (
+                    public_key_ptr,
+                    public_key_size,
+                    message_ptr,
+                    message_size,
+                    signature_ptr,
+                    signature_size,
+                ): (_, _, _, u32, _, _) = Args::parse(args)?;
+                scoped_instrumenter.add_property("message_size", message_size);
+                let cost = host_function_costs.verify_ed25519;
+                let valid = self.verify_signature(
+                    cost,
+                    crypto::verify_ed25519,
+                    public_key_ptr,
+                    public_key_size,
+                    message_ptr,
+                    message_size,
+                    signature_ptr,
+                    signature_size,
+                )?;
+                Ok(Some(RuntimeValue::I32(i32::from(valid))))
+            }
+
+            FunctionIndex::VerifySecp256k1Index => {
+                // args(0) = pointer to public key in Wasm memory
+                // args(1) = size of public key
+                // args(2) = pointer to message
+                // args(3) = size of message
+                // args(4) = pointer to signature
+                // args(5) = size of signature
+                let (
+                    public_key_ptr,
+                    public_key_size,
+                    message_ptr,
+                    message_size,
+                    signature_ptr,
+                    signature_size,
+                ): (_, _, _, u32, _, _) = Args::parse(args)?;
+                scoped_instrumenter.add_property("message_size", message_size);
+                let cost = host_function_costs.verify_secp256k1;
+                let valid = self.verify_signature(
+                    cost,
+                    crypto::verify_secp256k1,
+                    public_key_ptr,
+                    public_key_size,
+                    message_ptr,
+                    message_size,
+                    signature_ptr,
+                    signature_size,
+                )?;
+                Ok(Some(RuntimeValue::I32(i32::from(valid))))
+            }
+
             FunctionIndex::AddFuncIndex => {
                 // args(0) = pointer to key in Wasm memory
                 // args(1) = size of key
                 // args(2) = pointer to value
                 // args(3) = size of value
                 let (key_ptr, key_size, value_ptr, value_size) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.add, &[key_size, value_size])?;
                 self.add(key_ptr, key_size, value_ptr, value_size)?;
                 Ok(None)
             }
@@ -99,6 +280,7 @@ where
                 // args(1) = pointer to initial value
                 // args(2) = size of initial value
                 let (uref_ptr, value_ptr, value_size): (_, _, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.new_uref, &[value_size])?;
                 scoped_instrumenter.add_property("value_size", value_size);
                 self.new_uref(uref_ptr, value_ptr, value_size)?;
                 Ok(None)
@@ -108,6 +290,7 @@ where
                 // args(0) = pointer to value
                 // args(1) = size of value
                 let (value_ptr, value_size): (_, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.ret, &[value_size])?;
                 scoped_instrumenter.add_property("value_size", value_size);
                 Err(self.ret(value_ptr, value_size as usize, &mut scoped_instrumenter))
             }
@@ -125,6 +308,7 @@ where
                     u32,
                     u32,
                 ) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_key, &[name_size])?;
                 scoped_instrumenter.add_property("name_size", name_size);
                 let ret = self.load_key(
                     name_ptr,
@@ -140,6 +324,7 @@ where
                 // args(0) = pointer to key name in Wasm memory
                 // args(1) = size of key name
                 let (name_ptr, name_size): (_, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.has_key, &[name_size])?;
                 scoped_instrumenter.add_property("name_size", name_size);
                 let result = self.has_key(name_ptr, name_size)?;
                 Ok(Some(RuntimeValue::I32(result)))
@@ -151,6 +336,10 @@ where
                 // args(2) = pointer to key in Wasm memory
                 // args(3) = size of key
                 let (name_ptr, name_size, key_ptr, key_size): (_, u32, _, _) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.put_key,
+                    &[name_size, key_size],
+                )?;
                 scoped_instrumenter.add_property("name_size", name_size);
                 self.put_key(name_ptr, name_size, key_ptr, key_size)?;
                 Ok(None)
@@ -160,6 +349,7 @@ where
                 // args(0) = pointer to key name in Wasm memory
                 // args(1) = size of key name
                 let (name_ptr, name_size): (_, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.remove_key, &[name_size])?;
                 scoped_instrumenter.add_property("name_size", name_size);
                 self.remove_key(name_ptr, name_size)?;
                 Ok(None)
@@ -168,6 +358,7 @@ where
             FunctionIndex::GetCallerIndex => {
                 // args(0) = pointer where a size of serialized bytes will be stored
                 let output_size = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_caller, &[])?;
                 let ret = self.get_caller(output_size)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
             }
@@ -175,6 +366,7 @@ where
             FunctionIndex::GetBlocktimeIndex => {
                 // args(0) = pointer to Wasm memory where to write.
                 let dest_ptr = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_blocktime, &[])?;
                 self.get_blocktime(dest_ptr)?;
                 Ok(None)
             }
@@ -189,6 +381,7 @@ where
                 // args(0) = pointer to value to validate
                 // args(1) = size of value
                 let (uref_ptr, uref_size) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.is_valid_uref, &[uref_size])?;
 
                 Ok(Some(RuntimeValue::I32(i32::from(
                     self.is_valid_uref(uref_ptr, uref_size)?,
@@ -198,6 +391,7 @@ where
             FunctionIndex::RevertFuncIndex => {
                 // args(0) = status u32
                 let status = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.revert, &[])?;
 
                 Err(self.revert(status))
             }
@@ -208,6 +402,10 @@ where
                 // args(2) = weight of the key
                 let (account_hash_ptr, account_hash_size, weight_value): (u32, u32, u8) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.add_associated_key,
+                    &[account_hash_size],
+                )?;
                 let value = self.add_associated_key(
                     account_hash_ptr,
                     account_hash_size as usize,
@@ -220,6 +418,10 @@ where
                 // args(0) = pointer to array of bytes of an account hash
                 // args(1) = size of an account hash
                 let (account_hash_ptr, account_hash_size): (_, u32) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.remove_associated_key,
+                    &[account_hash_size],
+                )?;
                 let value =
                     self.remove_associated_key(account_hash_ptr, account_hash_size as usize)?;
                 Ok(Some(RuntimeValue::I32(value)))
@@ -231,6 +433,10 @@ where
                 // args(2) = weight of the key
                 let (account_hash_ptr, account_hash_size, weight_value): (u32, u32, u8) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.update_associated_key,
+                    &[account_hash_size],
+                )?;
                 let value = self.update_associated_key(
                     account_hash_ptr,
                     account_hash_size as usize,
@@ -243,6 +449,7 @@ where
                 // args(0) = action type
                 // args(1) = new threshold
                 let (action_type_value, threshold_value): (u32, u8) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.set_action_threshold, &[])?;
                 let value = self.set_action_threshold(action_type_value, threshold_value)?;
                 Ok(Some(RuntimeValue::I32(value)))
             }
@@ -251,6 +458,7 @@ where
                 // args(0) = pointer to array for return value
                 // args(1) = length of array for return value
                 let (dest_ptr, dest_size): (u32, u32) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.create_purse, &[])?;
                 let purse = self.create_purse()?;
                 let purse_bytes = purse.into_bytes().map_err(Error::BytesRepr)?;
                 assert_eq!(dest_size, purse_bytes.len() as u32);
@@ -267,6 +475,10 @@ where
                 // args(3) = length of array of bytes of an amount
                 let (key_ptr, key_size, amount_ptr, amount_size): (u32, u32, u32, u32) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.transfer_to_account,
+                    &[key_size, amount_size],
+                )?;
                 let account_hash: AccountHash = {
                     let bytes = self.bytes_from_mem(key_ptr, key_size as usize)?;
                     bytesrepr::deserialize(bytes).map_err(Error::BytesRepr)?
@@ -294,6 +506,10 @@ where
                     u32,
                     u32,
                 ) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.transfer_from_purse_to_account,
+                    &[source_size, key_size, amount_size],
+                )?;
 
                 let source_purse = {
                     let bytes = self.bytes_from_mem(source_ptr, source_size as usize)?;
@@ -321,6 +537,10 @@ where
                 // args(5) = length of array of bytes in Wasm memory of an amount
                 let (source_ptr, source_size, target_ptr, target_size, amount_ptr, amount_size) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.transfer_from_purse_to_purse,
+                    &[source_size, target_size, amount_size],
+                )?;
                 let ret = self.transfer_from_purse_to_purse(
                     source_ptr,
                     source_size,
@@ -337,6 +557,7 @@ where
                 // args(1) = length of purse
                 // args(2) = pointer to output size (output)
                 let (ptr, ptr_size, output_size_ptr): (_, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_balance, &[ptr_size])?;
                 let ret = self.get_balance_host_buffer(ptr, ptr_size as usize, output_size_ptr)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
             }
@@ -344,6 +565,7 @@ where
             FunctionIndex::GetPhaseIndex => {
                 // args(0) = pointer to Wasm memory where to write.
                 let dest_ptr = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_phase, &[])?;
                 self.get_phase(dest_ptr)?;
                 Ok(None)
             }
@@ -353,6 +575,7 @@ where
                 // args(1) = dest pointer for storing serialized result
                 // args(2) = dest pointer size
                 let (system_contract_index, dest_ptr, dest_size) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_system_contract, &[])?;
                 let ret = self.get_system_contract(system_contract_index, dest_ptr, dest_size)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
             }
@@ -360,6 +583,7 @@ where
             FunctionIndex::GetMainPurseIndex => {
                 // args(0) = pointer to Wasm memory where to write.
                 let dest_ptr = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.get_main_purse, &[])?;
                 self.get_main_purse(dest_ptr)?;
                 Ok(None)
             }
@@ -367,6 +591,7 @@ where
             FunctionIndex::ReadHostBufferIndex => {
                 // args(0) = pointer to Wasm memory where to write size.
                 let (dest_ptr, dest_size, bytes_written_ptr): (_, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(host_function_costs.read_host_buffer, &[dest_size])?;
                 scoped_instrumenter.add_property("dest_size", dest_size);
                 let ret = self.read_host_buffer(dest_ptr, dest_size as usize, bytes_written_ptr)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
@@ -376,6 +601,10 @@ where
                 // args(0) = pointer to wasm memory where to write 32-byte Hash address
                 // args(1) = pointer to wasm memory where to write 32-byte access key address
                 let (hash_dest_ptr, access_dest_ptr) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.create_contract_package_at_hash,
+                    &[],
+                )?;
                 let (hash_addr, access_addr) = self.create_contract_package_at_hash()?;
                 self.function_address(hash_addr, hash_dest_ptr)?;
                 self.function_address(access_addr, access_dest_ptr)?;
@@ -401,6 +630,10 @@ where
                     existing_urefs_size,
                     output_size_ptr,
                 ): (_, _, _, u32, _, _, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.create_contract_user_group,
+                    &[package_key_size, label_size, existing_urefs_size],
+                )?;
                 scoped_instrumenter
                     .add_property("existing_urefs_size", existing_urefs_size.to_string());
                 scoped_instrumenter.add_property("label_size", label_size.to_string());
@@ -443,6 +676,14 @@ where
                     output_size,
                     bytes_written_ptr,
                 ): (u32, u32, u32, u32, u32, u32, u32, u32, u32, u32) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.add_contract_version,
+                    &[
+                        contract_package_hash_size,
+                        entry_points_size,
+                        named_keys_size,
+                    ],
+                )?;
 
                 scoped_instrumenter
                     .add_property("entry_points_size", entry_points_size.to_string());
@@ -472,6 +713,10 @@ where
                 // args(3) = size of contract hash in wasm memory
                 let (package_key_ptr, package_key_size, contract_hash_ptr, contract_hash_size) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.disable_contract_version,
+                    &[package_key_size, contract_hash_size],
+                )?;
 
                 let contract_package_hash = self.t_from_mem(package_key_ptr, package_key_size)?;
                 let contract_hash = self.t_from_mem(contract_hash_ptr, contract_hash_size)?;
@@ -498,6 +743,10 @@ where
                     args_size,
                     result_size_ptr,
                 ): (_, _, _, u32, _, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.call_contract,
+                    &[contract_hash_size, entry_point_name_size, args_size],
+                )?;
                 scoped_instrumenter
                     .add_property("entry_point_name_size", entry_point_name_size.to_string());
                 scoped_instrumenter.add_property("args_size", args_size.to_string());
@@ -542,6 +791,15 @@ where
                     args_size,
                     result_size_ptr,
                 ): (_, _, _, _, _, u32, _, u32, _) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.call_versioned_contract,
+                    &[
+                        contract_package_hash_size,
+                        contract_package_size,
+                        entry_point_name_size,
+                        args_size,
+                    ],
+                )?;
 
                 scoped_instrumenter
                     .add_property("entry_point_name_size", entry_point_name_size.to_string());
@@ -582,6 +840,10 @@ where
                 // args(1) = size of name of the host runtime arg
                 // args(2) = pointer to a argument size (output)
                 let (name_ptr, name_size, size_ptr): (u32, u32, u32) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.get_named_arg_size,
+                    &[name_size],
+                )?;
                 scoped_instrumenter.add_property("name_size", name_size.to_string());
                 let ret = self.get_named_arg_size(name_ptr, name_size as usize, size_ptr)?;
                 Ok(Some(RuntimeValue::I32(api_error::i32_from(ret))))
@@ -594,6 +856,10 @@ where
                 // args(3) = size of available data under output pointer
                 let (name_ptr, name_size, dest_ptr, dest_size): (u32, u32, u32, u32) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.get_named_arg,
+                    &[name_size, dest_size],
+                )?;
                 scoped_instrumenter.add_property("name_size", name_size.to_string());
                 scoped_instrumenter.add_property("dest_size", dest_size.to_string());
                 let ret =
@@ -608,6 +874,10 @@ where
                 // args(3) = size of serialized group label
                 let (package_key_ptr, package_key_size, label_ptr, label_size): (_, _, _, u32) =
                     Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.remove_contract_user_group,
+                    &[package_key_size, label_size],
+                )?;
                 scoped_instrumenter.add_property("label_size", label_size.to_string());
                 let package_key = self.t_from_mem(package_key_ptr, package_key_size)?;
                 let label: Group = self.t_from_mem(label_ptr, label_size)?;
@@ -629,6 +899,10 @@ where
                     u32,
                     _,
                 ) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.provision_contract_user_group_uref,
+                    &[package_size, label_size],
+                )?;
                 scoped_instrumenter.add_property("label_size", label_size.to_string());
                 let ret = self.provision_contract_user_group_uref(
                     package_ptr,
@@ -655,6 +929,10 @@ where
                     _,
                     u32,
                 ) = Args::parse(args)?;
+                self.charge_host_function_call(
+                    host_function_costs.remove_contract_user_group_urefs,
+                    &[package_size, label_size, urefs_size],
+                )?;
                 scoped_instrumenter.add_property("label_size", label_size.to_string());
                 scoped_instrumenter.add_property("urefs_size", urefs_size.to_string());
                 let ret = self.remove_contract_user_group_urefs(
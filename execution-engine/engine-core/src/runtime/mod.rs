@@ -1,4 +1,5 @@
 mod args;
+mod crypto;
 mod externals;
 mod mint_internal;
 mod proof_of_stake_internal;
@@ -6,10 +7,12 @@ mod scoped_instrumenter;
 mod standard_payment_internal;
 
 use std::{
+    cell::RefCell,
     cmp,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     convert::TryFrom,
     iter::IntoIterator,
+    rc::Rc,
 };
 
 use itertools::Itertools;
@@ -17,7 +20,9 @@ use parity_wasm::elements::Module;
 use wasmi::{ImportsBuilder, MemoryRef, ModuleInstance, ModuleRef, Trap, TrapKind};
 
 use ::mint::Mint;
-use engine_shared::{account::Account, gas::Gas, stored_value::StoredValue};
+use engine_shared::{
+    account::Account, gas::Gas, host_function_costs::HostFunctionCost, stored_value::StoredValue,
+};
 use engine_storage::{global_state::StateReader, protocol_data::ProtocolData};
 use proof_of_stake::ProofOfStake;
 use standard_payment::StandardPayment;
@@ -35,7 +40,10 @@ use types::{
 };
 
 use crate::{
-    engine_state::{system_contract_cache::SystemContractCache, EngineConfig},
+    engine_state::{
+        execution_trace::ExecutionTrace, module_cache::ModuleCache,
+        system_contract_cache::SystemContractCache, EngineConfig,
+    },
     execution::Error,
     resolvers::{create_module_resolver, memory_resolver::MemoryResolver},
     runtime_context::{self, RuntimeContext},
@@ -44,13 +52,20 @@ use crate::{
 use contracts::{ContractVersion, ContractVersions, DisabledVersions, Groups, NamedKeys};
 use scoped_instrumenter::ScopedInstrumenter;
 
+pub(crate) use self::proof_of_stake_internal::{BONDING_KEY, UNBONDING_KEY};
+
 pub struct Runtime<'a, R> {
     system_contract_cache: SystemContractCache,
+    module_cache: ModuleCache,
     config: EngineConfig,
     memory: MemoryRef,
     module: Module,
     host_buffer: Option<CLValue>,
     context: RuntimeContext<'a, R>,
+    /// The host calls recorded so far, shared with the runtimes of any called contracts.  `None`
+    /// unless tracing is enabled in the `config`.
+    trace: Option<Rc<RefCell<ExecutionTrace>>>,
+    call_depth: usize,
 }
 
 /// Rename function called `name` in the `module` to `call`.
@@ -1352,20 +1367,37 @@ where
     pub fn new(
         config: EngineConfig,
         system_contract_cache: SystemContractCache,
+        module_cache: ModuleCache,
         memory: MemoryRef,
         module: Module,
         context: RuntimeContext<'a, R>,
     ) -> Self {
+        let trace = if config.trace_host_calls() {
+            Some(Default::default())
+        } else {
+            None
+        };
         Runtime {
             config,
             system_contract_cache,
+            module_cache,
             memory,
             module,
             host_buffer: None,
             context,
+            trace,
+            call_depth: 0,
         }
     }
 
+    /// Returns the host calls recorded so far, which is empty unless tracing is enabled.
+    pub fn trace(&self) -> ExecutionTrace {
+        self.trace
+            .as_ref()
+            .map(|trace| trace.borrow().clone())
+            .unwrap_or_default()
+    }
+
     pub fn memory(&self) -> &MemoryRef {
         &self.memory
     }
@@ -1408,6 +1440,28 @@ where
         }
     }
 
+    /// Charges for a call to a host function, given the sizes of the buffers passed to it as input.
+    fn charge_host_function_call(
+        &mut self,
+        cost: HostFunctionCost,
+        input_sizes: &[u32],
+    ) -> Result<(), Trap> {
+        let input_size = input_sizes.iter().map(|size| *size as usize).sum();
+        self.gas(cost.calculate(input_size))
+    }
+
+    /// Charges for growing global state by `byte_size` bytes, at the per-byte storage cost of the
+    /// current protocol version.
+    fn charge_storage(&mut self, byte_size: usize) -> Result<(), Error> {
+        let storage_per_byte = self.context.protocol_data().wasm_costs().storage_per_byte;
+        let cost = Gas::new(U512::from(storage_per_byte) * U512::from(byte_size));
+        if self.charge_gas(cost) {
+            Ok(())
+        } else {
+            Err(Error::GasLimit)
+        }
+    }
+
     fn bytes_from_mem(&self, ptr: u32, size: usize) -> Result<Vec<u8>, Error> {
         self.memory.get(ptr, size).map_err(Into::into)
     }
@@ -1535,9 +1589,76 @@ where
     ) -> Result<(), Trap> {
         let name = self.string_from_mem(name_ptr, name_size)?;
         let key = self.key_from_mem(key_ptr, key_size)?;
+        // Named keys are stored with the account or contract, so only a new name, or a longer key
+        // under an existing name, grows global state.
+        let growth = match self.context.named_keys_get(&name) {
+            Some(existing_key) => key
+                .serialized_length()
+                .saturating_sub(existing_key.serialized_length()),
+            None => name.serialized_length() + key.serialized_length(),
+        };
+        self.charge_storage(growth)?;
         self.context.put_key(name, key).map_err(Into::into)
     }
 
+    /// Records an event emitted by the contract.
+    fn emit_event(
+        &mut self,
+        name_ptr: u32,
+        name_size: u32,
+        value_ptr: u32,
+        value_size: u32,
+    ) -> Result<(), Trap> {
+        let name = self.string_from_mem(name_ptr, name_size)?;
+        let value = self.cl_value_from_mem(value_ptr, value_size)?;
+        self.context.emit_event(name, value);
+        Ok(())
+    }
+
+    /// Hashes the input in Wasm memory with `hash_function`, after charging `cost` for it, and
+    /// writes the digest to the output buffer.
+    fn hash(
+        &mut self,
+        cost: HostFunctionCost,
+        hash_function: fn(&[u8]) -> [u8; crypto::DIGEST_LENGTH],
+        in_ptr: u32,
+        in_size: u32,
+        out_ptr: u32,
+        out_size: u32,
+    ) -> Result<Result<(), ApiError>, Trap> {
+        self.gas(cost.calculate(in_size as usize))?;
+        if (out_size as usize) < crypto::DIGEST_LENGTH {
+            return Ok(Err(ApiError::BufferTooSmall));
+        }
+        let input = self.bytes_from_mem(in_ptr, in_size as usize)?;
+        let digest = hash_function(&input);
+        self.memory
+            .set(out_ptr, &digest)
+            .map_err(|error| Error::Interpreter(error.into()))?;
+        Ok(Ok(()))
+    }
+
+    /// Checks a signature of a message in Wasm memory with `verify`, after charging `cost` for
+    /// it, and returns whether it is valid.
+    #[allow(clippy::too_many_arguments)]
+    fn verify_signature(
+        &mut self,
+        cost: HostFunctionCost,
+        verify: fn(&[u8], &[u8], &[u8]) -> bool,
+        public_key_ptr: u32,
+        public_key_size: u32,
+        message_ptr: u32,
+        message_size: u32,
+        signature_ptr: u32,
+        signature_size: u32,
+    ) -> Result<bool, Trap> {
+        self.gas(cost.calculate(message_size as usize))?;
+        let public_key = self.bytes_from_mem(public_key_ptr, public_key_size as usize)?;
+        let message = self.bytes_from_mem(message_ptr, message_size as usize)?;
+        let signature = self.bytes_from_mem(signature_ptr, signature_size as usize)?;
+        Ok(verify(&public_key, &message, &signature))
+    }
+
     fn remove_key(&mut self, name_ptr: u32, name_size: u32) -> Result<(), Trap> {
         let name = self.string_from_mem(name_ptr, name_size)?;
         self.context.remove_key(&name)?;
@@ -1816,6 +1937,7 @@ where
         let mut runtime = Runtime::new(
             self.config,
             SystemContractCache::clone(&self.system_contract_cache),
+            ModuleCache::clone(&self.module_cache),
             self.memory.clone(),
             self.module.clone(),
             runtime_context,
@@ -2106,7 +2228,23 @@ where
             };
             match maybe_module {
                 Some(module) => module,
-                None => parity_wasm::deserialize_buffer(contract_wasm.bytes())?,
+                None => {
+                    let contract_wasm_hash = contract.contract_wasm_hash();
+                    let wasm_costs = *self.context.protocol_data().wasm_costs();
+                    match self.module_cache.get(contract_wasm_hash, wasm_costs) {
+                        Some(module) => module,
+                        None => {
+                            let module: Module =
+                                parity_wasm::deserialize_buffer(contract_wasm.bytes())?;
+                            self.module_cache.insert(
+                                contract_wasm_hash,
+                                wasm_costs,
+                                module.clone(),
+                            );
+                            module
+                        }
+                    }
+                }
             }
         };
 
@@ -2124,6 +2262,8 @@ where
 
         let system_contract_cache = SystemContractCache::clone(&self.system_contract_cache);
 
+        let module_cache = ModuleCache::clone(&self.module_cache);
+
         let config = self.config;
 
         let host_buffer = None;
@@ -2151,11 +2291,14 @@ where
 
         let mut runtime = Runtime {
             system_contract_cache,
+            module_cache,
             config,
             memory,
             module,
             host_buffer,
             context,
+            trace: self.trace.clone(),
+            call_depth: self.call_depth + 1,
         };
 
         let result = instance.invoke_export(entry_point_name, &[], &mut runtime);
@@ -2351,6 +2494,8 @@ where
         let key = Key::Hash(addr);
         let (stored_value, access_key) = self.create_contract_value()?;
 
+        let growth = self.context.storage_growth(&key, &stored_value)?;
+        self.charge_storage(growth)?;
         self.context.state().borrow_mut().write(key, stored_value);
         Ok((addr, access_key.addr()))
     }
@@ -2479,20 +2624,32 @@ where
 
         let insert_contract_result = contract_package.insert_contract_version(major, contract_hash);
 
+        let contract_wasm = StoredValue::ContractWasm(contract_wasm);
+        let contract = StoredValue::Contract(contract);
+        let contract_package = StoredValue::ContractPackage(contract_package);
+        let growth = self
+            .context
+            .storage_growth(&contract_wasm_key, &contract_wasm)?
+            + self.context.storage_growth(&contract_key, &contract)?
+            + self
+                .context
+                .storage_growth(&contract_package_key, &contract_package)?;
+        self.charge_storage(growth)?;
+
         self.context
             .state()
             .borrow_mut()
-            .write(contract_wasm_key, StoredValue::ContractWasm(contract_wasm));
+            .write(contract_wasm_key, contract_wasm);
 
         self.context
             .state()
             .borrow_mut()
-            .write(contract_key, StoredValue::Contract(contract));
+            .write(contract_key, contract);
 
-        self.context.state().borrow_mut().write(
-            contract_package_key,
-            StoredValue::ContractPackage(contract_package),
-        );
+        self.context
+            .state()
+            .borrow_mut()
+            .write(contract_package_key, contract_package);
 
         // return contract key to caller
         {
@@ -2564,7 +2721,10 @@ where
     /// access_rights set.
     fn new_uref(&mut self, uref_ptr: u32, value_ptr: u32, value_size: u32) -> Result<(), Trap> {
         let cl_value = self.cl_value_from_mem(value_ptr, value_size)?; // read initial value from memory
-        let uref = self.context.new_uref(StoredValue::CLValue(cl_value))?;
+        let value = StoredValue::CLValue(cl_value);
+        // The new URef is not stored yet, so the whole of its key and value is charged for.
+        self.charge_storage(Key::max_serialized_length() + value.serialized_length())?;
+        let uref = self.context.new_uref(value)?;
         self.memory
             .set(uref_ptr, &uref.into_bytes().map_err(Error::BytesRepr)?)
             .map_err(|e| Error::Interpreter(e.into()).into())
@@ -2580,9 +2740,10 @@ where
     ) -> Result<(), Trap> {
         let key = self.key_from_mem(key_ptr, key_size)?;
         let cl_value = self.cl_value_from_mem(value_ptr, value_size)?;
-        self.context
-            .write_gs(key, StoredValue::CLValue(cl_value))
-            .map_err(Into::into)
+        let value = StoredValue::CLValue(cl_value);
+        let growth = self.context.storage_growth(&key, &value)?;
+        self.charge_storage(growth)?;
+        self.context.write_gs(key, value).map_err(Into::into)
     }
 
     /// Writes `value` under a key derived from `key` in the "local cluster" of
@@ -2596,11 +2757,29 @@ where
     ) -> Result<(), Trap> {
         let key_bytes = self.bytes_from_mem(key_ptr, key_size as usize)?;
         let cl_value = self.cl_value_from_mem(value_ptr, value_size)?;
+        let local_key = runtime_context::local_key(&key_bytes)?;
+        let growth = self
+            .context
+            .storage_growth(&local_key, &StoredValue::CLValue(cl_value.clone()))?;
+        self.charge_storage(growth)?;
         self.context
             .write_ls(&key_bytes, cl_value)
             .map_err(Into::into)
     }
 
+    /// Removes the value under `key` from GlobalState
+    fn remove(&mut self, key_ptr: u32, key_size: u32) -> Result<(), Trap> {
+        let key = self.key_from_mem(key_ptr, key_size)?;
+        self.context.delete_gs(key).map_err(Into::into)
+    }
+
+    /// Removes the value under a key derived from `key` in the "local cluster" of
+    /// GlobalState
+    fn remove_local(&mut self, key_ptr: u32, key_size: u32) -> Result<(), Trap> {
+        let key_bytes = self.bytes_from_mem(key_ptr, key_size as usize)?;
+        self.context.delete_ls(&key_bytes).map_err(Into::into)
+    }
+
     /// Adds `value` to the cell that `key` points at.
     fn add(
         &mut self,
@@ -2611,9 +2790,13 @@ where
     ) -> Result<(), Trap> {
         let key = self.key_from_mem(key_ptr, key_size)?;
         let cl_value = self.cl_value_from_mem(value_ptr, value_size)?;
-        self.context
-            .add_gs(key, StoredValue::CLValue(cl_value))
-            .map_err(Into::into)
+        // The growth is only known once the value has been added, e.g. adding a `(String, Key)` to
+        // an account or contract grows its named keys, while adding to a number rarely grows it.
+        let stored_size_before = self.context.stored_size(&key)?;
+        self.context.add_gs(key, StoredValue::CLValue(cl_value))?;
+        let stored_size_after = self.context.stored_size(&key)?;
+        self.charge_storage(stored_size_after.saturating_sub(stored_size_before))?;
+        Ok(())
     }
 
     /// Reads value from the GS living under key specified by `key_ptr` and
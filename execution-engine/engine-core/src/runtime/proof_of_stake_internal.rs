@@ -1,7 +1,4 @@
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    fmt::Write,
-};
+use std::{collections::BTreeSet, fmt::Write};
 
 use engine_shared::stored_value::StoredValue;
 use engine_storage::global_state::StateReader;
@@ -15,13 +12,15 @@ use types::{
 
 use crate::{execution, runtime::Runtime};
 
-const BONDING_KEY: [u8; 32] = {
+/// The key under which the bonding queue is stored.
+pub(crate) const BONDING_KEY: [u8; 32] = {
     let mut result = [0; 32];
     result[31] = 1;
     result
 };
 
-const UNBONDING_KEY: [u8; 32] = {
+/// The key under which the unbonding queue is stored.
+pub(crate) const UNBONDING_KEY: [u8; 32] = {
     let mut result = [0; 32];
     result[31] = 2;
     result
@@ -143,33 +142,11 @@ where
     R::Error: Into<execution::Error>,
 {
     fn read(&self) -> Result<Stakes, Error> {
-        let mut stakes = BTreeMap::new();
-        for (name, _) in self.context.named_keys().iter() {
-            let mut split_name = name.split('_');
-            if Some("v") != split_name.next() {
-                continue;
-            }
-            let hex_key = split_name
-                .next()
-                .ok_or(Error::StakesKeyDeserializationFailed)?;
-            if hex_key.len() != 64 {
-                return Err(Error::StakesKeyDeserializationFailed);
-            }
-            let mut key_bytes = [0u8; 32];
-            let _bytes_written = base16::decode_slice(hex_key, &mut key_bytes)
-                .map_err(|_| Error::StakesKeyDeserializationFailed)?;
-            debug_assert!(_bytes_written == key_bytes.len());
-            let pub_key = AccountHash::new(key_bytes);
-            let balance = split_name
-                .next()
-                .and_then(|b| U512::from_dec_str(b).ok())
-                .ok_or(Error::StakesDeserializationFailed)?;
-            stakes.insert(pub_key, balance);
-        }
-        if stakes.is_empty() {
+        let stakes = Stakes::from_strings(self.context.named_keys().keys().map(String::as_str))?;
+        if stakes.0.is_empty() {
             return Err(Error::StakesNotFound);
         }
-        Ok(Stakes(stakes))
+        Ok(stakes)
     }
 
     fn write(&mut self, stakes: &Stakes) {
@@ -20,7 +20,7 @@ use types::{
         AccountHash, ActionType, AddKeyFailure, RemoveKeyFailure, SetThresholdFailure,
         UpdateKeyFailure, Weight,
     },
-    bytesrepr,
+    bytesrepr::{self, ToBytes},
     contracts::NamedKeys,
     AccessRights, BlockTime, CLType, CLValue, Contract, ContractPackage, ContractPackageHash,
     EntryPointAccess, EntryPointType, Key, Phase, ProtocolVersion, RuntimeArgs, URef,
@@ -28,7 +28,7 @@ use types::{
 };
 
 use crate::{
-    engine_state::execution_effect::ExecutionEffect,
+    engine_state::execution_effect::{Event, ExecutionEffect},
     execution::{AddressGenerator, Error},
     tracking_copy::{AddResult, TrackingCopy},
     Address,
@@ -81,6 +81,19 @@ pub fn validate_entry_point_access_with(
     Ok(())
 }
 
+/// Returns the key in the "local cluster" of global state derived from `key_bytes`.
+pub fn local_key(key_bytes: &[u8]) -> Result<Key, Error> {
+    let actual_length = key_bytes.len();
+    if actual_length != KEY_HASH_LENGTH {
+        return Err(Error::InvalidKeyLength {
+            actual: actual_length,
+            expected: KEY_HASH_LENGTH,
+        });
+    }
+    let hash: [u8; KEY_HASH_LENGTH] = key_bytes.try_into().unwrap();
+    Ok(hash.into())
+}
+
 /// Holds information specific to the deployed contract.
 pub struct RuntimeContext<'a, R> {
     tracking_copy: Rc<RefCell<TrackingCopy<R>>>,
@@ -337,15 +350,7 @@ where
     }
 
     pub fn read_ls(&mut self, key_bytes: &[u8]) -> Result<Option<CLValue>, Error> {
-        let actual_length = key_bytes.len();
-        if actual_length != KEY_HASH_LENGTH {
-            return Err(Error::InvalidKeyLength {
-                actual: actual_length,
-                expected: KEY_HASH_LENGTH,
-            });
-        }
-        let hash: [u8; KEY_HASH_LENGTH] = key_bytes.try_into().unwrap();
-        let key: Key = hash.into();
+        let key = local_key(key_bytes)?;
         let maybe_stored_value = self
             .tracking_copy
             .borrow_mut()
@@ -360,20 +365,56 @@ where
     }
 
     pub fn write_ls(&mut self, key_bytes: &[u8], cl_value: CLValue) -> Result<(), Error> {
-        let actual_length = key_bytes.len();
-        if actual_length != KEY_HASH_LENGTH {
-            return Err(Error::InvalidKeyLength {
-                actual: actual_length,
-                expected: KEY_HASH_LENGTH,
-            });
-        }
-        let hash: [u8; KEY_HASH_LENGTH] = key_bytes.try_into().unwrap();
+        let key = local_key(key_bytes)?;
         self.tracking_copy
             .borrow_mut()
-            .write(hash.into(), StoredValue::CLValue(cl_value));
+            .write(key, StoredValue::CLValue(cl_value));
         Ok(())
     }
 
+    /// Removes the value under a key derived from `key_bytes` in the "local cluster" of global
+    /// state.
+    ///
+    /// Local keys have no owner, so `key_bytes` must be the address of a URef which the current
+    /// context may write to.  This keeps contracts, the deploy registry and the local values of
+    /// other contexts out of reach.
+    pub fn delete_ls(&mut self, key_bytes: &[u8]) -> Result<(), Error> {
+        let key = local_key(key_bytes)?;
+        let address: Address = key_bytes.try_into().unwrap();
+        self.validate_uref(&URef::new(address, AccessRights::WRITE))?;
+        self.tracking_copy.borrow_mut().delete(key);
+        Ok(())
+    }
+
+    /// Returns the number of bytes by which writing `value` under `key` grows global state: the
+    /// serialized size of both if nothing is stored under `key`, otherwise the amount by which
+    /// `value` is larger than the value it replaces.  The existing value is not recorded as read.
+    pub fn storage_growth(&mut self, key: &Key, value: &StoredValue) -> Result<usize, Error> {
+        let maybe_existing_value = self
+            .tracking_copy
+            .borrow_mut()
+            .peek(self.correlation_id, key)
+            .map_err(Into::into)?;
+        let growth = match maybe_existing_value {
+            Some(existing_value) => value
+                .serialized_length()
+                .saturating_sub(existing_value.serialized_length()),
+            None => key.serialized_length() + value.serialized_length(),
+        };
+        Ok(growth)
+    }
+
+    /// Returns the serialized size of the value under `key`, or zero if there is none.  The value
+    /// is not recorded as read.
+    pub fn stored_size(&mut self, key: &Key) -> Result<usize, Error> {
+        let maybe_value = self
+            .tracking_copy
+            .borrow_mut()
+            .peek(self.correlation_id, key)
+            .map_err(Into::into)?;
+        Ok(maybe_value.map_or(0, |value| value.serialized_length()))
+    }
+
     pub fn read_gs(&mut self, key: &Key) -> Result<Option<StoredValue>, Error> {
         self.validate_readable(key)?;
         self.validate_key(key)?;
@@ -422,6 +463,15 @@ where
         Ok(())
     }
 
+    /// Removes the value under `key` from global state.  Requires the same access as writing to
+    /// `key`.
+    pub fn delete_gs(&mut self, key: Key) -> Result<(), Error> {
+        self.validate_writeable(&key)?;
+        self.validate_key(&key)?;
+        self.tracking_copy.borrow_mut().delete(key);
+        Ok(())
+    }
+
     pub fn read_account(&mut self, key: &Key) -> Result<Option<StoredValue>, Error> {
         if let Key::Account(_) = key {
             self.validate_key(key)?;
@@ -484,6 +534,13 @@ where
         self.tracking_copy.borrow_mut().effect()
     }
 
+    /// Records an event emitted by the current contract.
+    pub fn emit_event(&mut self, name: String, value: CLValue) {
+        self.tracking_copy
+            .borrow_mut()
+            .emit_event(Event::new(name, value));
+    }
+
     /// Validates whether keys used in the `value` are not forged.
     fn validate_value(&self, value: &StoredValue) -> Result<(), Error> {
         match value {
@@ -23,6 +23,7 @@ use types::{
     account::{
         AccountHash, ActionType, AddKeyFailure, RemoveKeyFailure, SetThresholdFailure, Weight,
     },
+    bytesrepr::ToBytes,
     contracts::NamedKeys,
     AccessRights, BlockTime, CLValue, Contract, EntryPointType, EntryPoints, Key, Phase,
     ProtocolVersion, RuntimeArgs, URef, KEY_HASH_LENGTH,
@@ -30,8 +31,8 @@ use types::{
 
 use super::{Address, Error, RuntimeContext};
 use crate::{
-    execution::AddressGenerator, runtime::extract_access_rights_from_keys,
-    tracking_copy::TrackingCopy,
+    engine_state::deploy_registry, execution::AddressGenerator,
+    runtime::extract_access_rights_from_keys, tracking_copy::TrackingCopy,
 };
 
 const DEPLOY_HASH: [u8; 32] = [1u8; 32];
@@ -737,6 +738,31 @@ fn should_verify_ownership_before_setting_action_threshold() {
     let _ = test(access_rights, query);
 }
 
+#[test]
+fn uref_key_deletable_valid() {
+    let mut rng = AddressGenerator::new(&DEPLOY_HASH, PHASE);
+    let uref_key = create_uref(&mut rng, AccessRights::READ_WRITE);
+    let access_rights = extract_access_rights_from_keys(vec![uref_key]);
+    let query_result = test(access_rights, |mut rc| {
+        rc.write_gs(
+            uref_key,
+            StoredValue::CLValue(CLValue::from_t(1_i32).unwrap()),
+        )?;
+        rc.delete_gs(uref_key)?;
+        rc.read_gs(&uref_key)
+    });
+    assert_eq!(query_result.expect("should delete"), None);
+}
+
+#[test]
+fn uref_key_deletable_invalid() {
+    let mut rng = AddressGenerator::new(&DEPLOY_HASH, PHASE);
+    let uref_key = create_uref(&mut rng, AccessRights::READ);
+    let access_rights = extract_access_rights_from_keys(vec![uref_key]);
+    let query_result = test(access_rights, |mut rc| rc.delete_gs(uref_key));
+    assert_invalid_access(query_result, AccessRights::WRITE);
+}
+
 #[test]
 fn can_roundtrip_key_value_pairs() {
     let access_rights = HashMap::new();
@@ -757,6 +783,70 @@ fn can_roundtrip_key_value_pairs() {
     assert!(query_result)
 }
 
+#[test]
+fn can_delete_key_value_pairs() {
+    let mut rng = AddressGenerator::new(&DEPLOY_HASH, PHASE);
+    let uref_key = create_uref(&mut rng, AccessRights::READ_WRITE);
+    let access_rights = extract_access_rights_from_keys(vec![uref_key]);
+    let query = |mut runtime_context: RuntimeContext<InMemoryGlobalStateView>| {
+        let test_key = uref_key.into_uref().expect("should be uref").addr();
+        let test_value = CLValue::from_t("test_value".to_string()).unwrap();
+
+        runtime_context
+            .write_ls(&test_key, test_value)
+            .expect("should write_ls");
+        runtime_context
+            .delete_ls(&test_key)
+            .expect("should delete_ls");
+
+        let result = runtime_context.read_ls(&test_key).expect("should read_ls");
+        let transform = runtime_context
+            .effect()
+            .transforms
+            .get(&Key::Hash(test_key))
+            .cloned();
+
+        Ok(result.is_none() && transform == Some(Transform::Delete))
+    };
+    let query_result = test(access_rights, query).expect("should be ok");
+    assert!(query_result)
+}
+
+#[test]
+fn local_key_deletable_invalid() {
+    let mut rng = AddressGenerator::new(&DEPLOY_HASH, PHASE);
+    let uref_key = create_uref(&mut rng, AccessRights::READ);
+    let access_rights = extract_access_rights_from_keys(vec![uref_key]);
+    let address = uref_key.into_uref().expect("should be uref").addr();
+    let query_result = test(access_rights, |mut rc| rc.delete_ls(&address));
+    assert_forged_reference(query_result);
+}
+
+#[test]
+fn session_cannot_delete_contract_or_deploy_registry_entry() {
+    let mut rng = rand::thread_rng();
+    let contract_key = random_contract_key(&mut rng);
+    let registry_key = deploy_registry::deploy_registry_key(&[2u8; 32]);
+    let query_result = test(HashMap::new(), |mut rc| {
+        let state = rc.state();
+        state
+            .borrow_mut()
+            .write(contract_key, StoredValue::Contract(Contract::default()));
+        state.borrow_mut().write(
+            registry_key,
+            deploy_registry::deploy_registry_value(BlockTime::new(0)),
+        );
+
+        for key in &[contract_key, registry_key] {
+            let address = key.into_hash().expect("should be hash");
+            assert_forged_reference(rc.delete_ls(&address));
+            assert!(rc.read_gs(key)?.is_some());
+        }
+        Ok(())
+    });
+    query_result.expect("should read");
+}
+
 #[test]
 fn remove_uref_works() {
     // Test that `remove_uref` removes Key from both ephemeral representation
@@ -831,3 +921,31 @@ fn validate_valid_purse_of_an_account() {
     let purse = URef::new([53; 32], AccessRights::READ_ADD_WRITE);
     assert!(runtime_context.validate_uref(&purse).is_err());
 }
+
+#[test]
+fn storage_growth_is_the_increase_in_size() {
+    let key = Key::Hash([3u8; 32]);
+    let short_value = StoredValue::CLValue(CLValue::from_t(vec![1u8; 2]).unwrap());
+    let long_value = StoredValue::CLValue(CLValue::from_t(vec![1u8; 10]).unwrap());
+
+    let query_result = test(HashMap::new(), |mut rc| {
+        // Nothing is stored under the key yet, so both the key and the value are new
+        assert_eq!(
+            rc.storage_growth(&key, &short_value)?,
+            key.serialized_length() + short_value.serialized_length()
+        );
+        rc.state().borrow_mut().write(key, short_value.clone());
+
+        assert_eq!(
+            rc.storage_growth(&key, &long_value)?,
+            long_value.serialized_length() - short_value.serialized_length()
+        );
+        rc.state().borrow_mut().write(key, long_value.clone());
+
+        assert_eq!(rc.storage_growth(&key, &short_value)?, 0);
+        // The values which are replaced are not recorded as read
+        assert!(rc.state().borrow().reads().is_empty());
+        Ok(())
+    });
+    query_result.expect("should find storage growth");
+}
@@ -5,9 +5,10 @@ pub(self) mod meter;
 mod tests;
 
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     convert::From,
     iter,
+    sync::{Arc, Mutex},
 };
 
 use linked_hash_map::LinkedHashMap;
@@ -19,17 +20,31 @@ use engine_shared::{
     transform::{self, Transform},
     TypeMismatch,
 };
-use engine_storage::global_state::StateReader;
-use types::{bytesrepr, CLType, CLValueError, Key};
+use engine_storage::{
+    global_state::{KeyRange, StateReader},
+    trie::{TrieAbsenceProof, TrieMerkleProof},
+};
+use types::{
+    bytesrepr::{self, ToBytes},
+    CLType, CLValueError, Key,
+};
 
-use crate::engine_state::{execution_effect::ExecutionEffect, op::Op};
+use crate::engine_state::{
+    execution_effect::{Event, ExecutionEffect},
+    op::Op,
+};
 
 pub use self::ext::TrackingCopyExt;
 use self::meter::{heap_meter::HeapSize, Meter};
 
 #[derive(Debug)]
 pub enum TrackingCopyQueryResult {
-    Success(StoredValue),
+    Success {
+        value: StoredValue,
+        /// Proofs of the values read while following the query path, in the order visited.  Only
+        /// made by `TrackingCopy::query_with_proof()`.
+        proofs: Vec<TrieMerkleProof<Key, StoredValue>>,
+    },
     ValueNotFound(String),
     CircularReference(String),
 }
@@ -100,6 +115,7 @@ pub struct TrackingCopyCache<M> {
     current_cache_size: usize,
     reads_cached: LinkedHashMap<Key, StoredValue>,
     muts_cached: HashMap<Key, StoredValue>,
+    deletes_cached: HashSet<Key>,
     meter: M,
 }
 
@@ -114,6 +130,7 @@ impl<M: Meter<Key, StoredValue>> TrackingCopyCache<M> {
             current_cache_size: 0,
             reads_cached: LinkedHashMap::new(),
             muts_cached: HashMap::new(),
+            deletes_cached: HashSet::new(),
             meter,
         }
     }
@@ -136,11 +153,26 @@ impl<M: Meter<Key, StoredValue>> TrackingCopyCache<M> {
 
     /// Inserts `key` and `value` pair to Write/Add cache.
     pub fn insert_write(&mut self, key: Key, value: StoredValue) {
+        self.deletes_cached.remove(&key);
         self.muts_cached.insert(key, value);
     }
 
+    /// Marks `key` as deleted, so that it is absent regardless of the underlying state.
+    pub fn insert_delete(&mut self, key: Key) {
+        self.muts_cached.remove(&key);
+        self.deletes_cached.insert(key);
+    }
+
+    /// Returns `true` if `key` has been deleted.
+    pub fn is_deleted(&self, key: &Key) -> bool {
+        self.deletes_cached.contains(key)
+    }
+
     /// Gets value from `key` in the cache.
     pub fn get(&mut self, key: &Key) -> Option<&StoredValue> {
+        if self.is_deleted(key) {
+            return None;
+        }
         if let Some(value) = self.muts_cached.get(&key) {
             return Some(value);
         };
@@ -149,11 +181,43 @@ impl<M: Meter<Key, StoredValue>> TrackingCopyCache<M> {
     }
 }
 
+/// A thread-safe cache of values read from the global state under a single state root hash,
+/// which can be shared by the tracking copies of all deploys executed against that root.
+///
+/// Only values read from the underlying `StateReader` are cached, never a tracking copy's own
+/// writes, so it must not be shared by tracking copies reading from different state roots.
+#[derive(Clone)]
+pub struct SharedReadCache(Arc<Mutex<TrackingCopyCache<HeapSize>>>);
+
+impl SharedReadCache {
+    /// Creates an empty cache, whose least-recently-used values are invalidated once their
+    /// estimated heap size exceeds `max_cache_size`.
+    pub fn new(max_cache_size: usize) -> SharedReadCache {
+        SharedReadCache(Arc::new(Mutex::new(TrackingCopyCache::new(
+            max_cache_size,
+            HeapSize,
+        ))))
+    }
+
+    fn get(&self, key: &Key) -> Option<StoredValue> {
+        let mut cache = self.0.lock().unwrap();
+        cache.get(key).cloned()
+    }
+
+    fn insert(&self, key: Key, value: StoredValue) {
+        let mut cache = self.0.lock().unwrap();
+        cache.insert_read(key, value)
+    }
+}
+
 pub struct TrackingCopy<R> {
     reader: R,
     cache: TrackingCopyCache<HeapSize>,
+    shared_cache: Option<SharedReadCache>,
     ops: AdditiveMap<Key, Op>,
     fns: AdditiveMap<Key, Transform>,
+    reads: BTreeSet<Key>,
+    events: Vec<Event>,
 }
 
 #[derive(Debug)]
@@ -185,8 +249,22 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
             /* TODO: Should `max_cache_size`
              * be fraction of wasm memory
              * limit? */
+            shared_cache: None,
             ops: AdditiveMap::new(),
             fns: AdditiveMap::new(),
+            reads: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Creates a new `TrackingCopy` which consults `shared_cache` before reading from `reader`,
+    /// and adds the values it reads from `reader` to `shared_cache`.
+    ///
+    /// `shared_cache` must only be shared by tracking copies of the same state as `reader`.
+    pub fn with_shared_cache(reader: R, shared_cache: SharedReadCache) -> TrackingCopy<R> {
+        TrackingCopy {
+            shared_cache: Some(shared_cache),
+            ..TrackingCopy::new(reader)
         }
     }
 
@@ -210,16 +288,47 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
         TrackingCopy::new(self)
     }
 
+    /// Returns the value under `key`, recording `key` as read whether or not it is found.
     pub fn get(
         &mut self,
         correlation_id: CorrelationId,
         key: &Key,
+    ) -> Result<Option<StoredValue>, R::Error> {
+        self.reads.insert(key.normalize());
+        self.lookup(correlation_id, key)
+    }
+
+    /// Returns the value under `key` without recording it as read or accessed, e.g. to find the
+    /// size of a value which is about to be overwritten.
+    pub fn peek(
+        &mut self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<StoredValue>, R::Error> {
+        self.lookup(correlation_id, &key.normalize())
+    }
+
+    /// Returns the value under `key` without recording it as read.
+    fn lookup(
+        &mut self,
+        correlation_id: CorrelationId,
+        key: &Key,
     ) -> Result<Option<StoredValue>, R::Error> {
         if let Some(value) = self.cache.get(key) {
             return Ok(Some(value.to_owned()));
         }
+        if self.cache.is_deleted(key) {
+            return Ok(None);
+        }
+        if let Some(value) = self.shared_cache.as_ref().and_then(|cache| cache.get(key)) {
+            self.cache.insert_read(*key, value.to_owned());
+            return Ok(Some(value));
+        }
         if let Some(value) = self.reader.read(correlation_id, key)? {
             self.cache.insert_read(*key, value.to_owned());
+            if let Some(shared_cache) = self.shared_cache.as_ref() {
+                shared_cache.insert(*key, value.to_owned());
+            }
             Ok(Some(value))
         } else {
             Ok(None)
@@ -248,6 +357,15 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
         self.fns.insert_add(normalized_key, Transform::Write(value));
     }
 
+    /// Removes the value under `key`.  Deleting a key is recorded as a write, as it conflicts with
+    /// any other access to the same key.
+    pub fn delete(&mut self, key: Key) {
+        let normalized_key = key.normalize();
+        self.cache.insert_delete(normalized_key);
+        self.ops.insert_add(normalized_key, Op::Write);
+        self.fns.insert_add(normalized_key, Transform::Delete);
+    }
+
     /// Ok(None) represents missing key to which we want to "add" some value.
     /// Ok(Some(unit)) represents successful operation.
     /// Err(error) is reserved for unexpected errors when accessing global
@@ -259,7 +377,7 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
         value: StoredValue,
     ) -> Result<AddResult, R::Error> {
         let normalized_key = key.normalize();
-        let current_value = match self.get(correlation_id, &normalized_key)? {
+        let current_value = match self.lookup(correlation_id, &normalized_key)? {
             None => return Ok(AddResult::KeyNotFound(normalized_key)),
             Some(current_value) => current_value,
         };
@@ -325,8 +443,76 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
         }
     }
 
+    /// Applies `effect`, which must have been produced by executing against this `TrackingCopy`
+    /// (or a fork of it), so that subsequent reads observe it.  The ops, transforms, reads and
+    /// events of `effect` are added to those already recorded, so that `effect()` returns the
+    /// combined effect.
+    ///
+    /// Nothing is applied unless every transform can be applied.
+    pub fn apply_effect(
+        &mut self,
+        correlation_id: CorrelationId,
+        effect: &ExecutionEffect,
+    ) -> Result<AddResult, R::Error> {
+        let mut new_values = Vec::with_capacity(effect.transforms.len());
+        for (key, transform) in effect.transforms.iter() {
+            let new_value = match transform {
+                Transform::Identity => continue,
+                Transform::Write(value) => Some(value.clone()),
+                Transform::Delete => None,
+                transform => {
+                    let current_value = match self.lookup(correlation_id, key)? {
+                        None => return Ok(AddResult::KeyNotFound(*key)),
+                        Some(current_value) => current_value,
+                    };
+                    match transform.clone().apply(current_value) {
+                        Ok(new_value) => Some(new_value),
+                        Err(transform::Error::TypeMismatch(type_mismatch)) => {
+                            return Ok(AddResult::TypeMismatch(type_mismatch))
+                        }
+                        Err(transform::Error::Serialization(error)) => {
+                            return Ok(AddResult::Serialization(error))
+                        }
+                    }
+                }
+            };
+            new_values.push((*key, new_value));
+        }
+
+        for (key, new_value) in new_values {
+            match new_value {
+                Some(value) => self.cache.insert_write(key, value),
+                None => self.cache.insert_delete(key),
+            }
+        }
+        for (key, op) in effect.ops.iter() {
+            self.ops.insert_add(*key, *op);
+        }
+        for (key, transform) in effect.transforms.iter() {
+            self.fns.insert_add(*key, transform.clone());
+        }
+        self.reads.extend(effect.reads.iter().cloned());
+        self.events.extend(effect.events.iter().cloned());
+        Ok(AddResult::Success)
+    }
+
+    /// Returns the keys read so far, including those which were not found.
+    pub fn reads(&self) -> &BTreeSet<Key> {
+        &self.reads
+    }
+
+    /// Records an event emitted by a contract.
+    pub fn emit_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
     pub fn effect(&self) -> ExecutionEffect {
-        ExecutionEffect::new(self.ops.clone(), self.fns.clone())
+        ExecutionEffect {
+            ops: self.ops.clone(),
+            transforms: self.fns.clone(),
+            reads: self.reads.clone(),
+            events: self.events.clone(),
+        }
     }
 
     /// Calling `query()` avoids calling into `self.cache`, so this will not return any values
@@ -336,19 +522,59 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
     /// The intent is that `query()` is only used to satisfy `QueryRequest`s made to the server.
     /// Other EE internal use cases should call `read()` or `get()` in order to retrieve cached
     /// values.
+    ///
+    /// The proofs of a successful result are empty; use `query_with_proof()` to have them made.
     pub fn query(
         &self,
         correlation_id: CorrelationId,
         base_key: Key,
         path: &[String],
     ) -> Result<TrackingCopyQueryResult, R::Error> {
+        self.query_by(base_key, path, |key| self.reader.read(correlation_id, key))
+    }
+
+    /// As `query()`, but a successful result also holds proofs of the values read while following
+    /// the path, made against the state root of the underlying reader.
+    pub fn query_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        base_key: Key,
+        path: &[String],
+    ) -> Result<TrackingCopyQueryResult, R::Error> {
+        let mut proofs = Vec::new();
+        let result = self.query_by(base_key, path, |key| {
+            let maybe_proof = self.reader.read_with_proof(correlation_id, key)?;
+            Ok(maybe_proof.map(|proof| {
+                let stored_value = proof.value().clone();
+                proofs.push(proof);
+                stored_value
+            }))
+        })?;
+        match result {
+            TrackingCopyQueryResult::Success { value, .. } => {
+                Ok(TrackingCopyQueryResult::Success { value, proofs })
+            }
+            result => Ok(result),
+        }
+    }
+
+    /// Follows `path` from `base_key`, reading each visited key with `read`.
+    fn query_by<F>(
+        &self,
+        base_key: Key,
+        path: &[String],
+        mut read: F,
+    ) -> Result<TrackingCopyQueryResult, R::Error>
+    where
+        F: FnMut(&Key) -> Result<Option<StoredValue>, R::Error>,
+    {
         let mut query = Query::new(base_key, path);
 
         loop {
             if !query.visited_keys.insert(query.current_key) {
                 return Ok(query.into_circular_ref_result());
             }
-            let stored_value = match self.reader.read(correlation_id, &query.current_key)? {
+            let stored_value = match read(&query.current_key)? {
                 None => {
                     return Ok(query.into_not_found_result("Failed to find base key"));
                 }
@@ -356,7 +582,10 @@ impl<R: StateReader<Key, StoredValue>> TrackingCopy<R> {
             };
 
             if query.unvisited_names.is_empty() {
-                return Ok(TrackingCopyQueryResult::Success(stored_value));
+                return Ok(TrackingCopyQueryResult::Success {
+                    value: stored_value,
+                    proofs: Vec::new(),
+                });
             }
 
             match stored_value {
@@ -420,10 +649,90 @@ impl<R: StateReader<Key, StoredValue>> StateReader<Key, StoredValue> for &Tracki
         if let Some(value) = self.cache.muts_cached.get(key) {
             return Ok(Some(value.to_owned()));
         }
+        if self.cache.is_deleted(key) {
+            return Ok(None);
+        }
         if let Some(value) = self.reader.read(correlation_id, key)? {
             Ok(Some(value))
         } else {
             Ok(None)
         }
     }
+
+    /// Proofs are made against the state root of the underlying reader, so values written or
+    /// mutated in this `TrackingCopy` are not reflected in the result.
+    fn read_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieMerkleProof<Key, StoredValue>>, Self::Error> {
+        self.reader.read_with_proof(correlation_id, key)
+    }
+
+    /// As with `read_with_proof()`, values written or mutated in this `TrackingCopy` are not
+    /// reflected in the result.
+    fn prove_absence(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieAbsenceProof<Key, StoredValue>>, Self::Error> {
+        self.reader.prove_absence(correlation_id, key)
+    }
+
+    /// Values written or deleted in this `TrackingCopy` are reflected in the result.
+    fn read_range(
+        &self,
+        correlation_id: CorrelationId,
+        range: &KeyRange,
+        limit: usize,
+    ) -> Result<Vec<(Key, StoredValue)>, Self::Error> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Keyed by serialized key, so that iteration order matches that of the reader.
+        let mut pairs: BTreeMap<Vec<u8>, (Key, StoredValue)> = BTreeMap::new();
+        let mut page_range = range.clone();
+        let last_key_bytes = loop {
+            let page = self.reader.read_range(correlation_id, &page_range, limit)?;
+            let is_exhausted = page.len() < limit;
+            let last_key_bytes = page.last().map(|(key, _)| key_to_bytes(key));
+            for (key, value) in page {
+                if self.cache.is_deleted(&key) {
+                    continue;
+                }
+                let value = self.cache.muts_cached.get(&key).cloned().unwrap_or(value);
+                pairs.insert(key_to_bytes(&key), (key, value));
+            }
+            match last_key_bytes {
+                Some(last_key_bytes) if !is_exhausted && pairs.len() < limit => {
+                    page_range = page_range.after(&last_key_bytes)
+                }
+                // Nothing past the last key read from the reader is known.
+                Some(last_key_bytes) if !is_exhausted => break Some(last_key_bytes),
+                _ => break None,
+            }
+        };
+
+        for (key, value) in self.cache.muts_cached.iter() {
+            let key_bytes = key_to_bytes(key);
+            let is_known = match &last_key_bytes {
+                Some(last_key_bytes) => key_bytes <= *last_key_bytes,
+                None => true,
+            };
+            if is_known && range.contains(&key_bytes) {
+                pairs.insert(key_bytes, (*key, value.to_owned()));
+            }
+        }
+
+        Ok(pairs
+            .into_iter()
+            .map(|(_, pair)| pair)
+            .take(limit)
+            .collect())
+    }
+}
+
+fn key_to_bytes(key: &Key) -> Vec<u8> {
+    key.to_bytes().expect("should serialize key")
 }
@@ -1,26 +1,35 @@
-use std::{cell::Cell, iter, rc::Rc};
+use std::{cell::Cell, collections::BTreeSet, iter, rc::Rc};
 
 use assert_matches::assert_matches;
 use proptest::prelude::*;
 
 use engine_shared::{
     account::{Account, AssociatedKeys},
+    additive_map::AdditiveMap,
     newtypes::CorrelationId,
     stored_value::{gens::stored_value_arb, StoredValue},
     transform::Transform,
 };
-use engine_storage::global_state::{in_memory::InMemoryGlobalState, StateProvider, StateReader};
+use engine_storage::{
+    global_state::{in_memory::InMemoryGlobalState, KeyRange, StateProvider, StateReader},
+    trie::{TrieAbsenceProof, TrieMerkleProof},
+};
 use types::{
     account::{AccountHash, Weight, ACCOUNT_HASH_LENGTH},
+    bytesrepr::ToBytes,
     contracts::NamedKeys,
     gens::*,
     AccessRights, CLValue, Contract, EntryPoints, Key, ProtocolVersion, URef,
 };
 
 use super::{
-    meter::count_meter::Count, AddResult, TrackingCopy, TrackingCopyCache, TrackingCopyQueryResult,
+    meter::count_meter::Count, AddResult, SharedReadCache, TrackingCopy, TrackingCopyCache,
+    TrackingCopyQueryResult,
+};
+use crate::engine_state::{
+    execution_effect::{Event, ExecutionEffect},
+    op::Op,
 };
-use crate::engine_state::op::Op;
 
 struct CountingDb {
     count: Rc<Cell<i32>>,
@@ -58,6 +67,30 @@ impl StateReader<Key, StoredValue> for CountingDb {
         self.count.set(count + 1);
         Ok(Some(value))
     }
+
+    fn read_with_proof(
+        &self,
+        _correlation_id: CorrelationId,
+        _key: &Key,
+    ) -> Result<Option<TrieMerkleProof<Key, StoredValue>>, Self::Error> {
+        Ok(None)
+    }
+
+    fn prove_absence(
+        &self,
+        _correlation_id: CorrelationId,
+        _key: &Key,
+    ) -> Result<Option<TrieAbsenceProof<Key, StoredValue>>, Self::Error> {
+        Ok(None)
+    }
+    fn read_range(
+        &self,
+        _correlation_id: CorrelationId,
+        _range: &KeyRange,
+        _limit: usize,
+    ) -> Result<Vec<(Key, StoredValue)>, Self::Error> {
+        Ok(Vec::new())
+    }
 }
 
 #[test]
@@ -91,6 +124,60 @@ fn tracking_copy_caching() {
     assert_eq!(db_value, 1);
 }
 
+#[test]
+fn tracking_copy_shared_caching() {
+    let correlation_id = CorrelationId::new();
+    let counter = Rc::new(Cell::new(0));
+    let shared_cache = SharedReadCache::new(1024 * 16);
+    let mut tc_1 = TrackingCopy::with_shared_cache(
+        CountingDb::new(Rc::clone(&counter)),
+        SharedReadCache::clone(&shared_cache),
+    );
+    let mut tc_2 = TrackingCopy::with_shared_cache(
+        CountingDb::new(Rc::clone(&counter)),
+        SharedReadCache::clone(&shared_cache),
+    );
+    let k = Key::Hash([0u8; 32]);
+
+    let zero = StoredValue::CLValue(CLValue::from_t(0_i32).unwrap());
+    let value = tc_1.read(correlation_id, &k).unwrap().unwrap();
+    assert_eq!(value, zero);
+
+    // read by the second tracking copy; should use the shared cache instead of going back to the
+    // DB, but still be recorded as a read
+    let value = tc_2.read(correlation_id, &k).unwrap().unwrap();
+    assert_eq!(value, zero);
+    assert_eq!(counter.get(), 1);
+    assert!(tc_2.reads.contains(&k));
+    assert_eq!(tc_2.ops.get(&k), Some(&Op::Read));
+}
+
+#[test]
+fn tracking_copy_shared_cache_excludes_writes() {
+    let correlation_id = CorrelationId::new();
+    let counter = Rc::new(Cell::new(0));
+    let shared_cache = SharedReadCache::new(1024 * 16);
+    let mut tc_1 = TrackingCopy::with_shared_cache(
+        CountingDb::new(Rc::clone(&counter)),
+        SharedReadCache::clone(&shared_cache),
+    );
+    let mut tc_2 = TrackingCopy::with_shared_cache(
+        CountingDb::new(Rc::clone(&counter)),
+        SharedReadCache::clone(&shared_cache),
+    );
+    let k = Key::Hash([0u8; 32]);
+
+    let zero = StoredValue::CLValue(CLValue::from_t(0_i32).unwrap());
+    let one = StoredValue::CLValue(CLValue::from_t(1_i32).unwrap());
+    tc_1.read(correlation_id, &k).unwrap();
+    tc_1.write(k, one.clone());
+    assert_eq!(tc_1.read(correlation_id, &k).unwrap(), Some(one));
+
+    // the write is only visible to the tracking copy which made it
+    assert_eq!(tc_2.read(correlation_id, &k).unwrap(), Some(zero));
+    assert_eq!(counter.get(), 1);
+}
+
 #[test]
 fn tracking_copy_read() {
     let correlation_id = CorrelationId::new();
@@ -285,6 +372,210 @@ fn tracking_copy_aw() {
     assert_eq!(tc.ops.get(&k), Some(&Op::Write));
 }
 
+#[test]
+fn tracking_copy_delete() {
+    let correlation_id = CorrelationId::new();
+    let counter = Rc::new(Cell::new(0));
+    let db = CountingDb::new(Rc::clone(&counter));
+    let mut tc = TrackingCopy::new(db);
+    let k = Key::Hash([0u8; 32]);
+
+    // reading then deleting should hide the value in the underlying state
+    let _ = tc.read(correlation_id, &k);
+    tc.delete(k);
+    assert_eq!(tc.fns.len(), 1);
+    assert_eq!(tc.fns.get(&k), Some(&Transform::Delete));
+    assert_eq!(tc.ops.len(), 1);
+    assert_eq!(tc.ops.get(&k), Some(&Op::Write));
+    assert_eq!(tc.read(correlation_id, &k), Ok(None));
+    assert_eq!((&tc).read(correlation_id, &k), Ok(None));
+
+    // adding to a deleted key should fail
+    let value = StoredValue::CLValue(CLValue::from_t(3_i32).unwrap());
+    assert_matches!(
+        tc.add(correlation_id, k, value.clone()),
+        Ok(AddResult::KeyNotFound(_))
+    );
+
+    // writing after deleting should restore the key
+    tc.write(k, value.clone());
+    assert_eq!(tc.fns.get(&k), Some(&Transform::Write(value.clone())));
+    assert_eq!(tc.read(correlation_id, &k), Ok(Some(value)));
+}
+
+#[test]
+fn tracking_copy_reads() {
+    let correlation_id = CorrelationId::new();
+    let counter = Rc::new(Cell::new(0));
+    let db = CountingDb::new(counter);
+    let mut tc = TrackingCopy::new(db);
+    let k1 = Key::Hash([1u8; 32]);
+    let k2 = Key::Hash([2u8; 32]);
+    let k3 = Key::Hash([3u8; 32]);
+    let uref = URef::new([4u8; 32], AccessRights::READ);
+
+    // reads are recorded whether or not the key is found, and under the normalized key
+    let _ = tc.read(correlation_id, &k1);
+    tc.delete(k2);
+    assert_eq!(tc.read(correlation_id, &k2), Ok(None));
+    let _ = tc.get(correlation_id, &Key::URef(uref));
+    // adding does not record a read, and neither does writing or peeking
+    let value = StoredValue::CLValue(CLValue::from_t(3_i32).unwrap());
+    assert_matches!(
+        tc.add(correlation_id, k3, value.clone()),
+        Ok(AddResult::Success)
+    );
+    tc.write(k3, value.clone());
+    assert_eq!(tc.peek(correlation_id, &k3), Ok(Some(value)));
+
+    let expected_reads: BTreeSet<Key> = vec![k1, k2, Key::URef(uref).normalize()]
+        .into_iter()
+        .collect();
+    assert_eq!(tc.reads(), &expected_reads);
+
+    let effect = tc.effect();
+    assert_eq!(effect.reads, expected_reads);
+    // `k1` was only read
+    let expected_writes: BTreeSet<Key> = vec![k2, k3].into_iter().collect();
+    assert_eq!(effect.write_set(), expected_writes);
+}
+
+#[test]
+fn tracking_copy_events() {
+    let counter = Rc::new(Cell::new(0));
+    let db = CountingDb::new(counter);
+    let mut tc = TrackingCopy::new(db);
+    let first = Event::new("first".to_string(), CLValue::from_t(1_i32).unwrap());
+    let second = Event::new("second".to_string(), CLValue::from_t(2_i32).unwrap());
+
+    tc.emit_event(first.clone());
+    tc.emit_event(second.clone());
+    assert_eq!(tc.effect().events, vec![first, second]);
+
+    // a fork only reports the events emitted through it
+    let mut fork = tc.fork();
+    assert!(fork.effect().events.is_empty());
+    let third = Event::new("third".to_string(), CLValue::from_t(3_i32).unwrap());
+    fork.emit_event(third.clone());
+    assert_eq!(fork.effect().events, vec![third]);
+    assert_eq!(tc.effect().events.len(), 2);
+}
+
+#[test]
+fn tracking_copy_apply_effect() {
+    let correlation_id = CorrelationId::new();
+    let ten = StoredValue::CLValue(CLValue::from_t(10_i32).unwrap());
+    let db = CountingDb::new_init(ten);
+    let mut tc = TrackingCopy::new(db);
+    let k1 = Key::Hash([1u8; 32]);
+    let k2 = Key::Hash([2u8; 32]);
+    let k3 = Key::Hash([3u8; 32]);
+    let value = StoredValue::CLValue(CLValue::from_t(String::from("value")).unwrap());
+
+    let first_effect = {
+        let mut fork = tc.fork();
+        let three = StoredValue::CLValue(CLValue::from_t(3_i32).unwrap());
+        assert_matches!(fork.add(correlation_id, k1, three), Ok(AddResult::Success));
+        fork.write(k2, value.clone());
+        fork.delete(k3);
+        fork.effect()
+    };
+    assert_matches!(
+        tc.apply_effect(correlation_id, &first_effect),
+        Ok(AddResult::Success)
+    );
+    assert_eq!(tc.effect(), first_effect);
+    let thirteen = StoredValue::CLValue(CLValue::from_t(13_i32).unwrap());
+    assert_eq!((&tc).read(correlation_id, &k1), Ok(Some(thirteen.clone())));
+    assert_eq!((&tc).read(correlation_id, &k2), Ok(Some(value)));
+    assert_eq!((&tc).read(correlation_id, &k3), Ok(None));
+
+    // a later effect sees, and is combined with, the earlier one
+    let second_effect = {
+        let mut fork = tc.fork();
+        assert_eq!(fork.read(correlation_id, &k1), Ok(Some(thirteen)));
+        let two = StoredValue::CLValue(CLValue::from_t(2_i32).unwrap());
+        assert_matches!(fork.add(correlation_id, k1, two), Ok(AddResult::Success));
+        fork.effect()
+    };
+    assert_matches!(
+        tc.apply_effect(correlation_id, &second_effect),
+        Ok(AddResult::Success)
+    );
+    assert_eq!(tc.effect(), first_effect.clone().then(second_effect));
+    assert_eq!(tc.fns.get(&k1), Some(&Transform::AddInt32(5)));
+    let fifteen = StoredValue::CLValue(CLValue::from_t(15_i32).unwrap());
+    assert_eq!(tc.get(correlation_id, &k1), Ok(Some(fifteen.clone())));
+
+    // an effect which cannot be applied in full is not applied at all
+    let mut transforms = AdditiveMap::new();
+    transforms.insert(k1, Transform::AddInt32(1));
+    transforms.insert(k3, Transform::AddInt32(1));
+    let missing_key_effect = ExecutionEffect::new(AdditiveMap::new(), transforms);
+    assert_matches!(
+        tc.apply_effect(correlation_id, &missing_key_effect),
+        Ok(AddResult::KeyNotFound(key)) if key == k3
+    );
+    assert_eq!(tc.get(correlation_id, &k1), Ok(Some(fifteen)));
+    assert_eq!(tc.fns.get(&k1), Some(&Transform::AddInt32(5)));
+}
+
+#[test]
+fn tracking_copy_read_range() {
+    let correlation_id = CorrelationId::new();
+    let hash_key = |byte: u8| Key::Hash([byte; 32]);
+    let value = |n: i32| StoredValue::CLValue(CLValue::from_t(n).unwrap());
+    let (gs, root_hash) = InMemoryGlobalState::from_pairs(
+        correlation_id,
+        &[
+            (hash_key(1), value(1)),
+            (hash_key(2), value(2)),
+            (hash_key(3), value(3)),
+            (hash_key(5), value(5)),
+        ],
+    )
+    .unwrap();
+    let view = gs.checkout(root_hash).unwrap().unwrap();
+    let mut tc = TrackingCopy::new(view);
+
+    tc.write(hash_key(0), value(0));
+    tc.write(hash_key(1), value(10));
+    tc.delete(hash_key(2));
+    tc.write(hash_key(4), value(4));
+    tc.write(
+        Key::URef(URef::new([1u8; 32], AccessRights::READ)),
+        value(6),
+    );
+
+    let hash_tag = hash_key(0).to_bytes().unwrap()[..1].to_vec();
+    let hashes = KeyRange::with_prefix(&hash_tag);
+    let expected = vec![
+        (hash_key(0), value(0)),
+        (hash_key(1), value(10)),
+        (hash_key(3), value(3)),
+        (hash_key(4), value(4)),
+        (hash_key(5), value(5)),
+    ];
+    assert_eq!(
+        (&tc).read_range(correlation_id, &hashes, usize::max_value()),
+        Ok(expected.clone())
+    );
+
+    // reading in pages should give the same results, including the writes between pages
+    let mut paged = Vec::new();
+    let mut page_range = hashes;
+    loop {
+        let page = (&tc).read_range(correlation_id, &page_range, 2).unwrap();
+        assert!(page.len() <= 2);
+        match page.last() {
+            Some((last_key, _)) => page_range = page_range.after(&last_key.to_bytes().unwrap()),
+            None => break,
+        }
+        paged.extend(page);
+    }
+    assert_eq!(paged, expected);
+}
+
 proptest! {
     #[test]
     fn query_empty_path(k in key_arb(), missing_key in key_arb(), v in stored_value_arb()) {
@@ -293,7 +584,7 @@ proptest! {
         let view = gs.checkout(root_hash).unwrap().unwrap();
         let tc = TrackingCopy::new(view);
         let empty_path = Vec::new();
-        if let Ok(TrackingCopyQueryResult::Success(result)) = tc.query(correlation_id, k, &empty_path) {
+        if let Ok(TrackingCopyQueryResult::Success { value: result, .. }) = tc.query(correlation_id, k, &empty_path) {
             assert_eq!(v, result);
         } else {
             panic!("Query failed when it should not have!");
@@ -333,7 +624,7 @@ proptest! {
         let view = gs.checkout(root_hash).unwrap().unwrap();
         let tc = TrackingCopy::new(view);
         let path = vec!(name.clone());
-        if let Ok(TrackingCopyQueryResult::Success(result)) = tc.query(correlation_id, contract_key, &path) {
+        if let Ok(TrackingCopyQueryResult::Success { value: result, .. }) = tc.query(correlation_id, contract_key, &path) {
             assert_eq!(v, result);
         } else {
             panic!("Query failed when it should not have!");
@@ -375,7 +666,7 @@ proptest! {
         let view = gs.checkout(root_hash).unwrap().unwrap();
         let tc = TrackingCopy::new(view);
         let path = vec!(name.clone());
-        if let Ok(TrackingCopyQueryResult::Success(result)) = tc.query(correlation_id, account_key, &path) {
+        if let Ok(TrackingCopyQueryResult::Success { value: result, .. }) = tc.query(correlation_id, account_key, &path) {
             assert_eq!(v, result);
         } else {
             panic!("Query failed when it should not have!");
@@ -435,8 +726,19 @@ proptest! {
         let path = vec!(contract_name, state_name);
 
         let result =  tc.query(correlation_id, account_key, &path);
-        if let Ok(TrackingCopyQueryResult::Success(result)) = result {
+        if let Ok(TrackingCopyQueryResult::Success { value: result, proofs }) = result {
+            assert_eq!(v, result);
+            assert!(proofs.is_empty());
+        } else {
+            panic!("Query failed when it should not have!");
+        }
+
+        let result =  tc.query_with_proof(correlation_id, account_key, &path);
+        if let Ok(TrackingCopyQueryResult::Success { value: result, proofs }) = result {
             assert_eq!(v, result);
+            let proof_keys: Vec<Key> = proofs.iter().map(|proof| *proof.key()).collect();
+            assert_eq!(proof_keys, vec![account_key, contract_key, k.normalize()]);
+            assert_eq!(proofs.last().map(|proof| proof.value()), Some(&v));
         } else {
             panic!("Query failed when it should not have!");
         }
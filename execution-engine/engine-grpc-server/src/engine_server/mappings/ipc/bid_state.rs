@@ -0,0 +1,117 @@
+use std::convert::{TryFrom, TryInto};
+
+use engine_core::engine_state::bid_state::{BidStateRequest, QueueEntry};
+use engine_shared::newtypes::BLAKE2B_DIGEST_LENGTH;
+use types::{account::AccountHash, BlockTime, U512};
+
+use crate::engine_server::{ipc, mappings::MappingError};
+
+impl TryFrom<ipc::BidStateRequest> for BidStateRequest {
+    type Error = MappingError;
+
+    fn try_from(mut request: ipc::BidStateRequest) -> Result<Self, Self::Error> {
+        let parent_state_hash = {
+            let parent_state_hash = request.get_parent_state_hash();
+            let length = parent_state_hash.len();
+            if length != BLAKE2B_DIGEST_LENGTH {
+                return Err(MappingError::InvalidStateHashLength {
+                    expected: BLAKE2B_DIGEST_LENGTH,
+                    actual: length,
+                });
+            }
+            parent_state_hash
+                .try_into()
+                .map_err(|_| MappingError::TryFromSlice)?
+        };
+
+        let protocol_version = request.take_protocol_version().into();
+
+        Ok(BidStateRequest::new(parent_state_hash, protocol_version))
+    }
+}
+
+impl From<(AccountHash, U512)> for ipc::BidState_Bid {
+    fn from((account_hash, amount): (AccountHash, U512)) -> Self {
+        let mut pb_bid = ipc::BidState_Bid::new();
+        pb_bid.set_id(account_hash.as_bytes().to_vec());
+        pb_bid.set_value(amount.into());
+        pb_bid
+    }
+}
+
+impl TryFrom<ipc::BidState_Bid> for (AccountHash, U512) {
+    type Error = MappingError;
+
+    fn try_from(mut pb_bid: ipc::BidState_Bid) -> Result<Self, Self::Error> {
+        let account_hash = AccountHash::try_from(pb_bid.get_id())
+            .map_err(|_| MappingError::invalid_account_hash_length(pb_bid.id.len()))?;
+
+        let amount = pb_bid.take_value().try_into()?;
+
+        Ok((account_hash, amount))
+    }
+}
+
+impl From<QueueEntry> for ipc::BidState_QueueEntry {
+    fn from(entry: QueueEntry) -> Self {
+        let mut pb_entry = ipc::BidState_QueueEntry::new();
+        pb_entry.set_validator_account_hash(entry.validator.as_bytes().to_vec());
+        pb_entry.set_amount(entry.amount.into());
+        pb_entry.set_timestamp(entry.timestamp.into());
+        pb_entry
+    }
+}
+
+impl TryFrom<ipc::BidState_QueueEntry> for QueueEntry {
+    type Error = MappingError;
+
+    fn try_from(mut pb_entry: ipc::BidState_QueueEntry) -> Result<Self, Self::Error> {
+        let validator =
+            AccountHash::try_from(pb_entry.get_validator_account_hash()).map_err(|_| {
+                MappingError::invalid_account_hash_length(pb_entry.validator_account_hash.len())
+            })?;
+
+        let amount = pb_entry.take_amount().try_into()?;
+
+        let timestamp = BlockTime::new(pb_entry.get_timestamp());
+
+        Ok(QueueEntry {
+            validator,
+            amount,
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{prelude::any, proptest};
+
+    use types::gens;
+
+    use super::*;
+    use crate::engine_server::mappings::test_utils;
+
+    proptest! {
+        #[test]
+        fn bid_round_trip(account_hash in gens::account_hash_arb(), u512 in gens::u512_arb()) {
+            test_utils::protobuf_round_trip::<(AccountHash, U512), ipc::BidState_Bid>(
+                (account_hash, u512)
+            );
+        }
+
+        #[test]
+        fn queue_entry_round_trip(
+            validator in gens::account_hash_arb(),
+            amount in gens::u512_arb(),
+            timestamp in any::<u64>()
+        ) {
+            let entry = QueueEntry {
+                validator,
+                amount,
+                timestamp: BlockTime::new(timestamp),
+            };
+            test_utils::protobuf_round_trip::<QueueEntry, ipc::BidState_QueueEntry>(entry);
+        }
+    }
+}
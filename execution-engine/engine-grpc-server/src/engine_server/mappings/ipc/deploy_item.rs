@@ -42,6 +42,10 @@ impl TryFrom<ipc::DeployItem> for DeployItem {
             MappingError::invalid_deploy_hash_length(pb_deploy_item.deploy_hash.len())
         })?;
 
+        let timestamp = pb_deploy_item.get_timestamp();
+
+        let ttl_millis = pb_deploy_item.get_ttl_millis();
+
         Ok(DeployItem::new(
             address,
             session,
@@ -49,6 +53,8 @@ impl TryFrom<ipc::DeployItem> for DeployItem {
             gas_price,
             authorization_keys,
             deploy_hash,
+            timestamp,
+            ttl_millis,
         ))
     }
 }
@@ -68,6 +74,8 @@ impl From<DeployItem> for ipc::DeployItem {
                 .collect(),
         );
         result.set_deploy_hash(deploy_item.deploy_hash.to_vec());
+        result.set_timestamp(deploy_item.timestamp);
+        result.set_ttl_millis(deploy_item.ttl_millis);
         result
     }
 }
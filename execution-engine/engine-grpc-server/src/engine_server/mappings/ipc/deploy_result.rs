@@ -11,14 +11,24 @@ use crate::engine_server::ipc::{DeployError_OutOfGasError, DeployResult};
 
 impl From<ExecutionResult> for DeployResult {
     fn from(execution_result: ExecutionResult) -> DeployResult {
-        match execution_result {
-            ExecutionResult::Success { effect, cost } => detail::execution_success(effect, cost),
+        let (mut pb_deploy_result, trace) = match execution_result {
+            ExecutionResult::Success {
+                effect,
+                cost,
+                trace,
+            } => (detail::execution_success(effect, cost), trace),
             ExecutionResult::Failure {
                 error,
                 effect,
                 cost,
-            } => (error, effect, cost).into(),
+                trace,
+            } => ((error, effect, cost).into(), trace),
+        };
+        if pb_deploy_result.has_execution_result() {
+            let pb_trace = trace.into_iter().map(Into::into).collect();
+            pb_deploy_result.mut_execution_result().set_trace(pb_trace);
         }
+        pb_deploy_result
     }
 }
 
@@ -38,12 +48,17 @@ impl From<(EngineStateError, ExecutionEffect, Gas)> for DeployResult {
             | error @ EngineStateError::InvalidKeyVariant(_)
             | error @ EngineStateError::Authorization
             | error @ EngineStateError::InvalidDeployItemVariant(_)
-            | error @ EngineStateError::InvalidUpgradeResult => {
+            | error @ EngineStateError::InvalidUpgradeResult
+            | error @ EngineStateError::DeployReplay
+            | error @ EngineStateError::DeployExpired => {
                 detail::precondition_error(error.to_string())
             }
             EngineStateError::Storage(storage_error) => {
                 detail::execution_error(storage_error, effect, cost)
             }
+            EngineStateError::FileStorage(storage_error) => {
+                detail::execution_error(storage_error, effect, cost)
+            }
             EngineStateError::MissingSystemContract(msg) => {
                 detail::execution_error(msg, effect, cost)
             }
@@ -134,6 +149,12 @@ mod detail {
                 .mut_exec_error()
                 .set_message(msg),
         }
+        let write_set = effect.write_set().into_iter().map(Into::into).collect();
+        pb_execution_result.set_write_set(write_set);
+        let read_set = effect.reads.iter().cloned().map(Into::into).collect();
+        pb_execution_result.set_read_set(read_set);
+        let events = effect.events.iter().cloned().map(Into::into).collect();
+        pb_execution_result.set_events(events);
         pb_execution_result.set_effects(effect.into());
         pb_execution_result.set_cost(cost.value().into());
 
@@ -145,8 +166,11 @@ mod detail {
 mod tests {
     use std::convert::TryInto;
 
+    use engine_core::engine_state::{execution_effect::Event, execution_trace::HostCall};
     use engine_shared::{additive_map::AdditiveMap, transform::Transform};
-    use types::{bytesrepr::Error as BytesReprError, AccessRights, ApiError, Key, URef, U512};
+    use types::{
+        bytesrepr::Error as BytesReprError, AccessRights, ApiError, CLValue, Key, URef, U512,
+    };
 
     use super::*;
 
@@ -165,6 +189,7 @@ mod tests {
         let execution_result = ExecutionResult::Success {
             effect: execution_effect,
             cost,
+            trace: Default::default(),
         };
         let mut ipc_deploy_result: DeployResult = execution_result.into();
         assert!(ipc_deploy_result.has_execution_result());
@@ -185,11 +210,114 @@ mod tests {
         assert_eq!(input_transforms, ipc_transforms);
     }
 
+    #[test]
+    fn deploy_result_to_ipc_read_and_write_sets() {
+        let read_key = Key::Hash([1u8; 32]);
+        let missing_key = Key::Hash([2u8; 32]);
+        let written_key = Key::Hash([3u8; 32]);
+        let mut transforms = AdditiveMap::new();
+        transforms.insert(read_key, Transform::Identity);
+        transforms.insert(written_key, Transform::AddInt32(10));
+        let mut execution_effect = ExecutionEffect::new(AdditiveMap::new(), transforms);
+        execution_effect.reads = vec![read_key, missing_key].into_iter().collect();
+        let execution_result = ExecutionResult::Success {
+            effect: execution_effect,
+            cost: Gas::default(),
+            trace: Default::default(),
+        };
+
+        let mut ipc_deploy_result: DeployResult = execution_result.into();
+        let mut success = ipc_deploy_result.take_execution_result();
+        let read_set = success
+            .take_read_set()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<Key>, _>>()
+            .unwrap();
+        assert_eq!(read_set, vec![read_key, missing_key]);
+        let write_set = success
+            .take_write_set()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<Key>, _>>()
+            .unwrap();
+        assert_eq!(write_set, vec![written_key]);
+    }
+
+    #[test]
+    fn deploy_result_to_ipc_events() {
+        let mut execution_effect = ExecutionEffect::default();
+        execution_effect.events = vec![
+            Event::new("first".to_string(), CLValue::from_t(1u64).unwrap()),
+            Event::new("second".to_string(), CLValue::from_t(()).unwrap()),
+        ];
+        let execution_result = ExecutionResult::Success {
+            effect: execution_effect,
+            cost: Gas::default(),
+            trace: Default::default(),
+        };
+
+        let mut ipc_deploy_result: DeployResult = execution_result.into();
+        let ipc_events = ipc_deploy_result.take_execution_result().take_events();
+        let events = ipc_events
+            .into_iter()
+            .map(|mut ipc_event| {
+                let value: CLValue = ipc_event.take_value().try_into().unwrap();
+                (ipc_event.take_name(), value)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("first".to_string(), CLValue::from_t(1u64).unwrap()),
+                ("second".to_string(), CLValue::from_t(()).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn deploy_result_to_ipc_trace() {
+        let host_call = HostCall {
+            name: "host_function_write",
+            args: "[I32(1), I32(2)]".to_string(),
+            result: "Ok(None)".to_string(),
+            gas_before: Gas::new(U512::from(10)),
+            gas_after: Gas::new(U512::from(25)),
+            call_depth: 1,
+        };
+        let execution_result = ExecutionResult::Failure {
+            error: EngineStateError::Exec(ExecutionError::GasLimit),
+            effect: Default::default(),
+            cost: Gas::new(U512::from(25)),
+            trace: vec![host_call],
+        };
+
+        let mut ipc_deploy_result: DeployResult = execution_result.into();
+        let mut ipc_trace = ipc_deploy_result.take_execution_result().take_trace();
+        assert_eq!(ipc_trace.len(), 1);
+        let mut ipc_host_call = ipc_trace.remove(0);
+        assert_eq!(ipc_host_call.get_name(), "host_function_write");
+        assert_eq!(ipc_host_call.get_args(), "[I32(1), I32(2)]");
+        assert_eq!(ipc_host_call.get_result(), "Ok(None)");
+        let gas_before: U512 = ipc_host_call
+            .take_gas_before()
+            .try_into()
+            .expect("should map to U512");
+        assert_eq!(gas_before, U512::from(10));
+        let gas_after: U512 = ipc_host_call
+            .take_gas_after()
+            .try_into()
+            .expect("should map to U512");
+        assert_eq!(gas_after, U512::from(25));
+        assert_eq!(ipc_host_call.get_call_depth(), 1);
+    }
+
     fn test_cost<E: Into<EngineStateError>>(expected_cost: Gas, error: E) -> Gas {
         let execution_failure = ExecutionResult::Failure {
             error: error.into(),
             effect: Default::default(),
             cost: expected_cost,
+            trace: Default::default(),
         };
         let mut ipc_deploy_result: DeployResult = execution_failure.into();
         assert!(ipc_deploy_result.has_execution_result());
@@ -224,6 +352,23 @@ mod tests {
         assert_eq!(test_cost(cost, forged_ref_error), cost);
     }
 
+    #[test]
+    fn replayed_and_expired_deploys_map_to_precondition_failure() {
+        for error in vec![
+            EngineStateError::DeployReplay,
+            EngineStateError::DeployExpired,
+        ] {
+            let expected_message = error.to_string();
+            let mut ipc_deploy_result: DeployResult =
+                ExecutionResult::precondition_failure(error).into();
+            assert!(ipc_deploy_result.has_precondition_failure());
+            assert_eq!(
+                ipc_deploy_result.take_precondition_failure().get_message(),
+                expected_message
+            );
+        }
+    }
+
     #[test]
     fn revert_error_maps_to_execution_error() {
         let expected_revert = ApiError::UnexpectedContractRefVariant;
@@ -233,6 +378,7 @@ mod tests {
             error: EngineStateError::Exec(revert_error),
             effect: Default::default(),
             cost: Gas::new(amount),
+            trace: Default::default(),
         };
         let mut ipc_result: DeployResult = exec_result.into();
         assert!(
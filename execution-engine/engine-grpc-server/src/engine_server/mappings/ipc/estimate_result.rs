@@ -0,0 +1,17 @@
+use engine_core::engine_state::estimate::EstimateResult;
+
+use crate::engine_server::ipc;
+
+impl From<EstimateResult> for ipc::EstimateResult {
+    fn from(estimate_result: EstimateResult) -> Self {
+        let mut pb_estimate_result = ipc::EstimateResult::new();
+        pb_estimate_result.set_payment_cost(estimate_result.payment_cost().value().into());
+        pb_estimate_result.set_session_cost(estimate_result.session_cost().value().into());
+        pb_estimate_result.set_finalize_cost(estimate_result.finalize_cost().value().into());
+        if let Some(minimum_payment) = estimate_result.minimum_payment() {
+            pb_estimate_result.set_minimum_payment(minimum_payment.value().into());
+        }
+        pb_estimate_result.set_deploy_result(estimate_result.take_execution_result().into());
+        pb_estimate_result
+    }
+}
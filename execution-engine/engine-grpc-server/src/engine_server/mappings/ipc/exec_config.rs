@@ -1,6 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 
 use engine_core::engine_state::genesis::{ExecConfig, GenesisAccount};
+use engine_shared::host_function_costs::HostFunctionCosts;
 
 use crate::engine_server::{ipc, mappings::MappingError};
 
@@ -15,7 +16,13 @@ impl TryFrom<ipc::ChainSpec_GenesisConfig_ExecConfig> for ExecConfig {
             .into_iter()
             .map(TryInto::try_into)
             .collect::<Result<Vec<GenesisAccount>, Self::Error>>()?;
-        let wasm_costs = pb_exec_config.take_costs().take_wasm().into();
+        let mut pb_costs = pb_exec_config.take_costs();
+        let wasm_costs = pb_costs.take_wasm().into();
+        let host_function_costs = if !pb_costs.has_host_function() {
+            HostFunctionCosts::default()
+        } else {
+            pb_costs.take_host_function().into()
+        };
         let mint_initializer_bytes = pb_exec_config.take_mint_installer();
         let proof_of_stake_initializer_bytes = pb_exec_config.take_pos_installer();
         let standard_payment_installer_bytes = pb_exec_config.take_standard_payment_installer();
@@ -25,6 +32,7 @@ impl TryFrom<ipc::ChainSpec_GenesisConfig_ExecConfig> for ExecConfig {
             standard_payment_installer_bytes,
             accounts,
             wasm_costs,
+            host_function_costs,
         ))
     }
 }
@@ -50,6 +58,9 @@ impl From<ExecConfig> for ipc::ChainSpec_GenesisConfig_ExecConfig {
             .mut_costs()
             .set_wasm(exec_config.wasm_costs().into());
         pb_exec_config
+            .mut_costs()
+            .set_host_function(exec_config.host_function_costs().into());
+        pb_exec_config
     }
 }
 
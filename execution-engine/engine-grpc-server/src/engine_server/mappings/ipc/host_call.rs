@@ -0,0 +1,16 @@
+use engine_core::engine_state::execution_trace::HostCall;
+
+use crate::engine_server::ipc;
+
+impl From<HostCall> for ipc::HostCall {
+    fn from(host_call: HostCall) -> Self {
+        let mut pb_host_call = ipc::HostCall::new();
+        pb_host_call.set_name(host_call.name.to_string());
+        pb_host_call.set_args(host_call.args);
+        pb_host_call.set_result(host_call.result);
+        pb_host_call.set_gas_before(host_call.gas_before.value().into());
+        pb_host_call.set_gas_after(host_call.gas_after.value().into());
+        pb_host_call.set_call_depth(host_call.call_depth as u32);
+        pb_host_call
+    }
+}
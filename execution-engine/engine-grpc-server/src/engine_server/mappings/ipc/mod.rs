@@ -1,16 +1,23 @@
 //! Functions for converting between CasperLabs types and their Protobuf equivalents which are
 //! defined in protobuf/io/casperlabs/ipc/ipc.proto
 
+mod bid_state;
 mod bond;
 mod deploy_item;
 mod deploy_result;
+mod estimate_request;
+mod estimate_result;
+mod event;
 mod exec_config;
 mod executable_deploy_item;
 mod execute_request;
 mod execution_effect;
 mod genesis_account;
 mod genesis_config;
+mod host_call;
+mod host_function_costs;
 mod query_request;
 mod run_genesis_request;
+mod trie_merkle_proof;
 mod upgrade_request;
 mod wasm_costs;
@@ -0,0 +1,26 @@
+use std::convert::TryFrom;
+
+use engine_shared::stored_value::StoredValue;
+use engine_storage::trie::TrieMerkleProof;
+use types::{
+    bytesrepr::{self, ToBytes},
+    Key,
+};
+
+use crate::engine_server::ipc;
+
+impl TryFrom<TrieMerkleProof<Key, StoredValue>> for ipc::TrieMerkleProof {
+    type Error = bytesrepr::Error;
+
+    fn try_from(proof: TrieMerkleProof<Key, StoredValue>) -> Result<Self, Self::Error> {
+        let mut pb_proof = ipc::TrieMerkleProof::new();
+        pb_proof.set_key(proof.key().to_bytes()?);
+        let proof_steps = proof
+            .proof_steps()
+            .iter()
+            .map(ToBytes::to_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        pb_proof.set_proof_steps(proof_steps.into());
+        Ok(pb_proof)
+    }
+}
@@ -30,10 +30,21 @@ impl TryFrom<UpgradeRequest> for UpgradeConfig {
                 (bytes, args)
             };
 
-        let wasm_costs = if !upgrade_point.has_new_costs() {
-            None
+        let (wasm_costs, host_function_costs) = if !upgrade_point.has_new_costs() {
+            (None, None)
         } else {
-            Some(upgrade_point.mut_new_costs().take_wasm().into())
+            let new_costs = upgrade_point.mut_new_costs();
+            let wasm_costs = if !new_costs.has_wasm() {
+                None
+            } else {
+                Some(new_costs.take_wasm().into())
+            };
+            let host_function_costs = if !new_costs.has_host_function() {
+                None
+            } else {
+                Some(new_costs.take_host_function().into())
+            };
+            (wasm_costs, host_function_costs)
         };
         let activation_point = if !upgrade_point.has_activation_point() {
             None
@@ -48,6 +59,7 @@ impl TryFrom<UpgradeRequest> for UpgradeConfig {
             upgrade_installer_args,
             upgrade_installer_bytes,
             wasm_costs,
+            host_function_costs,
             activation_point,
         ))
     }
@@ -15,6 +15,7 @@ impl From<WasmCosts> for ChainSpec_CostTable_WasmCosts {
             max_stack_height: wasm_costs.max_stack_height,
             opcodes_mul: wasm_costs.opcodes_mul,
             opcodes_div: wasm_costs.opcodes_div,
+            storage_per_byte: wasm_costs.storage_per_byte,
             ..Default::default()
         }
     }
@@ -33,6 +34,7 @@ impl From<ChainSpec_CostTable_WasmCosts> for WasmCosts {
             max_stack_height: pb_wasm_costs.max_stack_height,
             opcodes_mul: pb_wasm_costs.opcodes_mul,
             opcodes_div: pb_wasm_costs.opcodes_div,
+            storage_per_byte: pb_wasm_costs.storage_per_byte,
         }
     }
 }
@@ -30,7 +30,10 @@ use grpc::{Error as GrpcError, RequestOptions, ServerBuilder, SingleResponse};
 use log::{info, warn, Level};
 
 use engine_core::engine_state::{
+    bid_state::{BidStateRequest, BidStateResult},
+    estimate::EstimateRequest,
     execute_request::ExecuteRequest,
+    execution_effect::ExecutionEffect,
     genesis::GenesisResult,
     query::{QueryRequest, QueryResult},
     run_genesis_request::RunGenesisRequest,
@@ -46,22 +49,27 @@ use types::{bytesrepr::ToBytes, ProtocolVersion};
 
 use self::{
     ipc::{
-        BidStateRequest, BidStateResponse, CommitRequest, CommitResponse, DistributeRewardsRequest,
-        DistributeRewardsResponse, ExecuteResponse, GenesisResponse, QueryResponse, SlashRequest,
-        SlashResponse, UnbondPayoutRequest, UnbondPayoutResponse, UpgradeRequest, UpgradeResponse,
+        BidStateResponse, CommitRequest, CommitResponse, DistributeRewardsRequest,
+        DistributeRewardsResponse, EstimateResponse, ExecuteResponse, GenesisResponse,
+        QueryResponse, SlashRequest, SlashResponse, UnbondPayoutRequest, UnbondPayoutResponse,
+        UpgradeRequest, UpgradeResponse,
     },
     ipc_grpc::{ExecutionEngineService, ExecutionEngineServiceServer},
     mappings::{ParsingError, TransformMap},
 };
 
+const METRIC_DURATION_BID_STATE: &str = "bid_state_duration";
 const METRIC_DURATION_COMMIT: &str = "commit_duration";
 const METRIC_DURATION_EXEC: &str = "exec_duration";
+const METRIC_DURATION_ESTIMATE: &str = "estimate_duration";
 const METRIC_DURATION_QUERY: &str = "query_duration";
 const METRIC_DURATION_GENESIS: &str = "genesis_duration";
 const METRIC_DURATION_UPGRADE: &str = "upgrade_duration";
 
+const TAG_RESPONSE_BID_STATE: &str = "bid_state_response";
 const TAG_RESPONSE_COMMIT: &str = "commit_response";
 const TAG_RESPONSE_EXEC: &str = "exec_response";
+const TAG_RESPONSE_ESTIMATE: &str = "estimate_response";
 const TAG_RESPONSE_QUERY: &str = "query_response";
 const TAG_RESPONSE_GENESIS: &str = "genesis_response";
 const TAG_RESPONSE_UPGRADE: &str = "upgrade_response";
@@ -77,7 +85,7 @@ const DEFAULT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V1_0_0;
 // (outer layer) leading to cleaner design.
 impl<S> ExecutionEngineService for EngineState<S>
 where
-    S: StateProvider,
+    S: StateProvider + Sync,
     EngineError: From<S::Error>,
     S::Error: Into<engine_core::execution::Error> + Debug,
 {
@@ -106,18 +114,27 @@ where
             }
         };
 
-        let result = self.run_query(correlation_id, request);
+        let result = self.run_query_with_proof(correlation_id, request);
 
         let response = match result {
-            Ok(QueryResult::Success(value)) => {
+            Ok(QueryResult::Success { value, proofs }) => {
                 let mut result = ipc::QueryResponse::new();
-                match value.to_bytes() {
-                    Ok(serialized_value) => {
+                let serialized = value.to_bytes().and_then(|serialized_value| {
+                    let pb_proofs: Vec<ipc::TrieMerkleProof> = proofs
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<_, _>>()?;
+                    Ok((serialized_value, pb_proofs))
+                });
+                match serialized {
+                    Ok((serialized_value, pb_proofs)) => {
                         info!("query successful; correlation_id: {}", correlation_id);
                         result.set_success(serialized_value);
+                        result.set_proofs(pb_proofs.into());
                     }
                     Err(error_msg) => {
-                        let log_message = format!("Failed to serialize StoredValue: {}", error_msg);
+                        let log_message =
+                            format!("Failed to serialize query result: {}", error_msg);
                         warn!("{}", log_message);
                         result.set_failure(log_message);
                     }
@@ -179,6 +196,8 @@ where
 
         let mut exec_response = ExecuteResponse::new();
 
+        let chained = exec_request.chained;
+
         let results = match self.run_execute(correlation_id, exec_request) {
             Ok(results) => results,
             Err(error) => {
@@ -194,10 +213,22 @@ where
             }
         };
 
+        if chained {
+            let cumulative_effects = results
+                .iter()
+                .map(|result| result.effect().clone())
+                .fold(ExecutionEffect::default(), ExecutionEffect::then);
+            exec_response
+                .mut_success()
+                .set_cumulative_effects(cumulative_effects.into());
+        }
+
         let protobuf_results_iter = results.into_iter().map(Into::into);
         exec_response
             .mut_success()
             .set_deploy_results(FromIterator::from_iter(protobuf_results_iter));
+        self.module_cache()
+            .log_metrics(correlation_id, TAG_RESPONSE_EXEC);
         log_duration(
             correlation_id,
             METRIC_DURATION_EXEC,
@@ -207,6 +238,51 @@ where
         SingleResponse::completed(exec_response)
     }
 
+    fn estimate(
+        &self,
+        _request_options: RequestOptions,
+        estimate_request: ipc::EstimateRequest,
+    ) -> SingleResponse<EstimateResponse> {
+        let start = Instant::now();
+        let correlation_id = CorrelationId::new();
+
+        let mut estimate_response = EstimateResponse::new();
+
+        let estimate_request: EstimateRequest = match estimate_request.try_into() {
+            Ok(ret) => ret,
+            Err(err) => {
+                let log_message = format!("{}", err);
+                warn!("{}", log_message);
+                estimate_response.set_failure(log_message);
+                log_duration(
+                    correlation_id,
+                    METRIC_DURATION_ESTIMATE,
+                    TAG_RESPONSE_ESTIMATE,
+                    start.elapsed(),
+                );
+                return SingleResponse::completed(estimate_response);
+            }
+        };
+
+        match self.run_estimate(correlation_id, estimate_request) {
+            Ok(estimate_result) => estimate_response.set_success(estimate_result.into()),
+            Err(error) => {
+                info!("estimate error: RootNotFound");
+                estimate_response
+                    .mut_missing_parent()
+                    .set_hash(error.to_vec());
+            }
+        }
+
+        log_duration(
+            correlation_id,
+            METRIC_DURATION_ESTIMATE,
+            TAG_RESPONSE_ESTIMATE,
+            start.elapsed(),
+        );
+        SingleResponse::completed(estimate_response)
+    }
+
     fn commit(
         &self,
         _request_options: RequestOptions,
@@ -456,9 +532,63 @@ where
     fn bid_state(
         &self,
         _request_options: RequestOptions,
-        _bid_state_request: BidStateRequest,
+        bid_state_request: ipc::BidStateRequest,
     ) -> SingleResponse<BidStateResponse> {
-        SingleResponse::err(GrpcError::Panic(UNIMPLEMENTED.to_string()))
+        let start = Instant::now();
+        let correlation_id = CorrelationId::new();
+
+        let mut bid_state_response = BidStateResponse::new();
+
+        let bid_state_request: BidStateRequest = match bid_state_request.try_into() {
+            Ok(ret) => ret,
+            Err(err) => {
+                let log_message = format!("{}", err);
+                warn!("{}", log_message);
+                bid_state_response.set_failure(log_message);
+                log_duration(
+                    correlation_id,
+                    METRIC_DURATION_BID_STATE,
+                    TAG_RESPONSE_BID_STATE,
+                    start.elapsed(),
+                );
+                return SingleResponse::completed(bid_state_response);
+            }
+        };
+
+        let state_hash = bid_state_request.state_hash();
+
+        match self.get_bid_state(correlation_id, bid_state_request) {
+            Ok(BidStateResult::Success {
+                stakes,
+                bonding_queue,
+                unbonding_queue,
+            }) => {
+                let bid_state = bid_state_response.mut_success();
+                bid_state.set_bids(stakes.0.into_iter().map(Into::into).collect());
+                bid_state.set_bonding_queue(bonding_queue.0.into_iter().map(Into::into).collect());
+                bid_state
+                    .set_unbonding_queue(unbonding_queue.0.into_iter().map(Into::into).collect());
+            }
+            Ok(BidStateResult::RootNotFound) => {
+                info!("bid state error: RootNotFound");
+                bid_state_response
+                    .mut_missing_parent()
+                    .set_hash(state_hash.to_vec());
+            }
+            Err(error) => {
+                let log_message = format!("{:?}", error);
+                warn!("{}", log_message);
+                bid_state_response.set_failure(log_message);
+            }
+        }
+
+        log_duration(
+            correlation_id,
+            METRIC_DURATION_BID_STATE,
+            TAG_RESPONSE_BID_STATE,
+            start.elapsed(),
+        );
+        SingleResponse::completed(bid_state_response)
     }
 
     fn distribute_rewards(
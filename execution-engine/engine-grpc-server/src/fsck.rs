@@ -0,0 +1,216 @@
+//! Checks the integrity of the global state stored in an LMDB data dir.
+//!
+//! Every trie element reachable from the given state roots is checked, and a JSON report is
+//! written to stdout.  The exit code is 0 if no problems were found, 1 if there were problems, and
+//! 2 if the check could not be run, e.g. because there is no global state in the data dir.
+use std::{convert::TryFrom, path::PathBuf, process, str::FromStr};
+
+use clap::{App, Arg, ArgMatches};
+use dirs::home_dir;
+use serde_json::{json, Value};
+
+use engine_shared::{
+    newtypes::{Blake2bHash, CorrelationId},
+    os::get_page_size,
+};
+use engine_storage::{
+    error,
+    global_state::{self, IntegrityProblem, IntegrityReport},
+    transaction_source::lmdb::LmdbEnvironment,
+    trie::Pointer,
+    trie_store::lmdb::LmdbTrieStore,
+};
+
+const APP_NAME: &str = "CasperLabs Execution Engine Integrity Checker";
+
+// data-dir / pages, as for the server
+const ARG_DATA_DIR: &str = "data-dir";
+const ARG_DATA_DIR_SHORT: &str = "d";
+const ARG_DATA_DIR_VALUE: &str = "DIR";
+const ARG_DATA_DIR_HELP: &str = "Sets the data directory of the server to check";
+const DEFAULT_DATA_DIR_RELATIVE: &str = ".casperlabs";
+const GLOBAL_STATE_DIR: &str = "global_state";
+const GET_HOME_DIR_EXPECT: &str = "Could not get home directory";
+const ARG_PAGES: &str = "pages";
+const ARG_PAGES_SHORT: &str = "p";
+const ARG_PAGES_VALUE: &str = "NUM";
+const ARG_PAGES_HELP: &str = "Sets the max number of pages to use for lmdb's mmap";
+const GET_PAGES_EXPECT: &str = "Could not parse pages argument";
+// 750 GiB = 805306368000 bytes
+// page size on x86_64 linux = 4096 bytes
+// 805306368000 / 4096 = 196608000
+const DEFAULT_PAGES: usize = 196_608_000;
+
+// state roots
+const ARG_STATE_ROOTS: &str = "state-roots";
+const ARG_STATE_ROOTS_VALUE: &str = "STATE_ROOT";
+const ARG_STATE_ROOTS_HELP: &str = "Hex-encoded state roots to check";
+const ARG_STATE_ROOTS_EXPECT: &str = "state roots required";
+const PARSE_STATE_ROOT_EXPECT: &str = "Could not parse state root as a hex-encoded 32-byte hash";
+
+const CHECK_INTEGRITY_FAILED_EXIT_CODE: i32 = 2;
+
+fn main() {
+    let arg_matches = get_args();
+    let data_dir = get_data_dir(&arg_matches);
+    let map_size = get_map_size(&arg_matches);
+    let state_roots = get_state_roots(&arg_matches);
+
+    let report = match check(&data_dir, map_size, &state_roots) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!(
+                "Could not check integrity of {}: {}",
+                data_dir.display(),
+                error
+            );
+            process::exit(CHECK_INTEGRITY_FAILED_EXIT_CODE);
+        }
+    };
+
+    println!("{}", report_to_json(&report));
+
+    if !report.is_intact() {
+        process::exit(1);
+    }
+}
+
+/// Gets command line arguments
+fn get_args() -> ArgMatches<'static> {
+    App::new(APP_NAME)
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(
+            Arg::with_name(ARG_DATA_DIR)
+                .short(ARG_DATA_DIR_SHORT)
+                .long(ARG_DATA_DIR)
+                .value_name(ARG_DATA_DIR_VALUE)
+                .help(ARG_DATA_DIR_HELP)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_PAGES)
+                .short(ARG_PAGES_SHORT)
+                .long(ARG_PAGES)
+                .value_name(ARG_PAGES_VALUE)
+                .help(ARG_PAGES_HELP)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_STATE_ROOTS)
+                .required(true)
+                .multiple(true)
+                .value_name(ARG_STATE_ROOTS_VALUE)
+                .help(ARG_STATE_ROOTS_HELP)
+                .index(1),
+        )
+        .get_matches()
+}
+
+/// Gets value of data-dir argument, without creating it
+fn get_data_dir(arg_matches: &ArgMatches) -> PathBuf {
+    let mut buf = arg_matches.value_of(ARG_DATA_DIR).map_or(
+        {
+            let mut dir = home_dir().expect(GET_HOME_DIR_EXPECT);
+            dir.push(DEFAULT_DATA_DIR_RELATIVE);
+            dir
+        },
+        PathBuf::from,
+    );
+    buf.push(GLOBAL_STATE_DIR);
+    buf
+}
+
+///  Parses pages argument and returns map size
+fn get_map_size(arg_matches: &ArgMatches) -> usize {
+    let page_size = get_page_size().unwrap();
+    let pages = arg_matches
+        .value_of(ARG_PAGES)
+        .map_or(Ok(DEFAULT_PAGES), usize::from_str)
+        .expect(GET_PAGES_EXPECT);
+    page_size * pages
+}
+
+/// Parses the state-roots arguments
+fn get_state_roots(arg_matches: &ArgMatches) -> Vec<Blake2bHash> {
+    arg_matches
+        .values_of(ARG_STATE_ROOTS)
+        .expect(ARG_STATE_ROOTS_EXPECT)
+        .map(|state_root| {
+            let bytes = base16::decode(state_root).expect(PARSE_STATE_ROOT_EXPECT);
+            Blake2bHash::try_from(bytes.as_slice()).expect(PARSE_STATE_ROOT_EXPECT)
+        })
+        .collect()
+}
+
+/// Checks every trie element reachable from `state_roots`.
+///
+/// The data dir is opened read-only, so it is never created or modified.
+fn check(
+    data_dir: &PathBuf,
+    map_size: usize,
+    state_roots: &[Blake2bHash],
+) -> Result<IntegrityReport, error::Error> {
+    let environment = LmdbEnvironment::open_read_only(data_dir, map_size)?;
+    let trie_store = LmdbTrieStore::open(&environment, None)?;
+    global_state::check_integrity::<_, _, error::Error>(
+        CorrelationId::new(),
+        &environment,
+        &trie_store,
+        state_roots,
+    )
+}
+
+fn hash_to_json(hash: &Blake2bHash) -> Value {
+    Value::String(format!("{:x}", hash))
+}
+
+fn pointer_to_json(pointer: &Pointer) -> Value {
+    let kind = match pointer {
+        Pointer::LeafPointer(_) => "leaf",
+        Pointer::NodePointer(_) => "node",
+    };
+    json!({ "kind": kind, "hash": hash_to_json(pointer.hash()) })
+}
+
+fn problem_to_json(problem: &IntegrityProblem) -> Value {
+    match problem {
+        IntegrityProblem::RootNotFound(hash) => json!({
+            "problem": "root_not_found",
+            "hash": hash_to_json(hash),
+        }),
+        IntegrityProblem::TrieNotFound { parent, hash } => json!({
+            "problem": "trie_not_found",
+            "parent": hash_to_json(parent),
+            "hash": hash_to_json(hash),
+        }),
+        IntegrityProblem::HashMismatch { hash, actual } => json!({
+            "problem": "hash_mismatch",
+            "hash": hash_to_json(hash),
+            "actual": hash_to_json(actual),
+        }),
+        IntegrityProblem::InvalidTrie { hash, error } => json!({
+            "problem": "invalid_trie",
+            "hash": hash_to_json(hash),
+            "error": error.to_string(),
+        }),
+        IntegrityProblem::InvalidLeafValue { hash, error } => json!({
+            "problem": "invalid_leaf_value",
+            "hash": hash_to_json(hash),
+            "error": error.to_string(),
+        }),
+        IntegrityProblem::PointerKindMismatch { parent, pointer } => json!({
+            "problem": "pointer_kind_mismatch",
+            "parent": hash_to_json(parent),
+            "pointer": pointer_to_json(pointer),
+        }),
+    }
+}
+
+fn report_to_json(report: &IntegrityReport) -> Value {
+    json!({
+        "intact": report.is_intact(),
+        "tries_checked": report.tries_checked,
+        "leaves_checked": report.leaves_checked,
+        "problems": report.problems.iter().map(problem_to_json).collect::<Vec<_>>(),
+    })
+}
@@ -22,8 +22,10 @@ use engine_shared::{
     socket,
 };
 use engine_storage::{
-    global_state::lmdb::LmdbGlobalState, transaction_source::lmdb::LmdbEnvironment,
-    trie_store::lmdb::LmdbTrieStore,
+    global_state::{file::FileGlobalState, lmdb::LmdbGlobalState},
+    protocol_data_store::file::FileProtocolDataStore,
+    transaction_source::{file::FileEnvironment, lmdb::LmdbEnvironment},
+    trie_store::{file::FileTrieStore, lmdb::LmdbTrieStore},
 };
 
 use casperlabs_engine_grpc_server::engine_server;
@@ -48,6 +50,17 @@ const LMDB_ENVIRONMENT_EXPECT: &str = "Could not create LmdbEnvironment";
 const LMDB_TRIE_STORE_EXPECT: &str = "Could not create LmdbTrieStore";
 const LMDB_PROTOCOL_DATA_STORE_EXPECT: &str = "Could not create LmdbProtocolDataStore";
 const LMDB_GLOBAL_STATE_EXPECT: &str = "Could not create LmdbGlobalState";
+const FILE_ENVIRONMENT_EXPECT: &str = "Could not create FileEnvironment";
+const FILE_GLOBAL_STATE_EXPECT: &str = "Could not create FileGlobalState";
+
+// storage backend
+const ARG_STORAGE_BACKEND: &str = "storage-backend";
+const ARG_STORAGE_BACKEND_VALUE: &str = "BACKEND";
+const ARG_STORAGE_BACKEND_HELP: &str =
+    "Sets the global state storage backend.  The file backend stores global state in an \
+     append-only log, and ignores the pages argument";
+const STORAGE_BACKEND_LMDB: &str = "lmdb";
+const STORAGE_BACKEND_FILE: &str = "file";
 
 // pages / lmdb
 const ARG_PAGES: &str = "pages";
@@ -93,7 +106,8 @@ const ARG_THREAD_COUNT: &str = "threads";
 const ARG_THREAD_COUNT_SHORT: &str = "t";
 const ARG_THREAD_COUNT_DEFAULT: &str = "1";
 const ARG_THREAD_COUNT_VALUE: &str = "NUM";
-const ARG_THREAD_COUNT_HELP: &str = "Worker thread count";
+const ARG_THREAD_COUNT_HELP: &str =
+    "Worker thread count, used both for serving requests and for executing the deploys of each";
 const ARG_THREAD_COUNT_EXPECT: &str = "expected valid thread count";
 
 // use system contracts
@@ -107,10 +121,36 @@ const ARG_ENABLE_BONDING: &str = "enable-bonding";
 const ARG_ENABLE_BONDING_SHORT: &str = "b";
 const ARG_ENABLE_BONDING_HELP: &str = "Enable bonding";
 
+// host call tracing
+const ARG_TRACE_HOST_CALLS: &str = "trace-host-calls";
+const ARG_TRACE_HOST_CALLS_HELP: &str =
+    "Record every host function invoked by each deploy and return the trace in its result";
+
+// estimate gas limit
+const ARG_ESTIMATE_GAS_LIMIT: &str = "estimate-gas-limit";
+const ARG_ESTIMATE_GAS_LIMIT_VALUE: &str = "GAS";
+const ARG_ESTIMATE_GAS_LIMIT_HELP: &str =
+    "Gas limit of the session code when estimating the cost of a deploy [default: 1000000000]";
+const ARG_ESTIMATE_GAS_LIMIT_EXPECT: &str = "expected valid gas limit";
+
+// max ttl
+const ARG_MAX_TTL_MILLIS: &str = "max-ttl-millis";
+const ARG_MAX_TTL_MILLIS_VALUE: &str = "MILLIS";
+const ARG_MAX_TTL_MILLIS_HELP: &str =
+    "Maximum time to live of a deploy in milliseconds, applied to deploys without a TTL or with \
+     a longer one.  Must be at least the maximum TTL of the chain [default: 86400000]";
+const ARG_MAX_TTL_MILLIS_EXPECT: &str = "expected valid max TTL";
+
 // runnable
 const SIGINT_HANDLE_EXPECT: &str = "Error setting Ctrl-C handler";
 const RUNNABLE_CHECK_INTERVAL_SECONDS: u64 = 3;
 
+/// The global state storage backend selected on the command line.
+enum StorageBackend {
+    Lmdb { map_size: usize },
+    File,
+}
+
 fn main() {
     set_panic_hook();
 
@@ -129,13 +169,19 @@ fn main() {
 
     let data_dir = get_data_dir(&arg_matches);
 
-    let map_size = get_map_size(&arg_matches);
+    let storage_backend = get_storage_backend(&arg_matches);
 
     let thread_count = get_thread_count(&arg_matches);
 
     let engine_config: EngineConfig = get_engine_config(&arg_matches);
 
-    let _server = get_grpc_server(&socket, data_dir, map_size, thread_count, engine_config);
+    let _server = get_grpc_server(
+        &socket,
+        data_dir,
+        storage_backend,
+        thread_count,
+        engine_config,
+    );
 
     log_listening_message(&socket);
 
@@ -218,6 +264,17 @@ fn get_args() -> ArgMatches<'static> {
                 .help(ARG_PAGES_HELP)
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name(ARG_STORAGE_BACKEND)
+                .required(false)
+                .long(ARG_STORAGE_BACKEND)
+                .takes_value(true)
+                .possible_value(STORAGE_BACKEND_LMDB)
+                .possible_value(STORAGE_BACKEND_FILE)
+                .default_value(STORAGE_BACKEND_LMDB)
+                .value_name(ARG_STORAGE_BACKEND_VALUE)
+                .help(ARG_STORAGE_BACKEND_HELP),
+        )
         .arg(
             Arg::with_name(ARG_THREAD_COUNT)
                 .short(ARG_THREAD_COUNT_SHORT)
@@ -239,6 +296,25 @@ fn get_args() -> ArgMatches<'static> {
                 .long(ARG_ENABLE_BONDING)
                 .help(ARG_ENABLE_BONDING_HELP),
         )
+        .arg(
+            Arg::with_name(ARG_TRACE_HOST_CALLS)
+                .long(ARG_TRACE_HOST_CALLS)
+                .help(ARG_TRACE_HOST_CALLS_HELP),
+        )
+        .arg(
+            Arg::with_name(ARG_ESTIMATE_GAS_LIMIT)
+                .long(ARG_ESTIMATE_GAS_LIMIT)
+                .takes_value(true)
+                .value_name(ARG_ESTIMATE_GAS_LIMIT_VALUE)
+                .help(ARG_ESTIMATE_GAS_LIMIT_HELP),
+        )
+        .arg(
+            Arg::with_name(ARG_MAX_TTL_MILLIS)
+                .long(ARG_MAX_TTL_MILLIS)
+                .takes_value(true)
+                .value_name(ARG_MAX_TTL_MILLIS_VALUE)
+                .help(ARG_MAX_TTL_MILLIS_HELP),
+        )
         .arg(
             Arg::with_name(ARG_SOCKET)
                 .required(true)
@@ -291,6 +367,20 @@ fn get_map_size(arg_matches: &ArgMatches) -> usize {
     page_size * pages
 }
 
+/// Parses storage-backend argument, along with the pages argument for the LMDB backend
+fn get_storage_backend(arg_matches: &ArgMatches) -> StorageBackend {
+    match arg_matches
+        .value_of(ARG_STORAGE_BACKEND)
+        .expect("should have default value if not explicitly set")
+    {
+        STORAGE_BACKEND_LMDB => StorageBackend::Lmdb {
+            map_size: get_map_size(arg_matches),
+        },
+        STORAGE_BACKEND_FILE => StorageBackend::File,
+        _ => unreachable!("should validate storage-backend arg to match one of the options"),
+    }
+}
+
 fn get_thread_count(arg_matches: &ArgMatches) -> usize {
     arg_matches
         .value_of(ARG_THREAD_COUNT)
@@ -304,28 +394,53 @@ fn get_engine_config(arg_matches: &ArgMatches) -> EngineConfig {
     // feature flags go here
     let use_system_contracts = arg_matches.is_present(ARG_USE_SYSTEM_CONTRACTS);
     let enable_bonding = arg_matches.is_present(ARG_ENABLE_BONDING);
-    EngineConfig::new()
+    let execution_threads = get_thread_count(arg_matches);
+    let trace_host_calls = arg_matches.is_present(ARG_TRACE_HOST_CALLS);
+    let engine_config = EngineConfig::new()
         .with_use_system_contracts(use_system_contracts)
         .with_enable_bonding(enable_bonding)
+        .with_execution_threads(execution_threads)
+        .with_trace_host_calls(trace_host_calls);
+    let engine_config = match arg_matches.value_of(ARG_ESTIMATE_GAS_LIMIT) {
+        Some(value) => {
+            let estimate_gas_limit = value.parse().expect(ARG_ESTIMATE_GAS_LIMIT_EXPECT);
+            engine_config.with_estimate_gas_limit(estimate_gas_limit)
+        }
+        None => engine_config,
+    };
+    match arg_matches.value_of(ARG_MAX_TTL_MILLIS) {
+        Some(value) => {
+            let max_ttl_millis = value.parse().expect(ARG_MAX_TTL_MILLIS_EXPECT);
+            engine_config.with_max_ttl_millis(max_ttl_millis)
+        }
+        None => engine_config,
+    }
 }
 
 /// Builds and returns a gRPC server.
 fn get_grpc_server(
     socket: &socket::Socket,
     data_dir: PathBuf,
-    map_size: usize,
+    storage_backend: StorageBackend,
     thread_count: usize,
     engine_config: EngineConfig,
 ) -> grpc::Server {
-    let engine_state = get_engine_state(data_dir, map_size, engine_config);
+    let server_builder = match storage_backend {
+        StorageBackend::Lmdb { map_size } => {
+            let engine_state = get_lmdb_engine_state(data_dir, map_size, engine_config);
+            engine_server::new(socket.as_str(), thread_count, engine_state)
+        }
+        StorageBackend::File => {
+            let engine_state = get_file_engine_state(data_dir, engine_config);
+            engine_server::new(socket.as_str(), thread_count, engine_state)
+        }
+    };
 
-    engine_server::new(socket.as_str(), thread_count, engine_state)
-        .build()
-        .expect(SERVER_START_EXPECT)
+    server_builder.build().expect(SERVER_START_EXPECT)
 }
 
-/// Builds and returns engine global state
-fn get_engine_state(
+/// Builds and returns engine global state backed by LMDB
+fn get_lmdb_engine_state(
     data_dir: PathBuf,
     map_size: usize,
     engine_config: EngineConfig,
@@ -353,6 +468,26 @@ fn get_engine_state(
     EngineState::new(global_state, engine_config)
 }
 
+/// Builds and returns engine global state backed by an append-only log file
+fn get_file_engine_state(
+    data_dir: PathBuf,
+    engine_config: EngineConfig,
+) -> EngineState<FileGlobalState> {
+    let environment = {
+        let ret = FileEnvironment::new(&data_dir).expect(FILE_ENVIRONMENT_EXPECT);
+        Arc::new(ret)
+    };
+
+    let trie_store = Arc::new(FileTrieStore::new(&environment, None));
+
+    let protocol_data_store = Arc::new(FileProtocolDataStore::new(&environment, None));
+
+    let global_state = FileGlobalState::empty(environment, trie_store, protocol_data_store)
+        .expect(FILE_GLOBAL_STATE_EXPECT);
+
+    EngineState::new(global_state, engine_config)
+}
+
 /// Builds and returns log settings
 fn get_log_settings(arg_matches: &ArgMatches) -> Settings {
     let max_level = match arg_matches
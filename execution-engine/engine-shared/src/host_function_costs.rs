@@ -0,0 +1,494 @@
+use types::{
+    bytesrepr::{self, FromBytes, ToBytes, U32_SERIALIZED_LENGTH},
+    U512,
+};
+
+use crate::gas::Gas;
+
+const HOST_FUNCTION_COST_SERIALIZED_LENGTH: usize = 2 * U32_SERIALIZED_LENGTH;
+const NUM_FIELDS: usize = 47;
+pub const HOST_FUNCTION_COSTS_SERIALIZED_LENGTH: usize =
+    NUM_FIELDS * HOST_FUNCTION_COST_SERIALIZED_LENGTH;
+
+/// The cost of calling a host function: a fixed cost, plus a cost for each byte of its input.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct HostFunctionCost {
+    pub base: u32,
+    pub per_byte: u32,
+}
+
+impl HostFunctionCost {
+    pub const fn new(base: u32, per_byte: u32) -> Self {
+        HostFunctionCost { base, per_byte }
+    }
+
+    /// Returns the cost of a call with `input_size` bytes of input.
+    pub fn calculate(&self, input_size: usize) -> Gas {
+        Gas::new(U512::from(self.base) + U512::from(self.per_byte) * U512::from(input_size))
+    }
+}
+
+impl ToBytes for HostFunctionCost {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut ret = bytesrepr::unchecked_allocate_buffer(self);
+        ret.append(&mut self.base.to_bytes()?);
+        ret.append(&mut self.per_byte.to_bytes()?);
+        Ok(ret)
+    }
+
+    fn serialized_length(&self) -> usize {
+        HOST_FUNCTION_COST_SERIALIZED_LENGTH
+    }
+}
+
+impl FromBytes for HostFunctionCost {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (base, rem): (u32, &[u8]) = FromBytes::from_bytes(bytes)?;
+        let (per_byte, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+        Ok((HostFunctionCost { base, per_byte }, rem))
+    }
+}
+
+/// The costs of calling each host function, charged on top of the Wasm opcodes executed by the
+/// caller.
+///
+/// `gas` is not listed, as it is the host function through which opcodes are charged, and neither
+/// is `print`, which is only available in test builds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HostFunctionCosts {
+    /// Cost of writing to global state, per byte of the key and value
+    pub write: HostFunctionCost,
+    /// Cost of writing to local state, per byte of the key and value
+    pub write_local: HostFunctionCost,
+    /// Cost of reading from global state, per byte of the key
+    pub read_value: HostFunctionCost,
+    /// Cost of reading from local state, per byte of the key
+    pub read_value_local: HostFunctionCost,
+    /// Cost of adding to a value in global state, per byte of the key and value
+    pub add: HostFunctionCost,
+    /// Cost of creating a new URef, per byte of its initial value
+    pub new_uref: HostFunctionCost,
+    /// Cost of returning from a contract, per byte of the returned value
+    pub ret: HostFunctionCost,
+    /// Cost of calling a contract, per byte of the contract hash, entry point name and arguments
+    pub call_contract: HostFunctionCost,
+    /// Cost of getting a named key, per byte of the name
+    pub get_key: HostFunctionCost,
+    /// Cost of checking for a named key, per byte of the name
+    pub has_key: HostFunctionCost,
+    /// Cost of putting a named key, per byte of the name and key
+    pub put_key: HostFunctionCost,
+    /// Cost of validating a URef, per byte of the URef
+    pub is_valid_uref: HostFunctionCost,
+    /// Cost of reverting
+    pub revert: HostFunctionCost,
+    /// Cost of adding an associated key, per byte of the account hash
+    pub add_associated_key: HostFunctionCost,
+    /// Cost of removing an associated key, per byte of the account hash
+    pub remove_associated_key: HostFunctionCost,
+    /// Cost of updating an associated key, per byte of the account hash
+    pub update_associated_key: HostFunctionCost,
+    /// Cost of setting an action threshold
+    pub set_action_threshold: HostFunctionCost,
+    /// Cost of loading the named keys
+    pub load_named_keys: HostFunctionCost,
+    /// Cost of removing a named key, per byte of the name
+    pub remove_key: HostFunctionCost,
+    /// Cost of getting the caller
+    pub get_caller: HostFunctionCost,
+    /// Cost of getting the block time
+    pub get_blocktime: HostFunctionCost,
+    /// Cost of creating a purse
+    pub create_purse: HostFunctionCost,
+    /// Cost of transferring from the main purse to an account, per byte of the account hash and
+    /// amount
+    pub transfer_to_account: HostFunctionCost,
+    /// Cost of transferring from a purse to an account, per byte of the purse, account hash and
+    /// amount
+    pub transfer_from_purse_to_account: HostFunctionCost,
+    /// Cost of transferring from a purse to a purse, per byte of the purses and amount
+    pub transfer_from_purse_to_purse: HostFunctionCost,
+    /// Cost of getting the balance of a purse, per byte of the purse
+    pub get_balance: HostFunctionCost,
+    /// Cost of getting the execution phase
+    pub get_phase: HostFunctionCost,
+    /// Cost of getting a system contract
+    pub get_system_contract: HostFunctionCost,
+    /// Cost of getting the main purse
+    pub get_main_purse: HostFunctionCost,
+    /// Cost of reading the host buffer, per byte of the destination
+    pub read_host_buffer: HostFunctionCost,
+    /// Cost of creating a contract package
+    pub create_contract_package_at_hash: HostFunctionCost,
+    /// Cost of adding a contract version, per byte of the package hash, entry points and named
+    /// keys
+    pub add_contract_version: HostFunctionCost,
+    /// Cost of disabling a contract version, per byte of the package and contract hashes
+    pub disable_contract_version: HostFunctionCost,
+    /// Cost of calling a versioned contract, per byte of the package hash, version, entry point
+    /// name and arguments
+    pub call_versioned_contract: HostFunctionCost,
+    /// Cost of creating a contract user group, per byte of the package hash, label and existing
+    /// URefs
+    pub create_contract_user_group: HostFunctionCost,
+    /// Cost of getting the size of a named argument, per byte of the name
+    pub get_named_arg_size: HostFunctionCost,
+    /// Cost of getting a named argument, per byte of the name and destination
+    pub get_named_arg: HostFunctionCost,
+    /// Cost of removing a contract user group, per byte of the package hash and label
+    pub remove_contract_user_group: HostFunctionCost,
+    /// Cost of provisioning a URef for a contract user group, per byte of the package hash and
+    /// label
+    pub provision_contract_user_group_uref: HostFunctionCost,
+    /// Cost of removing URefs from a contract user group, per byte of the package hash, label and
+    /// URefs
+    pub remove_contract_user_group_urefs: HostFunctionCost,
+    /// Cost of removing a value from global state, per byte of the key
+    pub remove: HostFunctionCost,
+    /// Cost of removing a value from local state, per byte of the key
+    pub remove_local: HostFunctionCost,
+    /// Cost of emitting an event, per byte of its name and value
+    pub emit_event: HostFunctionCost,
+    /// Cost of hashing with blake2b-256, per byte hashed
+    pub blake2b: HostFunctionCost,
+    /// Cost of hashing with sha2-256, per byte hashed
+    pub sha256: HostFunctionCost,
+    /// Cost of verifying an ed25519 signature, per byte of the signed message
+    pub verify_ed25519: HostFunctionCost,
+    /// Cost of verifying a secp256k1 signature, per byte of the signed message
+    pub verify_secp256k1: HostFunctionCost,
+}
+
+impl HostFunctionCosts {
+    /// Returns costs under which every host function call is free, as it was before host
+    /// function calls were charged.
+    pub fn free() -> Self {
+        let free = HostFunctionCost::default();
+        HostFunctionCosts {
+            write: free,
+            write_local: free,
+            read_value: free,
+            read_value_local: free,
+            add: free,
+            new_uref: free,
+            ret: free,
+            call_contract: free,
+            get_key: free,
+            has_key: free,
+            put_key: free,
+            is_valid_uref: free,
+            revert: free,
+            add_associated_key: free,
+            remove_associated_key: free,
+            update_associated_key: free,
+            set_action_threshold: free,
+            load_named_keys: free,
+            remove_key: free,
+            get_caller: free,
+            get_blocktime: free,
+            create_purse: free,
+            transfer_to_account: free,
+            transfer_from_purse_to_account: free,
+            transfer_from_purse_to_purse: free,
+            get_balance: free,
+            get_phase: free,
+            get_system_contract: free,
+            get_main_purse: free,
+            read_host_buffer: free,
+            create_contract_package_at_hash: free,
+            add_contract_version: free,
+            disable_contract_version: free,
+            call_versioned_contract: free,
+            create_contract_user_group: free,
+            get_named_arg_size: free,
+            get_named_arg: free,
+            remove_contract_user_group: free,
+            provision_contract_user_group_uref: free,
+            remove_contract_user_group_urefs: free,
+            remove: free,
+            remove_local: free,
+            emit_event: free,
+            blake2b: free,
+            sha256: free,
+            verify_ed25519: free,
+            verify_secp256k1: free,
+        }
+    }
+}
+
+impl Default for HostFunctionCosts {
+    fn default() -> Self {
+        HostFunctionCosts {
+            write: HostFunctionCost::new(1_000, 1),
+            write_local: HostFunctionCost::new(1_000, 1),
+            read_value: HostFunctionCost::new(1_000, 1),
+            read_value_local: HostFunctionCost::new(1_000, 1),
+            add: HostFunctionCost::new(1_000, 1),
+            new_uref: HostFunctionCost::new(1_000, 1),
+            ret: HostFunctionCost::new(100, 1),
+            call_contract: HostFunctionCost::new(10_000, 1),
+            get_key: HostFunctionCost::new(200, 1),
+            has_key: HostFunctionCost::new(200, 1),
+            put_key: HostFunctionCost::new(1_000, 1),
+            is_valid_uref: HostFunctionCost::new(200, 0),
+            revert: HostFunctionCost::new(100, 0),
+            add_associated_key: HostFunctionCost::new(5_000, 0),
+            remove_associated_key: HostFunctionCost::new(5_000, 0),
+            update_associated_key: HostFunctionCost::new(5_000, 0),
+            set_action_threshold: HostFunctionCost::new(5_000, 0),
+            load_named_keys: HostFunctionCost::new(1_000, 0),
+            remove_key: HostFunctionCost::new(1_000, 1),
+            get_caller: HostFunctionCost::new(200, 0),
+            get_blocktime: HostFunctionCost::new(200, 0),
+            create_purse: HostFunctionCost::new(50_000, 0),
+            transfer_to_account: HostFunctionCost::new(50_000, 0),
+            transfer_from_purse_to_account: HostFunctionCost::new(50_000, 0),
+            transfer_from_purse_to_purse: HostFunctionCost::new(50_000, 0),
+            get_balance: HostFunctionCost::new(1_000, 0),
+            get_phase: HostFunctionCost::new(200, 0),
+            get_system_contract: HostFunctionCost::new(200, 0),
+            get_main_purse: HostFunctionCost::new(200, 0),
+            read_host_buffer: HostFunctionCost::new(200, 1),
+            create_contract_package_at_hash: HostFunctionCost::new(10_000, 0),
+            add_contract_version: HostFunctionCost::new(10_000, 1),
+            disable_contract_version: HostFunctionCost::new(5_000, 0),
+            call_versioned_contract: HostFunctionCost::new(10_000, 1),
+            create_contract_user_group: HostFunctionCost::new(5_000, 1),
+            get_named_arg_size: HostFunctionCost::new(200, 1),
+            get_named_arg: HostFunctionCost::new(200, 1),
+            remove_contract_user_group: HostFunctionCost::new(5_000, 1),
+            provision_contract_user_group_uref: HostFunctionCost::new(5_000, 1),
+            remove_contract_user_group_urefs: HostFunctionCost::new(5_000, 1),
+            remove: HostFunctionCost::new(1_000, 1),
+            remove_local: HostFunctionCost::new(1_000, 1),
+            emit_event: HostFunctionCost::new(0, 10),
+            blake2b: HostFunctionCost::new(1_000, 2),
+            sha256: HostFunctionCost::new(1_000, 4),
+            verify_ed25519: HostFunctionCost::new(50_000, 4),
+            verify_secp256k1: HostFunctionCost::new(60_000, 4),
+        }
+    }
+}
+
+impl ToBytes for HostFunctionCosts {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut ret = bytesrepr::unchecked_allocate_buffer(self);
+        ret.append(&mut self.write.to_bytes()?);
+        ret.append(&mut self.write_local.to_bytes()?);
+        ret.append(&mut self.read_value.to_bytes()?);
+        ret.append(&mut self.read_value_local.to_bytes()?);
+        ret.append(&mut self.add.to_bytes()?);
+        ret.append(&mut self.new_uref.to_bytes()?);
+        ret.append(&mut self.ret.to_bytes()?);
+        ret.append(&mut self.call_contract.to_bytes()?);
+        ret.append(&mut self.get_key.to_bytes()?);
+        ret.append(&mut self.has_key.to_bytes()?);
+        ret.append(&mut self.put_key.to_bytes()?);
+        ret.append(&mut self.is_valid_uref.to_bytes()?);
+        ret.append(&mut self.revert.to_bytes()?);
+        ret.append(&mut self.add_associated_key.to_bytes()?);
+        ret.append(&mut self.remove_associated_key.to_bytes()?);
+        ret.append(&mut self.update_associated_key.to_bytes()?);
+        ret.append(&mut self.set_action_threshold.to_bytes()?);
+        ret.append(&mut self.load_named_keys.to_bytes()?);
+        ret.append(&mut self.remove_key.to_bytes()?);
+        ret.append(&mut self.get_caller.to_bytes()?);
+        ret.append(&mut self.get_blocktime.to_bytes()?);
+        ret.append(&mut self.create_purse.to_bytes()?);
+        ret.append(&mut self.transfer_to_account.to_bytes()?);
+        ret.append(&mut self.transfer_from_purse_to_account.to_bytes()?);
+        ret.append(&mut self.transfer_from_purse_to_purse.to_bytes()?);
+        ret.append(&mut self.get_balance.to_bytes()?);
+        ret.append(&mut self.get_phase.to_bytes()?);
+        ret.append(&mut self.get_system_contract.to_bytes()?);
+        ret.append(&mut self.get_main_purse.to_bytes()?);
+        ret.append(&mut self.read_host_buffer.to_bytes()?);
+        ret.append(&mut self.create_contract_package_at_hash.to_bytes()?);
+        ret.append(&mut self.add_contract_version.to_bytes()?);
+        ret.append(&mut self.disable_contract_version.to_bytes()?);
+        ret.append(&mut self.call_versioned_contract.to_bytes()?);
+        ret.append(&mut self.create_contract_user_group.to_bytes()?);
+        ret.append(&mut self.get_named_arg_size.to_bytes()?);
+        ret.append(&mut self.get_named_arg.to_bytes()?);
+        ret.append(&mut self.remove_contract_user_group.to_bytes()?);
+        ret.append(&mut self.provision_contract_user_group_uref.to_bytes()?);
+        ret.append(&mut self.remove_contract_user_group_urefs.to_bytes()?);
+        ret.append(&mut self.remove.to_bytes()?);
+        ret.append(&mut self.remove_local.to_bytes()?);
+        ret.append(&mut self.emit_event.to_bytes()?);
+        ret.append(&mut self.blake2b.to_bytes()?);
+        ret.append(&mut self.sha256.to_bytes()?);
+        ret.append(&mut self.verify_ed25519.to_bytes()?);
+        ret.append(&mut self.verify_secp256k1.to_bytes()?);
+        Ok(ret)
+    }
+
+    fn serialized_length(&self) -> usize {
+        HOST_FUNCTION_COSTS_SERIALIZED_LENGTH
+    }
+}
+
+impl FromBytes for HostFunctionCosts {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (write, rem) = HostFunctionCost::from_bytes(bytes)?;
+        let (write_local, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (read_value, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (read_value_local, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (add, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (new_uref, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (ret, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (call_contract, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (has_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (put_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (is_valid_uref, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (revert, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (add_associated_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove_associated_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (update_associated_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (set_action_threshold, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (load_named_keys, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove_key, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_caller, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_blocktime, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (create_purse, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (transfer_to_account, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (transfer_from_purse_to_account, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (transfer_from_purse_to_purse, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_balance, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_phase, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_system_contract, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_main_purse, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (read_host_buffer, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (create_contract_package_at_hash, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (add_contract_version, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (disable_contract_version, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (call_versioned_contract, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (create_contract_user_group, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_named_arg_size, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (get_named_arg, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove_contract_user_group, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (provision_contract_user_group_uref, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove_contract_user_group_urefs, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (remove_local, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (emit_event, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (blake2b, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (sha256, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (verify_ed25519, rem) = HostFunctionCost::from_bytes(rem)?;
+        let (verify_secp256k1, rem) = HostFunctionCost::from_bytes(rem)?;
+        let host_function_costs = HostFunctionCosts {
+            write,
+            write_local,
+            read_value,
+            read_value_local,
+            add,
+            new_uref,
+            ret,
+            call_contract,
+            get_key,
+            has_key,
+            put_key,
+            is_valid_uref,
+            revert,
+            add_associated_key,
+            remove_associated_key,
+            update_associated_key,
+            set_action_threshold,
+            load_named_keys,
+            remove_key,
+            get_caller,
+            get_blocktime,
+            create_purse,
+            transfer_to_account,
+            transfer_from_purse_to_account,
+            transfer_from_purse_to_purse,
+            get_balance,
+            get_phase,
+            get_system_contract,
+            get_main_purse,
+            read_host_buffer,
+            create_contract_package_at_hash,
+            add_contract_version,
+            disable_contract_version,
+            call_versioned_contract,
+            create_contract_user_group,
+            get_named_arg_size,
+            get_named_arg,
+            remove_contract_user_group,
+            provision_contract_user_group_uref,
+            remove_contract_user_group_urefs,
+            remove,
+            remove_local,
+            emit_event,
+            blake2b,
+            sha256,
+            verify_ed25519,
+            verify_secp256k1,
+        };
+        Ok((host_function_costs, rem))
+    }
+}
+
+pub mod gens {
+    use proptest::{collection, num, prop_compose};
+
+    use types::bytesrepr::{self, ToBytes};
+
+    use super::{HostFunctionCost, HostFunctionCosts, NUM_FIELDS};
+
+    prop_compose! {
+        pub fn host_function_cost_arb()(
+            base in num::u32::ANY,
+            per_byte in num::u32::ANY,
+        ) -> HostFunctionCost {
+            HostFunctionCost { base, per_byte }
+        }
+    }
+
+    prop_compose! {
+        pub fn host_function_costs_arb()(
+            costs in collection::vec(host_function_cost_arb(), NUM_FIELDS),
+        ) -> HostFunctionCosts {
+            // There are too many fields to compose a strategy for each of them, so the table is
+            // deserialized from its fields' serialized form instead.
+            let bytes: Vec<u8> = costs
+                .iter()
+                .flat_map(|cost| cost.to_bytes().expect("should serialize"))
+                .collect();
+            bytesrepr::deserialize(bytes).expect("should deserialize")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::proptest;
+
+    use types::{bytesrepr, U512};
+
+    use super::{gens, HostFunctionCost, HostFunctionCosts};
+    use crate::gas::Gas;
+
+    #[test]
+    fn should_serialize_and_deserialize() {
+        bytesrepr::test_serialization_roundtrip(&HostFunctionCosts::default());
+        bytesrepr::test_serialization_roundtrip(&HostFunctionCosts::free());
+    }
+
+    #[test]
+    fn should_calculate_cost_from_input_size() {
+        let cost = HostFunctionCost::new(100, 3);
+        assert_eq!(cost.calculate(0), Gas::new(U512::from(100)));
+        assert_eq!(cost.calculate(10), Gas::new(U512::from(130)));
+    }
+
+    proptest! {
+        #[test]
+        fn should_serialize_and_deserialize_with_arbitrary_values(
+            host_function_costs in gens::host_function_costs_arb()
+        ) {
+            bytesrepr::test_serialization_roundtrip(&host_function_costs);
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub mod additive_map;
 #[macro_use]
 pub mod gas;
 pub mod account;
+pub mod host_function_costs;
 pub mod logging;
 pub mod motes;
 pub mod newtypes;
@@ -59,6 +59,8 @@ pub enum Transform {
     AddUInt256(U256),
     AddUInt512(U512),
     AddKeys(NamedKeys),
+    /// Removes the value from global state.
+    Delete,
     Failure(Error),
 }
 
@@ -136,6 +138,10 @@ where
 }
 
 impl Transform {
+    /// Applies the transform to `stored_value`, returning the updated value.
+    ///
+    /// `Transform::Delete` doesn't produce a value, so callers must handle it before calling this;
+    /// applying it returns a type mismatch.
     pub fn apply(self, stored_value: StoredValue) -> Result<StoredValue, Error> {
         match self {
             Transform::Identity => Ok(stored_value),
@@ -170,6 +176,11 @@ impl Transform {
                     Err(TypeMismatch::new(expected, found).into())
                 }
             },
+            Transform::Delete => {
+                let expected = "a transform producing a value".to_string();
+                let found = "Delete".to_string();
+                Err(TypeMismatch::new(expected, found).into())
+            }
             Transform::Failure(error) => Err(error),
         }
     }
@@ -217,6 +228,10 @@ impl Add for Transform {
             (a @ Transform::Failure(_), _) => a,
             (_, b @ Transform::Failure(_)) => b,
             (_, b @ Transform::Write(_)) => b,
+            (_, b @ Transform::Delete) => b,
+            (Transform::Delete, b) => Transform::Failure(
+                TypeMismatch::new("Write or Delete".to_owned(), format!("{:?}", b)).into(),
+            ),
             (Transform::Write(v), b) => {
                 // second transform changes value being written
                 match b.apply(v) {
@@ -287,6 +302,7 @@ pub mod gens {
     pub fn transform_arb() -> impl Strategy<Value = Transform> {
         prop_oneof![
             Just(Transform::Identity),
+            Just(Transform::Delete),
             stored_value_arb().prop_map(Transform::Write),
             any::<i32>().prop_map(Transform::AddInt32),
             any::<u64>().prop_map(Transform::AddUInt64),
@@ -746,4 +762,38 @@ mod tests {
         assert_eq!(ZERO_U512, add(MAX_U512, ONE_U512));
         assert_eq!(MAX_U512 - 1, add(MAX_U512, MAX_U512));
     }
+
+    #[test]
+    fn delete_should_override_earlier_transforms() {
+        let value = StoredValue::CLValue(CLValue::from_t(ONE_I32).unwrap());
+        assert_eq!(
+            Transform::Write(value) + Transform::Delete,
+            Transform::Delete
+        );
+        assert_eq!(
+            Transform::AddInt32(ONE_I32) + Transform::Delete,
+            Transform::Delete
+        );
+        assert_eq!(Transform::Identity + Transform::Delete, Transform::Delete);
+        assert_eq!(Transform::Delete + Transform::Identity, Transform::Delete);
+    }
+
+    #[test]
+    fn write_after_delete_should_succeed() {
+        let value = StoredValue::CLValue(CLValue::from_t(ONE_I32).unwrap());
+        assert_eq!(
+            Transform::Delete + Transform::Write(value.clone()),
+            Transform::Write(value)
+        );
+    }
+
+    #[test]
+    fn addition_after_delete_should_fail() {
+        match Transform::Delete + Transform::AddInt32(ONE_I32) {
+            Transform::Failure(Error::TypeMismatch(_)) => (),
+            other => panic!("expected type mismatch, got {:?}", other),
+        }
+        let value = StoredValue::CLValue(CLValue::from_t(ONE_I32).unwrap());
+        assert!(Transform::Delete.apply(value).is_err());
+    }
 }
@@ -0,0 +1,47 @@
+use std::{io, sync};
+
+use failure::Fail;
+
+use types::bytesrepr;
+
+#[derive(Debug, Clone, Fail, PartialEq, Eq)]
+pub enum Error {
+    #[fail(display = "I/O error: {}", _0)]
+    Io(String),
+
+    #[fail(display = "Log is corrupt at offset {}", _0)]
+    CorruptLog(u64),
+
+    #[fail(display = "Index run {} is corrupt", _0)]
+    CorruptRun(u64),
+
+    #[fail(display = "Directory is locked by another environment")]
+    DirectoryLocked,
+
+    #[fail(display = "A read transaction of an older state is open")]
+    ReadTransactionOpen,
+
+    #[fail(display = "{}", _0)]
+    BytesRepr(#[fail(cause)] bytesrepr::Error),
+
+    #[fail(display = "Another thread panicked while holding a lock")]
+    Poison,
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error.to_string())
+    }
+}
+
+impl From<bytesrepr::Error> for Error {
+    fn from(error: bytesrepr::Error) -> Self {
+        Error::BytesRepr(error)
+    }
+}
+
+impl<T> From<sync::PoisonError<T>> for Error {
+    fn from(_error: sync::PoisonError<T>) -> Self {
+        Error::Poison
+    }
+}
@@ -1,4 +1,6 @@
+pub mod file;
 pub mod in_memory;
 pub mod lmdb;
+pub mod snapshot;
 
 pub use self::lmdb::Error;
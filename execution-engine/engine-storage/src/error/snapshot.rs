@@ -0,0 +1,76 @@
+use std::io;
+
+use failure::Fail;
+
+use types::bytesrepr;
+
+use super::{file, in_memory, lmdb};
+
+#[derive(Debug, Clone, Fail, PartialEq, Eq)]
+pub enum Error {
+    #[fail(display = "I/O error: {}", _0)]
+    Io(String),
+
+    #[fail(display = "Not a global state snapshot")]
+    BadMagic,
+
+    #[fail(display = "Unsupported snapshot format version {}", _0)]
+    UnsupportedVersion(u32),
+
+    #[fail(display = "Unknown snapshot record tag {}", _0)]
+    UnknownRecord(u8),
+
+    #[fail(display = "Snapshot leaves are not in ascending order of their keys")]
+    UnsortedLeaves,
+
+    #[fail(display = "Snapshot record counts do not match its contents")]
+    CountMismatch,
+
+    #[fail(display = "Snapshot checksum does not match its contents")]
+    ChecksumMismatch,
+
+    #[fail(display = "{}", _0)]
+    BytesRepr(#[fail(cause)] bytesrepr::Error),
+
+    #[fail(display = "{}", _0)]
+    Storage(#[fail(cause)] lmdb::Error),
+
+    #[fail(display = "{}", _0)]
+    FileStorage(#[fail(cause)] file::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error.to_string())
+    }
+}
+
+impl From<bytesrepr::Error> for Error {
+    fn from(error: bytesrepr::Error) -> Self {
+        Error::BytesRepr(error)
+    }
+}
+
+impl From<lmdb::Error> for Error {
+    fn from(error: lmdb::Error) -> Self {
+        match error {
+            lmdb::Error::BytesRepr(error) => Error::BytesRepr(error),
+            error => Error::Storage(error),
+        }
+    }
+}
+
+impl From<in_memory::Error> for Error {
+    fn from(error: in_memory::Error) -> Self {
+        lmdb::Error::from(error).into()
+    }
+}
+
+impl From<file::Error> for Error {
+    fn from(error: file::Error) -> Self {
+        match error {
+            file::Error::BytesRepr(error) => Error::BytesRepr(error),
+            error => Error::FileStorage(error),
+        }
+    }
+}
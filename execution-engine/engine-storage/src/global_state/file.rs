@@ -0,0 +1,504 @@
+use std::{ops::Deref, sync::Arc};
+
+use engine_shared::{
+    additive_map::AdditiveMap,
+    newtypes::{Blake2bHash, CorrelationId},
+    stored_value::StoredValue,
+    transform::Transform,
+};
+use types::{Key, ProtocolVersion};
+
+use crate::{
+    error::file::Error,
+    global_state::{
+        commit, commit_many, CommitManyResult, CommitResult, DiffResult, KeyRange, PruneResult,
+        StateProvider, StateReader, PRUNE_BATCH_SIZE,
+    },
+    protocol_data::ProtocolData,
+    protocol_data_store::file::FileProtocolDataStore,
+    store::Store,
+    transaction_source::{
+        file::{FileEnvironment, FileReadTransaction},
+        Transaction, TransactionSource,
+    },
+    trie::{operations::create_hashed_empty_trie, Trie, TrieAbsenceProof, TrieMerkleProof},
+    trie_store::{
+        file::FileTrieStore,
+        operations::{
+            self, prove_absence, read, read_range, read_with_proof, AbsenceProofResult, ReadResult,
+        },
+        prune_marks::{self, PruneMarkStore},
+    },
+};
+
+pub struct FileGlobalState {
+    pub environment: Arc<FileEnvironment>,
+    pub trie_store: Arc<FileTrieStore>,
+    pub protocol_data_store: Arc<FileProtocolDataStore>,
+    pub empty_root_hash: Blake2bHash,
+}
+
+/// Represents a "view" of global state at a particular root hash.
+pub struct FileGlobalStateView {
+    pub environment: Arc<FileEnvironment>,
+    pub store: Arc<FileTrieStore>,
+    pub root_hash: Blake2bHash,
+}
+
+impl FileGlobalState {
+    /// Creates an empty state from an existing environment and trie_store.
+    pub fn empty(
+        environment: Arc<FileEnvironment>,
+        trie_store: Arc<FileTrieStore>,
+        protocol_data_store: Arc<FileProtocolDataStore>,
+    ) -> Result<Self, Error> {
+        let root_hash: Blake2bHash = {
+            let (root_hash, root) = create_hashed_empty_trie::<Key, StoredValue>()?;
+            // Every batch written to the log is kept, so only write the root the first time.
+            let mut txn = environment.create_read_write_txn()?;
+            let maybe_existing: Option<Trie<Key, StoredValue>> =
+                trie_store.get(&txn, &root_hash)?;
+            if maybe_existing.is_none() {
+                trie_store.put(&mut txn, &root_hash, &root)?;
+            }
+            txn.commit()?;
+            root_hash
+        };
+        Ok(FileGlobalState::new(
+            environment,
+            trie_store,
+            protocol_data_store,
+            root_hash,
+        ))
+    }
+
+    /// Creates a state from an existing environment, store, and root_hash.
+    /// Intended to be used for testing.
+    pub(crate) fn new(
+        environment: Arc<FileEnvironment>,
+        trie_store: Arc<FileTrieStore>,
+        protocol_data_store: Arc<FileProtocolDataStore>,
+        empty_root_hash: Blake2bHash,
+    ) -> Self {
+        FileGlobalState {
+            environment,
+            trie_store,
+            protocol_data_store,
+            empty_root_hash,
+        }
+    }
+}
+
+impl StateReader<Key, StoredValue> for FileGlobalStateView {
+    type Error = Error;
+
+    fn read(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<StoredValue>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match read::<Key, StoredValue, FileReadTransaction, FileTrieStore, Self::Error>(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            key,
+        )? {
+            ReadResult::Found(value) => Some(value),
+            ReadResult::NotFound => None,
+            ReadResult::RootNotFound => panic!("FileGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
+
+    fn read_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieMerkleProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match read_with_proof::<
+            Key,
+            StoredValue,
+            FileReadTransaction,
+            FileTrieStore,
+            Self::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            key,
+        )? {
+            ReadResult::Found(proof) => Some(proof),
+            ReadResult::NotFound => None,
+            ReadResult::RootNotFound => panic!("FileGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
+
+    fn prove_absence(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieAbsenceProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match prove_absence::<
+            Key,
+            StoredValue,
+            FileReadTransaction,
+            FileTrieStore,
+            Self::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            key,
+        )? {
+            AbsenceProofResult::Absent(proof) => Some(proof),
+            AbsenceProofResult::Present => None,
+            AbsenceProofResult::RootNotFound => panic!("FileGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
+
+    fn read_range(
+        &self,
+        correlation_id: CorrelationId,
+        range: &KeyRange,
+        limit: usize,
+    ) -> Result<Vec<(Key, StoredValue)>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret =
+            match read_range::<Key, StoredValue, FileReadTransaction, FileTrieStore, Self::Error>(
+                correlation_id,
+                &txn,
+                self.store.deref(),
+                &self.root_hash,
+                range,
+                limit,
+            )? {
+                ReadResult::Found(pairs) => pairs,
+                ReadResult::NotFound | ReadResult::RootNotFound => {
+                    panic!("FileGlobalState has invalid root")
+                }
+            };
+        txn.commit()?;
+        Ok(ret)
+    }
+}
+
+impl StateProvider for FileGlobalState {
+    type Error = Error;
+
+    type Reader = FileGlobalStateView;
+
+    fn checkout(&self, state_hash: Blake2bHash) -> Result<Option<Self::Reader>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let maybe_root: Option<Trie<Key, StoredValue>> = self.trie_store.get(&txn, &state_hash)?;
+        let maybe_state = maybe_root.map(|_| FileGlobalStateView {
+            environment: Arc::clone(&self.environment),
+            store: Arc::clone(&self.trie_store),
+            root_hash: state_hash,
+        });
+        txn.commit()?;
+        Ok(maybe_state)
+    }
+
+    fn commit(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Blake2bHash,
+        effects: AdditiveMap<Key, Transform>,
+    ) -> Result<CommitResult, Self::Error> {
+        let commit_result = commit::<FileEnvironment, FileTrieStore, _, Self::Error>(
+            &self.environment,
+            &self.trie_store,
+            correlation_id,
+            prestate_hash,
+            effects,
+        )?;
+        Ok(commit_result)
+    }
+
+    fn commit_many(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Blake2bHash,
+        effects: Vec<AdditiveMap<Key, Transform>>,
+    ) -> Result<CommitManyResult, Self::Error> {
+        let commit_many_result = commit_many::<FileEnvironment, FileTrieStore, _, Self::Error>(
+            &self.environment,
+            &self.trie_store,
+            correlation_id,
+            prestate_hash,
+            effects,
+        )?;
+        Ok(commit_many_result)
+    }
+
+    fn put_protocol_data(
+        &self,
+        protocol_version: ProtocolVersion,
+        protocol_data: &ProtocolData,
+    ) -> Result<(), Self::Error> {
+        let mut txn = self.environment.create_read_write_txn()?;
+        self.protocol_data_store
+            .put(&mut txn, &protocol_version, protocol_data)?;
+        txn.commit().map_err(Into::into)
+    }
+
+    fn get_protocol_data(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Option<ProtocolData>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let result = self.protocol_data_store.get(&txn, &protocol_version)?;
+        txn.commit()?;
+        Ok(result)
+    }
+
+    fn empty_root(&self) -> Blake2bHash {
+        self.empty_root_hash
+    }
+
+    fn prune(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: &[Blake2bHash],
+    ) -> Result<PruneResult, Self::Error> {
+        let mut roots_to_keep = state_roots.to_vec();
+        roots_to_keep.push(self.empty_root_hash);
+        let marks = PruneMarkStore::new(prune_marks::NAME.to_string());
+        let result =
+            operations::prune::<Key, StoredValue, FileEnvironment, FileTrieStore, Self::Error>(
+                correlation_id,
+                &self.environment,
+                &self.trie_store,
+                &marks,
+                &roots_to_keep,
+                PRUNE_BATCH_SIZE,
+            )?;
+        // Deleting only appends to the log, so it is compacted to reclaim the space.  If a read
+        // transaction of an older state is still open, this is left to the next prune.
+        match self.environment.compact() {
+            Ok(()) | Err(Error::ReadTransactionOpen) => Ok(result),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn diff(
+        &self,
+        correlation_id: CorrelationId,
+        left: Blake2bHash,
+        right: Blake2bHash,
+    ) -> Result<DiffResult<Key, StoredValue>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let result = operations::diff::<Key, StoredValue, _, FileTrieStore, Self::Error>(
+            correlation_id,
+            &txn,
+            &self.trie_store,
+            &left,
+            &right,
+        )?;
+        txn.commit()?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use tempfile::tempdir;
+
+    use types::{account::AccountHash, CLValue};
+
+    use crate::transaction_source::file::LOG_FILE_NAME;
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct TestPair {
+        key: Key,
+        value: StoredValue,
+    }
+
+    fn create_test_pairs() -> [TestPair; 2] {
+        [
+            TestPair {
+                key: Key::Account(AccountHash::new([1_u8; 32])),
+                value: StoredValue::CLValue(CLValue::from_t(1_i32).unwrap()),
+            },
+            TestPair {
+                key: Key::Account(AccountHash::new([2_u8; 32])),
+                value: StoredValue::CLValue(CLValue::from_t(2_i32).unwrap()),
+            },
+        ]
+    }
+
+    fn create_test_pairs_updated() -> [TestPair; 3] {
+        [
+            TestPair {
+                key: Key::Account(AccountHash::new([1u8; 32])),
+                value: StoredValue::CLValue(CLValue::from_t("one".to_string()).unwrap()),
+            },
+            TestPair {
+                key: Key::Account(AccountHash::new([2u8; 32])),
+                value: StoredValue::CLValue(CLValue::from_t("two".to_string()).unwrap()),
+            },
+            TestPair {
+                key: Key::Account(AccountHash::new([3u8; 32])),
+                value: StoredValue::CLValue(CLValue::from_t(3_i32).unwrap()),
+            },
+        ]
+    }
+
+    fn open_state(path: &Path) -> FileGlobalState {
+        let environment = Arc::new(FileEnvironment::new(&path.to_path_buf()).unwrap());
+        let trie_store = Arc::new(FileTrieStore::new(&environment, None));
+        let protocol_data_store = Arc::new(FileProtocolDataStore::new(&environment, None));
+        FileGlobalState::empty(environment, trie_store, protocol_data_store).unwrap()
+    }
+
+    fn commit_pairs(
+        state: &FileGlobalState,
+        root_hash: Blake2bHash,
+        pairs: &[TestPair],
+    ) -> Blake2bHash {
+        let correlation_id = CorrelationId::new();
+        let effects: AdditiveMap<Key, Transform> = pairs
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+        match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        }
+    }
+
+    #[test]
+    fn reads_from_a_checkout_return_expected_values() {
+        let correlation_id = CorrelationId::new();
+        let temp_dir = tempdir().unwrap();
+        let state = open_state(temp_dir.path());
+        let root_hash = commit_pairs(&state, state.empty_root(), &create_test_pairs());
+
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            assert_eq!(Some(value), checkout.read(correlation_id, &key).unwrap());
+        }
+    }
+
+    #[test]
+    fn checkout_fails_if_unknown_hash_is_given() {
+        let temp_dir = tempdir().unwrap();
+        let state = open_state(temp_dir.path());
+        let fake_hash: Blake2bHash = [1u8; 32].into();
+        let result = state.checkout(fake_hash).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn commit_updates_state_and_original_state_stays_intact() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs_updated = create_test_pairs_updated();
+        let temp_dir = tempdir().unwrap();
+        let state = open_state(temp_dir.path());
+        let root_hash = commit_pairs(&state, state.empty_root(), &create_test_pairs());
+
+        let updated_hash = commit_pairs(&state, root_hash, &test_pairs_updated);
+
+        let updated_checkout = state.checkout(updated_hash).unwrap().unwrap();
+        for TestPair { key, value } in test_pairs_updated.iter().cloned() {
+            assert_eq!(
+                Some(value),
+                updated_checkout.read(correlation_id, &key).unwrap()
+            );
+        }
+
+        let original_checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            assert_eq!(
+                Some(value),
+                original_checkout.read(correlation_id, &key).unwrap()
+            );
+        }
+        assert_eq!(
+            None,
+            original_checkout
+                .read(correlation_id, &test_pairs_updated[2].key)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn committed_state_survives_reopening() {
+        let correlation_id = CorrelationId::new();
+        let temp_dir = tempdir().unwrap();
+        let root_hash = {
+            let state = open_state(temp_dir.path());
+            commit_pairs(&state, state.empty_root(), &create_test_pairs())
+        };
+
+        let state = open_state(temp_dir.path());
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            assert_eq!(Some(value), checkout.read(correlation_id, &key).unwrap());
+        }
+    }
+
+    #[test]
+    fn reopening_does_not_grow_the_log() {
+        let temp_dir = tempdir().unwrap();
+        let log_length = || {
+            fs::metadata(temp_dir.path().join(LOG_FILE_NAME))
+                .unwrap()
+                .len()
+        };
+        drop(open_state(temp_dir.path()));
+        let initial_length = log_length();
+
+        drop(open_state(temp_dir.path()));
+        assert_eq!(log_length(), initial_length);
+    }
+
+    #[test]
+    fn prune_removes_states_which_are_not_kept() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs_updated = create_test_pairs_updated();
+        let temp_dir = tempdir().unwrap();
+        let log_path = temp_dir.path().join(LOG_FILE_NAME);
+        let state = open_state(temp_dir.path());
+        let root_hash = commit_pairs(&state, state.empty_root(), &create_test_pairs());
+        let updated_hash = commit_pairs(&state, root_hash, &test_pairs_updated);
+        let log_length = fs::metadata(&log_path).unwrap().len();
+
+        match state.prune(correlation_id, &[updated_hash]).unwrap() {
+            PruneResult::Pruned { deleted, .. } => assert!(deleted > 0),
+            PruneResult::RootNotFound(root) => panic!("root not found: {}", root),
+        }
+        // The log is compacted, dropping the pruned tries.
+        assert!(fs::metadata(&log_path).unwrap().len() < log_length);
+
+        // Pruning is durable, and leaves the kept states intact.
+        drop(state);
+        let state = open_state(temp_dir.path());
+        assert!(state.checkout(root_hash).unwrap().is_none());
+        assert!(state.checkout(state.empty_root()).unwrap().is_some());
+
+        let updated_checkout = state.checkout(updated_hash).unwrap().unwrap();
+        for TestPair { key, value } in test_pairs_updated.iter().cloned() {
+            assert_eq!(
+                Some(value),
+                updated_checkout.read(correlation_id, &key).unwrap()
+            );
+        }
+    }
+}
@@ -10,7 +10,10 @@ use types::{Key, ProtocolVersion};
 
 use crate::{
     error::{self, in_memory},
-    global_state::{commit, CommitResult, StateProvider, StateReader},
+    global_state::{
+        commit, commit_many, CommitManyResult, CommitResult, DiffResult, KeyRange, PruneResult,
+        StateProvider, StateReader, PRUNE_BATCH_SIZE,
+    },
     protocol_data::ProtocolData,
     protocol_data_store::in_memory::InMemoryProtocolDataStore,
     store::Store,
@@ -18,10 +21,14 @@ use crate::{
         in_memory::{InMemoryEnvironment, InMemoryReadTransaction},
         Transaction, TransactionSource,
     },
-    trie::{operations::create_hashed_empty_trie, Trie},
+    trie::{operations::create_hashed_empty_trie, Trie, TrieAbsenceProof, TrieMerkleProof},
     trie_store::{
         in_memory::InMemoryTrieStore,
-        operations::{self, read, ReadResult, WriteResult},
+        operations::{
+            self, prove_absence, read, read_range, read_with_proof, AbsenceProofResult, ReadResult,
+            WriteResult,
+        },
+        prune_marks::{self, PruneMarkStore},
     },
 };
 
@@ -137,6 +144,90 @@ impl StateReader<Key, StoredValue> for InMemoryGlobalStateView {
         txn.commit()?;
         Ok(ret)
     }
+
+    fn read_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieMerkleProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match read_with_proof::<
+            Key,
+            StoredValue,
+            InMemoryReadTransaction,
+            InMemoryTrieStore,
+            Self::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            key,
+        )? {
+            ReadResult::Found(proof) => Some(proof),
+            ReadResult::NotFound => None,
+            ReadResult::RootNotFound => panic!("InMemoryGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
+
+    fn prove_absence(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieAbsenceProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match prove_absence::<
+            Key,
+            StoredValue,
+            InMemoryReadTransaction,
+            InMemoryTrieStore,
+            Self::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            key,
+        )? {
+            AbsenceProofResult::Absent(proof) => Some(proof),
+            AbsenceProofResult::Present => None,
+            AbsenceProofResult::RootNotFound => panic!("InMemoryGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
+
+    fn read_range(
+        &self,
+        correlation_id: CorrelationId,
+        range: &KeyRange,
+        limit: usize,
+    ) -> Result<Vec<(Key, StoredValue)>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let ret = match read_range::<
+            Key,
+            StoredValue,
+            InMemoryReadTransaction,
+            InMemoryTrieStore,
+            Self::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.store.deref(),
+            &self.root_hash,
+            range,
+            limit,
+        )? {
+            ReadResult::Found(pairs) => pairs,
+            ReadResult::NotFound | ReadResult::RootNotFound => {
+                panic!("InMemoryGlobalState has invalid root")
+            }
+        };
+        txn.commit()?;
+        Ok(ret)
+    }
 }
 
 impl StateProvider for InMemoryGlobalState {
@@ -173,6 +264,23 @@ impl StateProvider for InMemoryGlobalState {
         Ok(commit_result)
     }
 
+    fn commit_many(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Blake2bHash,
+        effects: Vec<AdditiveMap<Key, Transform>>,
+    ) -> Result<CommitManyResult, Self::Error> {
+        let commit_many_result =
+            commit_many::<InMemoryEnvironment, InMemoryTrieStore, _, Self::Error>(
+                &self.environment,
+                &self.trie_store,
+                correlation_id,
+                prestate_hash,
+                effects,
+            )?;
+        Ok(commit_many_result)
+    }
+
     fn put_protocol_data(
         &self,
         protocol_version: ProtocolVersion,
@@ -197,11 +305,52 @@ impl StateProvider for InMemoryGlobalState {
     fn empty_root(&self) -> Blake2bHash {
         self.empty_root_hash
     }
+
+    fn prune(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: &[Blake2bHash],
+    ) -> Result<PruneResult, Self::Error> {
+        let mut roots_to_keep = state_roots.to_vec();
+        roots_to_keep.push(self.empty_root_hash);
+        let marks = PruneMarkStore::new(Some(prune_marks::NAME.to_string()));
+        operations::prune::<Key, StoredValue, InMemoryEnvironment, InMemoryTrieStore, Self::Error>(
+            correlation_id,
+            &self.environment,
+            &self.trie_store,
+            &marks,
+            &roots_to_keep,
+            PRUNE_BATCH_SIZE,
+        )
+    }
+
+    fn diff(
+        &self,
+        correlation_id: CorrelationId,
+        left: Blake2bHash,
+        right: Blake2bHash,
+    ) -> Result<DiffResult<Key, StoredValue>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let result = operations::diff::<Key, StoredValue, _, InMemoryTrieStore, Self::Error>(
+            correlation_id,
+            &txn,
+            &self.trie_store,
+            &left,
+            &right,
+        )?;
+        txn.commit()?;
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use types::{account::AccountHash, CLValue};
+    use types::{
+        account::AccountHash,
+        bytesrepr::ToBytes,
+        merkle_proof::{verify_absence_proof, verify_inclusion_proof},
+        CLValue,
+    };
 
     use super::*;
 
@@ -263,6 +412,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reads_with_proof_from_a_checkout_return_verifiable_values() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            let proof = checkout
+                .read_with_proof(correlation_id, &key)
+                .unwrap()
+                .unwrap();
+            assert_eq!(proof.value(), &value);
+            let proof_steps: Vec<Vec<u8>> = proof
+                .proof_steps()
+                .iter()
+                .map(|step| step.to_bytes().unwrap())
+                .collect();
+            let value_bytes =
+                verify_inclusion_proof(&root_hash.value(), &key, &proof_steps).unwrap();
+            assert_eq!(value_bytes, value.to_bytes().unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn proves_absence_of_keys_missing_from_a_checkout() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, .. } in create_test_pairs().iter().cloned() {
+            assert_eq!(None, checkout.prove_absence(correlation_id, &key).unwrap());
+        }
+        for key in &[
+            Key::Account(AccountHash::new([3_u8; 32])),
+            Key::Hash([1_u8; 32]),
+        ] {
+            let proof = checkout
+                .prove_absence(correlation_id, key)
+                .unwrap()
+                .unwrap();
+            let proof_steps: Vec<Vec<u8>> = proof
+                .proof_steps()
+                .iter()
+                .map(|step| step.to_bytes().unwrap())
+                .collect();
+            verify_absence_proof(&root_hash.value(), key, &proof_steps).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_ranges_of_keys_from_a_checkout() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        let expected: Vec<(Key, StoredValue)> = create_test_pairs()
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, value))
+            .collect();
+
+        let account_tag = &expected[0].0.to_bytes().unwrap()[..1];
+        let accounts = KeyRange::with_prefix(account_tag);
+        assert_eq!(
+            expected,
+            checkout
+                .read_range(correlation_id, &accounts, usize::max_value())
+                .unwrap()
+        );
+
+        let first_page = checkout.read_range(correlation_id, &accounts, 1).unwrap();
+        assert_eq!(&expected[..1], first_page.as_slice());
+        let next_range = accounts.after(&first_page[0].0.to_bytes().unwrap());
+        let second_page = checkout.read_range(correlation_id, &next_range, 1).unwrap();
+        assert_eq!(&expected[1..], second_page.as_slice());
+
+        let hash_tag = Key::Hash([1_u8; 32]).to_bytes().unwrap()[..1].to_vec();
+        let hashes = KeyRange::with_prefix(&hash_tag);
+        assert!(checkout
+            .read_range(correlation_id, &hashes, usize::max_value())
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn checkout_fails_if_unknown_hash_is_given() {
         let (state, _) = create_test_state();
@@ -343,6 +573,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diff_returns_changes_between_roots() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs = create_test_pairs();
+        let test_pairs_updated = create_test_pairs_updated();
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+
+        let updated_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        let expected: Vec<(Key, Option<StoredValue>, Option<StoredValue>)> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| {
+                let old_value = test_pairs
+                    .iter()
+                    .find(|pair| pair.key == key)
+                    .map(|pair| pair.value.clone());
+                (key, old_value, Some(value))
+            })
+            .collect();
+
+        assert_eq!(
+            state.diff(correlation_id, root_hash, updated_hash).unwrap(),
+            DiffResult::Diff(expected.clone())
+        );
+        assert_eq!(
+            state.diff(correlation_id, updated_hash, root_hash).unwrap(),
+            DiffResult::Diff(
+                expected
+                    .into_iter()
+                    .map(|(key, left, right)| (key, right, left))
+                    .collect()
+            )
+        );
+        assert_eq!(
+            state.diff(correlation_id, root_hash, root_hash).unwrap(),
+            DiffResult::Diff(Vec::new())
+        );
+    }
+
+    #[test]
+    fn commit_deletes_keys() {
+        let correlation_id = CorrelationId::new();
+        let [deleted_pair, kept_pair] = create_test_pairs();
+        let missing_key = Key::Account(AccountHash::new([9u8; 32]));
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = vec![
+            (deleted_pair.key, Transform::Delete),
+            (missing_key, Transform::Delete),
+        ]
+        .into_iter()
+        .collect();
+
+        let deleted_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        let checkout = state.checkout(deleted_hash).unwrap().unwrap();
+        assert_eq!(
+            None,
+            checkout.read(correlation_id, &deleted_pair.key).unwrap()
+        );
+        assert_eq!(
+            Some(kept_pair.value.clone()),
+            checkout.read(correlation_id, &kept_pair.key).unwrap()
+        );
+
+        // The state is the same as if the deleted key had never been written.
+        let effects: AdditiveMap<Key, Transform> =
+            vec![(kept_pair.key, Transform::Write(kept_pair.value))]
+                .into_iter()
+                .collect();
+        let expected_hash = match state
+            .commit(correlation_id, state.empty_root(), effects)
+            .unwrap()
+        {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+        assert_eq!(deleted_hash, expected_hash);
+    }
+
+    #[test]
+    fn prune_removes_states_which_are_not_kept() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs_updated = create_test_pairs_updated();
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+
+        let updated_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        match state.prune(correlation_id, &[updated_hash]).unwrap() {
+            PruneResult::Pruned { deleted, .. } => assert!(deleted > 0),
+            PruneResult::RootNotFound(root) => panic!("root not found: {}", root),
+        }
+
+        assert!(state.checkout(root_hash).unwrap().is_none());
+        assert!(state.checkout(state.empty_root()).unwrap().is_some());
+
+        let updated_checkout = state.checkout(updated_hash).unwrap().unwrap();
+        for TestPair { key, value } in test_pairs_updated.iter().cloned() {
+            assert_eq!(
+                Some(value),
+                updated_checkout.read(correlation_id, &key).unwrap()
+            );
+        }
+    }
+
     #[test]
     fn initial_state_has_the_expected_hash() {
         let correlation_id = CorrelationId::new();
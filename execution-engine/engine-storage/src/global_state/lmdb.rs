@@ -1,4 +1,6 @@
-use std::{ops::Deref, sync::Arc};
+use std::{collections::HashSet, ops::Deref, sync::Arc};
+
+use lmdb::{Database, DatabaseFlags};
 
 use engine_shared::{
     additive_map::AdditiveMap,
@@ -10,23 +12,44 @@ use types::{Key, ProtocolVersion};
 
 use crate::{
     error,
-    global_state::{commit, CommitResult, StateProvider, StateReader},
+    global_state::{
+        self, commit, commit_many, CommitManyResult, CommitResult, DiffResult, ExportChunkResult,
+        ImportChunkResult, IntegrityReport, KeyRange, PruneResult, StateProvider, StateReader,
+        PRUNE_BATCH_SIZE,
+    },
     protocol_data::ProtocolData,
     protocol_data_store::lmdb::LmdbProtocolDataStore,
     store::Store,
     transaction_source::{lmdb::LmdbEnvironment, Transaction, TransactionSource},
-    trie::{operations::create_hashed_empty_trie, Trie},
+    trie::{
+        operations::create_hashed_empty_trie, Trie, TrieAbsenceProof, TrieChunk, TrieMerkleProof,
+    },
     trie_store::{
+        cache::{CachingTrieStore, TrieCache, DEFAULT_TRIE_CACHE_CAPACITY},
+        incomplete_roots::{self, IncompleteRootStore},
         lmdb::LmdbTrieStore,
-        operations::{read, ReadResult},
+        operations::{
+            self, prove_absence, read, read_range, read_with_proof, AbsenceProofResult, ReadResult,
+        },
+        prune_marks::{self, PruneMarkStore},
     },
 };
 
+const READ: &str = "read";
+const READ_WITH_PROOF: &str = "read_with_proof";
+const PROVE_ABSENCE: &str = "prove_absence";
+const READ_RANGE: &str = "read_range";
+const COMMIT: &str = "commit";
+const COMMIT_MANY: &str = "commit_many";
+const DIFF: &str = "diff";
+
 pub struct LmdbGlobalState {
     pub environment: Arc<LmdbEnvironment>,
     pub trie_store: Arc<LmdbTrieStore>,
     pub protocol_data_store: Arc<LmdbProtocolDataStore>,
     pub empty_root_hash: Blake2bHash,
+    /// Deserialized trie elements, shared by all the views of this state.
+    pub trie_cache: Arc<TrieCache<Key, StoredValue>>,
 }
 
 /// Represents a "view" of global state at a particular root hash.
@@ -34,6 +57,7 @@ pub struct LmdbGlobalStateView {
     pub environment: Arc<LmdbEnvironment>,
     pub store: Arc<LmdbTrieStore>,
     pub root_hash: Blake2bHash,
+    pub trie_cache: Arc<TrieCache<Key, StoredValue>>,
 }
 
 impl LmdbGlobalState {
@@ -71,8 +95,110 @@ impl LmdbGlobalState {
             trie_store,
             protocol_data_store,
             empty_root_hash,
+            trie_cache: Arc::new(TrieCache::new(DEFAULT_TRIE_CACHE_CAPACITY)),
         }
     }
+
+    /// Replaces the trie cache with an empty one holding at most roughly `capacity` bytes.
+    pub fn with_trie_cache_capacity(mut self, capacity: usize) -> Self {
+        self.trie_cache = Arc::new(TrieCache::new(capacity));
+        self
+    }
+
+    /// Exports a chunk of at most `chunk_size` serialized trie elements for state sync, taken from
+    /// the `pending` stack of hashes.
+    ///
+    /// Seed `pending` with a state root, or with the result of [`Self::missing_descendants`] from
+    /// the importing side when resuming, and call repeatedly until it is empty.
+    pub fn export_chunk(
+        &self,
+        correlation_id: CorrelationId,
+        pending: &mut Vec<Blake2bHash>,
+        chunk_size: usize,
+    ) -> Result<ExportChunkResult, error::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let result = operations::export_chunk::<Key, StoredValue, _, _, error::Error>(
+            correlation_id,
+            &txn,
+            self.trie_store.deref(),
+            pending,
+            chunk_size,
+        )?;
+        txn.commit()?;
+        Ok(result)
+    }
+
+    /// Verifies and imports a chunk of the state under `state_root` produced by
+    /// [`Self::export_chunk`].
+    ///
+    /// `expected` holds the hashes which may be imported next, and is updated as chunks are
+    /// imported.  Seed it with `state_root`, or with the result of [`Self::missing_descendants`]
+    /// when resuming.  Once it is empty, `state_root` can be checked out; until then,
+    /// [`StateProvider::checkout`] returns `None` for it.
+    pub fn import_chunk(
+        &self,
+        correlation_id: CorrelationId,
+        state_root: &Blake2bHash,
+        expected: &mut HashSet<Blake2bHash>,
+        chunk: &TrieChunk,
+    ) -> Result<ImportChunkResult, error::Error> {
+        let incomplete_roots = IncompleteRootStore::new(
+            self.environment
+                .env()
+                .create_db(Some(incomplete_roots::NAME), DatabaseFlags::empty())?,
+        );
+        operations::import_chunk::<Key, StoredValue, _, _, error::Error>(
+            correlation_id,
+            self.environment.deref(),
+            self.trie_store.deref(),
+            &incomplete_roots,
+            state_root,
+            expected,
+            chunk,
+        )
+    }
+
+    /// Returns the store of roots whose import has not finished, or `None` if no import has ever
+    /// started.
+    fn incomplete_roots(
+        &self,
+    ) -> Result<Option<IncompleteRootStore<Database, error::Error>>, error::Error> {
+        match self.environment.env().open_db(Some(incomplete_roots::NAME)) {
+            Ok(db) => Ok(Some(IncompleteRootStore::new(db))),
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Returns the hashes of the trie elements reachable from `state_root` which are not yet in the
+    /// store, or nothing if the state is complete.
+    pub fn missing_descendants(
+        &self,
+        state_root: &Blake2bHash,
+    ) -> Result<Vec<Blake2bHash>, error::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let missing = operations::missing_descendants::<Key, StoredValue, _, _, error::Error>(
+            &txn,
+            self.trie_store.deref(),
+            state_root,
+        )?;
+        txn.commit()?;
+        Ok(missing)
+    }
+
+    /// Checks the integrity of every trie element reachable from the given state roots.
+    pub fn check_integrity(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: &[Blake2bHash],
+    ) -> Result<IntegrityReport, error::Error> {
+        global_state::check_integrity::<_, _, error::Error>(
+            correlation_id,
+            self.environment.deref(),
+            self.trie_store.deref(),
+            state_roots,
+        )
+    }
 }
 
 impl StateReader<Key, StoredValue> for LmdbGlobalStateView {
@@ -84,10 +210,11 @@ impl StateReader<Key, StoredValue> for LmdbGlobalStateView {
         key: &Key,
     ) -> Result<Option<StoredValue>, Self::Error> {
         let txn = self.environment.create_read_txn()?;
-        let ret = match read::<Key, StoredValue, lmdb::RoTransaction, LmdbTrieStore, Self::Error>(
+        let store = CachingTrieStore::read_through(self.store.deref(), self.trie_cache.deref());
+        let ret = match read::<Key, StoredValue, lmdb::RoTransaction, _, Self::Error>(
             correlation_id,
             &txn,
-            self.store.deref(),
+            &store,
             &self.root_hash,
             key,
         )? {
@@ -96,6 +223,79 @@ impl StateReader<Key, StoredValue> for LmdbGlobalStateView {
             ReadResult::RootNotFound => panic!("LmdbGlobalState has invalid root"),
         };
         txn.commit()?;
+        store.log_metrics(correlation_id, READ);
+        Ok(ret)
+    }
+
+    fn read_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieMerkleProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let store = CachingTrieStore::read_through(self.store.deref(), self.trie_cache.deref());
+        let ret = match read_with_proof::<Key, StoredValue, lmdb::RoTransaction, _, Self::Error>(
+            correlation_id,
+            &txn,
+            &store,
+            &self.root_hash,
+            key,
+        )? {
+            ReadResult::Found(proof) => Some(proof),
+            ReadResult::NotFound => None,
+            ReadResult::RootNotFound => panic!("LmdbGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        store.log_metrics(correlation_id, READ_WITH_PROOF);
+        Ok(ret)
+    }
+
+    fn prove_absence(
+        &self,
+        correlation_id: CorrelationId,
+        key: &Key,
+    ) -> Result<Option<TrieAbsenceProof<Key, StoredValue>>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let store = CachingTrieStore::read_through(self.store.deref(), self.trie_cache.deref());
+        let ret = match prove_absence::<Key, StoredValue, lmdb::RoTransaction, _, Self::Error>(
+            correlation_id,
+            &txn,
+            &store,
+            &self.root_hash,
+            key,
+        )? {
+            AbsenceProofResult::Absent(proof) => Some(proof),
+            AbsenceProofResult::Present => None,
+            AbsenceProofResult::RootNotFound => panic!("LmdbGlobalState has invalid root"),
+        };
+        txn.commit()?;
+        store.log_metrics(correlation_id, PROVE_ABSENCE);
+        Ok(ret)
+    }
+
+    fn read_range(
+        &self,
+        correlation_id: CorrelationId,
+        range: &KeyRange,
+        limit: usize,
+    ) -> Result<Vec<(Key, StoredValue)>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let store = CachingTrieStore::read_through(self.store.deref(), self.trie_cache.deref());
+        let ret = match read_range::<Key, StoredValue, lmdb::RoTransaction, _, Self::Error>(
+            correlation_id,
+            &txn,
+            &store,
+            &self.root_hash,
+            range,
+            limit,
+        )? {
+            ReadResult::Found(pairs) => pairs,
+            ReadResult::NotFound | ReadResult::RootNotFound => {
+                panic!("LmdbGlobalState has invalid root")
+            }
+        };
+        txn.commit()?;
+        store.log_metrics(correlation_id, READ_RANGE);
         Ok(ret)
     }
 }
@@ -106,12 +306,21 @@ impl StateProvider for LmdbGlobalState {
     type Reader = LmdbGlobalStateView;
 
     fn checkout(&self, state_hash: Blake2bHash) -> Result<Option<Self::Reader>, Self::Error> {
+        let maybe_incomplete_roots = self.incomplete_roots()?;
         let txn = self.environment.create_read_txn()?;
+        if let Some(incomplete_roots) = maybe_incomplete_roots {
+            // The root of a partial import is present, but not all of its descendants are.
+            if incomplete_roots.get(&txn, &state_hash)?.is_some() {
+                txn.commit()?;
+                return Ok(None);
+            }
+        }
         let maybe_root: Option<Trie<Key, StoredValue>> = self.trie_store.get(&txn, &state_hash)?;
         let maybe_state = maybe_root.map(|_| LmdbGlobalStateView {
             environment: Arc::clone(&self.environment),
             store: Arc::clone(&self.trie_store),
             root_hash: state_hash,
+            trie_cache: Arc::clone(&self.trie_cache),
         });
         txn.commit()?;
         Ok(maybe_state)
@@ -123,16 +332,36 @@ impl StateProvider for LmdbGlobalState {
         prestate_hash: Blake2bHash,
         effects: AdditiveMap<Key, Transform>,
     ) -> Result<CommitResult, Self::Error> {
-        let commit_result = commit::<LmdbEnvironment, LmdbTrieStore, _, Self::Error>(
+        let store = CachingTrieStore::lookup_only(self.trie_store.deref(), self.trie_cache.deref());
+        let commit_result = commit::<LmdbEnvironment, _, _, Self::Error>(
             &self.environment,
-            &self.trie_store,
+            &store,
             correlation_id,
             prestate_hash,
             effects,
         )?;
+        store.log_metrics(correlation_id, COMMIT);
         Ok(commit_result)
     }
 
+    fn commit_many(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Blake2bHash,
+        effects: Vec<AdditiveMap<Key, Transform>>,
+    ) -> Result<CommitManyResult, Self::Error> {
+        let store = CachingTrieStore::lookup_only(self.trie_store.deref(), self.trie_cache.deref());
+        let commit_many_result = commit_many::<LmdbEnvironment, _, _, Self::Error>(
+            &self.environment,
+            &store,
+            correlation_id,
+            prestate_hash,
+            effects,
+        )?;
+        store.log_metrics(correlation_id, COMMIT_MANY);
+        Ok(commit_many_result)
+    }
+
     fn put_protocol_data(
         &self,
         protocol_version: ProtocolVersion,
@@ -157,6 +386,51 @@ impl StateProvider for LmdbGlobalState {
     fn empty_root(&self) -> Blake2bHash {
         self.empty_root_hash
     }
+
+    fn prune(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: &[Blake2bHash],
+    ) -> Result<PruneResult, Self::Error> {
+        let mut roots_to_keep = state_roots.to_vec();
+        roots_to_keep.push(self.empty_root_hash);
+        // Pruned elements are evicted from the cache as they are deleted.
+        let store = CachingTrieStore::lookup_only(self.trie_store.deref(), self.trie_cache.deref());
+        let marks = PruneMarkStore::new(
+            self.environment
+                .env()
+                .create_db(Some(prune_marks::NAME), DatabaseFlags::empty())?,
+        );
+        operations::prune::<Key, StoredValue, LmdbEnvironment, _, Self::Error>(
+            correlation_id,
+            &self.environment,
+            &store,
+            &marks,
+            &roots_to_keep,
+            PRUNE_BATCH_SIZE,
+        )
+    }
+
+    fn diff(
+        &self,
+        correlation_id: CorrelationId,
+        left: Blake2bHash,
+        right: Blake2bHash,
+    ) -> Result<DiffResult<Key, StoredValue>, Self::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let store =
+            CachingTrieStore::read_through(self.trie_store.deref(), self.trie_cache.deref());
+        let result = operations::diff::<Key, StoredValue, _, _, Self::Error>(
+            correlation_id,
+            &txn,
+            &store,
+            &left,
+            &right,
+        )?;
+        txn.commit()?;
+        store.log_metrics(correlation_id, DIFF);
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
@@ -164,7 +438,12 @@ mod tests {
     use lmdb::DatabaseFlags;
     use tempfile::tempdir;
 
-    use types::{account::AccountHash, CLValue};
+    use types::{
+        account::AccountHash,
+        bytesrepr::ToBytes,
+        merkle_proof::{verify_absence_proof, verify_inclusion_proof},
+        CLValue,
+    };
 
     use crate::{
         trie_store::operations::{write, WriteResult},
@@ -259,6 +538,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reads_with_proof_from_a_checkout_return_verifiable_values() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            let proof = checkout
+                .read_with_proof(correlation_id, &key)
+                .unwrap()
+                .unwrap();
+            assert_eq!(proof.value(), &value);
+            let proof_steps: Vec<Vec<u8>> = proof
+                .proof_steps()
+                .iter()
+                .map(|step| step.to_bytes().unwrap())
+                .collect();
+            let value_bytes =
+                verify_inclusion_proof(&root_hash.value(), &key, &proof_steps).unwrap();
+            assert_eq!(value_bytes, value.to_bytes().unwrap().as_slice());
+        }
+    }
+
+    #[test]
+    fn proves_absence_of_keys_missing_from_a_checkout() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, .. } in create_test_pairs().iter().cloned() {
+            assert_eq!(None, checkout.prove_absence(correlation_id, &key).unwrap());
+        }
+        for key in &[
+            Key::Account(AccountHash::new([3_u8; 32])),
+            Key::Hash([1_u8; 32]),
+        ] {
+            let proof = checkout
+                .prove_absence(correlation_id, key)
+                .unwrap()
+                .unwrap();
+            let proof_steps: Vec<Vec<u8>> = proof
+                .proof_steps()
+                .iter()
+                .map(|step| step.to_bytes().unwrap())
+                .collect();
+            verify_absence_proof(&root_hash.value(), key, &proof_steps).unwrap();
+        }
+    }
+
+    #[test]
+    fn synced_state_can_be_checked_out() {
+        let correlation_id = CorrelationId::new();
+        let (source, root_hash) = create_test_state();
+
+        let temp_dir = tempdir().unwrap();
+        let environment =
+            Arc::new(LmdbEnvironment::new(&temp_dir.path().to_path_buf(), *TEST_MAP_SIZE).unwrap());
+        let trie_store =
+            Arc::new(LmdbTrieStore::new(&environment, None, DatabaseFlags::empty()).unwrap());
+        let protocol_data_store = Arc::new(
+            LmdbProtocolDataStore::new(&environment, None, DatabaseFlags::empty()).unwrap(),
+        );
+        let destination =
+            LmdbGlobalState::empty(environment, trie_store, protocol_data_store).unwrap();
+        assert!(destination.checkout(root_hash).unwrap().is_none());
+
+        let mut pending = destination.missing_descendants(&root_hash).unwrap();
+        assert_eq!(pending, vec![root_hash]);
+        let mut expected: HashSet<Blake2bHash> = pending.iter().copied().collect();
+        while !pending.is_empty() {
+            let chunk = match source
+                .export_chunk(correlation_id, &mut pending, 1)
+                .unwrap()
+            {
+                ExportChunkResult::Exported(chunk) => chunk,
+                ExportChunkResult::TrieNotFound(hash) => panic!("should export {:?}", hash),
+            };
+            assert_eq!(
+                destination
+                    .import_chunk(correlation_id, &root_hash, &mut expected, &chunk)
+                    .unwrap(),
+                ImportChunkResult::Imported(1)
+            );
+            // The root is written by the first chunk, but cannot be read until the last.
+            assert_eq!(
+                destination.checkout(root_hash).unwrap().is_some(),
+                expected.is_empty()
+            );
+        }
+        assert!(expected.is_empty());
+        assert!(destination
+            .missing_descendants(&root_hash)
+            .unwrap()
+            .is_empty());
+
+        let checkout = destination.checkout(root_hash).unwrap().unwrap();
+        for TestPair { key, value } in create_test_pairs().iter().cloned() {
+            assert_eq!(Some(value), checkout.read(correlation_id, &key).unwrap());
+        }
+    }
+
+    #[test]
+    fn reads_from_checkouts_share_the_trie_cache() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+        assert!(state.trie_cache.is_empty());
+
+        let checkout = state.checkout(root_hash).unwrap().unwrap();
+        let TestPair { key, value } = create_test_pairs()[0].clone();
+        assert_eq!(
+            Some(value.clone()),
+            checkout.read(correlation_id, &key).unwrap()
+        );
+        assert!(state.trie_cache.get(&root_hash).is_some());
+
+        // A read through another checkout is served from the cache.
+        let cached_len = state.trie_cache.len();
+        let other_checkout = state.checkout(root_hash).unwrap().unwrap();
+        assert_eq!(
+            Some(value),
+            other_checkout.read(correlation_id, &key).unwrap()
+        );
+        assert_eq!(state.trie_cache.len(), cached_len);
+    }
+
+    #[test]
+    fn check_integrity_finds_no_problems_in_committed_states() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = create_test_pairs_updated()
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+        let updated_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        let report = state
+            .check_integrity(correlation_id, &[root_hash, updated_hash])
+            .unwrap();
+        assert!(report.is_intact(), "{:?}", report);
+        // The three updated leaves and the two leaves they replaced.
+        assert_eq!(report.leaves_checked, 5);
+    }
+
     #[test]
     fn checkout_fails_if_unknown_hash_is_given() {
         let (state, _) = create_test_state();
@@ -339,4 +764,241 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn diff_returns_changes_between_roots() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs = create_test_pairs();
+        let test_pairs_updated = create_test_pairs_updated();
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+
+        let updated_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        let expected: Vec<(Key, Option<StoredValue>, Option<StoredValue>)> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| {
+                let old_value = test_pairs
+                    .iter()
+                    .find(|pair| pair.key == key)
+                    .map(|pair| pair.value.clone());
+                (key, old_value, Some(value))
+            })
+            .collect();
+
+        assert_eq!(
+            state.diff(correlation_id, root_hash, updated_hash).unwrap(),
+            DiffResult::Diff(expected.clone())
+        );
+        assert_eq!(
+            state.diff(correlation_id, updated_hash, root_hash).unwrap(),
+            DiffResult::Diff(
+                expected
+                    .into_iter()
+                    .map(|(key, left, right)| (key, right, left))
+                    .collect()
+            )
+        );
+        assert_eq!(
+            state.diff(correlation_id, root_hash, root_hash).unwrap(),
+            DiffResult::Diff(Vec::new())
+        );
+    }
+
+    #[test]
+    fn commit_deletes_keys() {
+        let correlation_id = CorrelationId::new();
+        let [deleted_pair, kept_pair] = create_test_pairs();
+        let missing_key = Key::Account(AccountHash::new([9u8; 32]));
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = vec![
+            (deleted_pair.key, Transform::Delete),
+            (missing_key, Transform::Delete),
+        ]
+        .into_iter()
+        .collect();
+
+        let deleted_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        let checkout = state.checkout(deleted_hash).unwrap().unwrap();
+        assert_eq!(
+            None,
+            checkout.read(correlation_id, &deleted_pair.key).unwrap()
+        );
+        assert_eq!(
+            Some(kept_pair.value.clone()),
+            checkout.read(correlation_id, &kept_pair.key).unwrap()
+        );
+
+        // The state is the same as if the deleted key had never been written.
+        let effects: AdditiveMap<Key, Transform> =
+            vec![(kept_pair.key, Transform::Write(kept_pair.value))]
+                .into_iter()
+                .collect();
+        let expected_hash = match state
+            .commit(correlation_id, state.empty_root(), effects)
+            .unwrap()
+        {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+        assert_eq!(deleted_hash, expected_hash);
+    }
+
+    #[test]
+    fn commit_many_returns_the_state_root_after_each_effects() {
+        let correlation_id = CorrelationId::new();
+        let [deleted_pair, _] = create_test_pairs();
+
+        let (state, root_hash) = create_test_state();
+
+        let updates: AdditiveMap<Key, Transform> = create_test_pairs_updated()
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+        let deletes: AdditiveMap<Key, Transform> = vec![(deleted_pair.key, Transform::Delete)]
+            .into_iter()
+            .collect();
+
+        let state_roots = match state
+            .commit_many(
+                correlation_id,
+                root_hash,
+                vec![updates.clone(), AdditiveMap::new(), deletes.clone()],
+            )
+            .unwrap()
+        {
+            CommitManyResult::Success { state_roots } => state_roots,
+            other => panic!("commit_many failed: {}", other),
+        };
+
+        let mut expected_state_roots = Vec::new();
+        let mut expected_root = root_hash;
+        for effects in vec![updates, AdditiveMap::new(), deletes] {
+            expected_root = match state
+                .commit(correlation_id, expected_root, effects)
+                .unwrap()
+            {
+                CommitResult::Success { state_root, .. } => state_root,
+                other => panic!("commit failed: {}", other),
+            };
+            expected_state_roots.push(expected_root);
+        }
+        assert_eq!(state_roots, expected_state_roots);
+
+        let checkout = state.checkout(state_roots[2]).unwrap().unwrap();
+        assert_eq!(
+            None,
+            checkout.read(correlation_id, &deleted_pair.key).unwrap()
+        );
+    }
+
+    #[test]
+    fn commit_many_commits_nothing_when_any_effects_fail() {
+        let correlation_id = CorrelationId::new();
+        let [TestPair { key, .. }, _] = create_test_pairs();
+        let new_pair = create_test_pairs_updated()[2].clone();
+
+        let (state, root_hash) = create_test_state();
+
+        let writes: AdditiveMap<Key, Transform> =
+            vec![(new_pair.key, Transform::Write(new_pair.value))]
+                .into_iter()
+                .collect();
+        let mismatched: AdditiveMap<Key, Transform> =
+            vec![(key, Transform::AddKeys(Default::default()))]
+                .into_iter()
+                .collect();
+
+        match state
+            .commit_many(correlation_id, root_hash, vec![writes.clone(), mismatched])
+            .unwrap()
+        {
+            CommitManyResult::Failed {
+                index: 1,
+                failure: CommitResult::TypeMismatch(_),
+            } => (),
+            other => panic!("commit_many should fail on the second effects: {}", other),
+        }
+
+        // The same writes committed to an identical state give the root which would have been
+        // committed along with them.
+        let (other_state, other_root_hash) = create_test_state();
+        assert_eq!(root_hash, other_root_hash);
+        let discarded_root = match other_state
+            .commit(correlation_id, other_root_hash, writes)
+            .unwrap()
+        {
+            CommitResult::Success { state_root, .. } => state_root,
+            other => panic!("commit failed: {}", other),
+        };
+        assert!(state.checkout(discarded_root).unwrap().is_none());
+
+        match state
+            .commit_many(correlation_id, [1u8; 32].into(), vec![AdditiveMap::new()])
+            .unwrap()
+        {
+            CommitManyResult::RootNotFound => (),
+            other => panic!("commit_many should not find the root: {}", other),
+        }
+    }
+
+    #[test]
+    fn prune_removes_states_which_are_not_kept() {
+        let correlation_id = CorrelationId::new();
+        let test_pairs_updated = create_test_pairs_updated();
+
+        let (state, root_hash) = create_test_state();
+
+        let effects: AdditiveMap<Key, Transform> = test_pairs_updated
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, Transform::Write(value)))
+            .collect();
+
+        let updated_hash = match state.commit(correlation_id, root_hash, effects).unwrap() {
+            CommitResult::Success { state_root, .. } => state_root,
+            _ => panic!("commit failed"),
+        };
+
+        // Cache the original root, which pruning must evict.
+        let original_checkout = state.checkout(root_hash).unwrap().unwrap();
+        original_checkout
+            .read(correlation_id, &test_pairs_updated[0].key)
+            .unwrap();
+        assert!(state.trie_cache.get(&root_hash).is_some());
+
+        match state.prune(correlation_id, &[updated_hash]).unwrap() {
+            PruneResult::Pruned { deleted, .. } => assert!(deleted > 0),
+            PruneResult::RootNotFound(root) => panic!("root not found: {}", root),
+        }
+
+        assert!(state.trie_cache.get(&root_hash).is_none());
+        assert!(state.checkout(root_hash).unwrap().is_none());
+        assert!(state.checkout(state.empty_root()).unwrap().is_some());
+
+        let updated_checkout = state.checkout(updated_hash).unwrap().unwrap();
+        for TestPair { key, value } in test_pairs_updated.iter().cloned() {
+            assert_eq!(
+                Some(value),
+                updated_checkout.read(correlation_id, &key).unwrap()
+            );
+        }
+    }
 }
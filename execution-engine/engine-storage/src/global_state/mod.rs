@@ -1,5 +1,7 @@
+pub mod file;
 pub mod in_memory;
 pub mod lmdb;
+pub mod snapshot;
 
 use std::{collections::HashMap, fmt, hash::BuildHasher, time::Instant};
 
@@ -15,21 +17,34 @@ use types::{account::AccountHash, bytesrepr, Key, ProtocolVersion, U512};
 
 use crate::{
     protocol_data::ProtocolData,
-    transaction_source::{Transaction, TransactionSource},
-    trie::Trie,
+    transaction_source::{Readable, Transaction, TransactionSource, Writable},
+    trie::{Trie, TrieAbsenceProof, TrieMerkleProof},
     trie_store::{
-        operations::{read, write, ReadResult, WriteResult},
+        operations::{self, delete, read, write, DeleteResult, ReadResult, WriteResult},
         TrieStore,
     },
     GAUGE_METRIC_KEY,
 };
 
+pub use crate::trie_store::operations::{
+    DiffEntry, DiffResult, ExportChunkResult, ImportChunkResult, IntegrityProblem, IntegrityReport,
+    KeyRange, PruneResult,
+};
+
 const GLOBAL_STATE_COMMIT_READS: &str = "global_state_commit_reads";
 const GLOBAL_STATE_COMMIT_WRITES: &str = "global_state_commit_writes";
+const GLOBAL_STATE_COMMIT_DELETES: &str = "global_state_commit_deletes";
 const GLOBAL_STATE_COMMIT_DURATION: &str = "global_state_commit_duration";
 const GLOBAL_STATE_COMMIT_READ_DURATION: &str = "global_state_commit_read_duration";
 const GLOBAL_STATE_COMMIT_WRITE_DURATION: &str = "global_state_commit_write_duration";
+const GLOBAL_STATE_COMMIT_DELETE_DURATION: &str = "global_state_commit_delete_duration";
 const COMMIT: &str = "commit";
+const GLOBAL_STATE_COMMIT_MANY_DURATION: &str = "global_state_commit_many_duration";
+const GLOBAL_STATE_COMMIT_MANY_EFFECTS: &str = "global_state_commit_many_effects";
+const COMMIT_MANY: &str = "commit_many";
+
+/// The maximum number of trie elements examined in a single read-write transaction while pruning.
+pub(crate) const PRUNE_BATCH_SIZE: usize = 10_000;
 
 /// A reader of state
 pub trait StateReader<K, V> {
@@ -38,6 +53,32 @@ pub trait StateReader<K, V> {
 
     /// Returns the state value from the corresponding key
     fn read(&self, correlation_id: CorrelationId, key: &K) -> Result<Option<V>, Self::Error>;
+
+    /// Returns the state value from the corresponding key along with a proof of its inclusion
+    fn read_with_proof(
+        &self,
+        correlation_id: CorrelationId,
+        key: &K,
+    ) -> Result<Option<TrieMerkleProof<K, V>>, Self::Error>;
+
+    /// Returns a proof that the corresponding key is absent, or `None` if the key is present
+    fn prove_absence(
+        &self,
+        correlation_id: CorrelationId,
+        key: &K,
+    ) -> Result<Option<TrieAbsenceProof<K, V>>, Self::Error>;
+
+    /// Returns up to `limit` key-value pairs whose serialized keys are within `range`, in ascending
+    /// order of their serialized keys.
+    ///
+    /// Use [`KeyRange::with_prefix`] to read e.g. every account, and [`KeyRange::after`] with the
+    /// last key read to read the next page.
+    fn read_range(
+        &self,
+        correlation_id: CorrelationId,
+        range: &KeyRange,
+        limit: usize,
+    ) -> Result<Vec<(K, V)>, Self::Error>;
 }
 
 #[derive(Debug)]
@@ -73,6 +114,35 @@ impl fmt::Display for CommitResult {
     }
 }
 
+#[derive(Debug)]
+pub enum CommitManyResult {
+    RootNotFound,
+    /// The state root after each of the effects, in the order they were applied.
+    Success {
+        state_roots: Vec<Blake2bHash>,
+    },
+    /// The effects at `index` could not be applied, with `failure` being what committing them
+    /// alone would have returned.  None of the effects have been committed.
+    Failed {
+        index: usize,
+        failure: CommitResult,
+    },
+}
+
+impl fmt::Display for CommitManyResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            CommitManyResult::RootNotFound => write!(f, "Root not found"),
+            CommitManyResult::Success { state_roots } => {
+                write!(f, "Success: state_roots: {:?}", state_roots)
+            }
+            CommitManyResult::Failed { index, failure } => {
+                write!(f, "Failed at effects {}: {}", index, failure)
+            }
+        }
+    }
+}
+
 impl From<transform::Error> for CommitResult {
     fn from(error: transform::Error) -> Self {
         match error {
@@ -100,6 +170,15 @@ pub trait StateProvider {
         effects: AdditiveMap<Key, Transform>,
     ) -> Result<CommitResult, Self::Error>;
 
+    /// Applies each of `effects` in turn, starting from `state_hash`, and returns the post state
+    /// hash of each.  Either all of the effects are committed, or none are.
+    fn commit_many(
+        &self,
+        correlation_id: CorrelationId,
+        state_hash: Blake2bHash,
+        effects: Vec<AdditiveMap<Key, Transform>>,
+    ) -> Result<CommitManyResult, Self::Error>;
+
     fn put_protocol_data(
         &self,
         protocol_version: ProtocolVersion,
@@ -112,37 +191,84 @@ pub trait StateProvider {
     ) -> Result<Option<ProtocolData>, Self::Error>;
 
     fn empty_root(&self) -> Blake2bHash;
+
+    /// Deletes all trie elements which are not reachable from the given state roots (or the empty
+    /// root).
+    ///
+    /// Must not be called concurrently with [`StateProvider::commit`].
+    fn prune(
+        &self,
+        correlation_id: CorrelationId,
+        state_roots: &[Blake2bHash],
+    ) -> Result<PruneResult, Self::Error>;
+
+    /// Returns every key which was added, removed or modified between the `left` and `right` state
+    /// roots, along with its value under each root.
+    fn diff(
+        &self,
+        correlation_id: CorrelationId,
+        left: Blake2bHash,
+        right: Blake2bHash,
+    ) -> Result<DiffResult<Key, StoredValue>, Self::Error>;
 }
 
-pub fn commit<'a, R, S, H, E>(
+/// Checks the integrity of every trie element reachable from the given state roots, within a single
+/// read transaction.
+///
+/// See [`IntegrityProblem`] for the problems which are detected.
+pub fn check_integrity<'a, R, S, E>(
+    correlation_id: CorrelationId,
     environment: &'a R,
     store: &S,
-    correlation_id: CorrelationId,
-    prestate_hash: Blake2bHash,
-    effects: AdditiveMap<Key, Transform, H>,
-) -> Result<CommitResult, E>
+    state_roots: &[Blake2bHash],
+) -> Result<IntegrityReport, E>
 where
     R: TransactionSource<'a, Handle = S::Handle>,
     S: TrieStore<Key, StoredValue>,
     S::Error: From<R::Error>,
     E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
-    H: BuildHasher,
 {
-    let mut txn = environment.create_read_write_txn()?;
-    let mut state_root = prestate_hash;
-
-    let maybe_root: Option<Trie<Key, StoredValue>> = store.get(&txn, &state_root)?;
-
-    if maybe_root.is_none() {
-        return Ok(CommitResult::RootNotFound);
-    };
+    let txn = environment.create_read_txn()?;
+    let report = operations::check_integrity::<Key, StoredValue, _, _, E>(
+        correlation_id,
+        &txn,
+        store,
+        state_roots,
+    )?;
+    txn.commit()?;
+    Ok(report)
+}
 
-    let start = Instant::now();
-    let mut reads: i32 = 0;
-    let mut writes: i32 = 0;
+/// The number of reads, writes and deletes made while applying effects.
+#[derive(Default)]
+struct CommitCounts {
+    reads: i32,
+    writes: i32,
+    deletes: i32,
+}
 
+/// Applies `effects` to the state at `state_root` within `txn`.
+///
+/// Returns `Ok(Err(commit_result))` if the effects cannot be applied, in which case `txn` must not
+/// be committed.
+fn apply_effects<T, S, H, E>(
+    correlation_id: CorrelationId,
+    txn: &mut T,
+    store: &S,
+    start: Instant,
+    mut state_root: Blake2bHash,
+    effects: AdditiveMap<Key, Transform, H>,
+    counts: &mut CommitCounts,
+) -> Result<Result<Blake2bHash, CommitResult>, E>
+where
+    T: Readable<Handle = S::Handle> + Writable<Handle = S::Handle>,
+    S: TrieStore<Key, StoredValue>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<types::bytesrepr::Error>,
+    H: BuildHasher,
+{
     for (key, transform) in effects.into_iter() {
-        let read_result = read::<_, _, _, _, E>(correlation_id, &txn, store, &state_root, &key)?;
+        let read_result = read::<_, _, _, _, E>(correlation_id, txn, store, &state_root, &key)?;
 
         log_duration(
             correlation_id,
@@ -151,22 +277,45 @@ where
             start.elapsed(),
         );
 
-        reads += 1;
+        counts.reads += 1;
 
         let value = match (read_result, transform) {
+            // Deleting a key which is already absent leaves the state unchanged.
+            (ReadResult::NotFound, Transform::Delete) => continue,
+            (ReadResult::Found(_), Transform::Delete) => {
+                let delete_result =
+                    delete::<_, _, _, _, E>(correlation_id, txn, store, &state_root, &key)?;
+
+                log_duration(
+                    correlation_id,
+                    GLOBAL_STATE_COMMIT_DELETE_DURATION,
+                    COMMIT,
+                    start.elapsed(),
+                );
+
+                match delete_result {
+                    DeleteResult::Deleted(root_hash) => {
+                        state_root = root_hash;
+                        counts.deletes += 1;
+                    }
+                    // The key was just read from this root.
+                    other => panic!("failed to delete {:?}: {:?}", key, other),
+                }
+                continue;
+            }
             (ReadResult::NotFound, Transform::Write(new_value)) => new_value,
             (ReadResult::NotFound, _) => {
-                return Ok(CommitResult::KeyNotFound(key));
+                return Ok(Err(CommitResult::KeyNotFound(key)));
             }
             (ReadResult::Found(current_value), transform) => match transform.apply(current_value) {
                 Ok(updated_value) => updated_value,
-                Err(err) => return Ok(err.into()),
+                Err(err) => return Ok(Err(err.into())),
             },
             _x @ (ReadResult::RootNotFound, _) => panic!(stringify!(_x._1)),
         };
 
         let write_result =
-            write::<_, _, _, _, E>(correlation_id, &mut txn, store, &state_root, &key, &value)?;
+            write::<_, _, _, _, E>(correlation_id, txn, store, &state_root, &key, &value)?;
 
         log_duration(
             correlation_id,
@@ -178,37 +327,90 @@ where
         match write_result {
             WriteResult::Written(root_hash) => {
                 state_root = root_hash;
-                writes += 1;
+                counts.writes += 1;
             }
             WriteResult::AlreadyExists => (),
             _x @ WriteResult::RootNotFound => panic!(stringify!(_x)),
         }
     }
 
-    txn.commit()?;
+    Ok(Ok(state_root))
+}
 
-    log_duration(
+fn log_commit_counts(correlation_id: CorrelationId, tag: &str, counts: &CommitCounts) {
+    log_metric(
         correlation_id,
-        GLOBAL_STATE_COMMIT_DURATION,
-        COMMIT,
-        start.elapsed(),
+        GLOBAL_STATE_COMMIT_READS,
+        tag,
+        GAUGE_METRIC_KEY,
+        f64::from(counts.reads),
     );
 
     log_metric(
         correlation_id,
-        GLOBAL_STATE_COMMIT_READS,
-        COMMIT,
+        GLOBAL_STATE_COMMIT_WRITES,
+        tag,
         GAUGE_METRIC_KEY,
-        f64::from(reads),
+        f64::from(counts.writes),
     );
 
     log_metric(
         correlation_id,
-        GLOBAL_STATE_COMMIT_WRITES,
-        COMMIT,
+        GLOBAL_STATE_COMMIT_DELETES,
+        tag,
         GAUGE_METRIC_KEY,
-        f64::from(writes),
+        f64::from(counts.deletes),
     );
+}
+
+pub fn commit<'a, R, S, H, E>(
+    environment: &'a R,
+    store: &S,
+    correlation_id: CorrelationId,
+    prestate_hash: Blake2bHash,
+    effects: AdditiveMap<Key, Transform, H>,
+) -> Result<CommitResult, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<Key, StoredValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+    H: BuildHasher,
+{
+    let mut txn = environment.create_read_write_txn()?;
+
+    let maybe_root: Option<Trie<Key, StoredValue>> = store.get(&txn, &prestate_hash)?;
+
+    if maybe_root.is_none() {
+        return Ok(CommitResult::RootNotFound);
+    };
+
+    let start = Instant::now();
+    let mut counts = CommitCounts::default();
+
+    let state_root = match apply_effects::<_, _, _, E>(
+        correlation_id,
+        &mut txn,
+        store,
+        start,
+        prestate_hash,
+        effects,
+        &mut counts,
+    )? {
+        Ok(state_root) => state_root,
+        Err(commit_result) => return Ok(commit_result),
+    };
+
+    txn.commit()?;
+
+    log_duration(
+        correlation_id,
+        GLOBAL_STATE_COMMIT_DURATION,
+        COMMIT,
+        start.elapsed(),
+    );
+
+    log_commit_counts(correlation_id, COMMIT, &counts);
 
     let bonded_validators = Default::default();
 
@@ -217,3 +419,73 @@ where
         bonded_validators,
     })
 }
+
+/// Applies each of `effects` in turn, starting from the state at `prestate_hash`, within a single
+/// read-write transaction.
+///
+/// If any of the effects cannot be applied, none of them are committed.
+pub fn commit_many<'a, R, S, H, E>(
+    environment: &'a R,
+    store: &S,
+    correlation_id: CorrelationId,
+    prestate_hash: Blake2bHash,
+    effects: Vec<AdditiveMap<Key, Transform, H>>,
+) -> Result<CommitManyResult, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<Key, StoredValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+    H: BuildHasher,
+{
+    let mut txn = environment.create_read_write_txn()?;
+
+    let maybe_root: Option<Trie<Key, StoredValue>> = store.get(&txn, &prestate_hash)?;
+
+    if maybe_root.is_none() {
+        return Ok(CommitManyResult::RootNotFound);
+    };
+
+    let start = Instant::now();
+    let mut counts = CommitCounts::default();
+    let mut state_root = prestate_hash;
+    let mut state_roots = Vec::with_capacity(effects.len());
+
+    for (index, effects) in effects.into_iter().enumerate() {
+        state_root = match apply_effects::<_, _, _, E>(
+            correlation_id,
+            &mut txn,
+            store,
+            start,
+            state_root,
+            effects,
+            &mut counts,
+        )? {
+            Ok(state_root) => state_root,
+            // Dropping `txn` without committing it discards the effects applied so far.
+            Err(failure) => return Ok(CommitManyResult::Failed { index, failure }),
+        };
+        state_roots.push(state_root);
+    }
+
+    txn.commit()?;
+
+    log_duration(
+        correlation_id,
+        GLOBAL_STATE_COMMIT_MANY_DURATION,
+        COMMIT_MANY,
+        start.elapsed(),
+    );
+
+    log_metric(
+        correlation_id,
+        GLOBAL_STATE_COMMIT_MANY_EFFECTS,
+        COMMIT_MANY,
+        GAUGE_METRIC_KEY,
+        state_roots.len() as f64,
+    );
+
+    log_commit_counts(correlation_id, COMMIT_MANY, &counts);
+
+    Ok(CommitManyResult::Success { state_roots })
+}
@@ -0,0 +1,617 @@
+//! A portable snapshot of the global state at a single state root.
+//!
+//! A snapshot holds every `(Key, StoredValue)` leaf of the state along with the [`ProtocolData`] of
+//! every protocol version, so it can be loaded into a fresh data dir regardless of the storage
+//! backend it was exported from.  Its layout is:
+//!
+//! * the magic bytes `CLSNAPSH` followed by the format version as a little-endian `u32`
+//! * the state root the snapshot was exported from
+//! * a sequence of records, each starting with a one-byte tag:
+//!   * a protocol data record: the serialized `ProtocolVersion` and `ProtocolData`
+//!   * a leaf record: the serialized `Key` and `StoredValue`
+//!   * an end record: the number of protocol data and leaf records as little-endian `u64`s
+//! * the BLAKE2b checksum of everything preceding it
+//!
+//! Every serialized value in a record is prefixed by its length as a little-endian `u64`.
+use std::{
+    io::{self, Read, Write},
+    time::Instant,
+};
+
+use blake2::{
+    digest::{Input, VariableOutput},
+    VarBlake2b,
+};
+
+use engine_shared::{
+    logging::log_duration,
+    newtypes::{Blake2bHash, CorrelationId},
+    stored_value::StoredValue,
+};
+use types::{
+    bytesrepr::{self, FromBytes, ToBytes},
+    Key, ProtocolVersion, BLAKE2B_DIGEST_LENGTH,
+};
+
+pub use crate::error::snapshot::Error;
+use crate::{
+    protocol_data::ProtocolData,
+    protocol_data_store::ProtocolDataStore,
+    transaction_source::{Transaction, TransactionSource},
+    trie_store::{
+        operations::{for_each_leaf, AppendLeafResult, TrieBuilder},
+        TrieStore,
+    },
+};
+
+const MAGIC: &[u8; 8] = b"CLSNAPSH";
+
+/// The version of the snapshot format written by [`export_snapshot`].
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+const PROTOCOL_DATA_TAG: u8 = 0;
+const LEAF_TAG: u8 = 1;
+const END_TAG: u8 = 2;
+
+/// The number of protocol versions read from the store at a time while exporting.
+const PROTOCOL_VERSIONS_PAGE_SIZE: usize = 100;
+
+/// The maximum number of leaves written in a single read-write transaction while importing.
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+const GLOBAL_STATE_EXPORT_SNAPSHOT_DURATION: &str = "global_state_export_snapshot_duration";
+const GLOBAL_STATE_IMPORT_SNAPSHOT_DURATION: &str = "global_state_import_snapshot_duration";
+const EXPORT_SNAPSHOT: &str = "export_snapshot";
+const IMPORT_SNAPSHOT: &str = "import_snapshot";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExportSnapshotResult {
+    RootNotFound,
+    Exported { protocol_data: u64, leaves: u64 },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportSnapshotResult {
+    /// The rebuilt trie has the state root recorded in the snapshot, and the protocol data was
+    /// stored.
+    Imported {
+        state_root: Blake2bHash,
+        protocol_data: u64,
+        leaves: u64,
+    },
+    /// The rebuilt trie does not have the state root recorded in the snapshot.  The protocol data
+    /// was not stored.
+    RootMismatch {
+        expected: Blake2bHash,
+        actual: Blake2bHash,
+    },
+}
+
+fn new_hasher() -> VarBlake2b {
+    // Safe to unwrap here because our digest length is constant and valid
+    VarBlake2b::new(BLAKE2B_DIGEST_LENGTH).unwrap()
+}
+
+fn finish_hasher(hasher: VarBlake2b) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    let mut checksum = [0u8; BLAKE2B_DIGEST_LENGTH];
+    hasher.variable_result(|hash| checksum.clone_from_slice(hash));
+    checksum
+}
+
+/// Writes to the inner writer while hashing everything written.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: VarBlake2b,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: new_hasher(),
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hasher.input(bytes);
+        self.inner.write_all(bytes)
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    fn write_value<T: ToBytes>(&mut self, value: &T) -> Result<(), Error> {
+        let bytes = value.to_bytes()?;
+        self.write_u64(bytes.len() as u64)?;
+        self.write_bytes(&bytes)?;
+        Ok(())
+    }
+
+    /// Appends the checksum of everything written so far, and flushes the inner writer.
+    fn finish(self) -> io::Result<()> {
+        let HashingWriter { mut inner, hasher } = self;
+        inner.write_all(&finish_hasher(hasher))?;
+        inner.flush()
+    }
+}
+
+/// Reads from the inner reader while hashing everything read.
+struct HashingReader<R> {
+    inner: R,
+    hasher: VarBlake2b,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: new_hasher(),
+        }
+    }
+
+    fn read_array<A: AsMut<[u8]> + Default>(&mut self) -> io::Result<A> {
+        let mut array = A::default();
+        self.inner.read_exact(array.as_mut())?;
+        self.hasher.input(array.as_mut());
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        self.read_array::<[u8; 1]>().map(|[byte]| byte)
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        self.read_array().map(u32::from_le_bytes)
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    fn read_value<T: FromBytes>(&mut self) -> Result<T, Error> {
+        let length = self.read_u64()?;
+        // Reading through `take` rather than allocating `length` bytes up front means a corrupt
+        // length prefix results in an error rather than a huge allocation.
+        let mut bytes = Vec::new();
+        (&mut self.inner).take(length).read_to_end(&mut bytes)?;
+        if (bytes.len() as u64) < length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        self.hasher.input(&bytes);
+        Ok(bytesrepr::deserialize(bytes)?)
+    }
+
+    /// Reads the checksum following everything read so far, and checks that it matches.
+    fn finish(mut self) -> Result<(), Error> {
+        let mut checksum = [0u8; BLAKE2B_DIGEST_LENGTH];
+        self.inner.read_exact(&mut checksum)?;
+        if finish_hasher(self.hasher) != checksum {
+            return Err(Error::ChecksumMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Writes a snapshot of the state at `state_root`, along with all the protocol data in
+/// `protocol_data_store`, to `writer`.
+///
+/// The snapshot is taken within a single read transaction, so it is consistent even if other
+/// states are committed meanwhile.  Leaves are written in ascending order of their serialized
+/// keys.
+pub fn export_snapshot<'a, R, S, P, W>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    trie_store: &S,
+    protocol_data_store: &P,
+    state_root: &Blake2bHash,
+    writer: W,
+) -> Result<ExportSnapshotResult, Error>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<Key, StoredValue>,
+    P: ProtocolDataStore<Handle = S::Handle>,
+    S::Error: From<R::Error>,
+    P::Error: From<R::Error>,
+    Error: From<R::Error> + From<S::Error> + From<P::Error>,
+    W: Write,
+{
+    let start = Instant::now();
+    let txn = environment.create_read_txn()?;
+
+    if trie_store.get(&txn, state_root)?.is_none() {
+        return Ok(ExportSnapshotResult::RootNotFound);
+    }
+
+    let mut writer = HashingWriter::new(writer);
+    writer.write_bytes(MAGIC)?;
+    writer.write_bytes(&SNAPSHOT_FORMAT_VERSION.to_le_bytes())?;
+    writer.write_bytes(&state_root.value())?;
+
+    let mut protocol_data_count: u64 = 0;
+    let mut maybe_last_version: Option<ProtocolVersion> = None;
+    loop {
+        let protocol_versions = protocol_data_store.keys(
+            &txn,
+            maybe_last_version.as_ref(),
+            PROTOCOL_VERSIONS_PAGE_SIZE,
+        )?;
+        for protocol_version in &protocol_versions {
+            // The version was just listed by the same transaction.
+            let protocol_data = protocol_data_store
+                .get(&txn, protocol_version)?
+                .expect("listed protocol version should have protocol data");
+            writer.write_bytes(&[PROTOCOL_DATA_TAG])?;
+            writer.write_value(protocol_version)?;
+            writer.write_value(&protocol_data)?;
+            protocol_data_count += 1;
+        }
+        match protocol_versions.last() {
+            Some(protocol_version) if protocol_versions.len() == PROTOCOL_VERSIONS_PAGE_SIZE => {
+                maybe_last_version = Some(*protocol_version)
+            }
+            _ => break,
+        }
+    }
+
+    let mut leaf_count: u64 = 0;
+    for_each_leaf::<Key, StoredValue, _, _, Error, _>(
+        correlation_id,
+        &txn,
+        trie_store,
+        state_root,
+        |key, value| {
+            writer.write_bytes(&[LEAF_TAG])?;
+            writer.write_value(&key)?;
+            writer.write_value(&value)?;
+            leaf_count += 1;
+            Ok(())
+        },
+    )?;
+
+    writer.write_bytes(&[END_TAG])?;
+    writer.write_u64(protocol_data_count)?;
+    writer.write_u64(leaf_count)?;
+    writer.finish()?;
+    txn.commit()?;
+
+    log_duration(
+        correlation_id,
+        GLOBAL_STATE_EXPORT_SNAPSHOT_DURATION,
+        EXPORT_SNAPSHOT,
+        start.elapsed(),
+    );
+
+    Ok(ExportSnapshotResult::Exported {
+        protocol_data: protocol_data_count,
+        leaves: leaf_count,
+    })
+}
+
+/// Reads a snapshot written by [`export_snapshot`] from `reader`, rebuilding its trie and storing
+/// its protocol data.
+///
+/// The trie is built bottom-up from the leaves, which must be in ascending order of their
+/// serialized keys, so only the elements of the final trie are written.  They are written in
+/// batches of separate transactions, so if the snapshot turns out to be corrupt or its root does
+/// not match, the trie elements written so far are left in the store, unreachable from any state
+/// root which was there before, until they are pruned.  The protocol data is only stored once the
+/// checksum and the state root have both been verified.
+pub fn import_snapshot<'a, R, S, P, Rd>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    trie_store: &S,
+    protocol_data_store: &P,
+    reader: Rd,
+) -> Result<ImportSnapshotResult, Error>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<Key, StoredValue>,
+    P: ProtocolDataStore<Handle = S::Handle>,
+    S::Error: From<R::Error>,
+    P::Error: From<R::Error>,
+    Error: From<R::Error> + From<S::Error> + From<P::Error>,
+    Rd: Read,
+{
+    let start = Instant::now();
+    let mut reader = HashingReader::new(reader);
+
+    let magic: [u8; 8] = reader.read_array()?;
+    if &magic != MAGIC {
+        return Err(Error::BadMagic);
+    }
+    let version = reader.read_u32()?;
+    if version != SNAPSHOT_FORMAT_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    let expected_root = Blake2bHash::from(reader.read_array::<[u8; BLAKE2B_DIGEST_LENGTH]>()?);
+
+    let mut protocol_data: Vec<(ProtocolVersion, ProtocolData)> = Vec::new();
+    let mut leaf_count: u64 = 0;
+    let mut builder = TrieBuilder::new();
+    let mut txn = environment.create_read_write_txn()?;
+
+    loop {
+        match reader.read_u8()? {
+            PROTOCOL_DATA_TAG => {
+                let protocol_version = reader.read_value()?;
+                protocol_data.push((protocol_version, reader.read_value()?));
+            }
+            LEAF_TAG => {
+                let key: Key = reader.read_value()?;
+                let value: StoredValue = reader.read_value()?;
+                match builder.append::<_, _, Error>(&mut txn, trie_store, key, value)? {
+                    AppendLeafResult::Appended => (),
+                    AppendLeafResult::NotAscending => return Err(Error::UnsortedLeaves),
+                }
+                leaf_count += 1;
+                if leaf_count % IMPORT_BATCH_SIZE as u64 == 0 {
+                    txn.commit()?;
+                    txn = environment.create_read_write_txn()?;
+                }
+            }
+            END_TAG => {
+                let protocol_data_count = reader.read_u64()?;
+                let expected_leaf_count = reader.read_u64()?;
+                if protocol_data_count != protocol_data.len() as u64
+                    || expected_leaf_count != leaf_count
+                {
+                    return Err(Error::CountMismatch);
+                }
+                break;
+            }
+            tag => return Err(Error::UnknownRecord(tag)),
+        }
+    }
+
+    reader.finish()?;
+
+    let state_root = builder.finish::<_, _, Error>(&mut txn, trie_store)?;
+    if state_root != expected_root {
+        txn.commit()?;
+        return Ok(ImportSnapshotResult::RootMismatch {
+            expected: expected_root,
+            actual: state_root,
+        });
+    }
+
+    for (protocol_version, protocol_data) in &protocol_data {
+        protocol_data_store.put(&mut txn, protocol_version, protocol_data)?;
+    }
+    txn.commit()?;
+
+    log_duration(
+        correlation_id,
+        GLOBAL_STATE_IMPORT_SNAPSHOT_DURATION,
+        IMPORT_SNAPSHOT,
+        start.elapsed(),
+    );
+
+    Ok(ImportSnapshotResult::Imported {
+        state_root,
+        protocol_data: protocol_data.len() as u64,
+        leaves: leaf_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use types::{account::AccountHash, CLValue};
+
+    use super::*;
+    use crate::global_state::{
+        in_memory::InMemoryGlobalState, PruneResult, StateProvider, StateReader,
+    };
+
+    const CHECKSUM_OFFSET_FROM_END: usize = BLAKE2B_DIGEST_LENGTH;
+    const STATE_ROOT_OFFSET: usize = 12;
+
+    fn create_test_pairs() -> Vec<(Key, StoredValue)> {
+        (0..10_u8)
+            .map(|i| {
+                (
+                    Key::Account(AccountHash::new([i; 32])),
+                    StoredValue::CLValue(CLValue::from_t(i32::from(i)).unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    fn create_test_state() -> (InMemoryGlobalState, Blake2bHash) {
+        let (state, root_hash) =
+            InMemoryGlobalState::from_pairs(CorrelationId::new(), &create_test_pairs()).unwrap();
+        for minor in 0..2 {
+            state
+                .put_protocol_data(
+                    ProtocolVersion::from_parts(1, minor, 0),
+                    &Default::default(),
+                )
+                .unwrap();
+        }
+        (state, root_hash)
+    }
+
+    fn export(state: &InMemoryGlobalState, state_root: &Blake2bHash) -> Vec<u8> {
+        let mut snapshot = Vec::new();
+        let result = export_snapshot(
+            CorrelationId::new(),
+            state.environment.deref(),
+            state.trie_store.deref(),
+            state.protocol_data_store.deref(),
+            state_root,
+            &mut snapshot,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            ExportSnapshotResult::Exported {
+                protocol_data: 2,
+                leaves: 10
+            }
+        );
+        snapshot
+    }
+
+    fn import(state: &InMemoryGlobalState, snapshot: &[u8]) -> Result<ImportSnapshotResult, Error> {
+        import_snapshot(
+            CorrelationId::new(),
+            state.environment.deref(),
+            state.trie_store.deref(),
+            state.protocol_data_store.deref(),
+            snapshot,
+        )
+    }
+
+    fn update_checksum(snapshot: &mut Vec<u8>) {
+        let body_length = snapshot.len() - CHECKSUM_OFFSET_FROM_END;
+        let mut hasher = new_hasher();
+        hasher.input(&snapshot[..body_length]);
+        snapshot.truncate(body_length);
+        snapshot.extend_from_slice(&finish_hasher(hasher));
+    }
+
+    #[test]
+    fn snapshot_round_trips_into_an_empty_state() {
+        let (source, root_hash) = create_test_state();
+        let snapshot = export(&source, &root_hash);
+
+        let destination = InMemoryGlobalState::empty().unwrap();
+        assert_eq!(
+            import(&destination, &snapshot).unwrap(),
+            ImportSnapshotResult::Imported {
+                state_root: root_hash,
+                protocol_data: 2,
+                leaves: 10
+            }
+        );
+
+        let checkout = destination.checkout(root_hash).unwrap().unwrap();
+        for (key, value) in create_test_pairs() {
+            assert_eq!(
+                checkout.read(CorrelationId::new(), &key).unwrap(),
+                Some(value)
+            );
+        }
+        for minor in 0..2 {
+            assert_eq!(
+                destination
+                    .get_protocol_data(ProtocolVersion::from_parts(1, minor, 0))
+                    .unwrap(),
+                Some(ProtocolData::default())
+            );
+        }
+
+        // Exporting the imported state reproduces the same snapshot.
+        assert_eq!(export(&destination, &root_hash), snapshot);
+    }
+
+    #[test]
+    fn import_writes_only_the_final_trie() {
+        let (source, root_hash) = create_test_state();
+        let snapshot = export(&source, &root_hash);
+
+        let destination = InMemoryGlobalState::empty().unwrap();
+        import(&destination, &snapshot).unwrap();
+
+        // Keeping the imported root, there is nothing else to prune.
+        match destination
+            .prune(CorrelationId::new(), &[root_hash])
+            .unwrap()
+        {
+            PruneResult::Pruned { deleted, .. } => assert_eq!(deleted, 0),
+            PruneResult::RootNotFound(root) => panic!("root not found: {}", root),
+        }
+    }
+
+    #[test]
+    fn exporting_an_unknown_root_writes_nothing() {
+        let (state, _) = create_test_state();
+        let mut snapshot = Vec::new();
+        let result = export_snapshot(
+            CorrelationId::new(),
+            state.environment.deref(),
+            state.trie_store.deref(),
+            state.protocol_data_store.deref(),
+            &Blake2bHash::new(&[]),
+            &mut snapshot,
+        )
+        .unwrap();
+        assert_eq!(result, ExportSnapshotResult::RootNotFound);
+        assert!(snapshot.is_empty());
+    }
+
+    #[test]
+    fn corrupt_snapshot_is_rejected() {
+        let (source, root_hash) = create_test_state();
+        let mut snapshot = export(&source, &root_hash);
+        snapshot[STATE_ROOT_OFFSET] ^= 1;
+
+        let destination = InMemoryGlobalState::empty().unwrap();
+        assert_eq!(
+            import(&destination, &snapshot),
+            Err(Error::ChecksumMismatch)
+        );
+        assert_eq!(
+            destination
+                .get_protocol_data(ProtocolVersion::from_parts(1, 0, 0))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn truncated_snapshot_is_rejected() {
+        let (source, root_hash) = create_test_state();
+        let snapshot = export(&source, &root_hash);
+
+        let destination = InMemoryGlobalState::empty().unwrap();
+        let result = import(&destination, &snapshot[..snapshot.len() - 1]);
+        match result {
+            Err(Error::Io(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn snapshot_with_another_format_is_rejected() {
+        let (source, root_hash) = create_test_state();
+        let mut snapshot = export(&source, &root_hash);
+        let destination = InMemoryGlobalState::empty().unwrap();
+
+        snapshot[MAGIC.len()] += 1;
+        assert_eq!(
+            import(&destination, &snapshot),
+            Err(Error::UnsupportedVersion(SNAPSHOT_FORMAT_VERSION + 1))
+        );
+
+        snapshot[0] = 0;
+        assert_eq!(import(&destination, &snapshot), Err(Error::BadMagic));
+    }
+
+    #[test]
+    fn snapshot_with_wrong_root_is_not_imported() {
+        let (source, root_hash) = create_test_state();
+        let mut snapshot = export(&source, &root_hash);
+        let wrong_root = Blake2bHash::new(&[1]);
+        snapshot[STATE_ROOT_OFFSET..STATE_ROOT_OFFSET + BLAKE2B_DIGEST_LENGTH]
+            .copy_from_slice(&wrong_root.value());
+        update_checksum(&mut snapshot);
+
+        let destination = InMemoryGlobalState::empty().unwrap();
+        assert_eq!(
+            import(&destination, &snapshot).unwrap(),
+            ImportSnapshotResult::RootMismatch {
+                expected: wrong_root,
+                actual: root_hash
+            }
+        );
+        assert_eq!(
+            destination
+                .get_protocol_data(ProtocolVersion::from_parts(1, 0, 0))
+                .unwrap(),
+            None
+        );
+    }
+}
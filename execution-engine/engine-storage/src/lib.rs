@@ -12,7 +12,7 @@ pub mod trie_store;
 use lazy_static::lazy_static;
 
 pub(crate) const GAUGE_METRIC_KEY: &str = "gauge";
-const MAX_DBS: u32 = 2;
+const MAX_DBS: u32 = 4;
 
 #[cfg(test)]
 lazy_static! {
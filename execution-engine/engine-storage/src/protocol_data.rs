@@ -1,17 +1,33 @@
+use engine_shared::host_function_costs::{
+    HostFunctionCosts, HOST_FUNCTION_COSTS_SERIALIZED_LENGTH,
+};
 use engine_wasm_prep::wasm_costs::{WasmCosts, WASM_COSTS_SERIALIZED_LENGTH};
 use std::collections::BTreeMap;
 use types::{
-    bytesrepr::{self, FromBytes, ToBytes},
+    bytesrepr::{self, FromBytes, ToBytes, U32_SERIALIZED_LENGTH, U8_SERIALIZED_LENGTH},
     ContractHash, HashAddr, KEY_HASH_LENGTH,
 };
 
-const PROTOCOL_DATA_SERIALIZED_LENGTH: usize = WASM_COSTS_SERIALIZED_LENGTH + 3 * KEY_HASH_LENGTH;
+const PROTOCOL_DATA_SERIALIZED_LENGTH: usize = U32_SERIALIZED_LENGTH
+    + U8_SERIALIZED_LENGTH
+    + WASM_COSTS_SERIALIZED_LENGTH
+    + HOST_FUNCTION_COSTS_SERIALIZED_LENGTH
+    + 3 * KEY_HASH_LENGTH;
 const DEFAULT_ADDRESS: [u8; 32] = [0; 32];
 
+// Protocol data was first serialized without a version, starting directly with the `regular`
+// opcode cost of its `WasmCosts`.  Versioned layouts start with this marker in its place instead,
+// which can't be mistaken for it as no deploy could run at an opcode cost of `u32::MAX`.
+const VERSION_MARKER: u32 = u32::MAX;
+// The version of the current layout: `WasmCosts` with `storage_per_byte`, followed by
+// `HostFunctionCosts`.
+const VERSION: u8 = 1;
+
 /// Represents a protocol's data. Intended to be associated with a given protocol version.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ProtocolData {
     wasm_costs: WasmCosts,
+    host_function_costs: HostFunctionCosts,
     mint: ContractHash,
     proof_of_stake: ContractHash,
     standard_payment: ContractHash,
@@ -25,6 +41,7 @@ impl Default for ProtocolData {
     fn default() -> ProtocolData {
         ProtocolData {
             wasm_costs: WasmCosts::default(),
+            host_function_costs: HostFunctionCosts::default(),
             mint: DEFAULT_ADDRESS,
             proof_of_stake: DEFAULT_ADDRESS,
             standard_payment: DEFAULT_ADDRESS,
@@ -33,15 +50,18 @@ impl Default for ProtocolData {
 }
 
 impl ProtocolData {
-    /// Creates a new [`ProtocolData`] value from a given [`WasmCosts`] value.
+    /// Creates a new [`ProtocolData`] value from given [`WasmCosts`] and [`HostFunctionCosts`]
+    /// values.
     pub fn new(
         wasm_costs: WasmCosts,
+        host_function_costs: HostFunctionCosts,
         mint: ContractHash,
         proof_of_stake: ContractHash,
         standard_payment: ContractHash,
     ) -> Self {
         ProtocolData {
             wasm_costs,
+            host_function_costs,
             mint,
             proof_of_stake,
             standard_payment,
@@ -80,6 +100,11 @@ impl ProtocolData {
         &self.wasm_costs
     }
 
+    /// Gets the [`HostFunctionCosts`] value from a given [`ProtocolData`] value.
+    pub fn host_function_costs(&self) -> &HostFunctionCosts {
+        &self.host_function_costs
+    }
+
     pub fn mint(&self) -> ContractHash {
         self.mint
     }
@@ -126,7 +151,10 @@ impl ProtocolData {
 impl ToBytes for ProtocolData {
     fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
         let mut ret = bytesrepr::unchecked_allocate_buffer(self);
+        ret.append(&mut VERSION_MARKER.to_bytes()?);
+        ret.append(&mut VERSION.to_bytes()?);
         ret.append(&mut self.wasm_costs.to_bytes()?);
+        ret.append(&mut self.host_function_costs.to_bytes()?);
         ret.append(&mut self.mint.to_bytes()?);
         ret.append(&mut self.proof_of_stake.to_bytes()?);
         ret.append(&mut self.standard_payment.to_bytes()?);
@@ -138,9 +166,51 @@ impl ToBytes for ProtocolData {
     }
 }
 
+/// Deserializes [`WasmCosts`] in the unversioned layout, which has no `storage_per_byte`.
+fn legacy_wasm_costs_from_bytes(bytes: &[u8]) -> Result<(WasmCosts, &[u8]), bytesrepr::Error> {
+    let (regular, rem): (u32, &[u8]) = FromBytes::from_bytes(bytes)?;
+    let (div, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (mul, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (mem, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (initial_mem, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (grow_mem, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (memcpy, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (max_stack_height, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (opcodes_mul, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let (opcodes_div, rem): (u32, &[u8]) = FromBytes::from_bytes(rem)?;
+    let wasm_costs = WasmCosts {
+        regular,
+        div,
+        mul,
+        mem,
+        initial_mem,
+        grow_mem,
+        memcpy,
+        max_stack_height,
+        opcodes_mul,
+        opcodes_div,
+        ..Default::default()
+    };
+    Ok((wasm_costs, rem))
+}
+
 impl FromBytes for ProtocolData {
     fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
-        let (wasm_costs, rem) = WasmCosts::from_bytes(bytes)?;
+        let (marker, versioned_rem): (u32, &[u8]) = FromBytes::from_bytes(bytes)?;
+        let (wasm_costs, host_function_costs, rem) = if marker == VERSION_MARKER {
+            let (version, rem): (u8, &[u8]) = FromBytes::from_bytes(versioned_rem)?;
+            if version != VERSION {
+                return Err(bytesrepr::Error::Formatting);
+            }
+            let (wasm_costs, rem) = WasmCosts::from_bytes(rem)?;
+            let (host_function_costs, rem) = HostFunctionCosts::from_bytes(rem)?;
+            (wasm_costs, host_function_costs, rem)
+        } else {
+            // Data written before storage and host function calls were charged for keeps them
+            // free, so that its protocol version is executed the same by every node.
+            let (wasm_costs, rem) = legacy_wasm_costs_from_bytes(bytes)?;
+            (wasm_costs, HostFunctionCosts::free(), rem)
+        };
         let (mint, rem) = HashAddr::from_bytes(rem)?;
         let (proof_of_stake, rem) = HashAddr::from_bytes(rem)?;
         let (standard_payment, rem) = HashAddr::from_bytes(rem)?;
@@ -148,6 +218,7 @@ impl FromBytes for ProtocolData {
         Ok((
             ProtocolData {
                 wasm_costs,
+                host_function_costs,
                 mint,
                 proof_of_stake,
                 standard_payment,
@@ -161,6 +232,7 @@ impl FromBytes for ProtocolData {
 pub(crate) mod gens {
     use proptest::prop_compose;
 
+    use engine_shared::host_function_costs::gens as host_function_costs_gens;
     use engine_wasm_prep::wasm_costs::gens as wasm_costs_gens;
     use types::gens;
 
@@ -169,12 +241,14 @@ pub(crate) mod gens {
     prop_compose! {
         pub fn protocol_data_arb()(
             wasm_costs in wasm_costs_gens::wasm_costs_arb(),
+            host_function_costs in host_function_costs_gens::host_function_costs_arb(),
             mint in gens::u8_slice_32(),
             proof_of_stake in gens::u8_slice_32(),
             standard_payment in gens::u8_slice_32(),
         ) -> ProtocolData {
             ProtocolData {
                 wasm_costs,
+                host_function_costs,
                 mint,
                 proof_of_stake,
                 standard_payment,
@@ -187,8 +261,12 @@ pub(crate) mod gens {
 mod tests {
     use proptest::proptest;
 
+    use engine_shared::host_function_costs::HostFunctionCosts;
     use engine_wasm_prep::wasm_costs::WasmCosts;
-    use types::{bytesrepr, ContractHash};
+    use types::{
+        bytesrepr::{self, ToBytes, U32_SERIALIZED_LENGTH},
+        ContractHash,
+    };
 
     use super::{gens, ProtocolData};
 
@@ -204,6 +282,7 @@ mod tests {
             max_stack_height: 64 * 1024,
             opcodes_mul: 3,
             opcodes_div: 8,
+            storage_per_byte: 1,
         }
     }
 
@@ -219,6 +298,7 @@ mod tests {
             max_stack_height: 64 * 1024,
             opcodes_mul: 1,
             opcodes_div: 1,
+            storage_per_byte: 0,
         }
     }
 
@@ -231,6 +311,7 @@ mod tests {
             let standard_payment_reference = [3u8; 32];
             ProtocolData::new(
                 costs,
+                HostFunctionCosts::default(),
                 mint_reference,
                 proof_of_stake_reference,
                 standard_payment_reference,
@@ -243,6 +324,7 @@ mod tests {
             let standard_payment_reference = [2u8; 32];
             ProtocolData::new(
                 costs,
+                HostFunctionCosts::default(),
                 mint_reference,
                 proof_of_stake_reference,
                 standard_payment_reference,
@@ -261,6 +343,7 @@ mod tests {
             let costs = wasm_costs_mock();
             ProtocolData::new(
                 costs,
+                HostFunctionCosts::default(),
                 mint_reference,
                 proof_of_stake_reference,
                 standard_payment_reference,
@@ -291,6 +374,7 @@ mod tests {
             let costs = wasm_costs_mock();
             ProtocolData::new(
                 costs,
+                HostFunctionCosts::default(),
                 mint_reference,
                 proof_of_stake_reference,
                 standard_payment_reference,
@@ -308,6 +392,66 @@ mod tests {
         assert_eq!(actual[1], standard_payment_reference);
     }
 
+    /// Serializes `protocol_data` in the unversioned layout, which has neither `storage_per_byte`
+    /// nor host function costs.
+    fn unversioned_bytes(protocol_data: &ProtocolData) -> Vec<u8> {
+        let wasm_costs = protocol_data.wasm_costs();
+        let mut bytes = Vec::new();
+        for field in &[
+            wasm_costs.regular,
+            wasm_costs.div,
+            wasm_costs.mul,
+            wasm_costs.mem,
+            wasm_costs.initial_mem,
+            wasm_costs.grow_mem,
+            wasm_costs.memcpy,
+            wasm_costs.max_stack_height,
+            wasm_costs.opcodes_mul,
+            wasm_costs.opcodes_div,
+        ] {
+            bytes.append(&mut field.to_bytes().unwrap());
+        }
+        bytes.append(&mut protocol_data.mint().to_bytes().unwrap());
+        bytes.append(&mut protocol_data.proof_of_stake().to_bytes().unwrap());
+        bytes.append(&mut protocol_data.standard_payment().to_bytes().unwrap());
+        bytes
+    }
+
+    #[test]
+    fn should_deserialize_unversioned_layout_without_storage_and_host_function_costs() {
+        let protocol_data = ProtocolData::new(
+            wasm_costs_mock(),
+            HostFunctionCosts::default(),
+            [1u8; 32],
+            [2u8; 32],
+            [3u8; 32],
+        );
+        let expected = ProtocolData::new(
+            WasmCosts {
+                storage_per_byte: 0,
+                ..wasm_costs_mock()
+            },
+            HostFunctionCosts::free(),
+            [1u8; 32],
+            [2u8; 32],
+            [3u8; 32],
+        );
+        assert_eq!(
+            bytesrepr::deserialize(unversioned_bytes(&protocol_data)),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn should_not_deserialize_unknown_version() {
+        let mut bytes = ProtocolData::default().to_bytes().unwrap();
+        bytes[U32_SERIALIZED_LENGTH] += 1;
+        assert_eq!(
+            bytesrepr::deserialize::<ProtocolData>(bytes),
+            Err(bytesrepr::Error::Formatting)
+        );
+    }
+
     proptest! {
         #[test]
         fn should_serialize_and_deserialize_with_arbitrary_values(
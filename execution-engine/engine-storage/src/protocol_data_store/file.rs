@@ -0,0 +1,36 @@
+use types::ProtocolVersion;
+
+use crate::{
+    error::file::Error,
+    protocol_data::ProtocolData,
+    protocol_data_store::{self, ProtocolDataStore},
+    store::Store,
+    transaction_source::file::FileEnvironment,
+};
+
+/// A file-backed protocol data store.
+#[derive(Debug, Clone)]
+pub struct FileProtocolDataStore {
+    name: String,
+}
+
+impl FileProtocolDataStore {
+    pub fn new(_env: &FileEnvironment, maybe_name: Option<&str>) -> Self {
+        let name = maybe_name
+            .map(|name| format!("{}-{}", protocol_data_store::NAME, name))
+            .unwrap_or_else(|| String::from(protocol_data_store::NAME));
+        FileProtocolDataStore { name }
+    }
+}
+
+impl Store<ProtocolVersion, ProtocolData> for FileProtocolDataStore {
+    type Error = Error;
+
+    type Handle = String;
+
+    fn handle(&self) -> Self::Handle {
+        self.name.to_owned()
+    }
+}
+
+impl ProtocolDataStore for FileProtocolDataStore {}
@@ -2,6 +2,7 @@
 //! protocol versions.
 use types::ProtocolVersion;
 
+pub mod file;
 pub mod in_memory;
 pub mod lmdb;
 #[cfg(test)]
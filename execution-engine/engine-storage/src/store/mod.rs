@@ -42,4 +42,33 @@ pub trait Store<K, V> {
         txn.write(handle, &key.to_bytes()?, &value.to_bytes()?)
             .map_err(Into::into)
     }
+
+    fn delete<T>(&self, txn: &mut T, key: &K) -> Result<(), Self::Error>
+    where
+        T: Writable<Handle = Self::Handle>,
+        K: ToBytes,
+        Self::Error: From<T::Error>,
+    {
+        let handle = self.handle();
+        txn.delete(handle, &key.to_bytes()?).map_err(Into::into)
+    }
+
+    /// Returns up to `limit` keys in ascending order of their serialized form, starting strictly
+    /// after `maybe_start` if it is given.
+    fn keys<T>(&self, txn: &T, maybe_start: Option<&K>, limit: usize) -> Result<Vec<K>, Self::Error>
+    where
+        T: Readable<Handle = Self::Handle>,
+        K: ToBytes + FromBytes,
+        Self::Error: From<T::Error>,
+    {
+        let handle = self.handle();
+        let maybe_start_bytes = match maybe_start {
+            Some(start) => Some(start.to_bytes()?),
+            None => None,
+        };
+        txn.read_keys(handle, maybe_start_bytes.as_deref(), limit)?
+            .into_iter()
+            .map(|key_bytes| bytesrepr::deserialize(key_bytes).map_err(Into::into))
+            .collect()
+    }
 }
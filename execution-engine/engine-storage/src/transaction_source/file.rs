@@ -0,0 +1,1871 @@
+//! An append-only, file-backed transaction source.
+//!
+//! All the databases of a [`FileEnvironment`] share a single log file.  Each committed read-write
+//! transaction is appended to the log as one checksummed batch of puts and deletes.  An incomplete
+//! batch at the end of the log, as left by a crash part-way through a commit, is discarded when the
+//! environment is opened.
+//!
+//! The index mapping each key to the location of its value in the log is kept on disk, so that its
+//! memory use does not grow with the data set.  The changes since the latest checkpoint are held in
+//! memory.  Each time the log has grown by a set number of bytes, they are written out as a sorted
+//! run of index entries, and runs of similar size are merged, so a lookup consults a number of runs
+//! which is logarithmic in the number of keys.  Only every [`FENCE_INTERVAL`]th entry of a run is
+//! kept in memory, to find the block of the run which may hold a key.  A checkpoint file lists the
+//! runs and the part of the log they cover, so only the batches after that are replayed when the
+//! environment is opened.  A missing, corrupt or stale checkpoint or run is ignored, and the whole
+//! log is replayed instead.
+//!
+//! Values are never modified in place, so the log only grows until it is compacted by
+//! [`FileEnvironment::compact`], which rewrites it with only the live values.  Unlike LMDB, no
+//! address space needs to be reserved up front.
+//!
+//! Like LMDB, an environment takes an exclusive lock on its directory, so that two engines cannot
+//! append to the same log.
+//!
+//! # Log format
+//!
+//! The log starts with the magic bytes `CLSTLOG1`, followed by a sequence of batches, each of which
+//! is laid out as:
+//!
+//! * the length of the batch body as a little-endian `u64`
+//! * the BLAKE2b hash of the batch body
+//! * the batch body, which is a sequence of records
+//!
+//! A record is an operation tag, followed by the database name and the key, and for a put the
+//! value.  Each of these byte strings is prefixed by its length as a little-endian `u64`.
+//!
+//! # Run format
+//!
+//! A run starts with the magic bytes `CLSTRUN1`, followed by its entries in ascending order of
+//! database name and then key.  An entry is the database name and the key as length-prefixed byte
+//! strings, followed by an operation tag, and for a put the offset and length of the value in the
+//! log as little-endian `u64`s.  A delete entry hides the key's entries in older runs.
+//!
+//! # Checkpoint format
+//!
+//! The checkpoint starts with the magic bytes `CLSTIDX2`, followed by the BLAKE2b hash of its body.
+//! The body holds the length of the log covered by the checkpoint, the offset and hash of the last
+//! batch in that part of the log, and the id to give the next run, each as a little-endian `u64` or
+//! raw hash.  These are followed by the id, length, number of entries and BLAKE2b hash of the
+//! entries of each run, oldest first.
+use std::{
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap, HashMap, HashSet},
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    iter, mem,
+    ops::Bound,
+    os::unix::{fs::FileExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, RwLock},
+};
+
+use blake2::{
+    digest::{Input, VariableOutput},
+    VarBlake2b,
+};
+
+use engine_shared::newtypes::{Blake2bHash, BLAKE2B_DIGEST_LENGTH};
+
+use crate::{
+    error::file::Error,
+    transaction_source::{Readable, Transaction, TransactionSource, Writable},
+};
+
+pub(crate) const LOG_FILE_NAME: &str = "data.log";
+const LOG_TEMP_FILE_NAME: &str = "data.log.tmp";
+const LOG_MAGIC: &[u8; 8] = b"CLSTLOG1";
+const LOCK_FILE_NAME: &str = "data.lock";
+const CHECKPOINT_FILE_NAME: &str = "data.index";
+const CHECKPOINT_TEMP_FILE_NAME: &str = "data.index.tmp";
+const CHECKPOINT_MAGIC: &[u8; 8] = b"CLSTIDX2";
+const RUN_FILE_PREFIX: &str = "data.run.";
+const RUN_MAGIC: &[u8; 8] = b"CLSTRUN1";
+/// The default number of bytes appended to the log between checkpoints of the index.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 64 * 1024 * 1024;
+/// The number of entries of a run per entry kept in memory.
+pub const FENCE_INTERVAL: u64 = 128;
+/// The number of bytes read at a time when reading a run or the log sequentially.
+const READ_BLOCK_SIZE: usize = 64 * 1024;
+/// The number of bytes of records in each batch of a compacted log.
+const COMPACTION_BATCH_SIZE: usize = 4 * 1024 * 1024;
+const LENGTH_PREFIX_LENGTH: usize = mem::size_of::<u64>();
+const BATCH_HEADER_LENGTH: usize = LENGTH_PREFIX_LENGTH + BLAKE2B_DIGEST_LENGTH;
+const PUT_TAG: u8 = 0;
+const DELETE_TAG: u8 = 1;
+
+/// The location of a value in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    offset: u64,
+    length: usize,
+}
+
+/// The values a key has had, in ascending order of the sequence numbers of the batches which wrote
+/// them.  `None` marks a deletion.
+type Versions = Vec<(u64, Option<Location>)>;
+
+/// A change to a key made by a committed batch.
+type Change = (String, Vec<u8>, Option<Location>);
+
+/// The offset in the log of a batch, and the hash of its body.
+type BatchId = (u64, [u8; BLAKE2B_DIGEST_LENGTH]);
+
+fn range_after<'a, V>(
+    map: &'a BTreeMap<Vec<u8>, V>,
+    maybe_start: Option<&[u8]>,
+) -> btree_map::Range<'a, Vec<u8>, V> {
+    let lower = maybe_start.map_or(Bound::Unbounded, Bound::Excluded);
+    map.range::<[u8], _>((lower, Bound::Unbounded))
+}
+
+fn new_hasher() -> VarBlake2b {
+    // Safe to unwrap here because our digest length is constant and valid
+    VarBlake2b::new(BLAKE2B_DIGEST_LENGTH).unwrap()
+}
+
+fn finish_hasher(hasher: VarBlake2b) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    let mut checksum = [0u8; BLAKE2B_DIGEST_LENGTH];
+    hasher.variable_result(|hash| checksum.clone_from_slice(hash));
+    checksum
+}
+
+fn push_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+/// Splits a length-prefixed byte string off the front of `remainder`.
+fn take_bytes<'a>(remainder: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if remainder.len() < LENGTH_PREFIX_LENGTH {
+        return None;
+    }
+    let (length_bytes, rest) = remainder.split_at(LENGTH_PREFIX_LENGTH);
+    let length: usize = u64::from_le_bytes(length_bytes.try_into().ok()?)
+        .try_into()
+        .ok()?;
+    if rest.len() < length {
+        return None;
+    }
+    let (bytes, rest) = rest.split_at(length);
+    *remainder = rest;
+    Some(bytes)
+}
+
+/// Splits a little-endian `u64` off the front of `remainder`.
+fn take_u64(remainder: &mut &[u8]) -> Option<u64> {
+    if remainder.len() < LENGTH_PREFIX_LENGTH {
+        return None;
+    }
+    let (bytes, rest) = remainder.split_at(LENGTH_PREFIX_LENGTH);
+    *remainder = rest;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Splits a hash off the front of `remainder`.
+fn take_hash(remainder: &mut &[u8]) -> Option<[u8; BLAKE2B_DIGEST_LENGTH]> {
+    if remainder.len() < BLAKE2B_DIGEST_LENGTH {
+        return None;
+    }
+    let (bytes, rest) = remainder.split_at(BLAKE2B_DIGEST_LENGTH);
+    *remainder = rest;
+    bytes.try_into().ok()
+}
+
+/// Parses the records of a batch body which starts at `body_offset` in the log.
+fn parse_batch(body: &[u8], body_offset: u64) -> Option<Vec<Change>> {
+    let mut changes = Vec::new();
+    let mut remainder = body;
+    while let Some((&tag, rest)) = remainder.split_first() {
+        remainder = rest;
+        let handle = String::from_utf8(take_bytes(&mut remainder)?.to_vec()).ok()?;
+        let key = take_bytes(&mut remainder)?;
+        let maybe_location = match tag {
+            PUT_TAG => {
+                let value = take_bytes(&mut remainder)?;
+                let value_end = body.len() - remainder.len();
+                Some(Location {
+                    offset: body_offset + (value_end - value.len()) as u64,
+                    length: value.len(),
+                })
+            }
+            DELETE_TAG => None,
+            _ => return None,
+        };
+        changes.push((handle, key.to_vec(), maybe_location));
+    }
+    Some(changes)
+}
+
+fn read_header(file: &File, offset: u64) -> Result<(u64, [u8; BLAKE2B_DIGEST_LENGTH]), Error> {
+    let mut header = [0u8; BATCH_HEADER_LENGTH];
+    file.read_exact_at(&mut header, offset)?;
+    let mut length_bytes = [0u8; LENGTH_PREFIX_LENGTH];
+    length_bytes.copy_from_slice(&header[..LENGTH_PREFIX_LENGTH]);
+    let mut checksum = [0u8; BLAKE2B_DIGEST_LENGTH];
+    checksum.copy_from_slice(&header[LENGTH_PREFIX_LENGTH..]);
+    Ok((u64::from_le_bytes(length_bytes), checksum))
+}
+
+/// The records of a batch, laid out to be appended to the log at `offset`.
+struct Batch {
+    offset: u64,
+    body: Vec<u8>,
+}
+
+impl Batch {
+    fn new(offset: u64) -> Self {
+        Batch {
+            offset,
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds a put of `maybe_value`, or a delete if it is `None`, returning the location the value
+    /// will have in the log.
+    fn push(&mut self, handle: &str, key: &[u8], maybe_value: Option<&[u8]>) -> Option<Location> {
+        self.body.push(if maybe_value.is_some() {
+            PUT_TAG
+        } else {
+            DELETE_TAG
+        });
+        push_bytes(&mut self.body, handle.as_bytes());
+        push_bytes(&mut self.body, key);
+        let body_offset = self.offset + BATCH_HEADER_LENGTH as u64;
+        maybe_value.map(|value| {
+            push_bytes(&mut self.body, value);
+            Location {
+                offset: body_offset + (self.body.len() - value.len()) as u64,
+                length: value.len(),
+            }
+        })
+    }
+
+    /// Writes the batch to `log` without syncing it, returning the offset of its end and its id.
+    fn write_to(self, log: &File) -> Result<(u64, BatchId), Error> {
+        let checksum = Blake2bHash::new(&self.body).value();
+        let mut bytes = Vec::with_capacity(BATCH_HEADER_LENGTH + self.body.len());
+        bytes.extend_from_slice(&(self.body.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&checksum);
+        bytes.extend_from_slice(&self.body);
+        log.write_all_at(&bytes, self.offset)?;
+        Ok((self.offset + bytes.len() as u64, (self.offset, checksum)))
+    }
+}
+
+/// An entry of a run: a key of a database, and the location of its value or `None` if the key was
+/// deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    handle: String,
+    key: Vec<u8>,
+    location: Option<Location>,
+}
+
+impl Entry {
+    fn cmp_key(&self, handle: &str, key: &[u8]) -> Ordering {
+        (self.handle.as_bytes(), self.key.as_slice()).cmp(&(handle.as_bytes(), key))
+    }
+}
+
+fn push_entry(buffer: &mut Vec<u8>, entry: &Entry) {
+    push_bytes(buffer, entry.handle.as_bytes());
+    push_bytes(buffer, &entry.key);
+    match entry.location {
+        Some(Location { offset, length }) => {
+            buffer.push(PUT_TAG);
+            buffer.extend_from_slice(&offset.to_le_bytes());
+            buffer.extend_from_slice(&(length as u64).to_le_bytes());
+        }
+        None => buffer.push(DELETE_TAG),
+    }
+}
+
+/// Splits an entry off the front of `remainder`, or returns `None` if it is incomplete or
+/// malformed.
+fn take_entry(remainder: &mut &[u8]) -> Option<Entry> {
+    let mut rest = *remainder;
+    let handle = String::from_utf8(take_bytes(&mut rest)?.to_vec()).ok()?;
+    let key = take_bytes(&mut rest)?.to_vec();
+    let (&tag, after_tag) = rest.split_first()?;
+    rest = after_tag;
+    let location = match tag {
+        PUT_TAG => {
+            let offset = take_u64(&mut rest)?;
+            let length = take_u64(&mut rest)?.try_into().ok()?;
+            Some(Location { offset, length })
+        }
+        DELETE_TAG => None,
+        _ => return None,
+    };
+    *remainder = rest;
+    Some(Entry {
+        handle,
+        key,
+        location,
+    })
+}
+
+fn run_file_name(id: u64) -> String {
+    format!("{}{}", RUN_FILE_PREFIX, id)
+}
+
+/// A sorted run of index entries, held in its own file.
+///
+/// Every [`FENCE_INTERVAL`]th entry is kept in memory as a fence, which marks the start of the
+/// block of the file holding the entries up to the next fence.
+struct Run {
+    id: u64,
+    file: File,
+    /// The length of the file.
+    length: u64,
+    entry_count: u64,
+    /// The hash of the entries, which follow the magic bytes in the file.
+    checksum: [u8; BLAKE2B_DIGEST_LENGTH],
+    /// The database name and key of each fence, and its offset in the file.
+    fences: Vec<(String, Vec<u8>, u64)>,
+}
+
+impl Run {
+    /// Opens the run with the given id in the directory at `path`, returning `None` if it does not
+    /// match the given description from the checkpoint.
+    fn open(
+        path: &Path,
+        id: u64,
+        length: u64,
+        entry_count: u64,
+        checksum: [u8; BLAKE2B_DIGEST_LENGTH],
+    ) -> Option<Run> {
+        let file = File::open(path.join(run_file_name(id))).ok()?;
+        if length < RUN_MAGIC.len() as u64 || file.metadata().ok()?.len() != length {
+            return None;
+        }
+        let mut magic = [0u8; 8];
+        file.read_exact_at(&mut magic, 0).ok()?;
+        if &magic != RUN_MAGIC {
+            return None;
+        }
+        let mut hasher = new_hasher();
+        let mut block = vec![0u8; READ_BLOCK_SIZE];
+        let mut offset = RUN_MAGIC.len() as u64;
+        while offset < length {
+            let block_length = (length - offset).min(READ_BLOCK_SIZE as u64) as usize;
+            file.read_exact_at(&mut block[..block_length], offset)
+                .ok()?;
+            hasher.input(&block[..block_length]);
+            offset += block_length as u64;
+        }
+        if finish_hasher(hasher) != checksum {
+            return None;
+        }
+
+        let mut run = Run {
+            id,
+            file,
+            length,
+            entry_count,
+            checksum,
+            fences: Vec::new(),
+        };
+        let mut fences = Vec::new();
+        let mut cursor = run.cursor();
+        let mut count = 0;
+        loop {
+            let offset = cursor.offset();
+            match cursor.next_entry().ok()? {
+                Some(entry) => {
+                    if count % FENCE_INTERVAL == 0 {
+                        fences.push((entry.handle, entry.key, offset));
+                    }
+                    count += 1;
+                }
+                None => break,
+            }
+        }
+        if count != entry_count {
+            return None;
+        }
+        run.fences = fences;
+        Some(run)
+    }
+
+    /// Returns the offsets in the file of the start and end of the block which would hold the given
+    /// key, or `None` if it would precede every entry.
+    fn block(&self, handle: &str, key: &[u8]) -> Option<(u64, u64)> {
+        let index = match self
+            .fences
+            .binary_search_by(|(fence_handle, fence_key, _)| {
+                (fence_handle.as_bytes(), fence_key.as_slice()).cmp(&(handle.as_bytes(), key))
+            }) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let end = self
+            .fences
+            .get(index + 1)
+            .map_or(self.length, |(_, _, offset)| *offset);
+        Some((self.fences[index].2, end))
+    }
+
+    /// Returns the location in the run's entry for the given key, if it has one.
+    fn get(&self, handle: &str, key: &[u8]) -> Result<Option<Option<Location>>, Error> {
+        let (start, end) = match self.block(handle, key) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let mut cursor = RunCursor::new(self, start, end);
+        while let Some(entry) = cursor.next_entry()? {
+            match entry.cmp_key(handle, key) {
+                Ordering::Less => continue,
+                Ordering::Equal => return Ok(Some(entry.location)),
+                Ordering::Greater => break,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns a cursor over all the entries of the run.
+    fn cursor(&self) -> RunCursor {
+        RunCursor::new(self, RUN_MAGIC.len() as u64, self.length)
+    }
+
+    /// Returns a cursor over the entries of the run from the start of the block which would hold
+    /// the given key.
+    fn cursor_from(&self, handle: &str, key: &[u8]) -> RunCursor {
+        let start = self
+            .block(handle, key)
+            .map_or(RUN_MAGIC.len() as u64, |(start, _)| start);
+        RunCursor::new(self, start, self.length)
+    }
+}
+
+/// Reads the entries of a run in order, from an offset in its file up to another.
+struct RunCursor<'a> {
+    run: &'a Run,
+    /// The offset in the file of the first byte of `buffer`.
+    buffer_offset: u64,
+    buffer: Vec<u8>,
+    /// The position in `buffer` of the next entry.
+    position: usize,
+    end: u64,
+}
+
+impl<'a> RunCursor<'a> {
+    fn new(run: &'a Run, offset: u64, end: u64) -> Self {
+        RunCursor {
+            run,
+            buffer_offset: offset,
+            buffer: Vec::new(),
+            position: 0,
+            end,
+        }
+    }
+
+    /// The offset in the file of the next entry.
+    fn offset(&self) -> u64 {
+        self.buffer_offset + self.position as u64
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Entry>, Error> {
+        loop {
+            let mut remainder = &self.buffer[self.position..];
+            if let Some(entry) = take_entry(&mut remainder) {
+                self.position = self.buffer.len() - remainder.len();
+                return Ok(Some(entry));
+            }
+            let loaded_end = self.buffer_offset + self.buffer.len() as u64;
+            if loaded_end == self.end {
+                return if self.position == self.buffer.len() {
+                    Ok(None)
+                } else {
+                    Err(Error::CorruptRun(self.run.id))
+                };
+            }
+            self.buffer.drain(..self.position);
+            self.buffer_offset += self.position as u64;
+            self.position = 0;
+            let start = self.buffer.len();
+            let length = (self.end - loaded_end).min(READ_BLOCK_SIZE as u64) as usize;
+            self.buffer.resize(start + length, 0);
+            self.run
+                .file
+                .read_exact_at(&mut self.buffer[start..], loaded_end)?;
+        }
+    }
+}
+
+/// The entries of one database in a run, after a given key.
+struct RunKeys<'a> {
+    cursor: RunCursor<'a>,
+    handle: &'a str,
+    /// The next entry, or `None` once the entries of the database are exhausted.
+    head: Option<Entry>,
+}
+
+impl<'a> RunKeys<'a> {
+    fn new(run: &'a Run, handle: &'a str, maybe_start: Option<&[u8]>) -> Result<Self, Error> {
+        let mut run_keys = RunKeys {
+            cursor: run.cursor_from(handle, maybe_start.unwrap_or(&[])),
+            handle,
+            head: None,
+        };
+        run_keys.advance()?;
+        if let Some(start) = maybe_start {
+            while run_keys
+                .head
+                .as_ref()
+                .map_or(false, |entry| entry.key.as_slice() <= start)
+            {
+                run_keys.advance()?;
+            }
+        }
+        Ok(run_keys)
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        self.head = None;
+        while let Some(entry) = self.cursor.next_entry()? {
+            match entry.handle.as_str().cmp(self.handle) {
+                Ordering::Less => continue,
+                Ordering::Equal => self.head = Some(entry),
+                Ordering::Greater => (),
+            }
+            break;
+        }
+        Ok(())
+    }
+}
+
+/// Merges runs into a single sorted sequence of entries, in which each key has the entry from the
+/// newest run holding it.
+struct MergedEntries<'a> {
+    /// A cursor over each run with its next entry, newest first.
+    cursors: Vec<(RunCursor<'a>, Option<Entry>)>,
+    keep_deletions: bool,
+}
+
+impl<'a> MergedEntries<'a> {
+    /// Merges `runs`, given newest first.  Delete entries are only needed to hide entries of older
+    /// runs, so unless `keep_deletions` is set they are dropped.
+    fn new<I: Iterator<Item = &'a Run>>(runs: I, keep_deletions: bool) -> Result<Self, Error> {
+        let mut cursors = Vec::new();
+        for run in runs {
+            let mut cursor = run.cursor();
+            let head = cursor.next_entry()?;
+            cursors.push((cursor, head));
+        }
+        Ok(MergedEntries {
+            cursors,
+            keep_deletions,
+        })
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Entry>, Error> {
+        loop {
+            // Of equal entries, `min_by` returns the first, which is from the newest run.
+            let maybe_newest = self
+                .cursors
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (_, head))| head.as_ref().map(|entry| (index, entry)))
+                .min_by(|(_, left), (_, right)| left.cmp_key(&right.handle, &right.key))
+                .map(|(index, _)| index);
+            let entry = match maybe_newest.and_then(|newest| self.cursors[newest].1.take()) {
+                Some(entry) => entry,
+                None => return Ok(None),
+            };
+            // Advances past the key every cursor holding it, including the one whose head was taken
+            // and those which are exhausted.
+            for (cursor, head) in self.cursors.iter_mut() {
+                let is_same_key = head.as_ref().map_or(true, |other| {
+                    other.cmp_key(&entry.handle, &entry.key) == Ordering::Equal
+                });
+                if is_same_key {
+                    *head = cursor.next_entry()?;
+                }
+            }
+            if entry.location.is_some() || self.keep_deletions {
+                return Ok(Some(entry));
+            }
+        }
+    }
+}
+
+/// Writes a run to a new file, from entries given in ascending order.
+struct RunWriter {
+    id: u64,
+    writer: BufWriter<File>,
+    hasher: VarBlake2b,
+    length: u64,
+    entry_count: u64,
+    fences: Vec<(String, Vec<u8>, u64)>,
+    buffer: Vec<u8>,
+}
+
+impl RunWriter {
+    fn new(path: &Path, id: u64) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.join(run_file_name(id)))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(RUN_MAGIC)?;
+        Ok(RunWriter {
+            id,
+            writer,
+            hasher: new_hasher(),
+            length: RUN_MAGIC.len() as u64,
+            entry_count: 0,
+            fences: Vec::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, entry: Entry) -> Result<(), Error> {
+        self.buffer.clear();
+        push_entry(&mut self.buffer, &entry);
+        self.writer.write_all(&self.buffer)?;
+        self.hasher.input(&self.buffer);
+        if self.entry_count % FENCE_INTERVAL == 0 {
+            self.fences.push((entry.handle, entry.key, self.length));
+        }
+        self.length += self.buffer.len() as u64;
+        self.entry_count += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Run, Error> {
+        let RunWriter {
+            id,
+            writer,
+            hasher,
+            length,
+            entry_count,
+            fences,
+            ..
+        } = self;
+        let file = writer.into_inner().map_err(io::Error::from)?;
+        file.sync_all()?;
+        Ok(Run {
+            id,
+            file,
+            length,
+            entry_count,
+            checksum: finish_hasher(hasher),
+            fences,
+        })
+    }
+}
+
+/// Maps the keys of each database to the locations of their values in the log.
+///
+/// The entries as of the latest checkpoint are held in runs on disk, and the changes since then in
+/// memory.  Older values of a recently changed key are retained for as long as a read transaction
+/// which can see them is open.
+struct Index {
+    /// The sequence number of the latest committed batch.
+    sequence: u64,
+    log: File,
+    /// The runs, oldest first.
+    runs: Vec<Run>,
+    /// The values keys have had since the latest checkpoint.
+    recent: HashMap<String, BTreeMap<Vec<u8>, Versions>>,
+}
+
+impl Index {
+    /// Returns the location of the value of `key` as of the batch numbered `sequence`.
+    fn get(&self, handle: &str, key: &[u8], sequence: u64) -> Result<Option<Location>, Error> {
+        if let Some(versions) = self
+            .recent
+            .get(handle)
+            .and_then(|database| database.get(key))
+        {
+            if let Some(maybe_location) = Self::visible(versions, sequence) {
+                return Ok(maybe_location);
+            }
+        }
+        for run in self.runs.iter().rev() {
+            if let Some(maybe_location) = run.get(handle, key)? {
+                return Ok(maybe_location);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns up to `limit` keys of `handle` which have values as of the batch numbered
+    /// `sequence` and which `include` accepts, in ascending byte order and strictly after
+    /// `maybe_start` if it is given.
+    fn keys<F: Fn(&[u8]) -> bool>(
+        &self,
+        handle: &str,
+        maybe_start: Option<&[u8]>,
+        sequence: u64,
+        limit: usize,
+        include: F,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut recent = self
+            .recent
+            .get(handle)
+            .into_iter()
+            .flat_map(|database| range_after(database, maybe_start))
+            .peekable();
+        // Newest first, so that the first run holding a key decides whether it has a value.
+        let mut runs = self
+            .runs
+            .iter()
+            .rev()
+            .map(|run| RunKeys::new(run, handle, maybe_start))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut keys = Vec::new();
+        while keys.len() < limit {
+            let mut maybe_next: Option<&[u8]> = recent.peek().map(|(key, _)| key.as_slice());
+            for run_keys in &runs {
+                if let Some(entry) = &run_keys.head {
+                    if maybe_next.map_or(true, |next| entry.key.as_slice() < next) {
+                        maybe_next = Some(&entry.key);
+                    }
+                }
+            }
+            let next = match maybe_next {
+                Some(next) => next.to_vec(),
+                None => break,
+            };
+
+            let mut maybe_is_live = None;
+            if recent.peek().map_or(false, |(key, _)| **key == next) {
+                if let Some((_, versions)) = recent.next() {
+                    maybe_is_live = Self::visible(versions, sequence)
+                        .map(|maybe_location| maybe_location.is_some());
+                }
+            }
+            for run_keys in &mut runs {
+                if let Some(entry) = &run_keys.head {
+                    if entry.key == next {
+                        maybe_is_live = maybe_is_live.or_else(|| Some(entry.location.is_some()));
+                        run_keys.advance()?;
+                    }
+                }
+            }
+            if maybe_is_live == Some(true) && include(&next) {
+                keys.push(next);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns the latest of `versions` as of the batch numbered `sequence`: the location of the
+    /// value, or `None` for a deletion.  Returns `None` if every version is later.
+    fn visible(versions: &[(u64, Option<Location>)], sequence: u64) -> Option<Option<Location>> {
+        versions
+            .iter()
+            .rev()
+            .find(|(version, _)| *version <= sequence)
+            .map(|(_, maybe_location)| *maybe_location)
+    }
+
+    /// Applies the changes of the batch numbered `sequence`, dropping the versions of the changed
+    /// keys which are no longer visible to any transaction at or after `oldest_sequence`.
+    fn apply(&mut self, sequence: u64, changes: Vec<Change>, oldest_sequence: u64) {
+        for (handle, key, maybe_location) in changes {
+            let versions = self
+                .recent
+                .entry(handle)
+                .or_default()
+                .entry(key)
+                .or_default();
+            versions.push((sequence, maybe_location));
+            if let Some(oldest_visible) = versions
+                .iter()
+                .rposition(|(version, _)| *version <= oldest_sequence)
+            {
+                versions.drain(..oldest_visible);
+            }
+        }
+        self.sequence = sequence;
+    }
+
+    /// Returns the recent changes as of the latest batch, as entries in ascending order.
+    fn recent_entries(&self) -> Vec<Entry> {
+        let mut handles: Vec<&String> = self.recent.keys().collect();
+        handles.sort();
+        let mut entries = Vec::new();
+        for handle in handles {
+            for (key, versions) in &self.recent[handle] {
+                if let Some(location) = Self::visible(versions, self.sequence) {
+                    entries.push(Entry {
+                        handle: handle.clone(),
+                        key: key.clone(),
+                        location,
+                    });
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// The capability to append to the log, guarding its committed length.
+struct LogWriter {
+    length: u64,
+    /// The latest batch in the log.
+    last_batch: Option<BatchId>,
+    /// The length of the log covered by the latest checkpoint.
+    checkpointed_length: u64,
+    /// The id to give the next run written.
+    next_run_id: u64,
+}
+
+impl LogWriter {
+    /// Returns the writer of a log holding no batches.
+    fn empty(next_run_id: u64) -> Self {
+        LogWriter {
+            length: LOG_MAGIC.len() as u64,
+            last_batch: None,
+            checkpointed_length: LOG_MAGIC.len() as u64,
+            next_run_id,
+        }
+    }
+}
+
+/// Syncs the directory at `path`, making the renames and deletions of files in it durable.
+fn sync_directory(path: &Path) -> Result<(), Error> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+/// Writes a checkpoint listing `runs`, oldest first, which index the log as described by `writer`,
+/// to the directory at `path`.
+///
+/// The checkpoint is written to a temporary file which then replaces any previous checkpoint, so
+/// a crash part-way through leaves the previous checkpoint intact.
+fn write_checkpoint(path: &Path, runs: &[&Run], writer: &LogWriter) -> Result<(), Error> {
+    let (last_batch_offset, last_batch_checksum) = match writer.last_batch {
+        Some(last_batch) => last_batch,
+        None => {
+            // A log without batches needs no checkpoint.
+            match fs::remove_file(path.join(CHECKPOINT_FILE_NAME)) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error.into()),
+                _ => return sync_directory(path),
+            }
+        }
+    };
+    let mut body = Vec::new();
+    body.extend_from_slice(&writer.length.to_le_bytes());
+    body.extend_from_slice(&last_batch_offset.to_le_bytes());
+    body.extend_from_slice(&last_batch_checksum);
+    body.extend_from_slice(&writer.next_run_id.to_le_bytes());
+    for run in runs {
+        body.extend_from_slice(&run.id.to_le_bytes());
+        body.extend_from_slice(&run.length.to_le_bytes());
+        body.extend_from_slice(&run.entry_count.to_le_bytes());
+        body.extend_from_slice(&run.checksum);
+    }
+
+    let temp_path = path.join(CHECKPOINT_TEMP_FILE_NAME);
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(CHECKPOINT_MAGIC)?;
+        file.write_all(&Blake2bHash::new(&body).value())?;
+        file.write_all(&body)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path.join(CHECKPOINT_FILE_NAME))?;
+    sync_directory(path)
+}
+
+/// Reads the checkpoint in the directory at `path`, returning its runs and the state of the part of
+/// `log` which they cover.
+///
+/// Returns `None` if there is no checkpoint, or if it or one of its runs is corrupt or does not
+/// match `log`.
+fn read_checkpoint(path: &Path, log: &File, log_length: u64) -> Option<(Vec<Run>, LogWriter)> {
+    let checkpoint = fs::read(path.join(CHECKPOINT_FILE_NAME)).ok()?;
+    if checkpoint.len() < CHECKPOINT_MAGIC.len() + BLAKE2B_DIGEST_LENGTH
+        || &checkpoint[..CHECKPOINT_MAGIC.len()] != CHECKPOINT_MAGIC
+    {
+        return None;
+    }
+    let (checksum, body) = checkpoint[CHECKPOINT_MAGIC.len()..].split_at(BLAKE2B_DIGEST_LENGTH);
+    if Blake2bHash::new(body).value() != checksum {
+        return None;
+    }
+
+    let mut remainder = body;
+    let length = take_u64(&mut remainder)?;
+    let last_batch_offset = take_u64(&mut remainder)?;
+    let last_batch_checksum = take_hash(&mut remainder)?;
+    let next_run_id = take_u64(&mut remainder)?;
+
+    // The last batch covered by the checkpoint must still be in place at the end of the covered
+    // part of the log.
+    if length > log_length
+        || last_batch_offset < LOG_MAGIC.len() as u64
+        || last_batch_offset + (BATCH_HEADER_LENGTH as u64) > length
+    {
+        return None;
+    }
+    let (body_length, header_checksum) = read_header(log, last_batch_offset).ok()?;
+    if last_batch_offset + BATCH_HEADER_LENGTH as u64 + body_length != length
+        || header_checksum != last_batch_checksum
+    {
+        return None;
+    }
+
+    let mut runs = Vec::new();
+    while !remainder.is_empty() {
+        let id = take_u64(&mut remainder)?;
+        let run_length = take_u64(&mut remainder)?;
+        let entry_count = take_u64(&mut remainder)?;
+        let run_checksum = take_hash(&mut remainder)?;
+        if id >= next_run_id {
+            return None;
+        }
+        runs.push(Run::open(path, id, run_length, entry_count, run_checksum)?);
+    }
+    let writer = LogWriter {
+        length,
+        last_batch: Some((last_batch_offset, last_batch_checksum)),
+        checkpointed_length: length,
+        next_run_id,
+    };
+    Some((runs, writer))
+}
+
+/// Removes the files in the directory at `path` left behind by interrupted checkpoints and
+/// compactions, and the runs other than `runs`.
+fn remove_stale_files(path: &Path, runs: &[Run]) -> Result<(), Error> {
+    let live_runs: HashSet<String> = runs.iter().map(|run| run_file_name(run.id)).collect();
+    for dir_entry in fs::read_dir(path)? {
+        let file_name = dir_entry?.file_name();
+        let file_name = match file_name.to_str() {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        let is_stale = file_name == LOG_TEMP_FILE_NAME
+            || file_name == CHECKPOINT_TEMP_FILE_NAME
+            || file_name.starts_with(RUN_FILE_PREFIX) && !live_runs.contains(file_name);
+        if is_stale {
+            fs::remove_file(path.join(file_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Takes an exclusive lock on the directory at `path`, which is held until the returned file is
+/// closed.
+fn lock_directory(path: &Path) -> Result<File, Error> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(path.join(LOCK_FILE_NAME))?;
+    // Safe as the descriptor belongs to `file`, which outlives the call.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let error = io::Error::last_os_error();
+        if error.kind() == io::ErrorKind::WouldBlock {
+            return Err(Error::DirectoryLocked);
+        }
+        return Err(error.into());
+    }
+    Ok(file)
+}
+
+/// A read transaction for the file-backed store.
+///
+/// Sees the store as of the latest batch committed when it was created.
+pub struct FileReadTransaction<'a> {
+    env: &'a FileEnvironment,
+    sequence: u64,
+}
+
+impl<'a> FileReadTransaction<'a> {
+    pub fn new(env: &'a FileEnvironment) -> Result<FileReadTransaction<'a>, Error> {
+        let index = env.index.read()?;
+        let sequence = index.sequence;
+        *env.readers.lock()?.entry(sequence).or_default() += 1;
+        Ok(FileReadTransaction { env, sequence })
+    }
+}
+
+impl<'a> Drop for FileReadTransaction<'a> {
+    fn drop(&mut self) {
+        if let Ok(mut readers) = self.env.readers.lock() {
+            if let Some(count) = readers.get_mut(&self.sequence) {
+                *count -= 1;
+                if *count == 0 {
+                    readers.remove(&self.sequence);
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Transaction for FileReadTransaction<'a> {
+    type Error = Error;
+
+    type Handle = String;
+
+    fn commit(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a> Readable for FileReadTransaction<'a> {
+    fn read(&self, handle: Self::Handle, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.env.read(&handle, key, self.sequence)
+    }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let index = self.env.index.read()?;
+        index.keys(&handle, maybe_start, self.sequence, limit, |_| true)
+    }
+}
+
+/// A read-write transaction for the file-backed store.
+///
+/// Writes are held in memory until the transaction is committed, when they are appended to the log
+/// as a single batch.
+pub struct FileReadWriteTransaction<'a> {
+    env: &'a FileEnvironment,
+    sequence: u64,
+    writes: HashMap<String, BTreeMap<Vec<u8>, Option<Vec<u8>>>>,
+    writer: MutexGuard<'a, LogWriter>,
+}
+
+impl<'a> FileReadWriteTransaction<'a> {
+    pub fn new(env: &'a FileEnvironment) -> Result<FileReadWriteTransaction<'a>, Error> {
+        let writer = env.writer.lock()?;
+        let sequence = env.index.read()?.sequence;
+        Ok(FileReadWriteTransaction {
+            env,
+            sequence,
+            writes: HashMap::new(),
+            writer,
+        })
+    }
+}
+
+impl<'a> Transaction for FileReadWriteTransaction<'a> {
+    type Error = Error;
+
+    type Handle = String;
+
+    fn commit(self) -> Result<(), Self::Error> {
+        let FileReadWriteTransaction {
+            env,
+            sequence,
+            writes,
+            mut writer,
+        } = self;
+        if writes.is_empty() {
+            return Ok(());
+        }
+
+        let mut batch = Batch::new(writer.length);
+        let mut changes = Vec::new();
+        for (handle, database) in writes {
+            for (key, maybe_value) in database {
+                let maybe_location = batch.push(&handle, &key, maybe_value.as_deref());
+                changes.push((handle.clone(), key, maybe_location));
+            }
+        }
+
+        let batch_offset = batch.offset;
+        let (batch_end, batch_id) = {
+            let index = env.index.read()?;
+            match batch.write_to(&index.log).and_then(|written| {
+                index.log.sync_data()?;
+                Ok(written)
+            }) {
+                Ok(written) => written,
+                Err(error) => {
+                    // Best effort: any partial batch left behind is discarded when the log is
+                    // replayed.
+                    let _ = index.log.set_len(batch_offset);
+                    return Err(error);
+                }
+            }
+        };
+        writer.length = batch_end;
+        writer.last_batch = Some(batch_id);
+
+        {
+            let mut index = env.index.write()?;
+            let next_sequence = sequence + 1;
+            let oldest_sequence = env
+                .readers
+                .lock()?
+                .keys()
+                .next()
+                .copied()
+                .unwrap_or(next_sequence);
+            index.apply(next_sequence, changes, oldest_sequence);
+        }
+
+        if writer.length - writer.checkpointed_length >= env.checkpoint_interval {
+            // Best effort: the batch is already durable, and a checkpoint which fails, or is put
+            // off by an old read transaction, is retried after the next commit.
+            let _ = env.checkpoint(&mut writer);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Readable for FileReadWriteTransaction<'a> {
+    fn read(&self, handle: Self::Handle, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        if let Some(maybe_value) = self
+            .writes
+            .get(&handle)
+            .and_then(|database| database.get(key))
+        {
+            return Ok(maybe_value.to_owned());
+        }
+        self.env.read(&handle, key, self.sequence)
+    }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        let maybe_writes = self.writes.get(&handle);
+        let index = self.env.index.read()?;
+        // The committed keys which are not overwritten here and the keys written here are disjoint
+        // and each sorted, so the first `limit` of each are enough.
+        let mut keys = index.keys(&handle, maybe_start, self.sequence, limit, |key| {
+            maybe_writes.map_or(true, |writes| !writes.contains_key(key))
+        })?;
+        if let Some(writes) = maybe_writes {
+            keys.extend(
+                range_after(writes, maybe_start)
+                    .filter(|(_, maybe_value)| maybe_value.is_some())
+                    .take(limit)
+                    .map(|(key, _)| key.to_owned()),
+            );
+        }
+        keys.sort();
+        keys.truncate(limit);
+        Ok(keys)
+    }
+}
+
+impl<'a> Writable for FileReadWriteTransaction<'a> {
+    fn write(&mut self, handle: Self::Handle, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+        let database = self.writes.entry(handle).or_default();
+        database.insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&mut self, handle: Self::Handle, key: &[u8]) -> Result<(), Self::Error> {
+        // Only deletions of committed keys need to be logged.
+        let is_committed = self
+            .env
+            .index
+            .read()?
+            .get(&handle, key, self.sequence)?
+            .is_some();
+        let database = self.writes.entry(handle).or_default();
+        if is_committed {
+            database.insert(key.to_vec(), None);
+        } else {
+            database.remove(key);
+        }
+        Ok(())
+    }
+}
+
+/// An environment for the file-backed store.
+///
+/// Keeps its log, and the runs and checkpoint of its index, in files inside the directory at
+/// `path`, which it locks while it is open.
+pub struct FileEnvironment {
+    path: PathBuf,
+    /// The open lock file, whose lock is released when it is closed.
+    _lock: File,
+    index: RwLock<Index>,
+    /// The number of open read transactions at each sequence number.
+    readers: Mutex<BTreeMap<u64, usize>>,
+    writer: Mutex<LogWriter>,
+    /// The number of bytes appended to the log between checkpoints of the index.
+    checkpoint_interval: u64,
+}
+
+impl FileEnvironment {
+    /// Opens the environment in the directory at `path`, creating an empty log if none exists.
+    pub fn new(path: &PathBuf) -> Result<Self, Error> {
+        Self::with_checkpoint_interval(path, DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    /// Opens the environment in the directory at `path`, checkpointing its index each time
+    /// `checkpoint_interval` bytes have been appended to the log.
+    ///
+    /// Fails with [`Error::DirectoryLocked`] if another environment is open in the directory.
+    pub fn with_checkpoint_interval(
+        path: &PathBuf,
+        checkpoint_interval: u64,
+    ) -> Result<Self, Error> {
+        let lock = lock_directory(path)?;
+        let log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path.join(LOG_FILE_NAME))?;
+
+        let log_length = log.metadata()?.len();
+        if log_length == 0 {
+            log.write_all_at(LOG_MAGIC, 0)?;
+            log.sync_all()?;
+        } else {
+            let mut magic = [0u8; 8];
+            if log_length < magic.len() as u64 {
+                return Err(Error::CorruptLog(0));
+            }
+            log.read_exact_at(&mut magic, 0)?;
+            if &magic != LOG_MAGIC {
+                return Err(Error::CorruptLog(0));
+            }
+        }
+
+        let (runs, writer) = read_checkpoint(path, &log, log_length)
+            .unwrap_or_else(|| (Vec::new(), LogWriter::empty(0)));
+        remove_stale_files(path, &runs)?;
+        let ret = FileEnvironment {
+            path: path.to_owned(),
+            _lock: lock,
+            index: RwLock::new(Index {
+                sequence: 0,
+                log,
+                runs,
+                recent: HashMap::new(),
+            }),
+            readers: Mutex::new(BTreeMap::new()),
+            writer: Mutex::new(writer),
+            checkpoint_interval,
+        };
+        ret.replay()?;
+        Ok(ret)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Applies the batches in the log after the part covered by the checkpoint, checkpointing as
+    /// they are replayed, and truncates any incomplete batch at the end of the log.
+    fn replay(&self) -> Result<(), Error> {
+        let mut writer = self.writer.lock()?;
+        let log_length = self.index.read()?.log.metadata()?.len();
+        while log_length - writer.length >= BATCH_HEADER_LENGTH as u64 {
+            let offset = writer.length;
+            let (changes, batch_end, checksum) = {
+                let index = self.index.read()?;
+                let (body_length, checksum) = read_header(&index.log, offset)?;
+                let body_offset = offset + BATCH_HEADER_LENGTH as u64;
+                if log_length - body_offset < body_length {
+                    break;
+                }
+                let batch_end = body_offset + body_length;
+                let mut body = vec![0u8; body_length as usize];
+                index.log.read_exact_at(&mut body, body_offset)?;
+                if Blake2bHash::new(&body).value() != checksum {
+                    // A bad final batch is the remains of an interrupted commit.
+                    if batch_end == log_length {
+                        break;
+                    }
+                    return Err(Error::CorruptLog(offset));
+                }
+                let changes = parse_batch(&body, body_offset).ok_or(Error::CorruptLog(offset))?;
+                (changes, batch_end, checksum)
+            };
+            {
+                let mut index = self.index.write()?;
+                let sequence = index.sequence + 1;
+                index.apply(sequence, changes, sequence);
+            }
+            writer.length = batch_end;
+            writer.last_batch = Some((offset, checksum));
+            if writer.length - writer.checkpointed_length >= self.checkpoint_interval {
+                self.checkpoint(&mut writer)?;
+            }
+        }
+
+        if writer.length < log_length {
+            let index = self.index.read()?;
+            index.log.set_len(writer.length)?;
+            index.log.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the changes since the latest checkpoint out as a new run, merging it with the newest
+    /// runs unless they are more than twice its size, and records the runs and the part of the log
+    /// which they cover in a new checkpoint.
+    ///
+    /// Holding the writer ensures no batch is appended meanwhile.  Fails with
+    /// [`Error::ReadTransactionOpen`] if a read transaction can see a state older than the latest
+    /// batch, as the runs only hold the latest state.
+    fn checkpoint(&self, writer: &mut LogWriter) -> Result<(), Error> {
+        let mut stale_runs = Vec::new();
+        let (kept_run_count, maybe_new_run) = {
+            let index = self.index.read()?;
+            let is_older_reader_open = self
+                .readers
+                .lock()?
+                .keys()
+                .next()
+                .map_or(false, |oldest| *oldest < index.sequence);
+            if is_older_reader_open {
+                return Err(Error::ReadTransactionOpen);
+            }
+
+            let recent_entries = index.recent_entries();
+            let maybe_new_run = if recent_entries.is_empty() {
+                None
+            } else {
+                let mut run_writer = RunWriter::new(&self.path, writer.next_run_id)?;
+                writer.next_run_id += 1;
+                for entry in recent_entries {
+                    run_writer.push(entry)?;
+                }
+                Some(run_writer.finish()?)
+            };
+
+            let mut kept_run_count = index.runs.len();
+            let maybe_new_run = match maybe_new_run {
+                Some(recent_run) => {
+                    let mut merged_entry_count = recent_run.entry_count;
+                    while kept_run_count > 0
+                        && index.runs[kept_run_count - 1].entry_count <= 2 * merged_entry_count
+                    {
+                        kept_run_count -= 1;
+                        merged_entry_count += index.runs[kept_run_count].entry_count;
+                    }
+                    if kept_run_count < index.runs.len() {
+                        let runs = iter::once(&recent_run)
+                            .chain(index.runs[kept_run_count..].iter().rev());
+                        // Deletions only need to be kept while there are older runs to hide.
+                        let mut merged = MergedEntries::new(runs, kept_run_count > 0)?;
+                        let mut run_writer = RunWriter::new(&self.path, writer.next_run_id)?;
+                        writer.next_run_id += 1;
+                        while let Some(entry) = merged.next_entry()? {
+                            run_writer.push(entry)?;
+                        }
+                        stale_runs.push(recent_run.id);
+                        stale_runs.extend(index.runs[kept_run_count..].iter().map(|run| run.id));
+                        Some(run_writer.finish()?)
+                    } else {
+                        Some(recent_run)
+                    }
+                }
+                None => None,
+            };
+
+            let runs: Vec<&Run> = index.runs[..kept_run_count]
+                .iter()
+                .chain(maybe_new_run.iter())
+                .collect();
+            write_checkpoint(&self.path, &runs, writer)?;
+            (kept_run_count, maybe_new_run)
+        };
+
+        {
+            let mut index = self.index.write()?;
+            index.runs.truncate(kept_run_count);
+            index.runs.extend(maybe_new_run);
+            index.recent.clear();
+        }
+        writer.checkpointed_length = writer.length;
+        for id in stale_runs {
+            // Best effort: stale runs are also removed when the environment is next opened.
+            let _ = fs::remove_file(self.path.join(run_file_name(id)));
+        }
+        Ok(())
+    }
+
+    /// Rewrites the log with only the live values, reclaiming the space taken by deleted and
+    /// replaced ones, and merges the index into a single run.
+    ///
+    /// Commits wait until it finishes.  Fails with [`Error::ReadTransactionOpen`] if a read
+    /// transaction can see a state older than the latest batch.
+    pub fn compact(&self) -> Result<(), Error> {
+        let mut writer = self.writer.lock()?;
+        self.checkpoint(&mut writer)?;
+
+        let temp_path = self.path.join(LOG_TEMP_FILE_NAME);
+        let log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        log.write_all_at(LOG_MAGIC, 0)?;
+        let mut new_writer = LogWriter::empty(writer.next_run_id + 1);
+        let (run, stale_runs) = {
+            let index = self.index.read()?;
+            let mut run_writer = RunWriter::new(&self.path, writer.next_run_id)?;
+            let mut merged = MergedEntries::new(index.runs.iter().rev(), false)?;
+            let mut batch = Batch::new(new_writer.length);
+            while let Some(entry) = merged.next_entry()? {
+                let location = match entry.location {
+                    Some(location) => location,
+                    None => continue,
+                };
+                let mut value = vec![0u8; location.length];
+                index.log.read_exact_at(&mut value, location.offset)?;
+                let location = batch.push(&entry.handle, &entry.key, Some(&value));
+                run_writer.push(Entry { location, ..entry })?;
+                if batch.body.len() >= COMPACTION_BATCH_SIZE {
+                    let (batch_end, batch_id) = batch.write_to(&log)?;
+                    new_writer.length = batch_end;
+                    new_writer.last_batch = Some(batch_id);
+                    batch = Batch::new(batch_end);
+                }
+            }
+            if !batch.body.is_empty() {
+                let (batch_end, batch_id) = batch.write_to(&log)?;
+                new_writer.length = batch_end;
+                new_writer.last_batch = Some(batch_id);
+            }
+            log.sync_all()?;
+            let stale_runs: Vec<u64> = index.runs.iter().map(|run| run.id).collect();
+            (run_writer.finish()?, stale_runs)
+        };
+        new_writer.checkpointed_length = new_writer.length;
+
+        // Until the checkpoint is replaced, the previous one does not match the new log, so a crash
+        // in between leads to the whole new log being replayed.
+        fs::rename(&temp_path, self.path.join(LOG_FILE_NAME))?;
+        sync_directory(&self.path)?;
+        write_checkpoint(&self.path, &[&run], &new_writer)?;
+        {
+            let mut index = self.index.write()?;
+            index.log = log;
+            index.runs = vec![run];
+        }
+        *writer = new_writer;
+        for id in stale_runs {
+            // Best effort: stale runs are also removed when the environment is next opened.
+            let _ = fs::remove_file(self.path.join(run_file_name(id)));
+        }
+        Ok(())
+    }
+
+    fn read(&self, handle: &str, key: &[u8], sequence: u64) -> Result<Option<Vec<u8>>, Error> {
+        let index = self.index.read()?;
+        match index.get(handle, key, sequence)? {
+            Some(Location { offset, length }) => {
+                let mut value = vec![0u8; length];
+                index.log.read_exact_at(&mut value, offset)?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a> TransactionSource<'a> for FileEnvironment {
+    type Error = Error;
+
+    type Handle = String;
+
+    type ReadTransaction = FileReadTransaction<'a>;
+
+    type ReadWriteTransaction = FileReadWriteTransaction<'a>;
+
+    fn create_read_txn(&'a self) -> Result<FileReadTransaction<'a>, Self::Error> {
+        FileReadTransaction::new(self)
+    }
+
+    fn create_read_write_txn(&'a self) -> Result<FileReadWriteTransaction<'a>, Self::Error> {
+        FileReadWriteTransaction::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File, OpenOptions};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const HANDLE: &str = "TEST";
+
+    fn put(env: &FileEnvironment, key: &[u8], value: &[u8]) {
+        let mut txn = env.create_read_write_txn().unwrap();
+        txn.write(HANDLE.to_string(), key, value).unwrap();
+        txn.commit().unwrap();
+    }
+
+    fn get(env: &FileEnvironment, key: &[u8]) -> Option<Vec<u8>> {
+        let txn = env.create_read_txn().unwrap();
+        txn.read(HANDLE.to_string(), key).unwrap()
+    }
+
+    fn delete(env: &FileEnvironment, key: &[u8]) {
+        let mut txn = env.create_read_write_txn().unwrap();
+        txn.delete(HANDLE.to_string(), key).unwrap();
+        txn.commit().unwrap();
+    }
+
+    fn keys(env: &FileEnvironment, maybe_start: Option<&[u8]>, limit: usize) -> Vec<Vec<u8>> {
+        let txn = env.create_read_txn().unwrap();
+        txn.read_keys(HANDLE.to_string(), maybe_start, limit)
+            .unwrap()
+    }
+
+    fn log_length(env: &FileEnvironment) -> u64 {
+        env.index.read().unwrap().log.metadata().unwrap().len()
+    }
+
+    #[test]
+    fn committed_writes_survive_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::new(&path).unwrap();
+            put(&env, b"a", b"1");
+            put(&env, b"b", b"2");
+            put(&env, b"a", b"3");
+            let mut txn = env.create_read_write_txn().unwrap();
+            txn.delete(HANDLE.to_string(), b"b").unwrap();
+            txn.commit().unwrap();
+        }
+        let env = FileEnvironment::new(&path).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"3".to_vec()));
+        assert_eq!(get(&env, b"b"), None);
+        let txn = env.create_read_txn().unwrap();
+        assert_eq!(
+            txn.read_keys(HANDLE.to_string(), None, 10).unwrap(),
+            vec![b"a".to_vec()]
+        );
+    }
+
+    #[test]
+    fn incomplete_final_batch_is_discarded() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let committed_length = {
+            let env = FileEnvironment::new(&path).unwrap();
+            put(&env, b"a", b"1");
+            let committed_length = log_length(&env);
+            put(&env, b"b", b"2");
+            committed_length
+        };
+
+        // Simulate a crash part-way through writing the second batch.
+        let log_path = path.join(LOG_FILE_NAME);
+        let full_length = OpenOptions::new()
+            .write(true)
+            .open(&log_path)
+            .and_then(|file| {
+                let full_length = file.metadata()?.len();
+                file.set_len(full_length - 1)?;
+                Ok(full_length)
+            })
+            .unwrap();
+        assert!(full_length > committed_length);
+
+        let env = FileEnvironment::new(&path).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"1".to_vec()));
+        assert_eq!(get(&env, b"b"), None);
+        assert_eq!(log_length(&env), committed_length);
+
+        put(&env, b"c", b"3");
+        drop(env);
+        let env = FileEnvironment::new(&path).unwrap();
+        assert_eq!(get(&env, b"c"), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn corrupt_batch_before_end_of_log_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::new(&path).unwrap();
+            put(&env, b"a", b"1");
+            put(&env, b"b", b"2");
+        }
+
+        // Flip the last byte of the first batch's value.
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path.join(LOG_FILE_NAME))
+            .unwrap();
+        let offset = (LOG_MAGIC.len() + BATCH_HEADER_LENGTH) as u64;
+        let (body_length, _) = read_header(&file, LOG_MAGIC.len() as u64).unwrap();
+        let mut byte = [0u8; 1];
+        file.read_exact_at(&mut byte, offset + body_length - 1)
+            .unwrap();
+        byte[0] ^= 0xff;
+        file.write_all_at(&byte, offset + body_length - 1).unwrap();
+
+        let expected = Error::CorruptLog(LOG_MAGIC.len() as u64);
+        assert_eq!(FileEnvironment::new(&path).err(), Some(expected));
+    }
+
+    #[test]
+    fn only_the_log_after_the_checkpoint_is_replayed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let checkpointed_length = {
+            let env = FileEnvironment::with_checkpoint_interval(&path, 1).unwrap();
+            put(&env, b"a", b"1");
+            put(&env, b"b", b"2");
+            log_length(&env)
+        };
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+            put(&env, b"a", b"3");
+            let mut txn = env.create_read_write_txn().unwrap();
+            txn.delete(HANDLE.to_string(), b"b").unwrap();
+            txn.commit().unwrap();
+            put(&env, b"c", b"4");
+        }
+
+        let log = File::open(path.join(LOG_FILE_NAME)).unwrap();
+        let log_length = log.metadata().unwrap().len();
+        let (_, writer) = read_checkpoint(&path, &log, log_length).unwrap();
+        assert_eq!(writer.length, checkpointed_length);
+        assert!(writer.length < log_length);
+
+        let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"3".to_vec()));
+        assert_eq!(get(&env, b"b"), None);
+        assert_eq!(get(&env, b"c"), Some(b"4".to_vec()));
+        let txn = env.create_read_txn().unwrap();
+        assert_eq!(
+            txn.read_keys(HANDLE.to_string(), None, 10).unwrap(),
+            vec![b"a".to_vec(), b"c".to_vec()]
+        );
+    }
+
+    #[test]
+    fn checkpoint_which_does_not_match_the_log_is_ignored() {
+        let checkpoint_dir = tempdir().unwrap();
+        let checkpoint_path = checkpoint_dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&checkpoint_path, 1).unwrap();
+            put(&env, b"a", b"1");
+        }
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+            put(&env, b"a", b"2");
+            put(&env, b"b", b"3");
+        }
+        fs::copy(
+            checkpoint_path.join(CHECKPOINT_FILE_NAME),
+            path.join(CHECKPOINT_FILE_NAME),
+        )
+        .unwrap();
+
+        let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"2".to_vec()));
+        assert_eq!(get(&env, b"b"), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn corrupt_checkpoint_is_ignored() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&path, 1).unwrap();
+            put(&env, b"a", b"1");
+            put(&env, b"b", b"2");
+        }
+
+        let checkpoint_path = path.join(CHECKPOINT_FILE_NAME);
+        let mut checkpoint = fs::read(&checkpoint_path).unwrap();
+        let last = checkpoint.len() - 1;
+        checkpoint[last] ^= 0xff;
+        fs::write(&checkpoint_path, checkpoint).unwrap();
+
+        let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"1".to_vec()));
+        assert_eq!(get(&env, b"b"), Some(b"2".to_vec()));
+    }
+
+    #[test]
+    fn read_transactions_keep_replaced_values_visible() {
+        let dir = tempdir().unwrap();
+        let env = FileEnvironment::new(&dir.path().to_path_buf()).unwrap();
+        put(&env, b"a", b"1");
+
+        let old_txn = env.create_read_txn().unwrap();
+        put(&env, b"a", b"2");
+        let mut txn = env.create_read_write_txn().unwrap();
+        txn.delete(HANDLE.to_string(), b"a").unwrap();
+        txn.commit().unwrap();
+
+        assert_eq!(
+            old_txn.read(HANDLE.to_string(), b"a").unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(get(&env, b"a"), None);
+        drop(old_txn);
+
+        // Once no reader can see them, the old versions are dropped.
+        put(&env, b"a", b"3");
+        let index = env.index.read().unwrap();
+        assert_eq!(index.recent[HANDLE][&b"a".to_vec()].len(), 1);
+    }
+
+    #[test]
+    fn directory_is_locked_while_environment_is_open() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let env = FileEnvironment::new(&path).unwrap();
+        assert_eq!(
+            FileEnvironment::new(&path).err(),
+            Some(Error::DirectoryLocked)
+        );
+
+        drop(env);
+        assert!(FileEnvironment::new(&path).is_ok());
+    }
+
+    #[test]
+    fn index_is_held_in_runs_which_survive_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let key_count: u8 = 200;
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&path, 1).unwrap();
+            for key in 0..key_count {
+                put(&env, &[key], &[key]);
+            }
+            for key in (0..key_count).step_by(3) {
+                delete(&env, &[key]);
+            }
+            put(&env, &[0], b"replaced");
+
+            // Each checkpoint empties the recent changes, and similar runs are merged.
+            let index = env.index.read().unwrap();
+            assert!(index.recent.is_empty());
+            assert!(index.runs.len() < 16);
+        }
+
+        let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+        assert!(env.index.read().unwrap().recent.is_empty());
+        for key in 1..key_count {
+            let expected = if key % 3 == 0 { None } else { Some(vec![key]) };
+            assert_eq!(get(&env, &[key]), expected);
+        }
+        assert_eq!(get(&env, &[0]), Some(b"replaced".to_vec()));
+
+        let expected_keys: Vec<Vec<u8>> = (0..key_count)
+            .filter(|key| *key == 0 || key % 3 != 0)
+            .map(|key| vec![key])
+            .collect();
+        assert_eq!(keys(&env, None, usize::MAX), expected_keys);
+        assert_eq!(keys(&env, Some(&[1]), 3), expected_keys[2..5].to_vec());
+
+        // Recent changes shadow the runs.
+        put(&env, &[3], b"restored");
+        delete(&env, &[1]);
+        assert_eq!(get(&env, &[3]), Some(b"restored".to_vec()));
+        assert_eq!(get(&env, &[1]), None);
+        assert_eq!(
+            keys(&env, None, 4),
+            vec![vec![0], vec![2], vec![3], vec![4]]
+        );
+    }
+
+    #[test]
+    fn corrupt_run_is_ignored() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::with_checkpoint_interval(&path, 1).unwrap();
+            put(&env, b"a", b"1");
+            put(&env, b"b", b"2");
+        }
+
+        let run_path = fs::read_dir(&path)
+            .unwrap()
+            .map(|dir_entry| dir_entry.unwrap().path())
+            .find(|run_path| {
+                run_path
+                    .file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .map_or(false, |file_name| file_name.starts_with(RUN_FILE_PREFIX))
+            })
+            .unwrap();
+        let mut run = fs::read(&run_path).unwrap();
+        let last = run.len() - 1;
+        run[last] ^= 0xff;
+        fs::write(&run_path, run).unwrap();
+
+        let env = FileEnvironment::with_checkpoint_interval(&path, u64::MAX).unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"1".to_vec()));
+        assert_eq!(get(&env, b"b"), Some(b"2".to_vec()));
+        assert!(!run_path.exists());
+    }
+
+    #[test]
+    fn compaction_drops_replaced_and_deleted_values() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let env = FileEnvironment::new(&path).unwrap();
+            for value in 0..100u8 {
+                put(&env, b"a", &[value; 64]);
+            }
+            put(&env, b"b", b"2");
+            put(&env, b"c", b"3");
+            delete(&env, b"c");
+
+            let length_before = log_length(&env);
+            env.compact().unwrap();
+            assert!(log_length(&env) < length_before / 10);
+            assert_eq!(get(&env, b"a"), Some(vec![99; 64]));
+            assert_eq!(get(&env, b"b"), Some(b"2".to_vec()));
+            assert_eq!(get(&env, b"c"), None);
+
+            put(&env, b"d", b"4");
+        }
+
+        let env = FileEnvironment::new(&path).unwrap();
+        assert_eq!(get(&env, b"a"), Some(vec![99; 64]));
+        assert_eq!(get(&env, b"c"), None);
+        assert_eq!(
+            keys(&env, None, 10),
+            vec![b"a".to_vec(), b"b".to_vec(), b"d".to_vec()]
+        );
+        assert!(!path.join(LOG_TEMP_FILE_NAME).exists());
+    }
+
+    #[test]
+    fn compaction_waits_for_older_read_transactions() {
+        let dir = tempdir().unwrap();
+        let env = FileEnvironment::new(&dir.path().to_path_buf()).unwrap();
+        put(&env, b"a", b"1");
+        let old_txn = env.create_read_txn().unwrap();
+        put(&env, b"a", b"2");
+
+        assert_eq!(env.compact(), Err(Error::ReadTransactionOpen));
+        assert_eq!(
+            old_txn.read(HANDLE.to_string(), b"a").unwrap(),
+            Some(b"1".to_vec())
+        );
+
+        drop(old_txn);
+        env.compact().unwrap();
+        assert_eq!(get(&env, b"a"), Some(b"2".to_vec()));
+    }
+}
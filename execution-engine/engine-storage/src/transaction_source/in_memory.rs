@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
     sync::{self, Arc, Mutex, MutexGuard},
 };
 
@@ -14,10 +15,31 @@ struct WriteCapability;
 
 type WriteLock<'a> = MutexGuard<'a, WriteCapability>;
 
-type BytesMap = HashMap<Vec<u8>, Vec<u8>>;
+type BytesMap = BTreeMap<Vec<u8>, Vec<u8>>;
 
 type PoisonError<'a> = sync::PoisonError<MutexGuard<'a, HashMap<Option<String>, BytesMap>>>;
 
+fn read_keys(
+    view: &HashMap<Option<String>, BytesMap>,
+    handle: &Option<String>,
+    maybe_start: Option<&[u8]>,
+    limit: usize,
+) -> Vec<Vec<u8>> {
+    let sub_view = match view.get(handle) {
+        Some(view) => view,
+        None => return Vec::new(),
+    };
+    let lower = match maybe_start {
+        Some(start) => Bound::Excluded(start),
+        None => Bound::Unbounded,
+    };
+    sub_view
+        .range::<[u8], _>((lower, Bound::Unbounded))
+        .take(limit)
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
 /// A read transaction for the in-memory trie store.
 pub struct InMemoryReadTransaction {
     view: HashMap<Option<String>, BytesMap>,
@@ -52,6 +74,15 @@ impl Readable for InMemoryReadTransaction {
         };
         Ok(sub_view.get(&key.to_vec()).cloned())
     }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(read_keys(&self.view, &handle, maybe_start, limit))
+    }
 }
 
 /// A read-write transaction for the in-memory trie store.
@@ -97,6 +128,15 @@ impl<'a> Readable for InMemoryReadWriteTransaction<'a> {
         };
         Ok(sub_view.get(&key.to_vec()).cloned())
     }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        Ok(read_keys(&self.view, &handle, maybe_start, limit))
+    }
 }
 
 impl<'a> Writable for InMemoryReadWriteTransaction<'a> {
@@ -105,6 +145,13 @@ impl<'a> Writable for InMemoryReadWriteTransaction<'a> {
         sub_view.insert(key.to_vec(), value.to_vec());
         Ok(())
     }
+
+    fn delete(&mut self, handle: Self::Handle, key: &[u8]) -> Result<(), Self::Error> {
+        if let Some(sub_view) = self.view.get_mut(&handle) {
+            sub_view.remove(key);
+        }
+        Ok(())
+    }
 }
 
 /// An environment for the in-memory trie store.
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use lmdb::{self, Database, Environment, RoTransaction, RwTransaction, WriteFlags};
+use lmdb::{
+    self, Cursor, Database, Environment, EnvironmentFlags, RoTransaction, RwTransaction, WriteFlags,
+};
 
 use crate::{
     error,
@@ -8,6 +10,30 @@ use crate::{
     MAX_DBS,
 };
 
+fn read_keys<T: lmdb::Transaction>(
+    txn: &T,
+    handle: Database,
+    maybe_start: Option<&[u8]>,
+    limit: usize,
+) -> Result<Vec<Vec<u8>>, lmdb::Error> {
+    let mut ret = Vec::new();
+    let mut cursor = txn.open_ro_cursor(handle)?;
+    if let Some(start) = maybe_start {
+        // Position the cursor at the first key greater than or equal to `start`.  If that key is
+        // not `start` itself (e.g. it has since been deleted), it must be returned too.
+        match cursor.get(Some(start), None, lmdb_sys::MDB_SET_RANGE) {
+            Ok((Some(key), _)) if key != start => ret.push(key.to_vec()),
+            Ok(_) => (),
+            Err(lmdb::Error::NotFound) => return Ok(ret),
+            Err(error) => return Err(error),
+        }
+    }
+    let remaining = limit.saturating_sub(ret.len());
+    ret.extend(cursor.iter().take(remaining).map(|(key, _)| key.to_vec()));
+    ret.truncate(limit);
+    Ok(ret)
+}
+
 impl<'a> Transaction for RoTransaction<'a> {
     type Error = lmdb::Error;
 
@@ -26,6 +52,15 @@ impl<'a> Readable for RoTransaction<'a> {
             Err(e) => Err(e),
         }
     }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        read_keys(self, handle, maybe_start, limit)
+    }
 }
 
 impl<'a> Transaction for RwTransaction<'a> {
@@ -46,6 +81,15 @@ impl<'a> Readable for RwTransaction<'a> {
             Err(e) => Err(e),
         }
     }
+
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error> {
+        read_keys(self, handle, maybe_start, limit)
+    }
 }
 
 impl<'a> Writable for RwTransaction<'a> {
@@ -53,6 +97,13 @@ impl<'a> Writable for RwTransaction<'a> {
         self.put(handle, &key, &value, WriteFlags::empty())
             .map_err(Into::into)
     }
+
+    fn delete(&mut self, handle: Self::Handle, key: &[u8]) -> Result<(), Self::Error> {
+        match self.del(handle, &key, None) {
+            Ok(()) | Err(lmdb::Error::NotFound) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 /// The environment for an LMDB-backed trie store.
@@ -74,6 +125,22 @@ impl LmdbEnvironment {
         Ok(LmdbEnvironment { path, env })
     }
 
+    /// Opens the existing environment at `path` for reading only.
+    ///
+    /// Unlike [`LmdbEnvironment::new`], this never creates the data file, so it fails if there is
+    /// no environment at `path`, and write transactions cannot be started.  `path` is the same
+    /// directory as is passed to [`LmdbEnvironment::new`], so the lock file is shared with any
+    /// process which has the environment open for writing.
+    pub fn open_read_only(path: &PathBuf, map_size: usize) -> Result<Self, error::Error> {
+        let env = Environment::new()
+            .set_flags(EnvironmentFlags::READ_ONLY)
+            .set_max_dbs(MAX_DBS)
+            .set_map_size(map_size)
+            .open(path)?;
+        let path = path.to_owned();
+        Ok(LmdbEnvironment { path, env })
+    }
+
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
@@ -100,3 +167,50 @@ impl<'a> TransactionSource<'a> for LmdbEnvironment {
         self.env.begin_rw_txn()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lmdb::DatabaseFlags;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::TEST_MAP_SIZE;
+
+    const DATA_FILE_NAME: &str = "data.mdb";
+
+    #[test]
+    fn open_read_only_does_not_create_an_environment() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+
+        assert!(LmdbEnvironment::open_read_only(&path, *TEST_MAP_SIZE).is_err());
+        assert!(!path.join(DATA_FILE_NAME).exists());
+
+        let missing_path = path.join("missing");
+        assert!(LmdbEnvironment::open_read_only(&missing_path, *TEST_MAP_SIZE).is_err());
+        assert!(!missing_path.exists());
+    }
+
+    #[test]
+    fn open_read_only_reads_but_does_not_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        {
+            let environment = LmdbEnvironment::new(&path, *TEST_MAP_SIZE).unwrap();
+            let db = environment
+                .env()
+                .create_db(Some("TEST"), DatabaseFlags::empty())
+                .unwrap();
+            let mut txn = environment.create_read_write_txn().unwrap();
+            txn.write(db, b"key", b"value").unwrap();
+            txn.commit().unwrap();
+        }
+
+        let environment = LmdbEnvironment::open_read_only(&path, *TEST_MAP_SIZE).unwrap();
+        let db = environment.env().open_db(Some("TEST")).unwrap();
+        let txn = environment.create_read_txn().unwrap();
+        assert_eq!(txn.read(db, b"key").unwrap(), Some(b"value".to_vec()));
+        txn.commit().unwrap();
+        assert!(environment.create_read_write_txn().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod file;
 pub mod in_memory;
 pub mod lmdb;
 
@@ -25,12 +26,28 @@ pub trait Transaction: Sized {
 pub trait Readable: Transaction {
     /// Returns the value from the corresponding key from a given [`Transaction::Handle`].
     fn read(&self, handle: Self::Handle, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Returns up to `limit` keys from a given [`Transaction::Handle`] in ascending byte order.
+    ///
+    /// If `maybe_start` is given, only keys strictly greater than it are returned.  This allows a
+    /// caller to page through all the keys of a handle using several transactions.
+    fn read_keys(
+        &self,
+        handle: Self::Handle,
+        maybe_start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, Self::Error>;
 }
 
 /// A transaction with the capability to write to a given [`Handle`](Transaction::Handle).
 pub trait Writable: Transaction {
     /// Inserts a key-value pair into a given [`Transaction::Handle`].
     fn write(&mut self, handle: Self::Handle, key: &[u8], value: &[u8]) -> Result<(), Self::Error>;
+
+    /// Removes a key-value pair from a given [`Transaction::Handle`].
+    ///
+    /// Removing a key which is not present is not an error.
+    fn delete(&mut self, handle: Self::Handle, key: &[u8]) -> Result<(), Self::Error>;
 }
 
 /// A source of transactions e.g. values that implement [`Readable`]
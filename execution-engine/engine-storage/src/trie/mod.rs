@@ -310,6 +310,110 @@ impl<K: FromBytes, V: FromBytes> FromBytes for Trie<K, V> {
     }
 }
 
+/// A value stored in a Merkle Trie along with the trie elements visited while reading it, ordered
+/// from the root down to the leaf holding the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieMerkleProof<K, V> {
+    key: K,
+    value: V,
+    proof_steps: Vec<Trie<K, V>>,
+}
+
+impl<K, V> TrieMerkleProof<K, V> {
+    pub fn new(key: K, value: V, proof_steps: Vec<Trie<K, V>>) -> Self {
+        TrieMerkleProof {
+            key,
+            value,
+            proof_steps,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    pub fn proof_steps(&self) -> &[Trie<K, V>] {
+        &self.proof_steps
+    }
+
+    pub fn take_value(self) -> V {
+        self.value
+    }
+}
+
+/// The trie elements visited while reading a key which is absent from a Merkle Trie, ordered from
+/// the root down to the element at which the path of the key ends.
+///
+/// The last element is a `Node` with no pointer at the key's next index, an `Extension` whose affix
+/// differs from the key's path, or a `Leaf` holding a different key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieAbsenceProof<K, V> {
+    key: K,
+    proof_steps: Vec<Trie<K, V>>,
+}
+
+impl<K, V> TrieAbsenceProof<K, V> {
+    pub fn new(key: K, proof_steps: Vec<Trie<K, V>>) -> Self {
+        TrieAbsenceProof { key, proof_steps }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn proof_steps(&self) -> &[Trie<K, V>] {
+        &self.proof_steps
+    }
+}
+
+/// A batch of serialized trie elements used to copy a trie between stores.
+///
+/// Elements are ordered such that every element follows the element pointing to it, so a receiver
+/// which trusts only the root hash can check each element's hash on receipt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrieChunk {
+    tries: Vec<Vec<u8>>,
+}
+
+impl TrieChunk {
+    pub fn new(tries: Vec<Vec<u8>>) -> Self {
+        TrieChunk { tries }
+    }
+
+    pub fn tries(&self) -> &[Vec<u8>] {
+        &self.tries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tries.len()
+    }
+}
+
+impl ToBytes for TrieChunk {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.tries.to_bytes()
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.tries.serialized_length()
+    }
+}
+
+impl FromBytes for TrieChunk {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tries, rem) = Vec::<Vec<u8>>::from_bytes(bytes)?;
+        Ok((TrieChunk { tries }, rem))
+    }
+}
+
 pub(crate) mod operations {
     use crate::trie::Trie;
     use engine_shared::newtypes::Blake2bHash;
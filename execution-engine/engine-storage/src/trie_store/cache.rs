@@ -0,0 +1,396 @@
+//! A cache of deserialized [`Trie`] values, keyed by their hashes.
+//!
+//! Trie elements are content-addressed, so a cached element never goes stale.  It is only evicted
+//! to keep the cache within its capacity, or when it is deleted from the underlying store.
+//!
+//! Cached elements are shared rather than copied out of the cache, and the cache is split into
+//! shards by hash, each with its own lock, so that concurrent readers rarely contend.
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    mem,
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+use engine_shared::{
+    logging::log_metric,
+    newtypes::{Blake2bHash, CorrelationId},
+};
+use types::bytesrepr::{self, FromBytes, ToBytes};
+
+use crate::{
+    store::Store,
+    transaction_source::{Readable, Writable},
+    trie::{PointerBlock, Trie},
+    trie_store::TrieStore,
+    GAUGE_METRIC_KEY,
+};
+
+/// The default capacity of a [`TrieCache`], in bytes: 64 MiB.
+pub const DEFAULT_TRIE_CACHE_CAPACITY: usize = 64 * 1024 * 1024;
+
+/// The number of shards of a [`TrieCache`], each holding an equal share of its capacity.
+const SHARD_COUNT: usize = 16;
+
+const TRIE_CACHE_HITS: &str = "trie_cache_hits";
+const TRIE_CACHE_MISSES: &str = "trie_cache_misses";
+
+/// Returns an estimate of the memory used by a deserialized trie element.
+fn estimated_size<K, V>(trie: &Trie<K, V>, serialized_length: usize) -> usize {
+    let pointer_block_size = match trie {
+        Trie::Node { .. } => mem::size_of::<PointerBlock>(),
+        Trie::Leaf { .. } | Trie::Extension { .. } => 0,
+    };
+    mem::size_of::<Trie<K, V>>() + pointer_block_size + serialized_length
+}
+
+struct CacheEntry<K, V> {
+    trie: Arc<Trie<K, V>>,
+    size: usize,
+    last_used: u64,
+}
+
+struct CacheState<K, V> {
+    entries: HashMap<Blake2bHash, CacheEntry<K, V>>,
+    /// The hashes of the cached entries, keyed by when they were last used.
+    recency: BTreeMap<u64, Blake2bHash>,
+    next_use: u64,
+    size: usize,
+}
+
+impl<K, V> CacheState<K, V> {
+    fn new() -> Self {
+        CacheState {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_use: 0,
+            size: 0,
+        }
+    }
+
+    fn remove(&mut self, hash: &Blake2bHash) {
+        if let Some(entry) = self.entries.remove(hash) {
+            self.recency.remove(&entry.last_used);
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// A size-bounded, thread-safe cache of deserialized trie elements, which evicts the least
+/// recently used elements of a shard once it is full.
+pub struct TrieCache<K, V> {
+    capacity: usize,
+    shards: Vec<Mutex<CacheState<K, V>>>,
+}
+
+impl<K, V> TrieCache<K, V> {
+    /// Creates an empty cache holding at most roughly `capacity` bytes of trie elements.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_shard_count(capacity, SHARD_COUNT)
+    }
+
+    fn with_shard_count(capacity: usize, shard_count: usize) -> Self {
+        TrieCache {
+            capacity,
+            shards: (0..shard_count)
+                .map(|_| Mutex::new(CacheState::new()))
+                .collect(),
+        }
+    }
+
+    /// Returns the capacity of the cache in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the estimated size in bytes of the cached elements.
+    pub fn size(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().size).sum()
+    }
+
+    /// Returns the number of cached elements.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().entries.len())
+            .sum()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity in bytes of each shard.
+    fn shard_capacity(&self) -> usize {
+        self.capacity / self.shards.len()
+    }
+
+    /// Returns the shard holding the element with the given hash.
+    fn shard(&self, hash: &Blake2bHash) -> &Mutex<CacheState<K, V>> {
+        // Hashes are uniformly distributed, so their first byte spreads elements evenly.
+        let index = usize::from(hash.value()[0]) % self.shards.len();
+        &self.shards[index]
+    }
+
+    /// Returns the cached element with the given hash, marking it as recently used.
+    pub fn get(&self, hash: &Blake2bHash) -> Option<Arc<Trie<K, V>>> {
+        let mut state = self.shard(hash).lock();
+        let next_use = state.next_use;
+        let entry = state.entries.get_mut(hash)?;
+        let last_used = mem::replace(&mut entry.last_used, next_use);
+        let trie = Arc::clone(&entry.trie);
+        state.recency.remove(&last_used);
+        state.recency.insert(next_use, *hash);
+        state.next_use += 1;
+        Some(trie)
+    }
+
+    /// Caches `trie` at `hash`, evicting the least recently used elements of its shard as needed.
+    ///
+    /// `serialized_length` is the length of the serialized element, which is used to estimate its
+    /// size.  An element too large to fit in a shard at all is not cached.
+    pub fn insert(&self, hash: Blake2bHash, trie: Arc<Trie<K, V>>, serialized_length: usize) {
+        let size = estimated_size(&trie, serialized_length);
+        let capacity = self.shard_capacity();
+        if size > capacity {
+            return;
+        }
+        let mut state = self.shard(&hash).lock();
+        state.remove(&hash);
+        while state.size + size > capacity {
+            let least_recently_used = match state.recency.values().next() {
+                Some(hash) => *hash,
+                None => break,
+            };
+            state.remove(&least_recently_used);
+        }
+        let last_used = state.next_use;
+        state.next_use += 1;
+        state.recency.insert(last_used, hash);
+        state.size += size;
+        state.entries.insert(
+            hash,
+            CacheEntry {
+                trie,
+                size,
+                last_used,
+            },
+        );
+    }
+
+    /// Evicts the element with the given hash, if cached.
+    pub fn remove(&self, hash: &Blake2bHash) {
+        self.shard(hash).lock().remove(hash)
+    }
+}
+
+/// A trie store which looks up trie elements in a [`TrieCache`] before falling back to the
+/// wrapped store, and which counts its cache hits and misses.
+///
+/// Elements read from the wrapped store are only added to the cache if the store was created with
+/// [`CachingTrieStore::read_through`], which should only be used with read transactions: an
+/// element read back within a read-write transaction might never be committed.  Deleting an element
+/// through either kind of store evicts it from the cache.
+pub(crate) struct CachingTrieStore<'a, K, V, S> {
+    store: &'a S,
+    cache: &'a TrieCache<K, V>,
+    populate: bool,
+    hits: Cell<u32>,
+    misses: Cell<u32>,
+}
+
+impl<'a, K, V, S> CachingTrieStore<'a, K, V, S> {
+    /// Creates a store which caches the elements it reads from `store`.
+    pub(crate) fn read_through(store: &'a S, cache: &'a TrieCache<K, V>) -> Self {
+        CachingTrieStore {
+            store,
+            cache,
+            populate: true,
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    /// Creates a store which uses the elements already cached, but does not add to the cache.
+    pub(crate) fn lookup_only(store: &'a S, cache: &'a TrieCache<K, V>) -> Self {
+        CachingTrieStore {
+            populate: false,
+            ..CachingTrieStore::read_through(store, cache)
+        }
+    }
+
+    /// Logs the number of cache hits and misses of this store.
+    pub(crate) fn log_metrics(&self, correlation_id: CorrelationId, tag: &str) {
+        log_metric(
+            correlation_id,
+            TRIE_CACHE_HITS,
+            tag,
+            GAUGE_METRIC_KEY,
+            f64::from(self.hits.get()),
+        );
+        log_metric(
+            correlation_id,
+            TRIE_CACHE_MISSES,
+            tag,
+            GAUGE_METRIC_KEY,
+            f64::from(self.misses.get()),
+        );
+    }
+}
+
+impl<'a, K, V, S> Store<Blake2bHash, Trie<K, V>> for CachingTrieStore<'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: TrieStore<K, V>,
+{
+    type Error = S::Error;
+
+    type Handle = S::Handle;
+
+    fn handle(&self) -> Self::Handle {
+        self.store.handle()
+    }
+
+    fn get<T>(&self, txn: &T, key: &Blake2bHash) -> Result<Option<Trie<K, V>>, Self::Error>
+    where
+        T: Readable<Handle = Self::Handle>,
+        Blake2bHash: ToBytes,
+        Trie<K, V>: FromBytes,
+        Self::Error: From<T::Error>,
+    {
+        let maybe_trie = self.get_shared(txn, key)?;
+        Ok(maybe_trie.map(|trie| Arc::try_unwrap(trie).unwrap_or_else(|trie| (*trie).clone())))
+    }
+
+    fn delete<T>(&self, txn: &mut T, key: &Blake2bHash) -> Result<(), Self::Error>
+    where
+        T: Writable<Handle = Self::Handle>,
+        Blake2bHash: ToBytes,
+        Self::Error: From<T::Error>,
+    {
+        self.cache.remove(key);
+        self.store.delete(txn, key)
+    }
+}
+
+impl<'a, K, V, S> TrieStore<K, V> for CachingTrieStore<'a, K, V, S>
+where
+    K: Clone,
+    V: Clone,
+    S: TrieStore<K, V>,
+{
+    fn get_shared<T>(
+        &self,
+        txn: &T,
+        key: &Blake2bHash,
+    ) -> Result<Option<Arc<Trie<K, V>>>, Self::Error>
+    where
+        T: Readable<Handle = Self::Handle>,
+        Blake2bHash: ToBytes,
+        Trie<K, V>: FromBytes,
+        Self::Error: From<T::Error>,
+    {
+        if let Some(trie) = self.cache.get(key) {
+            self.hits.set(self.hits.get().saturating_add(1));
+            return Ok(Some(trie));
+        }
+        self.misses.set(self.misses.get().saturating_add(1));
+        match txn.read(self.handle(), &key.to_bytes()?)? {
+            None => Ok(None),
+            Some(bytes) => {
+                let serialized_length = bytes.len();
+                let trie = Arc::new(bytesrepr::deserialize(bytes)?);
+                if self.populate {
+                    self.cache
+                        .insert(*key, Arc::clone(&trie), serialized_length);
+                }
+                Ok(Some(trie))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(key: u8) -> (Blake2bHash, Trie<Vec<u8>, Vec<u8>>) {
+        let leaf = Trie::Leaf {
+            key: vec![key],
+            value: vec![key; 8],
+        };
+        (Blake2bHash::new(&leaf.to_bytes().unwrap()), leaf)
+    }
+
+    fn leaf_size() -> usize {
+        let (_, leaf) = leaf(0);
+        estimated_size(&leaf, leaf.serialized_length())
+    }
+
+    fn insert(cache: &TrieCache<Vec<u8>, Vec<u8>>, key: u8) -> Blake2bHash {
+        let (hash, leaf) = leaf(key);
+        let serialized_length = leaf.serialized_length();
+        cache.insert(hash, Arc::new(leaf), serialized_length);
+        hash
+    }
+
+    #[test]
+    fn evicts_least_recently_used_elements() {
+        let cache = TrieCache::with_shard_count(3 * leaf_size(), 1);
+        let hash_0 = insert(&cache, 0);
+        let hash_1 = insert(&cache, 1);
+        let hash_2 = insert(&cache, 2);
+        assert_eq!(cache.len(), 3);
+
+        // Using the oldest element makes the next oldest the one to be evicted.
+        assert_eq!(cache.get(&hash_0), Some(Arc::new(leaf(0).1)));
+        let hash_3 = insert(&cache, 3);
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.size(), 3 * leaf_size());
+        assert!(cache.get(&hash_1).is_none());
+        for hash in &[hash_0, hash_2, hash_3] {
+            assert!(cache.get(hash).is_some());
+        }
+    }
+
+    #[test]
+    fn does_not_cache_elements_larger_than_capacity() {
+        let cache = TrieCache::with_shard_count(leaf_size() - 1, 1);
+        let hash = insert(&cache, 0);
+        assert!(cache.get(&hash).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn removes_elements() {
+        let cache = TrieCache::with_shard_count(3 * leaf_size(), 1);
+        let hash_0 = insert(&cache, 0);
+        let hash_1 = insert(&cache, 1);
+        cache.remove(&hash_0);
+        assert!(cache.get(&hash_0).is_none());
+        assert!(cache.get(&hash_1).is_some());
+        assert_eq!(cache.size(), leaf_size());
+    }
+
+    #[test]
+    fn shares_elements_and_bounds_each_shard() {
+        let shard_count = 4;
+        let cache = TrieCache::with_shard_count(shard_count * 2 * leaf_size(), shard_count);
+        let hashes: Vec<Blake2bHash> = (0..64).map(|key| insert(&cache, key)).collect();
+
+        // Each shard holds at most two of the elements.
+        assert!(cache.len() <= shard_count * 2);
+        assert!(cache.size() <= cache.capacity());
+
+        let hash = insert(&cache, 64);
+        let first = cache.get(&hash).unwrap();
+        let second = cache.get(&hash).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(hashes.iter().any(|hash| cache.get(hash).is_some()));
+    }
+}
@@ -0,0 +1,40 @@
+//! A trie store backed by an append-only log file.
+//!
+//! See the [file](crate::transaction_source::file) transaction source for a description of the
+//! log.
+use engine_shared::newtypes::Blake2bHash;
+
+use crate::{
+    error::file::Error,
+    store::Store,
+    transaction_source::file::FileEnvironment,
+    trie::Trie,
+    trie_store::{self, TrieStore},
+};
+
+/// A file-backed trie store.
+#[derive(Debug, Clone)]
+pub struct FileTrieStore {
+    name: String,
+}
+
+impl FileTrieStore {
+    pub fn new(_env: &FileEnvironment, maybe_name: Option<&str>) -> Self {
+        let name = maybe_name
+            .map(|name| format!("{}-{}", trie_store::NAME, name))
+            .unwrap_or_else(|| String::from(trie_store::NAME));
+        FileTrieStore { name }
+    }
+}
+
+impl<K, V> Store<Blake2bHash, Trie<K, V>> for FileTrieStore {
+    type Error = Error;
+
+    type Handle = String;
+
+    fn handle(&self) -> Self::Handle {
+        self.name.to_owned()
+    }
+}
+
+impl<K, V> TrieStore<K, V> for FileTrieStore {}
@@ -0,0 +1,38 @@
+//! A store for the state roots whose import through state sync has started but not finished.
+//!
+//! An imported root is written before its descendants, so it is recorded here until the last of
+//! them arrives, and must not be checked out in the meantime.
+use std::marker::PhantomData;
+
+use engine_shared::newtypes::Blake2bHash;
+use types::bytesrepr;
+
+use crate::store::Store;
+
+/// The name of the database holding the incomplete roots.
+pub const NAME: &str = "TRIE_STORE_INCOMPLETE_ROOTS";
+
+/// A set of state root hashes, stored under the given handle.
+pub struct IncompleteRootStore<H, E> {
+    handle: H,
+    _error: PhantomData<E>,
+}
+
+impl<H, E> IncompleteRootStore<H, E> {
+    pub fn new(handle: H) -> Self {
+        IncompleteRootStore {
+            handle,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<H: Clone, E: From<bytesrepr::Error>> Store<Blake2bHash, ()> for IncompleteRootStore<H, E> {
+    type Error = E;
+
+    type Handle = H;
+
+    fn handle(&self) -> Self::Handle {
+        self.handle.clone()
+    }
+}
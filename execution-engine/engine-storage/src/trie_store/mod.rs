@@ -2,17 +2,40 @@
 //!
 //! See the [in_memory](in_memory/index.html#usage) and
 //! [lmdb](lmdb/index.html#usage) modules for usage examples.
+pub mod cache;
+pub mod file;
 pub mod in_memory;
+pub mod incomplete_roots;
 pub mod lmdb;
 pub(crate) mod operations;
+pub mod prune_marks;
 #[cfg(test)]
 mod tests;
 
+use std::sync::Arc;
+
 use engine_shared::newtypes::Blake2bHash;
+use types::bytesrepr::{FromBytes, ToBytes};
 
-use crate::{store::Store, trie::Trie};
+use crate::{store::Store, transaction_source::Readable, trie::Trie};
 
 const NAME: &str = "TRIE_STORE";
 
 /// An entity which persists [`Trie`] values at their hashes.
-pub trait TrieStore<K, V>: Store<Blake2bHash, Trie<K, V>> {}
+pub trait TrieStore<K, V>: Store<Blake2bHash, Trie<K, V>> {
+    /// Returns the trie element at `key`, which may be shared with a cache of the store rather than
+    /// copied out of it.
+    fn get_shared<T>(
+        &self,
+        txn: &T,
+        key: &Blake2bHash,
+    ) -> Result<Option<Arc<Trie<K, V>>>, Self::Error>
+    where
+        T: Readable<Handle = Self::Handle>,
+        Blake2bHash: ToBytes,
+        Trie<K, V>: FromBytes,
+        Self::Error: From<T::Error>,
+    {
+        Ok(self.get(txn, key)?.map(Arc::new))
+    }
+}
@@ -1,7 +1,15 @@
 #[cfg(test)]
 mod tests;
 
-use std::{cmp, collections::VecDeque, mem, time::Instant};
+use std::{
+    cmp,
+    collections::{btree_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+    marker::PhantomData,
+    mem,
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+    time::Instant,
+};
 
 use engine_shared::{
     logging::{log_duration, log_metric},
@@ -10,9 +18,13 @@ use engine_shared::{
 use types::bytesrepr::{self, FromBytes, ToBytes};
 
 use crate::{
-    transaction_source::{Readable, Writable},
-    trie::{self, Parents, Pointer, Trie, RADIX},
-    trie_store::TrieStore,
+    store::Store,
+    transaction_source::{Readable, Transaction, TransactionSource, Writable},
+    trie::{
+        self, Parents, Pointer, PointerBlock, Trie, TrieAbsenceProof, TrieChunk, TrieMerkleProof,
+        RADIX,
+    },
+    trie_store::{incomplete_roots::IncompleteRootStore, prune_marks::PruneMarkStore, TrieStore},
     GAUGE_METRIC_KEY,
 };
 
@@ -22,11 +34,31 @@ const TRIE_STORE_SCAN_DURATION: &str = "trie_store_scan_duration";
 const TRIE_STORE_SCAN_GETS: &str = "trie_store_scan_gets";
 const TRIE_STORE_WRITE_DURATION: &str = "trie_store_write_duration";
 const TRIE_STORE_WRITE_PUTS: &str = "trie_store_write_puts";
+const TRIE_STORE_DELETE_DURATION: &str = "trie_store_delete_duration";
+const TRIE_STORE_DELETE_PUTS: &str = "trie_store_delete_puts";
+const TRIE_STORE_PRUNE_DURATION: &str = "trie_store_prune_duration";
+const TRIE_STORE_PRUNE_DELETES: &str = "trie_store_prune_deletes";
+const TRIE_STORE_EXPORT_CHUNK_DURATION: &str = "trie_store_export_chunk_duration";
+const TRIE_STORE_IMPORT_CHUNK_DURATION: &str = "trie_store_import_chunk_duration";
+const TRIE_STORE_DIFF_DURATION: &str = "trie_store_diff_duration";
+const TRIE_STORE_DIFF_ENTRIES: &str = "trie_store_diff_entries";
+const TRIE_STORE_FOR_EACH_LEAF_DURATION: &str = "trie_store_for_each_leaf_duration";
+const TRIE_STORE_READ_RANGE_DURATION: &str = "trie_store_read_range_duration";
+const TRIE_STORE_CHECK_INTEGRITY_DURATION: &str = "trie_store_check_integrity_duration";
+const TRIE_STORE_CHECK_INTEGRITY_PROBLEMS: &str = "trie_store_check_integrity_problems";
 const READ: &str = "read";
 const GET: &str = "get";
 const SCAN: &str = "scan";
 const WRITE: &str = "write";
 const PUT: &str = "put";
+const PRUNE: &str = "prune";
+const DELETE: &str = "delete";
+const EXPORT_CHUNK: &str = "export_chunk";
+const IMPORT_CHUNK: &str = "import_chunk";
+const DIFF: &str = "diff";
+const FOR_EACH_LEAF: &str = "for_each_leaf";
+const READ_RANGE: &str = "read_range";
+const CHECK_INTEGRITY: &str = "check_integrity";
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ReadResult<V> {
@@ -45,7 +77,7 @@ pub fn read<K, V, T, S, E>(
 ) -> Result<ReadResult<V>, E>
 where
     K: ToBytes + FromBytes + Eq + std::fmt::Debug,
-    V: ToBytes + FromBytes,
+    V: ToBytes + FromBytes + Clone,
     T: Readable<Handle = S::Handle>,
     S: TrieStore<K, V>,
     S::Error: From<T::Error>,
@@ -54,7 +86,8 @@ where
     let path: Vec<u8> = key.to_bytes()?;
 
     let mut depth: usize = 0;
-    let mut current: Trie<K, V> = match store.get(txn, root)? {
+    // Elements are shared with the store's cache, if it has one, so only the value found is copied.
+    let mut current: Arc<Trie<K, V>> = match store.get_shared(txn, root)? {
         Some(root) => root,
         None => return Ok(ReadResult::RootNotFound),
     };
@@ -63,13 +96,13 @@ where
     let mut get_counter: i32 = 0;
 
     loop {
-        match current {
+        match current.as_ref() {
             Trie::Leaf {
                 key: leaf_key,
                 value: leaf_value,
             } => {
-                let result = if *key == leaf_key {
-                    ReadResult::Found(leaf_value)
+                let result = if key == leaf_key {
+                    ReadResult::Found(leaf_value.clone())
                 } else {
                     // Keys may not match in the case of a compressed path from
                     // a Node directly to a Leaf
@@ -100,7 +133,7 @@ where
                     pointer_block[index]
                 };
                 match maybe_pointer {
-                    Some(pointer) => match store.get(txn, pointer.hash())? {
+                    Some(pointer) => match store.get_shared(txn, pointer.hash())? {
                         Some(next) => {
                             get_counter += 1;
                             depth += 1;
@@ -150,7 +183,7 @@ where
                 let sub_path = &path[depth..depth + affix.len()];
                 if sub_path == affix.as_slice() {
                     get_counter += 1;
-                    match store.get(txn, pointer.hash())? {
+                    match store.get_shared(txn, pointer.hash())? {
                         Some(next) => {
                             get_counter += 1;
                             depth += affix.len();
@@ -199,6 +232,102 @@ where
     }
 }
 
+/// Returns a value from the corresponding key at a given root in a given store, along with a
+/// [`TrieMerkleProof`] of its inclusion under that root.
+pub fn read_with_proof<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    root: &Blake2bHash,
+    key: &K,
+) -> Result<ReadResult<TrieMerkleProof<K, V>>, E>
+where
+    K: ToBytes + FromBytes + Eq + Clone,
+    V: ToBytes + FromBytes + Clone,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let path: Vec<u8> = key.to_bytes()?;
+
+    let root: Trie<K, V> = match store.get(txn, root)? {
+        Some(root) => root,
+        None => return Ok(ReadResult::RootNotFound),
+    };
+
+    let TrieScan { tip, parents } =
+        scan::<K, V, T, S, E>(correlation_id, txn, store, &path, &root)?;
+
+    match tip {
+        Trie::Leaf {
+            key: leaf_key,
+            value: leaf_value,
+        } if leaf_key == *key => {
+            let mut proof_steps: Vec<Trie<K, V>> =
+                parents.into_iter().map(|(_, parent)| parent).collect();
+            proof_steps.push(Trie::leaf(leaf_key, leaf_value.clone()));
+            Ok(ReadResult::Found(TrieMerkleProof::new(
+                key.clone(),
+                leaf_value,
+                proof_steps,
+            )))
+        }
+        // Keys may not match in the case of a compressed path from a Node directly to a Leaf
+        _ => Ok(ReadResult::NotFound),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AbsenceProofResult<K, V> {
+    Absent(TrieAbsenceProof<K, V>),
+    Present,
+    RootNotFound,
+}
+
+/// Returns a [`TrieAbsenceProof`] showing that the given key is absent at a given root in a given
+/// store, or [`AbsenceProofResult::Present`] if the key is in fact present.
+pub fn prove_absence<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    root: &Blake2bHash,
+    key: &K,
+) -> Result<AbsenceProofResult<K, V>, E>
+where
+    K: ToBytes + FromBytes + Eq + Clone,
+    V: ToBytes + FromBytes + Clone,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let path: Vec<u8> = key.to_bytes()?;
+
+    let root: Trie<K, V> = match store.get(txn, root)? {
+        Some(root) => root,
+        None => return Ok(AbsenceProofResult::RootNotFound),
+    };
+
+    let TrieScan { tip, parents } =
+        scan::<K, V, T, S, E>(correlation_id, txn, store, &path, &root)?;
+
+    if let Trie::Leaf { key: leaf_key, .. } = &tip {
+        if leaf_key == key {
+            return Ok(AbsenceProofResult::Present);
+        }
+    }
+
+    // The tip is either a Node with no pointer at the next index of the path, an Extension whose
+    // affix diverges from the path, or a Leaf holding a different key.
+    let mut proof_steps: Vec<Trie<K, V>> = parents.into_iter().map(|(_, parent)| parent).collect();
+    proof_steps.push(tip);
+    Ok(AbsenceProofResult::Absent(TrieAbsenceProof::new(
+        key.clone(),
+        proof_steps,
+    )))
+}
+
 struct TrieScan<K, V> {
     tip: Trie<K, V>,
     parents: Parents<K, V>,
@@ -731,6 +860,152 @@ where
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeleteResult {
+    Deleted(Blake2bHash),
+    DoesNotExist,
+    RootNotFound,
+}
+
+/// Takes the parent node of a deleted leaf, which is left with a single child, and the parents of
+/// that node.  Returns the trie element which replaces the node in its own parent.
+///
+/// A single leaf is hoisted in place of the node.  Otherwise the node becomes an extension over
+/// its child, absorbing both an extension above it and an extension below it, so that the trie
+/// has the same shape it would have had if the deleted leaf had never been written.
+fn collapse_node<K, V, T, S, E>(
+    txn: &T,
+    store: &S,
+    child_index: u8,
+    child_pointer: Pointer,
+    parents: &mut Parents<K, V>,
+) -> Result<Trie<K, V>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+{
+    // An extension above the node points at it, so it is merged into the new element.
+    let mut affix = match parents.pop() {
+        Some((_, Trie::Extension { affix, .. })) => affix,
+        Some(parent) => {
+            parents.push(parent);
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let child = match store.get(txn, child_pointer.hash())? {
+        Some(child) => child,
+        None => panic!("No trie value at key: {:?}", child_pointer.hash()),
+    };
+    match child {
+        leaf @ Trie::Leaf { .. } => Ok(leaf),
+        Trie::Node { .. } => {
+            affix.push(child_index);
+            Ok(Trie::extension(affix, child_pointer))
+        }
+        Trie::Extension {
+            affix: child_affix,
+            pointer,
+        } => {
+            affix.push(child_index);
+            affix.extend(child_affix);
+            Ok(Trie::extension(affix, pointer))
+        }
+    }
+}
+
+/// Deletes the leaf with the given `key` from the trie at `root`, returning the new root.
+///
+/// Elements of the old trie are left in the store, as they may still be referred to by other
+/// roots.
+pub fn delete<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &mut T,
+    store: &S,
+    root: &Blake2bHash,
+    key: &K,
+) -> Result<DeleteResult, E>
+where
+    K: ToBytes + FromBytes + Clone + Eq + std::fmt::Debug,
+    V: ToBytes + FromBytes + Clone + Eq,
+    T: Readable<Handle = S::Handle> + Writable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let start = Instant::now();
+    let mut put_counter: i32 = 0;
+
+    let current_root = match store.get(txn, root)? {
+        Some(current_root) => current_root,
+        None => return Ok(DeleteResult::RootNotFound),
+    };
+    let path: Vec<u8> = key.to_bytes()?;
+    let TrieScan { tip, mut parents } =
+        scan::<K, V, T, S, E>(correlation_id, txn, store, &path, &current_root)?;
+    match tip {
+        Trie::Leaf {
+            key: ref leaf_key, ..
+        } if key == leaf_key => (),
+        _ => {
+            log_duration(
+                correlation_id,
+                TRIE_STORE_DELETE_DURATION,
+                DELETE,
+                start.elapsed(),
+            );
+            return Ok(DeleteResult::DoesNotExist);
+        }
+    }
+
+    let (index, parent) = parents.pop().expect("a leaf should have a parent");
+    let mut pointer_block = match parent {
+        Trie::Node { pointer_block } => pointer_block,
+        _ => panic!("A leaf should have a node for its parent"),
+    };
+    pointer_block[<usize>::from(index)] = None;
+    let remaining: Vec<(u8, Pointer)> = pointer_block[..]
+        .iter()
+        .enumerate()
+        .filter_map(|(index, maybe_pointer)| maybe_pointer.map(|pointer| (index as u8, pointer)))
+        .collect();
+
+    // The root is always a node, however many children it has left.  Any other node left with a
+    // single child must be collapsed.
+    let new_tip = match remaining.as_slice() {
+        [(child_index, child_pointer)] if !parents.is_empty() => {
+            collapse_node::<K, V, T, S, E>(txn, store, *child_index, *child_pointer, &mut parents)?
+        }
+        _ => Trie::Node { pointer_block },
+    };
+
+    let new_elements = rehash(new_tip, parents)?;
+    let mut root_hash = root.to_owned();
+    for (hash, element) in new_elements.iter() {
+        put_counter += 1;
+        store.put(txn, hash, element)?;
+        root_hash = *hash;
+    }
+    log_metric(
+        correlation_id,
+        TRIE_STORE_DELETE_PUTS,
+        PUT,
+        GAUGE_METRIC_KEY,
+        f64::from(put_counter),
+    );
+    log_duration(
+        correlation_id,
+        TRIE_STORE_DELETE_DURATION,
+        DELETE,
+        start.elapsed(),
+    );
+    Ok(DeleteResult::Deleted(root_hash))
+}
+
 enum KeysIteratorState<K, V, S: TrieStore<K, V>> {
     /// Iterate normally
     Ok,
@@ -938,3 +1213,1277 @@ where
         state: init_state,
     }
 }
+
+/// A range of serialized keys, used to read ranges of key-value pairs from a trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRange {
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+}
+
+impl KeyRange {
+    /// Creates a range of the serialized keys between `start` and `end`.
+    pub fn new(start: Bound<Vec<u8>>, end: Bound<Vec<u8>>) -> Self {
+        KeyRange { start, end }
+    }
+
+    /// Creates a range of every serialized key.
+    pub fn all() -> Self {
+        KeyRange::new(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Creates a range of the serialized keys starting with `prefix`.
+    pub fn with_prefix(prefix: &[u8]) -> Self {
+        // The keys starting with `prefix` end just before the shortest byte string which is
+        // greater than all of them, if there is one.
+        let end = match prefix.iter().rposition(|byte| *byte != u8::max_value()) {
+            Some(index) => {
+                let mut end = prefix[..=index].to_vec();
+                end[index] += 1;
+                Bound::Excluded(end)
+            }
+            None => Bound::Unbounded,
+        };
+        KeyRange::new(Bound::Included(prefix.to_vec()), end)
+    }
+
+    /// Returns the part of this range strictly after `key_bytes`, which is used to read the next
+    /// page of a range whose last read key was `key_bytes`.
+    pub fn after(&self, key_bytes: &[u8]) -> Self {
+        let start = match &self.start {
+            Bound::Included(start) | Bound::Excluded(start) if start.as_slice() > key_bytes => {
+                self.start.clone()
+            }
+            _ => Bound::Excluded(key_bytes.to_vec()),
+        };
+        KeyRange::new(start, self.end.clone())
+    }
+
+    pub fn start(&self) -> Bound<&[u8]> {
+        as_slice_bound(&self.start)
+    }
+
+    pub fn end(&self) -> Bound<&[u8]> {
+        as_slice_bound(&self.end)
+    }
+
+    /// Returns `true` if `key_bytes` is within this range.
+    pub fn contains(&self, key_bytes: &[u8]) -> bool {
+        (self.start(), self.end()).contains(&key_bytes)
+    }
+
+    /// Returns `true` if every key under `path` in a trie is before the start of this range.
+    fn is_after_subtrie(&self, path: &[u8]) -> bool {
+        match &self.start {
+            Bound::Unbounded => false,
+            Bound::Included(start) | Bound::Excluded(start) => {
+                let length = cmp::min(path.len(), start.len());
+                path[..length] < start[..length]
+            }
+        }
+    }
+
+    /// Returns `true` if every key under `path` in a trie is past the end of this range.
+    fn is_before_subtrie(&self, path: &[u8]) -> bool {
+        let (end, is_end_included) = match &self.end {
+            Bound::Unbounded => return false,
+            Bound::Included(end) => (end, true),
+            Bound::Excluded(end) => (end, false),
+        };
+        let length = cmp::min(path.len(), end.len());
+        match path[..length].cmp(&end[..length]) {
+            cmp::Ordering::Less => false,
+            cmp::Ordering::Greater => true,
+            // Every key under `path` starts with `end`, so is at least `end` itself.
+            cmp::Ordering::Equal if is_end_included => path.len() > end.len(),
+            cmp::Ordering::Equal => path.len() >= end.len(),
+        }
+    }
+}
+
+fn as_slice_bound(bound: &Bound<Vec<u8>>) -> Bound<&[u8]> {
+    match bound {
+        Bound::Included(bytes) => Bound::Included(bytes.as_slice()),
+        Bound::Excluded(bytes) => Bound::Excluded(bytes.as_slice()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Returns up to `limit` key-value pairs of the trie at `root` whose serialized keys are within
+/// `range`, in ascending order of their serialized keys.
+///
+/// Subtries which lie entirely outside of `range` are never read, so reading the successive pages
+/// of a range using [`KeyRange::after`] costs roughly the same per page.
+pub fn read_range<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    root: &Blake2bHash,
+    range: &KeyRange,
+    limit: usize,
+) -> Result<ReadResult<Vec<(K, V)>>, E>
+where
+    K: ToBytes + FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<bytesrepr::Error>,
+{
+    let start = Instant::now();
+    let root_trie = match store.get(txn, root)? {
+        Some(trie) => trie,
+        None => return Ok(ReadResult::RootNotFound),
+    };
+
+    let mut pairs = Vec::new();
+    let mut to_visit: Vec<(Trie<K, V>, Vec<u8>)> = vec![(root_trie, Vec::new())];
+
+    while let Some((trie, path)) = to_visit.pop() {
+        if pairs.len() >= limit {
+            break;
+        }
+        let children: Vec<(Pointer, Vec<u8>)> = match trie {
+            Trie::Leaf { key, value } => {
+                let key_bytes = key.to_bytes()?;
+                if range.contains(&key_bytes) {
+                    pairs.push((key, value));
+                } else if range.is_before_subtrie(&key_bytes) {
+                    // Leaves are visited in ascending order, so no later leaf can be in range.
+                    break;
+                }
+                continue;
+            }
+            Trie::Node { pointer_block } => pointer_block[..]
+                .iter()
+                .enumerate()
+                .filter_map(|(index, maybe_pointer)| maybe_pointer.map(|pointer| (index, pointer)))
+                .map(|(index, pointer)| {
+                    let mut child_path = path.clone();
+                    child_path.push(index as u8);
+                    (pointer, child_path)
+                })
+                .collect(),
+            Trie::Extension { affix, pointer } => {
+                let mut child_path = path;
+                child_path.extend_from_slice(&affix);
+                vec![(pointer, child_path)]
+            }
+        };
+        // Reversed so that children are popped in ascending order of their path.
+        for (pointer, child_path) in children.into_iter().rev() {
+            if range.is_after_subtrie(&child_path) || range.is_before_subtrie(&child_path) {
+                continue;
+            }
+            let child = get_trie::<K, V, T, S, E>(txn, store, pointer.hash())?;
+            to_visit.push((child, child_path));
+        }
+    }
+
+    log_duration(
+        correlation_id,
+        TRIE_STORE_READ_RANGE_DURATION,
+        READ_RANGE,
+        start.elapsed(),
+    );
+
+    Ok(ReadResult::Found(pairs))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PruneResult {
+    /// Pruning finished.  `kept` is the number of trie elements reachable from the given roots,
+    /// `deleted` is the number of unreachable trie elements which were removed from the store.
+    Pruned { kept: usize, deleted: usize },
+    /// One of the given roots is not in the store.  Nothing has been deleted.
+    RootNotFound(Blake2bHash),
+}
+
+/// Returns the pointers held by a trie element.
+fn child_pointers<K, V>(trie: &Trie<K, V>) -> Vec<Pointer> {
+    match trie {
+        Trie::Leaf { .. } => Vec::new(),
+        Trie::Node { pointer_block } => pointer_block[..].iter().flatten().copied().collect(),
+        Trie::Extension { pointer, .. } => vec![*pointer],
+    }
+}
+
+/// Deletes every mark left in `marks`, e.g. by a prune which was interrupted, in batches of at
+/// most `batch_size` keys.
+fn clear_marks<'a, R, M, E>(environment: &'a R, marks: &M, batch_size: usize) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = M::Handle>,
+    M: Store<Blake2bHash, ()>,
+    M::Error: From<R::Error>,
+    E: From<R::Error> + From<M::Error>,
+{
+    loop {
+        let mut txn = environment.create_read_write_txn()?;
+        let hashes: Vec<Blake2bHash> = marks.keys(&txn, None, batch_size)?;
+        for hash in &hashes {
+            marks.delete(&mut txn, hash)?;
+        }
+        txn.commit()?;
+
+        if hashes.len() < batch_size {
+            return Ok(());
+        }
+    }
+}
+
+/// Marks the hashes of all trie elements reachable from the given roots, visiting at most
+/// `batch_size` elements in each read-write transaction.
+///
+/// Returns `Err` containing the first root which is missing from the store, in which case nothing
+/// is marked.  Otherwise returns the number of marked elements.  Leaves are never read from the
+/// store, since a [`Pointer::LeafPointer`] is enough to know their hash.
+fn mark_reachable<'a, K, V, R, S, E>(
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+    roots: &[Blake2bHash],
+    batch_size: usize,
+) -> Result<Result<usize, Blake2bHash>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error>,
+{
+    let maybe_missing_root = {
+        let txn = environment.create_read_txn()?;
+        let mut maybe_missing_root = None;
+        for root in roots {
+            if store.get(&txn, root)?.is_none() {
+                maybe_missing_root = Some(*root);
+                break;
+            }
+        }
+        txn.commit()?;
+        maybe_missing_root
+    };
+    if let Some(missing_root) = maybe_missing_root {
+        return Ok(Err(missing_root));
+    }
+
+    let mut marked: usize = 0;
+    let mut to_visit: Vec<Blake2bHash> = roots.to_vec();
+
+    while !to_visit.is_empty() {
+        let mut txn = environment.create_read_write_txn()?;
+        for _ in 0..batch_size {
+            let hash = match to_visit.pop() {
+                Some(hash) => hash,
+                None => break,
+            };
+            if marks.get(&txn, &hash)?.is_some() {
+                // This subtrie is shared with one which was already visited.
+                continue;
+            }
+            marks.put(&mut txn, &hash, &())?;
+            marked += 1;
+
+            let trie: Trie<K, V> = match store.get(&txn, &hash)? {
+                Some(trie) => trie,
+                None => panic!("No trie value at key: {:?}", hash),
+            };
+            for pointer in child_pointers(&trie) {
+                match pointer {
+                    Pointer::LeafPointer(leaf_hash) => {
+                        if marks.get(&txn, &leaf_hash)?.is_none() {
+                            marks.put(&mut txn, &leaf_hash, &())?;
+                            marked += 1;
+                        }
+                    }
+                    Pointer::NodePointer(node_hash) => {
+                        if marks.get(&txn, &node_hash)?.is_none() {
+                            to_visit.push(node_hash);
+                        }
+                    }
+                }
+            }
+        }
+        txn.commit()?;
+    }
+
+    Ok(Ok(marked))
+}
+
+/// Deletes every trie element in `store` which is not reachable from one of `roots_to_keep`.
+///
+/// Reachable elements are first marked in `marks`, then every element of `store` which is not
+/// marked is deleted, clearing the marks as they are passed.  Both phases work in batches of at
+/// most `batch_size` elements, each batch in its own read-write transaction, so that the store is
+/// never locked for the whole duration of a prune and the reachable set is never held in memory.
+/// Marks left behind by an interrupted prune are cleared before marking starts.
+///
+/// Callers must ensure that no new roots are committed to the store while pruning is in progress,
+/// otherwise the freshly written trie elements may be deleted.
+pub fn prune<'a, K, V, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+    roots_to_keep: &[Blake2bHash],
+    batch_size: usize,
+) -> Result<PruneResult, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    assert!(batch_size > 0, "batch_size must be > 0");
+
+    let start = Instant::now();
+
+    clear_marks::<_, _, E>(environment, marks, batch_size)?;
+    let kept = match mark_reachable::<K, V, _, _, E>(
+        environment,
+        store,
+        marks,
+        roots_to_keep,
+        batch_size,
+    )? {
+        Ok(kept) => kept,
+        Err(missing_root) => return Ok(PruneResult::RootNotFound(missing_root)),
+    };
+
+    let mut deleted: usize = 0;
+    let mut maybe_last_hash: Option<Blake2bHash> = None;
+
+    loop {
+        let mut txn = environment.create_read_write_txn()?;
+        let hashes: Vec<Blake2bHash> = store.keys(&txn, maybe_last_hash.as_ref(), batch_size)?;
+        for hash in &hashes {
+            if marks.get(&txn, hash)?.is_some() {
+                marks.delete(&mut txn, hash)?;
+            } else {
+                store.delete(&mut txn, hash)?;
+                deleted += 1;
+            }
+        }
+        txn.commit()?;
+
+        if hashes.len() < batch_size {
+            break;
+        }
+        maybe_last_hash = hashes.last().copied();
+    }
+
+    log_metric(
+        correlation_id,
+        TRIE_STORE_PRUNE_DELETES,
+        DELETE,
+        GAUGE_METRIC_KEY,
+        deleted as f64,
+    );
+    log_duration(
+        correlation_id,
+        TRIE_STORE_PRUNE_DURATION,
+        PRUNE,
+        start.elapsed(),
+    );
+
+    Ok(PruneResult::Pruned { kept, deleted })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExportChunkResult {
+    Exported(TrieChunk),
+    TrieNotFound(Blake2bHash),
+}
+
+/// Exports a chunk of at most `chunk_size` serialized trie elements, taken depth-first from the
+/// `pending` stack of hashes.
+///
+/// Exported elements are popped from `pending` and the hashes they point to are pushed onto it, so
+/// repeatedly calling this with the same `pending` stack, seeded with a state root, streams every
+/// element reachable from that root.  Export is complete once `pending` is empty.  Every element is
+/// exported after the element pointing to it.
+pub fn export_chunk<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    pending: &mut Vec<Blake2bHash>,
+    chunk_size: usize,
+) -> Result<ExportChunkResult, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<T::Error> + From<types::bytesrepr::Error>,
+{
+    assert!(chunk_size > 0, "chunk_size must be > 0");
+
+    let start = Instant::now();
+    let mut tries = Vec::new();
+
+    while tries.len() < chunk_size {
+        let hash = match pending.pop() {
+            Some(hash) => hash,
+            None => break,
+        };
+        let trie_bytes = match txn.read(store.handle(), &hash.to_bytes()?)? {
+            Some(trie_bytes) => trie_bytes,
+            None => return Ok(ExportChunkResult::TrieNotFound(hash)),
+        };
+        let (trie, _): (Trie<K, V>, _) = Trie::from_bytes(&trie_bytes)?;
+        // Reversed so that children are exported in ascending order of their index.
+        pending.extend(
+            child_pointers(&trie)
+                .into_iter()
+                .rev()
+                .map(|pointer| *pointer.hash()),
+        );
+        tries.push(trie_bytes);
+    }
+
+    log_duration(
+        correlation_id,
+        TRIE_STORE_EXPORT_CHUNK_DURATION,
+        EXPORT_CHUNK,
+        start.elapsed(),
+    );
+
+    Ok(ExportChunkResult::Exported(TrieChunk::new(tries)))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportChunkResult {
+    Imported(usize),
+    UnexpectedTrie(Blake2bHash),
+}
+
+/// Imports a chunk of the trie under `state_root` produced by [`export_chunk`], writing it to
+/// `store` in a single read-write transaction.
+///
+/// `expected` holds the hashes which may be imported next: initially the state root, or the
+/// result of [`missing_descendants`] when resuming an interrupted import.  Each element of the
+/// chunk must hash to an expected value, after which the hashes it points to which are not yet in
+/// the store become expected in turn, as do the missing descendants of those which are, since an
+/// interrupted import may have left incomplete subtries behind.  Elements already in the store are
+/// skipped.  If an element is neither expected nor present, nothing from the chunk is written and
+/// `expected` is left unchanged.  Import is complete once `expected` is empty.
+///
+/// `state_root` is recorded in `incomplete_roots` until the chunk completing its import is
+/// committed.
+pub fn import_chunk<'a, K, V, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    incomplete_roots: &IncompleteRootStore<S::Handle, S::Error>,
+    state_root: &Blake2bHash,
+    expected: &mut HashSet<Blake2bHash>,
+    chunk: &TrieChunk,
+) -> Result<ImportChunkResult, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let start = Instant::now();
+    let mut consumed: HashSet<Blake2bHash> = HashSet::new();
+    let mut discovered: HashSet<Blake2bHash> = HashSet::new();
+
+    let mut txn = environment.create_read_write_txn()?;
+    incomplete_roots.put(&mut txn, state_root, &())?;
+    for trie_bytes in chunk.tries() {
+        let hash = Blake2bHash::new(trie_bytes);
+        let is_expected =
+            discovered.remove(&hash) || (expected.contains(&hash) && consumed.insert(hash));
+        if !is_expected {
+            if txn.read(store.handle(), &hash.to_bytes()?)?.is_some() {
+                // Its missing descendants, if any, were made expected along with its parent.
+                continue;
+            }
+            // Dropping the uncommitted transaction discards the elements written so far.
+            return Ok(ImportChunkResult::UnexpectedTrie(hash));
+        }
+
+        let trie: Trie<K, V> = bytesrepr::deserialize(trie_bytes.clone())?;
+        for pointer in child_pointers(&trie) {
+            let child_hash = *pointer.hash();
+            if txn.read(store.handle(), &child_hash.to_bytes()?)?.is_none() {
+                discovered.insert(child_hash);
+            } else if let Pointer::NodePointer(_) = pointer {
+                discovered.extend(missing_descendants::<K, V, _, _, E>(
+                    &txn,
+                    store,
+                    &child_hash,
+                )?);
+            }
+        }
+        txn.write(store.handle(), &hash.to_bytes()?, trie_bytes)?;
+    }
+    if discovered.is_empty() && expected.iter().all(|hash| consumed.contains(hash)) {
+        incomplete_roots.delete(&mut txn, state_root)?;
+    }
+    txn.commit()?;
+
+    for hash in consumed {
+        expected.remove(&hash);
+    }
+    expected.extend(discovered);
+
+    log_duration(
+        correlation_id,
+        TRIE_STORE_IMPORT_CHUNK_DURATION,
+        IMPORT_CHUNK,
+        start.elapsed(),
+    );
+
+    Ok(ImportChunkResult::Imported(chunk.len()))
+}
+
+/// Returns the hashes of the trie elements reachable from `root` which are missing from `store`,
+/// without descending into them.
+///
+/// Returns `[root]` if the root itself is missing, and nothing if the whole trie is present.
+pub fn missing_descendants<K, V, T, S, E>(
+    txn: &T,
+    store: &S,
+    root: &Blake2bHash,
+) -> Result<Vec<Blake2bHash>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<T::Error> + From<types::bytesrepr::Error>,
+{
+    let mut missing = Vec::new();
+    let mut to_visit: Vec<Pointer> = vec![Pointer::NodePointer(*root)];
+
+    while let Some(pointer) = to_visit.pop() {
+        let hash = *pointer.hash();
+        match pointer {
+            // Leaves point to nothing, so only their presence needs checking.
+            Pointer::LeafPointer(_) => {
+                if txn.read(store.handle(), &hash.to_bytes()?)?.is_none() {
+                    missing.push(hash);
+                }
+            }
+            Pointer::NodePointer(_) => match store.get(txn, &hash)? {
+                Some(trie) => to_visit.extend(child_pointers(&trie)),
+                None => missing.push(hash),
+            },
+        }
+    }
+
+    Ok(missing)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AppendLeafResult {
+    Appended,
+    /// The serialized key is not greater than that of the previously appended leaf.  Nothing was
+    /// written.
+    NotAscending,
+}
+
+/// A node of a [`TrieBuilder`] which may still gain children.
+struct OpenNode {
+    /// The position in the path of the byte which indexes the node's pointer block.
+    depth: usize,
+    pointer_block: Box<PointerBlock>,
+}
+
+impl OpenNode {
+    fn new(depth: usize) -> Self {
+        OpenNode {
+            depth,
+            pointer_block: Box::new(PointerBlock::new()),
+        }
+    }
+}
+
+/// Writes a trie bottom-up from leaves appended in ascending order of their serialized keys.
+///
+/// The trie has the same shape, and so the same root, as one made by [`write`]ing the same leaves
+/// to an empty trie in any order.  Unlike repeated writes, which each store a new version of every
+/// element along the path to the leaf, each element is written exactly once, when all of its
+/// children are known.  Only the nodes along the path to the latest leaf are held in memory.
+pub struct TrieBuilder<K, V> {
+    /// The nodes along the path to the latest leaf, starting with the root.
+    open_nodes: Vec<OpenNode>,
+    /// The path and pointer of the latest leaf.  It is attached to its parent once the next leaf
+    /// shows how deep that parent is.
+    maybe_last_leaf: Option<(Vec<u8>, Pointer)>,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> Default for TrieBuilder<K, V> {
+    fn default() -> Self {
+        TrieBuilder {
+            open_nodes: vec![OpenNode::new(0)],
+            maybe_last_leaf: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: ToBytes, V: ToBytes> TrieBuilder<K, V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Writes a leaf, along with every element below the paths of this and all later leaves.
+    pub fn append<T, S, E>(
+        &mut self,
+        txn: &mut T,
+        store: &S,
+        key: K,
+        value: V,
+    ) -> Result<AppendLeafResult, E>
+    where
+        T: Writable<Handle = S::Handle>,
+        S: TrieStore<K, V>,
+        S::Error: From<T::Error>,
+        E: From<S::Error> + From<types::bytesrepr::Error>,
+    {
+        let path = key.to_bytes()?;
+        if let Some((last_path, last_pointer)) = self.maybe_last_leaf.take() {
+            if path <= last_path {
+                self.maybe_last_leaf = Some((last_path, last_pointer));
+                return Ok(AppendLeafResult::NotAscending);
+            }
+            // The latest leaf belongs in the node where its path and the new path diverge.
+            let depth = common_prefix(&last_path, &path).len();
+            assert!(
+                depth < last_path.len(),
+                "serialized key must not be a prefix of another"
+            );
+            self.close_below::<T, S, E>(txn, store, &last_path, last_pointer, depth)?;
+        }
+
+        let leaf = Trie::leaf(key, value);
+        let leaf_hash = Blake2bHash::new(&leaf.to_bytes()?);
+        store.put(txn, &leaf_hash, &leaf)?;
+        self.maybe_last_leaf = Some((path, Pointer::LeafPointer(leaf_hash)));
+        Ok(AppendLeafResult::Appended)
+    }
+
+    /// Writes the remaining elements of the trie, and returns its root.
+    pub fn finish<T, S, E>(mut self, txn: &mut T, store: &S) -> Result<Blake2bHash, E>
+    where
+        T: Writable<Handle = S::Handle>,
+        S: TrieStore<K, V>,
+        S::Error: From<T::Error>,
+        E: From<S::Error> + From<types::bytesrepr::Error>,
+    {
+        if let Some((last_path, last_pointer)) = self.maybe_last_leaf.take() {
+            self.close_below::<T, S, E>(txn, store, &last_path, last_pointer, 0)?;
+        }
+        let OpenNode { pointer_block, .. } = self.open_nodes.pop().expect("should have a root");
+        let root = Trie::Node { pointer_block };
+        let root_hash = Blake2bHash::new(&root.to_bytes()?);
+        store.put(txn, &root_hash, &root)?;
+        Ok(root_hash)
+    }
+
+    /// Attaches `pointer`, found along `path`, to the open node at `depth`, first opening that
+    /// node if needed.  The open nodes deeper than `depth` are complete, so they are written and
+    /// attached to their parents on the way up.
+    fn close_below<T, S, E>(
+        &mut self,
+        txn: &mut T,
+        store: &S,
+        path: &[u8],
+        mut pointer: Pointer,
+        depth: usize,
+    ) -> Result<(), E>
+    where
+        T: Writable<Handle = S::Handle>,
+        S: TrieStore<K, V>,
+        S::Error: From<T::Error>,
+        E: From<S::Error> + From<types::bytesrepr::Error>,
+    {
+        let mut child_depth = path.len();
+        loop {
+            let parent_depth = self.open_nodes.last().expect("should have a root").depth;
+            if parent_depth < depth {
+                self.open_nodes.push(OpenNode::new(depth));
+                continue;
+            }
+
+            // A node more than one byte below its parent hangs from an extension.
+            if let Pointer::NodePointer(_) = pointer {
+                if child_depth > parent_depth + 1 {
+                    let extension =
+                        Trie::extension(path[parent_depth + 1..child_depth].to_vec(), pointer);
+                    let extension_hash = Blake2bHash::new(&extension.to_bytes()?);
+                    store.put(txn, &extension_hash, &extension)?;
+                    pointer = Pointer::NodePointer(extension_hash);
+                }
+            }
+            let parent = self.open_nodes.last_mut().expect("should have a root");
+            parent.pointer_block[path[parent_depth].into()] = Some(pointer);
+            if parent_depth == depth {
+                return Ok(());
+            }
+
+            let OpenNode { pointer_block, .. } =
+                self.open_nodes.pop().expect("should have a parent");
+            let node = Trie::Node { pointer_block };
+            let node_hash = Blake2bHash::new(&node.to_bytes()?);
+            store.put(txn, &node_hash, &node)?;
+            pointer = Pointer::NodePointer(node_hash);
+            child_depth = parent_depth;
+        }
+    }
+}
+
+/// A single difference between two tries: a key along with its value in the left trie, if any,
+/// and its value in the right trie, if any.
+pub type DiffEntry<K, V> = (K, Option<V>, Option<V>);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffResult<K, V> {
+    /// Every key which was added, removed or modified between the two roots, in ascending order of
+    /// its serialized form.
+    Diff(Vec<DiffEntry<K, V>>),
+    /// One of the given roots is not in the store.
+    RootNotFound(Blake2bHash),
+}
+
+/// A child of a trie element being diffed.  `Virtual` holds the remainder of an [`Extension`]
+/// whose first byte of affix has been consumed, so that it can be compared against a [`Node`].
+///
+/// [`Extension`]: Trie::Extension
+/// [`Node`]: Trie::Node
+enum DiffChild<K, V> {
+    Stored(Pointer),
+    Virtual(Trie<K, V>),
+}
+
+impl<K: ToBytes, V: ToBytes> DiffChild<K, V> {
+    fn hash(&self) -> Result<Blake2bHash, bytesrepr::Error> {
+        match self {
+            DiffChild::Stored(pointer) => Ok(*pointer.hash()),
+            DiffChild::Virtual(trie) => Ok(Blake2bHash::new(&trie.to_bytes()?)),
+        }
+    }
+}
+
+/// Returns the children of a [`Trie::Node`] or [`Trie::Extension`] indexed by the next byte of
+/// the path, treating an extension as a node with a single child.
+fn diff_children<K, V>(trie: Trie<K, V>) -> Vec<Option<DiffChild<K, V>>> {
+    match trie {
+        Trie::Leaf { .. } => panic!("diff_children called on a leaf"),
+        Trie::Node { pointer_block } => pointer_block[..]
+            .iter()
+            .map(|maybe_pointer| maybe_pointer.map(DiffChild::Stored))
+            .collect(),
+        Trie::Extension { affix, pointer } => {
+            let mut children: Vec<Option<DiffChild<K, V>>> = (0..RADIX).map(|_| None).collect();
+            let child = if affix.len() == 1 {
+                DiffChild::Stored(pointer)
+            } else {
+                DiffChild::Virtual(Trie::extension(affix[1..].to_vec(), pointer))
+            };
+            children[affix[0] as usize] = Some(child);
+            children
+        }
+    }
+}
+
+fn get_trie<K, V, T, S, E>(txn: &T, store: &S, hash: &Blake2bHash) -> Result<Trie<K, V>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+{
+    match store.get(txn, hash)? {
+        Some(trie) => Ok(trie),
+        None => panic!("No trie value at key: {:?}", hash),
+    }
+}
+
+fn resolve_diff_child<K, V, T, S, E>(
+    txn: &T,
+    store: &S,
+    child: DiffChild<K, V>,
+) -> Result<Trie<K, V>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+{
+    match child {
+        DiffChild::Stored(pointer) => get_trie::<K, V, T, S, E>(txn, store, pointer.hash()),
+        DiffChild::Virtual(trie) => Ok(trie),
+    }
+}
+
+/// Collects every leaf of the subtrie rooted at `trie`, in ascending order of their paths.
+fn collect_leaves<K, V, T, S, E>(txn: &T, store: &S, trie: Trie<K, V>) -> Result<Vec<(K, V)>, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+{
+    let mut leaves = Vec::new();
+    visit_leaves::<K, V, T, S, E, _>(txn, store, trie, |key, value| {
+        leaves.push((key, value));
+        Ok(())
+    })?;
+    Ok(leaves)
+}
+
+/// Calls `visit` with every leaf of the subtrie rooted at `trie`, in ascending order of their
+/// paths.
+fn visit_leaves<K, V, T, S, E, F>(
+    txn: &T,
+    store: &S,
+    trie: Trie<K, V>,
+    mut visit: F,
+) -> Result<(), E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+    F: FnMut(K, V) -> Result<(), E>,
+{
+    let mut to_visit = vec![trie];
+
+    while let Some(trie) = to_visit.pop() {
+        if let Trie::Leaf { key, value } = trie {
+            visit(key, value)?;
+            continue;
+        }
+        // Reversed so that children are popped in ascending order of their index.
+        for pointer in child_pointers(&trie).into_iter().rev() {
+            to_visit.push(get_trie::<K, V, T, S, E>(txn, store, pointer.hash())?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls `visit` with every leaf of the trie at `root`, in ascending order of the serialized keys.
+///
+/// Returns `false` without calling `visit` if `root` is not in the store.
+pub fn for_each_leaf<K, V, T, S, E, F>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    root: &Blake2bHash,
+    visit: F,
+) -> Result<bool, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error>,
+    F: FnMut(K, V) -> Result<(), E>,
+{
+    let start = Instant::now();
+    let root_trie = match store.get(txn, root)? {
+        Some(trie) => trie,
+        None => return Ok(false),
+    };
+    visit_leaves::<K, V, T, S, E, F>(txn, store, root_trie, visit)?;
+    log_duration(
+        correlation_id,
+        TRIE_STORE_FOR_EACH_LEAF_DURATION,
+        FOR_EACH_LEAF,
+        start.elapsed(),
+    );
+    Ok(true)
+}
+
+/// Diffs two subtries found at the same path, appending the differences to `entries` in ascending
+/// order of the serialized keys.
+fn diff_subtries<K, V, T, S, E>(
+    txn: &T,
+    store: &S,
+    left: Option<Trie<K, V>>,
+    right: Option<Trie<K, V>>,
+    entries: &mut Vec<DiffEntry<K, V>>,
+) -> Result<(), E>
+where
+    K: ToBytes + FromBytes,
+    V: ToBytes + FromBytes + Eq,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<bytesrepr::Error>,
+{
+    match (left, right) {
+        (None, None) => {}
+        (Some(left), None) => {
+            for (key, value) in collect_leaves::<K, V, T, S, E>(txn, store, left)? {
+                entries.push((key, Some(value), None));
+            }
+        }
+        (None, Some(right)) => {
+            for (key, value) in collect_leaves::<K, V, T, S, E>(txn, store, right)? {
+                entries.push((key, None, Some(value)));
+            }
+        }
+        (Some(left @ Trie::Leaf { .. }), Some(right))
+        | (Some(left), Some(right @ Trie::Leaf { .. })) => {
+            // A leaf sits where the other side may hold a whole subtrie, so there is no structure
+            // left to compare.  Every leaf of that subtrie but one with the leaf's key is a
+            // difference anyway, so merging both sets of leaves costs no more than the diff.
+            let mut merged: BTreeMap<Vec<u8>, DiffEntry<K, V>> = BTreeMap::new();
+            for (key, value) in collect_leaves::<K, V, T, S, E>(txn, store, left)? {
+                merged.insert(key.to_bytes()?, (key, Some(value), None));
+            }
+            for (key, value) in collect_leaves::<K, V, T, S, E>(txn, store, right)? {
+                match merged.entry(key.to_bytes()?) {
+                    Entry::Occupied(mut entry) => entry.get_mut().2 = Some(value),
+                    Entry::Vacant(entry) => {
+                        entry.insert((key, None, Some(value)));
+                    }
+                }
+            }
+            entries.extend(
+                merged
+                    .into_iter()
+                    .map(|(_, entry)| entry)
+                    .filter(|(_, left_value, right_value)| left_value != right_value),
+            );
+        }
+        (
+            Some(Trie::Extension {
+                affix: left_affix,
+                pointer: left_pointer,
+            }),
+            Some(Trie::Extension {
+                affix: right_affix,
+                pointer: right_pointer,
+            }),
+        ) if left_affix == right_affix => {
+            if left_pointer.hash() != right_pointer.hash() {
+                let left = get_trie::<K, V, T, S, E>(txn, store, left_pointer.hash())?;
+                let right = get_trie::<K, V, T, S, E>(txn, store, right_pointer.hash())?;
+                diff_subtries::<K, V, T, S, E>(txn, store, Some(left), Some(right), entries)?;
+            }
+        }
+        (Some(left), Some(right)) => {
+            let children = diff_children(left)
+                .into_iter()
+                .zip(diff_children(right).into_iter());
+            for (left_child, right_child) in children {
+                if let (Some(left_child), Some(right_child)) = (&left_child, &right_child) {
+                    if left_child.hash()? == right_child.hash()? {
+                        continue;
+                    }
+                }
+                let left = match left_child {
+                    Some(child) => Some(resolve_diff_child::<K, V, T, S, E>(txn, store, child)?),
+                    None => None,
+                };
+                let right = match right_child {
+                    Some(child) => Some(resolve_diff_child::<K, V, T, S, E>(txn, store, child)?),
+                    None => None,
+                };
+                diff_subtries::<K, V, T, S, E>(txn, store, left, right, entries)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns every key which differs between the tries at `left_root` and `right_root`.
+///
+/// Both tries are walked in parallel, and subtries whose hashes are equal on both sides are
+/// skipped entirely, so the cost of a diff is proportional to the size of the change rather than
+/// to the size of the state.
+pub fn diff<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    left_root: &Blake2bHash,
+    right_root: &Blake2bHash,
+) -> Result<DiffResult<K, V>, E>
+where
+    K: ToBytes + FromBytes,
+    V: ToBytes + FromBytes + Eq,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<bytesrepr::Error>,
+{
+    let start = Instant::now();
+
+    let left = match store.get(txn, left_root)? {
+        Some(trie) => trie,
+        None => return Ok(DiffResult::RootNotFound(*left_root)),
+    };
+    let right = match store.get(txn, right_root)? {
+        Some(trie) => trie,
+        None => return Ok(DiffResult::RootNotFound(*right_root)),
+    };
+
+    let mut entries = Vec::new();
+    if left_root != right_root {
+        diff_subtries::<K, V, T, S, E>(txn, store, Some(left), Some(right), &mut entries)?;
+    }
+
+    log_metric(
+        correlation_id,
+        TRIE_STORE_DIFF_ENTRIES,
+        DIFF,
+        GAUGE_METRIC_KEY,
+        entries.len() as f64,
+    );
+    log_duration(
+        correlation_id,
+        TRIE_STORE_DIFF_DURATION,
+        DIFF,
+        start.elapsed(),
+    );
+
+    Ok(DiffResult::Diff(entries))
+}
+
+/// A problem found by [`check_integrity`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    /// A root given to the check is not in the store.
+    RootNotFound(Blake2bHash),
+    /// The target of a pointer in `parent` is not in the store.
+    TrieNotFound {
+        parent: Blake2bHash,
+        hash: Blake2bHash,
+    },
+    /// The bytes stored at `hash` actually hash to `actual`.
+    HashMismatch {
+        hash: Blake2bHash,
+        actual: Blake2bHash,
+    },
+    /// The bytes stored at `hash` are not a trie element.
+    InvalidTrie {
+        hash: Blake2bHash,
+        error: bytesrepr::Error,
+    },
+    /// The bytes stored at `hash` are a leaf whose key is valid but whose value is not.
+    InvalidLeafValue {
+        hash: Blake2bHash,
+        error: bytesrepr::Error,
+    },
+    /// `pointer` in `parent` is a leaf pointer to a node or extension, or a node pointer to a
+    /// leaf.
+    PointerKindMismatch {
+        parent: Blake2bHash,
+        pointer: Pointer,
+    },
+}
+
+/// The outcome of [`check_integrity`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The number of distinct trie elements read from the store.
+    pub tries_checked: u64,
+    /// The number of those elements which are leaves.
+    pub leaves_checked: u64,
+    /// The problems found, in the order they were found.
+    pub problems: Vec<IntegrityProblem>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if no problems were found.
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// The kind of trie element stored at a hash, as far as [`check_integrity`] could tell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum StoredKind {
+    Leaf,
+    NodeOrExtension,
+    Unknown,
+}
+
+/// Checks the trie element stored at `hash`, appending any problems to `report`.
+///
+/// Returns the kind of the element along with its child pointers, which are only known if the
+/// element deserializes.
+fn check_trie<K, V, T, S, E>(
+    txn: &T,
+    store: &S,
+    hash: &Blake2bHash,
+    maybe_parent: Option<Blake2bHash>,
+    report: &mut IntegrityReport,
+) -> Result<(StoredKind, Vec<Pointer>), E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<bytesrepr::Error>,
+{
+    let bytes = match txn
+        .read(store.handle(), &hash.to_bytes()?)
+        .map_err(S::Error::from)?
+    {
+        Some(bytes) => bytes,
+        None => {
+            report.problems.push(match maybe_parent {
+                Some(parent) => IntegrityProblem::TrieNotFound {
+                    parent,
+                    hash: *hash,
+                },
+                None => IntegrityProblem::RootNotFound(*hash),
+            });
+            return Ok((StoredKind::Unknown, Vec::new()));
+        }
+    };
+    report.tries_checked += 1;
+
+    let actual = Blake2bHash::new(&bytes);
+    if actual != *hash {
+        report.problems.push(IntegrityProblem::HashMismatch {
+            hash: *hash,
+            actual,
+        });
+    }
+
+    let result = match Trie::<K, V>::from_bytes(&bytes) {
+        Ok((trie, rem)) if rem.is_empty() => Ok(trie),
+        Ok(_) => Err(bytesrepr::Error::LeftOverBytes),
+        Err(error) => Err(error),
+    };
+    match result {
+        Ok(trie) => {
+            let kind = match trie {
+                Trie::Leaf { .. } => {
+                    report.leaves_checked += 1;
+                    StoredKind::Leaf
+                }
+                Trie::Node { .. } | Trie::Extension { .. } => StoredKind::NodeOrExtension,
+            };
+            Ok((kind, child_pointers(&trie)))
+        }
+        Err(error) => {
+            // A serialized leaf is its tag (zero), its key and then its value.
+            let has_valid_leaf_key = match bytes.split_first() {
+                Some((0, rem)) => K::from_bytes(rem).is_ok(),
+                _ => false,
+            };
+            if has_valid_leaf_key {
+                report.leaves_checked += 1;
+                report
+                    .problems
+                    .push(IntegrityProblem::InvalidLeafValue { hash: *hash, error });
+                Ok((StoredKind::Leaf, Vec::new()))
+            } else {
+                report
+                    .problems
+                    .push(IntegrityProblem::InvalidTrie { hash: *hash, error });
+                Ok((StoredKind::Unknown, Vec::new()))
+            }
+        }
+    }
+}
+
+/// Checks every trie element reachable from the given roots.
+///
+/// Verifies that every element is stored under the hash of its bytes, that every pointer targets
+/// an element which is in the store and of the kind the pointer expects, and that every element,
+/// including the values of leaves, deserializes.  Elements shared by several roots or subtries are
+/// only checked once.  A problem with an element never stops the check: the descendants of
+/// elements which cannot be deserialized are simply unreachable.
+pub fn check_integrity<K, V, T, S, E>(
+    correlation_id: CorrelationId,
+    txn: &T,
+    store: &S,
+    roots: &[Blake2bHash],
+) -> Result<IntegrityReport, E>
+where
+    K: FromBytes,
+    V: FromBytes,
+    T: Readable<Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<T::Error>,
+    E: From<S::Error> + From<bytesrepr::Error>,
+{
+    let start = Instant::now();
+    let mut report = IntegrityReport::default();
+    let mut kinds: HashMap<Blake2bHash, StoredKind> = HashMap::new();
+    // Each hash to visit is paired with the element and pointer it was reached through, if any.
+    let mut to_visit: Vec<(Blake2bHash, Option<(Blake2bHash, Pointer)>)> =
+        roots.iter().rev().map(|root| (*root, None)).collect();
+
+    while let Some((hash, maybe_parent)) = to_visit.pop() {
+        let kind = match kinds.get(&hash) {
+            Some(kind) => *kind,
+            None => {
+                let (kind, children) = check_trie::<K, V, T, S, E>(
+                    txn,
+                    store,
+                    &hash,
+                    maybe_parent.map(|(parent, _)| parent),
+                    &mut report,
+                )?;
+                kinds.insert(hash, kind);
+                // Reversed so that children are checked in ascending order of their index.
+                for pointer in children.into_iter().rev() {
+                    to_visit.push((*pointer.hash(), Some((hash, pointer))));
+                }
+                kind
+            }
+        };
+
+        if let Some((parent, pointer)) = maybe_parent {
+            let is_mismatch = match (pointer, kind) {
+                (Pointer::LeafPointer(_), StoredKind::NodeOrExtension) => true,
+                (Pointer::NodePointer(_), StoredKind::Leaf) => true,
+                _ => false,
+            };
+            if is_mismatch {
+                report
+                    .problems
+                    .push(IntegrityProblem::PointerKindMismatch { parent, pointer });
+            }
+        }
+    }
+
+    log_metric(
+        correlation_id,
+        TRIE_STORE_CHECK_INTEGRITY_PROBLEMS,
+        CHECK_INTEGRITY,
+        GAUGE_METRIC_KEY,
+        report.problems.len() as f64,
+    );
+    log_duration(
+        correlation_id,
+        TRIE_STORE_CHECK_INTEGRITY_DURATION,
+        CHECK_INTEGRITY,
+        start.elapsed(),
+    );
+
+    Ok(report)
+}
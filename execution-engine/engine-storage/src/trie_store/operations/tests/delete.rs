@@ -0,0 +1,229 @@
+use super::*;
+use crate::trie_store::operations::{delete, DeleteResult};
+
+fn delete_leaves<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    root: &Blake2bHash,
+    leaves: &[TestTrie],
+) -> Result<Vec<DeleteResult>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let mut results = Vec::new();
+    let mut root = root.to_owned();
+    let mut txn = environment.create_read_write_txn()?;
+    for leaf in leaves {
+        let key = leaf.key().expect("leaves should only contain leaves");
+        let result =
+            delete::<TestKey, TestValue, _, _, E>(correlation_id, &mut txn, store, &root, key)?;
+        if let DeleteResult::Deleted(new_root) = result {
+            root = new_root;
+        }
+        results.push(result);
+    }
+    txn.commit()?;
+    Ok(results)
+}
+
+/// Writes `leaves` onto the empty trie in `store` and returns the resulting root.
+fn root_of_leaves<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    leaves: &[TestTrie],
+) -> Result<Blake2bHash, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (empty_root_hash, empty_trie) = create_0_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &empty_trie)?;
+    let root = write_leaves::<_, _, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &empty_root_hash,
+        leaves,
+    )?
+    .into_iter()
+    .filter_map(|result| match result {
+        WriteResult::Written(root_hash) => Some(root_hash),
+        _ => None,
+    })
+    .last()
+    .unwrap_or(empty_root_hash);
+    Ok(root)
+}
+
+/// Deletes the leaves of the 6-leaf trie in reverse order of insertion, checking that each
+/// resulting root is the root of the corresponding smaller generated trie.
+fn deletes_from_n_leaf_trie_return_generated_roots<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (root, tries) = create_6_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+
+    let leaves: Vec<TestTrie> = TEST_LEAVES.iter().rev().cloned().collect();
+    let results = delete_leaves::<_, _, E>(correlation_id, environment, store, &root, &leaves)?;
+
+    for (num_leaves, result) in (0..TEST_LEAVES_LENGTH).rev().zip(results) {
+        let (expected_root, _) = TEST_TRIE_GENERATORS[num_leaves]()?;
+        assert_eq!(result, DeleteResult::Deleted(expected_root));
+        let (used, unused) = TEST_LEAVES.split_at(num_leaves);
+        check_leaves::<_, _, _, _, E>(
+            correlation_id,
+            environment,
+            store,
+            &expected_root,
+            used,
+            unused,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Deletes every leaf in turn from a trie holding `leaves`, checking that the resulting root is
+/// the root of a trie written without that leaf.
+fn delete_matches_writing_remaining_leaves<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    leaves: &[TestTrie],
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let root = root_of_leaves::<_, _, E>(correlation_id, environment, store, leaves)?;
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let remaining: Vec<TestTrie> = leaves
+            .iter()
+            .enumerate()
+            .filter(|(other_index, _)| *other_index != index)
+            .map(|(_, leaf)| leaf.clone())
+            .collect();
+        let expected_root =
+            root_of_leaves::<_, _, E>(correlation_id, environment, store, &remaining)?;
+
+        let results =
+            delete_leaves::<_, _, E>(correlation_id, environment, store, &root, &[leaf.clone()])?;
+        assert_eq!(results, vec![DeleteResult::Deleted(expected_root)]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_deletes_from_n_leaf_trie_return_generated_roots() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    deletes_from_n_leaf_trie_return_generated_roots::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_deletes_from_n_leaf_trie_return_generated_roots() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    deletes_from_n_leaf_trie_return_generated_roots::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_delete_matches_writing_remaining_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let leaves: Vec<TestTrie> = TEST_LEAVES
+        .iter()
+        .chain(TEST_LEAVES_ADJACENTS.iter())
+        .cloned()
+        .collect();
+    delete_matches_writing_remaining_leaves::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &leaves,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_delete_matches_writing_remaining_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let leaves: Vec<TestTrie> = TEST_LEAVES
+        .iter()
+        .chain(TEST_LEAVES_ADJACENTS.iter())
+        .cloned()
+        .collect();
+    delete_matches_writing_remaining_leaves::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &leaves,
+    )
+    .unwrap();
+}
+
+#[test]
+fn delete_of_missing_key_returns_does_not_exist() {
+    let correlation_id = CorrelationId::new();
+    let (root_hash, tries) = create_6_leaf_trie().unwrap();
+    let context = InMemoryTestContext::new(&tries).unwrap();
+
+    let results = delete_leaves::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &root_hash,
+        &TEST_LEAVES_ADJACENTS,
+    )
+    .unwrap();
+    assert!(results
+        .into_iter()
+        .all(|result| result == DeleteResult::DoesNotExist));
+}
+
+#[test]
+fn delete_from_missing_root_returns_root_not_found() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let missing_root: Blake2bHash = [1u8; 32].into();
+
+    let results = delete_leaves::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &missing_root,
+        &TEST_LEAVES[..1],
+    )
+    .unwrap();
+    assert_eq!(results, vec![DeleteResult::RootNotFound]);
+}
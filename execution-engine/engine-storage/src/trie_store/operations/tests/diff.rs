@@ -0,0 +1,182 @@
+use super::*;
+use crate::trie_store::operations::{diff, DiffEntry, DiffResult};
+
+fn diff_roots<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    left_root: &Blake2bHash,
+    right_root: &Blake2bHash,
+) -> Result<DiffResult<TestKey, TestValue>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let result =
+        diff::<TestKey, TestValue, _, _, E>(correlation_id, &txn, store, left_root, right_root)?;
+    txn.commit()?;
+    Ok(result)
+}
+
+/// Returns the expected diff from a trie holding `left` to a trie holding `right`, sorted by the
+/// serialized keys.
+fn expected_diff(left: &[TestTrie], right: &[TestTrie]) -> Vec<DiffEntry<TestKey, TestValue>> {
+    let value_of = |leaves: &[TestTrie], key: &TestKey| {
+        leaves.iter().find_map(|leaf| match leaf {
+            Trie::Leaf {
+                key: leaf_key,
+                value,
+            } if leaf_key == key => Some(*value),
+            _ => None,
+        })
+    };
+    let mut keys: Vec<TestKey> = left
+        .iter()
+        .chain(right.iter())
+        .map(|leaf| *leaf.key().expect("leaves should only contain leaves"))
+        .collect();
+    keys.sort_by_key(|key| key.to_bytes().unwrap());
+    keys.dedup();
+    keys.into_iter()
+        .map(|key| (key, value_of(left, &key), value_of(right, &key)))
+        .filter(|(_, left_value, right_value)| left_value != right_value)
+        .collect()
+}
+
+/// Writes `initial` then `updates` onto the empty trie in `store`, and checks the diff between
+/// every pair of the resulting roots in both directions.
+fn diffs_between_written_states_are_expected<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    initial: &[TestTrie],
+    updates: &[TestTrie],
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (empty_root_hash, empty_trie) = create_0_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &empty_trie)?;
+
+    // `states[i]` is the root after writing the first `i` leaves of `leaves`.
+    let leaves: Vec<TestTrie> = initial.iter().chain(updates.iter()).cloned().collect();
+    let mut states = vec![empty_root_hash];
+    for result in write_leaves::<_, _, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &empty_root_hash,
+        &leaves,
+    )? {
+        match result {
+            WriteResult::Written(root_hash) => states.push(root_hash),
+            _ => panic!("write_leaves resulted in non-write"),
+        }
+    }
+
+    // The state holding the first `n` leaves, with later writes to a key overriding earlier ones.
+    let contents = |n: usize| {
+        let mut contents: Vec<TestTrie> = Vec::new();
+        for leaf in &leaves[..n] {
+            contents.retain(|existing| existing.key() != leaf.key());
+            contents.push(leaf.clone());
+        }
+        contents
+    };
+
+    for (i, left_root) in states.iter().enumerate() {
+        for (j, right_root) in states.iter().enumerate() {
+            let result =
+                diff_roots::<_, _, E>(correlation_id, environment, store, left_root, right_root)?;
+            assert_eq!(
+                result,
+                DiffResult::Diff(expected_diff(&contents(i), &contents(j))),
+                "diff from state {} to state {}",
+                i,
+                j
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_diff_between_n_leaf_tries_returns_added_and_removed_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    diffs_between_written_states_are_expected::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &TEST_LEAVES,
+        &TEST_LEAVES_ADJACENTS,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_diff_between_n_leaf_tries_returns_added_and_removed_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    diffs_between_written_states_are_expected::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &TEST_LEAVES,
+        &TEST_LEAVES_ADJACENTS,
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_diff_between_updated_tries_returns_modified_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    diffs_between_written_states_are_expected::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &TEST_LEAVES,
+        &TEST_LEAVES_UPDATED,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_diff_between_updated_tries_returns_modified_leaves() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    diffs_between_written_states_are_expected::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &TEST_LEAVES,
+        &TEST_LEAVES_UPDATED,
+    )
+    .unwrap();
+}
+
+#[test]
+fn diff_with_missing_root_returns_root_not_found() {
+    let correlation_id = CorrelationId::new();
+    let (root_hash, tries) = create_6_leaf_trie().unwrap();
+    let context = InMemoryTestContext::new(&tries).unwrap();
+    let missing_root: Blake2bHash = [1u8; 32].into();
+
+    let result = diff_roots::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &root_hash,
+        &missing_root,
+    )
+    .unwrap();
+    assert_eq!(result, DiffResult::RootNotFound(missing_root));
+}
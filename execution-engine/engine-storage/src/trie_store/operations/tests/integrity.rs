@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+
+use super::*;
+use crate::{
+    transaction_source::Writable,
+    trie_store::operations::{check_integrity, IntegrityProblem, IntegrityReport},
+};
+
+fn check<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    roots: &[Blake2bHash],
+) -> Result<IntegrityReport, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let report =
+        check_integrity::<TestKey, TestValue, _, _, E>(correlation_id, &txn, store, roots)?;
+    txn.commit()?;
+    Ok(report)
+}
+
+/// Stores `bytes` under `hash`, or deletes whatever is stored under `hash` if `bytes` is `None`.
+fn corrupt<'a, R, S, E>(
+    environment: &'a R,
+    store: &S,
+    hash: &Blake2bHash,
+    maybe_bytes: Option<&[u8]>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let mut txn = environment.create_read_write_txn()?;
+    match maybe_bytes {
+        Some(bytes) => txn.write(store.handle(), &hash.to_bytes()?, bytes)?,
+        None => txn.delete(store.handle(), &hash.to_bytes()?)?,
+    }
+    txn.commit()?;
+    Ok(())
+}
+
+/// Checks the tries of every generator, and checks that each element was checked exactly once.
+fn intact_tries_have_no_problems<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let mut roots = Vec::new();
+    let mut hashes = HashSet::new();
+    let mut leaf_hashes = HashSet::new();
+    for generator in TEST_TRIE_GENERATORS.iter() {
+        let (root_hash, tries) = generator()?;
+        put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+        roots.push(root_hash);
+        for HashedTrie { hash, trie } in tries {
+            if let Trie::Leaf { .. } = trie {
+                leaf_hashes.insert(hash);
+            }
+            hashes.insert(hash);
+        }
+    }
+
+    let report = check::<_, _, E>(correlation_id, environment, store, &roots)?;
+    assert_eq!(
+        report,
+        IntegrityReport {
+            tries_checked: hashes.len() as u64,
+            leaves_checked: leaf_hashes.len() as u64,
+            problems: Vec::new(),
+        }
+    );
+    assert!(report.is_intact());
+
+    Ok(())
+}
+
+/// Deletes one leaf of a trie and replaces another with an extension, and checks that the missing
+/// leaf, the hash mismatch and the mismatching pointer are all reported.
+fn missing_and_misplaced_tries_are_reported<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    // The leaves, followed by the root, the extension and the node holding the leaves.
+    let (root_hash, tries) = create_2_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+    let (leaf_0, leaf_1, extension, node) = (&tries[0], &tries[1], &tries[3], &tries[4]);
+
+    corrupt::<_, _, E>(environment, store, &leaf_0.hash, None)?;
+    corrupt::<_, _, E>(
+        environment,
+        store,
+        &leaf_1.hash,
+        Some(&extension.trie.to_bytes()?),
+    )?;
+
+    let report = check::<_, _, E>(correlation_id, environment, store, &[root_hash])?;
+    assert_eq!(
+        report,
+        IntegrityReport {
+            tries_checked: 4,
+            leaves_checked: 0,
+            problems: vec![
+                IntegrityProblem::TrieNotFound {
+                    parent: node.hash,
+                    hash: leaf_0.hash,
+                },
+                IntegrityProblem::HashMismatch {
+                    hash: leaf_1.hash,
+                    actual: extension.hash,
+                },
+                IntegrityProblem::PointerKindMismatch {
+                    parent: node.hash,
+                    pointer: Pointer::LeafPointer(leaf_1.hash),
+                },
+            ],
+        }
+    );
+    assert!(!report.is_intact());
+
+    Ok(())
+}
+
+/// Overwrites a node and a leaf with bytes which do not deserialize, and checks that both are
+/// reported.
+fn undeserializable_tries_are_reported<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (root_hash_1, tries_1) = create_1_leaf_trie()?;
+    let (root_hash_2, tries_2) = create_2_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries_1)?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries_2)?;
+    let (leaf_0, node) = (&tries_1[0], &tries_2[4]);
+
+    let mut bad_leaf_bytes = leaf_0.trie.to_bytes()?;
+    bad_leaf_bytes.push(0);
+    corrupt::<_, _, E>(environment, store, &leaf_0.hash, Some(&bad_leaf_bytes))?;
+    let bad_node_bytes = [u8::max_value()];
+    corrupt::<_, _, E>(environment, store, &node.hash, Some(&bad_node_bytes))?;
+
+    let report = check::<_, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &[root_hash_1, root_hash_2],
+    )?;
+    assert_eq!(
+        report,
+        IntegrityReport {
+            tries_checked: 5,
+            leaves_checked: 1,
+            problems: vec![
+                IntegrityProblem::HashMismatch {
+                    hash: leaf_0.hash,
+                    actual: Blake2bHash::new(&bad_leaf_bytes),
+                },
+                IntegrityProblem::InvalidLeafValue {
+                    hash: leaf_0.hash,
+                    error: bytesrepr::Error::LeftOverBytes,
+                },
+                IntegrityProblem::HashMismatch {
+                    hash: node.hash,
+                    actual: Blake2bHash::new(&bad_node_bytes),
+                },
+                IntegrityProblem::InvalidTrie {
+                    hash: node.hash,
+                    error: bytesrepr::Error::Formatting,
+                },
+            ],
+        }
+    );
+
+    Ok(())
+}
+
+/// Checks a root which is not in the store alongside one which is.
+fn missing_root_is_reported<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (root_hash, tries) = create_1_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+    let missing_root: Blake2bHash = [1u8; 32].into();
+
+    let report = check::<_, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &[missing_root, root_hash],
+    )?;
+    assert_eq!(
+        report,
+        IntegrityReport {
+            tries_checked: 2,
+            leaves_checked: 1,
+            problems: vec![IntegrityProblem::RootNotFound(missing_root)],
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_intact_tries_have_no_problems() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    intact_tries_have_no_problems::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_intact_tries_have_no_problems() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    intact_tries_have_no_problems::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_missing_and_misplaced_tries_are_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    missing_and_misplaced_tries_are_reported::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_missing_and_misplaced_tries_are_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    missing_and_misplaced_tries_are_reported::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_undeserializable_tries_are_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    undeserializable_tries_are_reported::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_undeserializable_tries_are_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    undeserializable_tries_are_reported::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_missing_root_is_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    missing_root_is_reported::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_missing_root_is_reported() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    missing_root_is_reported::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
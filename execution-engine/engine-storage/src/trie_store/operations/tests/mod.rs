@@ -1,10 +1,21 @@
+mod delete;
+mod diff;
+mod integrity;
 mod keys;
 mod proptests;
+mod prune;
 mod read;
+mod read_range;
+mod read_with_proof;
 mod scan;
+mod state_sync;
+mod trie_builder;
 mod write;
 
-use std::{collections::HashMap, convert};
+use std::{
+    collections::{HashMap, HashSet},
+    convert,
+};
 
 use lmdb::DatabaseFlags;
 use tempfile::{tempdir, TempDir};
@@ -18,7 +29,7 @@ use crate::{
         in_memory::InMemoryEnvironment, lmdb::LmdbEnvironment, Readable, Transaction,
         TransactionSource,
     },
-    trie::{Pointer, Trie},
+    trie::{Pointer, Trie, TrieChunk},
     trie_store::{
         self,
         in_memory::InMemoryTrieStore,
@@ -502,6 +513,23 @@ where
     Ok(())
 }
 
+/// Returns the hashes of every trie element in the store.
+fn stored_hashes<'a, K, V, R, S, E>(
+    environment: &'a R,
+    store: &S,
+) -> Result<HashSet<Blake2bHash>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<K, V>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let hashes = store.keys(&txn, None, usize::max_value())?;
+    txn.commit()?;
+    Ok(hashes.into_iter().collect())
+}
+
 // A context for holding lmdb-based test resources
 struct LmdbTestContext {
     _temp_dir: TempDir,
@@ -7,6 +7,7 @@ use proptest::{
 };
 
 use super::*;
+use crate::trie_store::operations::{delete, DeleteResult};
 
 const DEFAULT_MIN_LENGTH: usize = 0;
 
@@ -76,6 +77,81 @@ fn in_memory_roundtrip_succeeds(pairs: &[(TestKey, TestValue)]) -> bool {
     .unwrap()
 }
 
+/// Writes `pairs`, deletes the keys of every other pair, and checks that the resulting root is the
+/// root of a trie holding only the pairs which were not deleted.
+fn delete_matches_writing_remaining_pairs<'a, R, S, E>(
+    environment: &'a R,
+    store: &S,
+    pairs: &[(TestKey, TestValue)],
+) -> Result<bool, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let correlation_id = CorrelationId::new();
+    let (empty_root_hash, _) = TEST_TRIE_GENERATORS[0]()?;
+    let deleted_keys: Vec<TestKey> = pairs.iter().step_by(2).map(|(key, _)| *key).collect();
+    let remaining_pairs: Vec<(TestKey, TestValue)> = pairs
+        .iter()
+        .filter(|(key, _)| !deleted_keys.contains(key))
+        .cloned()
+        .collect();
+
+    let all_root =
+        write_pairs::<_, _, _, _, E>(correlation_id, environment, store, &empty_root_hash, pairs)?
+            .last()
+            .copied()
+            .unwrap_or(empty_root_hash);
+    let expected_root = write_pairs::<_, _, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &empty_root_hash,
+        &remaining_pairs,
+    )?
+    .last()
+    .copied()
+    .unwrap_or(empty_root_hash);
+
+    let mut root = all_root;
+    let mut txn = environment.create_read_write_txn()?;
+    for key in deleted_keys.iter() {
+        match delete::<TestKey, TestValue, _, _, E>(correlation_id, &mut txn, store, &root, key)? {
+            DeleteResult::Deleted(new_root) => root = new_root,
+            // The key was already deleted as a duplicate.
+            DeleteResult::DoesNotExist => (),
+            DeleteResult::RootNotFound => panic!("delete given an invalid root"),
+        }
+    }
+    txn.commit()?;
+
+    Ok(root == expected_root)
+}
+
+fn lmdb_delete_succeeds(pairs: &[(TestKey, TestValue)]) -> bool {
+    let (_, tries) = TEST_TRIE_GENERATORS[0]().unwrap();
+    let context = LmdbTestContext::new(&tries).unwrap();
+    delete_matches_writing_remaining_pairs::<_, _, error::Error>(
+        &context.environment,
+        &context.store,
+        pairs,
+    )
+    .unwrap()
+}
+
+fn in_memory_delete_succeeds(pairs: &[(TestKey, TestValue)]) -> bool {
+    let (_, tries) = TEST_TRIE_GENERATORS[0]().unwrap();
+    let context = InMemoryTestContext::new(&tries).unwrap();
+    delete_matches_writing_remaining_pairs::<_, _, in_memory::Error>(
+        &context.environment,
+        &context.store,
+        pairs,
+    )
+    .unwrap()
+}
+
 fn test_key_arb() -> impl Strategy<Value = TestKey> {
     array::uniform7(any::<u8>()).prop_map(TestKey)
 }
@@ -94,4 +170,14 @@ proptest! {
     fn prop_lmdb_roundtrip_succeeds(inputs in vec((test_key_arb(), test_value_arb()), get_range())) {
         assert!(lmdb_roundtrip_succeeds(&inputs));
     }
+
+    #[test]
+    fn prop_in_memory_delete_succeeds(inputs in vec((test_key_arb(), test_value_arb()), get_range())) {
+        assert!(in_memory_delete_succeeds(&inputs));
+    }
+
+    #[test]
+    fn prop_lmdb_delete_succeeds(inputs in vec((test_key_arb(), test_value_arb()), get_range())) {
+        assert!(lmdb_delete_succeeds(&inputs));
+    }
 }
@@ -0,0 +1,330 @@
+use lmdb::Database;
+
+use super::*;
+use crate::{
+    store::Store,
+    trie_store::{
+        operations::{prune, PruneResult},
+        prune_marks::{self, PruneMarkStore},
+    },
+};
+
+const TEST_BATCH_SIZE: usize = 3;
+
+fn lmdb_marks(context: &LmdbTestContext) -> PruneMarkStore<Database, error::Error> {
+    let db = context
+        .environment
+        .env()
+        .create_db(Some(prune_marks::NAME), DatabaseFlags::empty())
+        .unwrap();
+    PruneMarkStore::new(db)
+}
+
+fn in_memory_marks() -> PruneMarkStore<Option<String>, in_memory::Error> {
+    PruneMarkStore::new(Some(prune_marks::NAME.to_string()))
+}
+
+/// Returns the hashes which are marked.
+fn marked_hashes<'a, R, S, E>(
+    environment: &'a R,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+) -> Result<Vec<Blake2bHash>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let hashes = marks.keys(&txn, None, usize::max_value())?;
+    txn.commit()?;
+    Ok(hashes)
+}
+
+/// Writes the tries of every generator to the store, prunes everything except the tries
+/// reachable from the root of the last generator, and checks that exactly those tries remain.
+fn prune_keeps_only_latest_state<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let mut roots = Vec::new();
+    for generator in TEST_TRIE_GENERATORS.iter() {
+        let (root_hash, tries) = generator()?;
+        put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+        roots.push(root_hash);
+    }
+
+    let (latest_root, latest_tries) = TEST_TRIE_GENERATORS[TEST_TRIE_GENERATORS_LENGTH - 1]()?;
+    let expected: HashSet<Blake2bHash> = latest_tries.iter().map(|trie| trie.hash).collect();
+    let before = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+
+    let result = prune::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        marks,
+        &[latest_root],
+        TEST_BATCH_SIZE,
+    )?;
+    assert_eq!(
+        result,
+        PruneResult::Pruned {
+            kept: expected.len(),
+            deleted: before.len() - expected.len(),
+        }
+    );
+
+    let after = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+    assert_eq!(after, expected);
+    assert!(marked_hashes::<_, S, E>(environment, marks)?.is_empty());
+
+    for root in &roots[..roots.len() - 1] {
+        assert!(!after.contains(root));
+    }
+
+    check_leaves::<_, _, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        &latest_root,
+        &TEST_LEAVES,
+        &[],
+    )?;
+
+    Ok(())
+}
+
+/// Prunes while keeping every root, and checks that nothing was deleted.
+fn prune_keeping_all_roots_deletes_nothing<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let mut roots = Vec::new();
+    for generator in TEST_TRIE_GENERATORS.iter() {
+        let (root_hash, tries) = generator()?;
+        put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+        roots.push(root_hash);
+    }
+
+    let before = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+    let result = prune::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        marks,
+        &roots,
+        TEST_BATCH_SIZE,
+    )?;
+    assert_eq!(
+        result,
+        PruneResult::Pruned {
+            kept: before.len(),
+            deleted: 0,
+        }
+    );
+    let after = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+    assert_eq!(after, before);
+
+    Ok(())
+}
+
+/// Prunes with a root which is not in the store, and checks that nothing was deleted.
+fn prune_with_missing_root_deletes_nothing<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let (root_hash, tries) = TEST_TRIE_GENERATORS[1]()?;
+    put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+    let missing_root: Blake2bHash = [1u8; 32].into();
+
+    let before = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+    let result = prune::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        marks,
+        &[root_hash, missing_root],
+        TEST_BATCH_SIZE,
+    )?;
+    assert_eq!(result, PruneResult::RootNotFound(missing_root));
+    let after = stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?;
+    assert_eq!(after, before);
+
+    Ok(())
+}
+
+/// Leaves a mark on an unreachable root, as an interrupted prune would, and checks that it does
+/// not stop the root from being pruned and that no marks remain afterwards.
+fn prune_ignores_stale_marks<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    marks: &PruneMarkStore<S::Handle, S::Error>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let (stale_root, stale_tries) = TEST_TRIE_GENERATORS[1]()?;
+    put_tries::<_, _, _, _, E>(environment, store, &stale_tries)?;
+    let (latest_root, latest_tries) = TEST_TRIE_GENERATORS[TEST_TRIE_GENERATORS_LENGTH - 1]()?;
+    put_tries::<_, _, _, _, E>(environment, store, &latest_tries)?;
+    assert_ne!(stale_root, latest_root);
+
+    let mut txn = environment.create_read_write_txn()?;
+    marks.put(&mut txn, &stale_root, &())?;
+    txn.commit()?;
+
+    prune::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        environment,
+        store,
+        marks,
+        &[latest_root],
+        TEST_BATCH_SIZE,
+    )?;
+
+    let expected: HashSet<Blake2bHash> = latest_tries.iter().map(|trie| trie.hash).collect();
+    assert_eq!(
+        stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?,
+        expected
+    );
+    assert!(marked_hashes::<_, S, E>(environment, marks)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_prune_keeps_only_latest_state() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_keeps_only_latest_state::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &lmdb_marks(&context),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_prune_keeps_only_latest_state() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_keeps_only_latest_state::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &in_memory_marks(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_prune_keeping_all_roots_deletes_nothing() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_keeping_all_roots_deletes_nothing::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &lmdb_marks(&context),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_prune_keeping_all_roots_deletes_nothing() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_keeping_all_roots_deletes_nothing::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &in_memory_marks(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_prune_with_missing_root_deletes_nothing() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_with_missing_root_deletes_nothing::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &lmdb_marks(&context),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_prune_with_missing_root_deletes_nothing() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_with_missing_root_deletes_nothing::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &in_memory_marks(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_prune_ignores_stale_marks() {
+    let correlation_id = CorrelationId::new();
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_ignores_stale_marks::<_, _, error::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &lmdb_marks(&context),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_prune_ignores_stale_marks() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    prune_ignores_stale_marks::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &in_memory_marks(),
+    )
+    .unwrap();
+}
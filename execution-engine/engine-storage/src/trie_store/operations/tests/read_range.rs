@@ -0,0 +1,185 @@
+use std::ops::Bound;
+
+use super::*;
+use crate::trie_store::operations::{read_range, KeyRange};
+
+fn read_range_from<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    root_hash: &Blake2bHash,
+    range: &KeyRange,
+    limit: usize,
+) -> Result<ReadResult<Vec<(TestKey, TestValue)>>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let ret = read_range::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        &txn,
+        store,
+        root_hash,
+        range,
+        limit,
+    )?;
+    txn.commit()?;
+    Ok(ret)
+}
+
+/// Returns the leaves of `tries` within `range`, in ascending order of their keys.
+fn expected_pairs(tries: &[HashedTestTrie], range: &KeyRange) -> Vec<(TestKey, TestValue)> {
+    let mut ret: Vec<(TestKey, TestValue)> = tries
+        .iter()
+        .filter_map(|HashedTrie { trie, .. }| match trie {
+            Trie::Leaf { key, value } if range.contains(&key.0) => Some((*key, *value)),
+            _ => None,
+        })
+        .collect();
+    ret.sort_by_key(|(key, _)| *key);
+    ret
+}
+
+fn test_ranges() -> Vec<KeyRange> {
+    vec![
+        KeyRange::all(),
+        KeyRange::with_prefix(&[]),
+        KeyRange::with_prefix(&[0]),
+        KeyRange::with_prefix(&[0, 0, 0]),
+        KeyRange::with_prefix(&[0, 0, 0, 0, 0]),
+        KeyRange::with_prefix(&[0, 0, 2]),
+        KeyRange::with_prefix(&[1]),
+        KeyRange::with_prefix(&TEST_LEAVES[1].key().unwrap().0),
+        KeyRange::new(
+            Bound::Excluded(vec![0, 0, 0, 0, 0, 0, 0]),
+            Bound::Included(vec![0, 0, 0, 2, 0, 0, 0]),
+        ),
+        KeyRange::new(
+            Bound::Included(vec![0, 0, 0, 0, 0, 1]),
+            Bound::Excluded(vec![0, 1]),
+        ),
+        KeyRange::new(Bound::Included(vec![0, 0, 1]), Bound::Unbounded),
+        KeyRange::new(Bound::Unbounded, Bound::Excluded(vec![0, 0, 0, 0, 0, 0, 1])),
+    ]
+}
+
+/// Reads each of the test ranges from the trie of every generator, both in one go and in pages of
+/// `page_size` pairs.
+fn reads_from_n_leaf_tries_had_expected_results<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    page_size: usize,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    for generator in TEST_TRIE_GENERATORS.iter() {
+        let (root_hash, tries) = generator()?;
+        put_tries::<_, _, _, _, E>(environment, store, &tries)?;
+
+        for range in test_ranges() {
+            let expected = expected_pairs(&tries, &range);
+
+            let all = read_range_from::<_, _, E>(
+                correlation_id,
+                environment,
+                store,
+                &root_hash,
+                &range,
+                usize::max_value(),
+            )?;
+            assert_eq!(all, ReadResult::Found(expected.clone()));
+
+            let mut paged = Vec::new();
+            let mut page_range = range.clone();
+            loop {
+                let page = match read_range_from::<_, _, E>(
+                    correlation_id,
+                    environment,
+                    store,
+                    &root_hash,
+                    &page_range,
+                    page_size,
+                )? {
+                    ReadResult::Found(page) => page,
+                    _ => panic!("should find the root"),
+                };
+                assert!(page.len() <= page_size);
+                match page.last() {
+                    Some((last_key, _)) => page_range = page_range.after(&last_key.0),
+                    None => break,
+                }
+                paged.extend(page);
+            }
+            assert_eq!(paged, expected);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn lmdb_reads_from_n_leaf_tries_had_expected_results() {
+    for page_size in 1..=3 {
+        let correlation_id = CorrelationId::new();
+        let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+        reads_from_n_leaf_tries_had_expected_results::<_, _, error::Error>(
+            correlation_id,
+            &context.environment,
+            &context.store,
+            page_size,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn in_memory_reads_from_n_leaf_tries_had_expected_results() {
+    for page_size in 1..=3 {
+        let correlation_id = CorrelationId::new();
+        let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+        reads_from_n_leaf_tries_had_expected_results::<_, _, in_memory::Error>(
+            correlation_id,
+            &context.environment,
+            &context.store,
+            page_size,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn in_memory_read_range_from_missing_root_is_root_not_found() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let result = read_range_from::<_, _, in_memory::Error>(
+        correlation_id,
+        &context.environment,
+        &context.store,
+        &[1u8; 32].into(),
+        &KeyRange::all(),
+        usize::max_value(),
+    )
+    .unwrap();
+    assert_eq!(result, ReadResult::RootNotFound);
+}
+
+#[test]
+fn key_range_with_prefix_excludes_the_successor_of_the_prefix() {
+    let range = KeyRange::with_prefix(&[0, 255, 255]);
+    assert_eq!(range.start(), Bound::Included(&[0u8, 255, 255][..]));
+    assert_eq!(range.end(), Bound::Excluded(&[1u8][..]));
+    assert!(range.contains(&[0, 255, 255, 255]));
+    assert!(!range.contains(&[1]));
+
+    let range = KeyRange::with_prefix(&[255, 255]);
+    assert_eq!(range.end(), Bound::Unbounded);
+    assert!(range.contains(&[255, 255, 0]));
+    assert!(!range.contains(&[255, 254, 255]));
+}
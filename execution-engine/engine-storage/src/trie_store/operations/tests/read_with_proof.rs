@@ -0,0 +1,199 @@
+//! This module contains tests for [`operations::read_with_proof`] and
+//! [`operations::prove_absence`], checking that the produced proofs are accepted by the standalone
+//! verifiers in [`types::merkle_proof`].
+
+use types::merkle_proof::{self, verify_absence_proof, verify_inclusion_proof};
+
+use super::*;
+use crate::trie_store::operations::{prove_absence, read_with_proof, AbsenceProofResult};
+
+fn serialize_proof_steps(proof_steps: &[TestTrie]) -> Result<Vec<Vec<u8>>, bytesrepr::Error> {
+    proof_steps.iter().map(ToBytes::to_bytes).collect()
+}
+
+/// Reads every used leaf with a proof from the trie at `root`, and checks that each proof verifies
+/// against `root` but not against `other_root`.  Checks that every unused leaf has a verifiable
+/// proof of absence instead.
+fn check_proofs<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    root: &Blake2bHash,
+    other_root: &Blake2bHash,
+    used: &[TestTrie],
+    unused: &[TestTrie],
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn: R::ReadTransaction = environment.create_read_txn()?;
+
+    for leaf in used {
+        let (key, value) = match leaf {
+            Trie::Leaf { key, value } => (key, value),
+            _ => panic!("leaves should only contain leaves"),
+        };
+        let proof = match read_with_proof::<_, _, _, _, E>(correlation_id, &txn, store, root, key)?
+        {
+            ReadResult::Found(proof) => proof,
+            _ => panic!("should find {:?}", key),
+        };
+        assert_eq!(proof.key(), key);
+        assert_eq!(proof.value(), value);
+        assert_eq!(proof.proof_steps().last(), Some(leaf));
+
+        let proof_steps = serialize_proof_steps(proof.proof_steps())?;
+        assert_eq!(
+            verify_inclusion_proof(&root.value(), key, &proof_steps),
+            Ok(value.to_bytes()?.as_slice())
+        );
+        assert_eq!(
+            verify_inclusion_proof(&other_root.value(), key, &proof_steps),
+            Err(merkle_proof::Error::StateRootMismatch)
+        );
+    }
+
+    for leaf in used {
+        let key = leaf.key().expect("leaves should only contain leaves");
+        let result = prove_absence::<_, _, _, _, E>(correlation_id, &txn, store, root, key)?;
+        assert_eq!(result, AbsenceProofResult::Present);
+    }
+
+    for leaf in unused {
+        let key = leaf.key().expect("leaves should only contain leaves");
+        let result = read_with_proof::<_, _, _, _, E>(correlation_id, &txn, store, root, key)?;
+        assert_eq!(result, ReadResult::NotFound);
+
+        let proof = match prove_absence::<_, _, _, _, E>(correlation_id, &txn, store, root, key)? {
+            AbsenceProofResult::Absent(proof) => proof,
+            _ => panic!("should prove absence of {:?}", key),
+        };
+        assert_eq!(proof.key(), key);
+
+        let proof_steps = serialize_proof_steps(proof.proof_steps())?;
+        assert_eq!(
+            verify_absence_proof(&root.value(), key, &proof_steps),
+            Ok(())
+        );
+        assert!(verify_inclusion_proof(&root.value(), key, &proof_steps).is_err());
+        assert_eq!(
+            verify_absence_proof(&other_root.value(), key, &proof_steps),
+            Err(merkle_proof::Error::StateRootMismatch)
+        );
+    }
+
+    txn.commit()?;
+    Ok(())
+}
+
+#[test]
+fn lmdb_read_with_proof_from_n_leaf_trie_verifies() {
+    let correlation_id = CorrelationId::new();
+    let other_root: Blake2bHash = [1u8; 32].into();
+    for (num_leaves, generator) in TEST_TRIE_GENERATORS.iter().enumerate() {
+        let (root_hash, tries) = generator().unwrap();
+        let context = LmdbTestContext::new(&tries).unwrap();
+        let (used, unused) = TEST_LEAVES.split_at(num_leaves);
+
+        check_proofs::<_, _, error::Error>(
+            correlation_id,
+            &context.environment,
+            &context.store,
+            &root_hash,
+            &other_root,
+            used,
+            unused,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn in_memory_read_with_proof_from_n_leaf_trie_verifies() {
+    let correlation_id = CorrelationId::new();
+    let other_root: Blake2bHash = [1u8; 32].into();
+    for (num_leaves, generator) in TEST_TRIE_GENERATORS.iter().enumerate() {
+        let (root_hash, tries) = generator().unwrap();
+        let context = InMemoryTestContext::new(&tries).unwrap();
+        let (used, unused) = TEST_LEAVES.split_at(num_leaves);
+
+        check_proofs::<_, _, in_memory::Error>(
+            correlation_id,
+            &context.environment,
+            &context.store,
+            &root_hash,
+            &other_root,
+            used,
+            unused,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn read_with_proof_from_missing_root_returns_root_not_found() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let missing_root: Blake2bHash = [1u8; 32].into();
+    let key = TEST_LEAVES[0].key().unwrap();
+
+    let txn = context.environment.create_read_txn().unwrap();
+    let result = read_with_proof::<_, TestValue, _, _, in_memory::Error>(
+        correlation_id,
+        &txn,
+        &context.store,
+        &missing_root,
+        key,
+    )
+    .unwrap();
+    assert_eq!(result, ReadResult::RootNotFound);
+}
+
+#[test]
+fn prove_absence_from_missing_root_returns_root_not_found() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let missing_root: Blake2bHash = [1u8; 32].into();
+    let key = TEST_LEAVES[0].key().unwrap();
+
+    let txn = context.environment.create_read_txn().unwrap();
+    let result = prove_absence::<_, TestValue, _, _, in_memory::Error>(
+        correlation_id,
+        &txn,
+        &context.store,
+        &missing_root,
+        key,
+    )
+    .unwrap();
+    assert_eq!(result, AbsenceProofResult::RootNotFound);
+}
+
+#[test]
+fn proof_of_absence_for_key_present_in_trie_is_rejected() {
+    let correlation_id = CorrelationId::new();
+    let (root_hash, tries) = create_6_leaf_trie().unwrap();
+    let context = InMemoryTestContext::new(&tries).unwrap();
+    let key = TEST_LEAVES[0].key().unwrap();
+
+    let txn = context.environment.create_read_txn().unwrap();
+    let proof = match read_with_proof::<_, _, _, _, in_memory::Error>(
+        correlation_id,
+        &txn,
+        &context.store,
+        &root_hash,
+        key,
+    )
+    .unwrap()
+    {
+        ReadResult::Found(proof) => proof,
+        _ => panic!("should find {:?}", key),
+    };
+    let proof_steps = serialize_proof_steps(proof.proof_steps()).unwrap();
+    assert_eq!(
+        verify_absence_proof(&root_hash.value(), key, &proof_steps),
+        Err(merkle_proof::Error::KeyPresent)
+    );
+}
@@ -0,0 +1,415 @@
+use std::collections::HashSet;
+
+use lmdb::Database;
+
+use super::*;
+use crate::{
+    store::Store,
+    trie_store::{
+        incomplete_roots::{self, IncompleteRootStore},
+        operations::{
+            child_pointers, export_chunk, import_chunk, missing_descendants, ExportChunkResult,
+            ImportChunkResult,
+        },
+    },
+};
+
+const TEST_CHUNK_SIZE: usize = 2;
+
+fn lmdb_incomplete_roots(context: &LmdbTestContext) -> IncompleteRootStore<Database, error::Error> {
+    let db = context
+        .environment
+        .env()
+        .create_db(Some(incomplete_roots::NAME), DatabaseFlags::empty())
+        .unwrap();
+    IncompleteRootStore::new(db)
+}
+
+fn in_memory_incomplete_roots() -> IncompleteRootStore<Option<String>, in_memory::Error> {
+    IncompleteRootStore::new(Some(incomplete_roots::NAME.to_string()))
+}
+
+fn is_incomplete<'a, R, S, E>(
+    environment: &'a R,
+    incomplete_roots: &IncompleteRootStore<S::Handle, S::Error>,
+    root: &Blake2bHash,
+) -> Result<bool, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let is_incomplete = incomplete_roots.get(&txn, root)?.is_some();
+    txn.commit()?;
+    Ok(is_incomplete)
+}
+
+fn export_next_chunk<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    environment: &'a R,
+    store: &S,
+    pending: &mut Vec<Blake2bHash>,
+) -> Result<TrieChunk, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let result = export_chunk::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        &txn,
+        store,
+        pending,
+        TEST_CHUNK_SIZE,
+    )?;
+    txn.commit()?;
+    match result {
+        ExportChunkResult::Exported(chunk) => Ok(chunk),
+        ExportChunkResult::TrieNotFound(hash) => panic!("should export {:?}", hash),
+    }
+}
+
+fn missing<'a, R, S, E>(
+    environment: &'a R,
+    store: &S,
+    root: &Blake2bHash,
+) -> Result<Vec<Blake2bHash>, E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let txn = environment.create_read_txn()?;
+    let missing = missing_descendants::<TestKey, TestValue, _, _, E>(&txn, store, root)?;
+    txn.commit()?;
+    Ok(missing)
+}
+
+/// Copies the trie of the last generator from `source` to the empty `destination` chunk by chunk,
+/// interrupting the import after `interrupt_after` chunks and resuming it from the elements missing
+/// from `destination`.
+fn sync_latest_state<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    source: (&'a R, &S),
+    destination: (&'a R, &S),
+    incomplete_roots: &IncompleteRootStore<S::Handle, S::Error>,
+    interrupt_after: usize,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (source_environment, source_store) = source;
+    let (destination_environment, destination_store) = destination;
+
+    let mut roots = Vec::new();
+    for generator in TEST_TRIE_GENERATORS.iter() {
+        let (root_hash, tries) = generator()?;
+        put_tries::<_, _, _, _, E>(source_environment, source_store, &tries)?;
+        roots.push(root_hash);
+    }
+    let root = roots[roots.len() - 1];
+
+    assert_eq!(
+        missing::<_, _, E>(destination_environment, destination_store, &root)?,
+        vec![root]
+    );
+
+    let mut pending = vec![root];
+    let mut expected: HashSet<Blake2bHash> = pending.iter().copied().collect();
+    let mut imported_chunks = 0;
+    while !pending.is_empty() {
+        if imported_chunks == interrupt_after {
+            // Forget the progress of both sides, as if the node had restarted.
+            pending = missing::<_, _, E>(destination_environment, destination_store, &root)?;
+            expected = pending.iter().copied().collect();
+        }
+        let chunk = export_next_chunk::<_, _, E>(
+            correlation_id,
+            source_environment,
+            source_store,
+            &mut pending,
+        )?;
+        assert!(!chunk.is_empty() && chunk.len() <= TEST_CHUNK_SIZE);
+        let result = import_chunk::<TestKey, TestValue, _, _, E>(
+            correlation_id,
+            destination_environment,
+            destination_store,
+            incomplete_roots,
+            &root,
+            &mut expected,
+            &chunk,
+        )?;
+        assert_eq!(result, ImportChunkResult::Imported(chunk.len()));
+        assert_eq!(
+            is_incomplete::<_, S, E>(destination_environment, incomplete_roots, &root)?,
+            !expected.is_empty()
+        );
+        imported_chunks += 1;
+    }
+
+    assert!(expected.is_empty());
+    assert!(missing::<_, _, E>(destination_environment, destination_store, &root)?.is_empty());
+
+    check_leaves::<_, _, _, _, E>(
+        correlation_id,
+        destination_environment,
+        destination_store,
+        &root,
+        &TEST_LEAVES,
+        &[],
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_sync_copies_latest_state() {
+    let correlation_id = CorrelationId::new();
+    let source = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_latest_state::<_, _, error::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &lmdb_incomplete_roots(&destination),
+        usize::max_value(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_sync_copies_latest_state() {
+    let correlation_id = CorrelationId::new();
+    let source = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_latest_state::<_, _, in_memory::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &in_memory_incomplete_roots(),
+        usize::max_value(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn lmdb_sync_resumes_after_interruption() {
+    let correlation_id = CorrelationId::new();
+    let source = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_latest_state::<_, _, error::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &lmdb_incomplete_roots(&destination),
+        2,
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_sync_resumes_after_interruption() {
+    let correlation_id = CorrelationId::new();
+    let source = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_latest_state::<_, _, in_memory::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &in_memory_incomplete_roots(),
+        2,
+    )
+    .unwrap();
+}
+
+/// Imports one chunk of the subtrie under the only child of the root of the 6-leaf trie into the
+/// empty `destination`, abandoning that import, then syncs the whole 6-leaf trie, whose import has
+/// to fill in the incomplete subtrie it finds in place.
+fn sync_after_interrupted_import_of_subtrie<'a, R, S, E>(
+    correlation_id: CorrelationId,
+    source: (&'a R, &S),
+    destination: (&'a R, &S),
+    incomplete_roots: &IncompleteRootStore<S::Handle, S::Error>,
+) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Handle: Clone,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<bytesrepr::Error>,
+{
+    let (source_environment, source_store) = source;
+    let (destination_environment, destination_store) = destination;
+
+    let (root, tries) = create_6_leaf_trie()?;
+    put_tries::<_, _, _, _, E>(source_environment, source_store, &tries)?;
+    let subtrie_root = {
+        let txn = source_environment.create_read_txn()?;
+        let root_trie = source_store.get(&txn, &root)?.expect("should have root");
+        txn.commit()?;
+        *child_pointers(&root_trie)[0].hash()
+    };
+
+    let mut pending = vec![subtrie_root];
+    let mut expected: HashSet<Blake2bHash> = pending.iter().copied().collect();
+    let chunk = export_next_chunk::<_, _, E>(
+        correlation_id,
+        source_environment,
+        source_store,
+        &mut pending,
+    )?;
+    import_chunk::<TestKey, TestValue, _, _, E>(
+        correlation_id,
+        destination_environment,
+        destination_store,
+        incomplete_roots,
+        &subtrie_root,
+        &mut expected,
+        &chunk,
+    )?;
+    assert!(!expected.is_empty());
+    assert!(is_incomplete::<_, S, E>(
+        destination_environment,
+        incomplete_roots,
+        &subtrie_root
+    )?);
+
+    let mut pending = vec![root];
+    let mut expected: HashSet<Blake2bHash> = pending.iter().copied().collect();
+    while !pending.is_empty() {
+        let chunk = export_next_chunk::<_, _, E>(
+            correlation_id,
+            source_environment,
+            source_store,
+            &mut pending,
+        )?;
+        let result = import_chunk::<TestKey, TestValue, _, _, E>(
+            correlation_id,
+            destination_environment,
+            destination_store,
+            incomplete_roots,
+            &root,
+            &mut expected,
+            &chunk,
+        )?;
+        assert_eq!(result, ImportChunkResult::Imported(chunk.len()));
+    }
+
+    assert!(expected.is_empty());
+    assert!(!is_incomplete::<_, S, E>(
+        destination_environment,
+        incomplete_roots,
+        &root
+    )?);
+    assert!(missing::<_, _, E>(destination_environment, destination_store, &root)?.is_empty());
+    check_leaves::<_, _, _, _, E>(
+        correlation_id,
+        destination_environment,
+        destination_store,
+        &root,
+        &TEST_LEAVES,
+        &[],
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn lmdb_sync_completes_subtrie_of_interrupted_import() {
+    let correlation_id = CorrelationId::new();
+    let source = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_after_interrupted_import_of_subtrie::<_, _, error::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &lmdb_incomplete_roots(&destination),
+    )
+    .unwrap();
+}
+
+#[test]
+fn in_memory_sync_completes_subtrie_of_interrupted_import() {
+    let correlation_id = CorrelationId::new();
+    let source = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let destination = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    sync_after_interrupted_import_of_subtrie::<_, _, in_memory::Error>(
+        correlation_id,
+        (&source.environment, &source.store),
+        (&destination.environment, &destination.store),
+        &in_memory_incomplete_roots(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn import_rejects_unexpected_trie() {
+    let correlation_id = CorrelationId::new();
+    let (root_hash, tries) = create_6_leaf_trie().unwrap();
+    let source = InMemoryTestContext::new(&tries).unwrap();
+    let destination = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+
+    let mut pending = vec![root_hash];
+    let chunk = export_next_chunk::<_, _, in_memory::Error>(
+        correlation_id,
+        &source.environment,
+        &source.store,
+        &mut pending,
+    )
+    .unwrap();
+
+    // A tampered chunk whose first element no longer hashes to the root.
+    let mut tampered_tries = chunk.tries().to_vec();
+    tampered_tries[0].push(0);
+    let tampered_chunk = TrieChunk::new(tampered_tries);
+
+    let mut expected: HashSet<Blake2bHash> = vec![root_hash].into_iter().collect();
+    let result = import_chunk::<TestKey, TestValue, _, _, in_memory::Error>(
+        correlation_id,
+        &destination.environment,
+        &destination.store,
+        &in_memory_incomplete_roots(),
+        &root_hash,
+        &mut expected,
+        &tampered_chunk,
+    )
+    .unwrap();
+    assert_eq!(
+        result,
+        ImportChunkResult::UnexpectedTrie(Blake2bHash::new(&tampered_chunk.tries()[0]))
+    );
+    assert_eq!(expected, vec![root_hash].into_iter().collect());
+    assert_eq!(
+        missing::<_, _, in_memory::Error>(&destination.environment, &destination.store, &root_hash)
+            .unwrap(),
+        vec![root_hash]
+    );
+}
+
+#[test]
+fn export_of_missing_trie_returns_trie_not_found() {
+    let correlation_id = CorrelationId::new();
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    let missing_root: Blake2bHash = [1u8; 32].into();
+
+    let txn = context.environment.create_read_txn().unwrap();
+    let result = export_chunk::<TestKey, TestValue, _, _, in_memory::Error>(
+        correlation_id,
+        &txn,
+        &context.store,
+        &mut vec![missing_root],
+        TEST_CHUNK_SIZE,
+    )
+    .unwrap();
+    assert_eq!(result, ExportChunkResult::TrieNotFound(missing_root));
+}
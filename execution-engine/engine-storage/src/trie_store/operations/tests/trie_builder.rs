@@ -0,0 +1,118 @@
+use super::*;
+use crate::trie_store::operations::{AppendLeafResult, TrieBuilder};
+
+/// Returns the first `num_leaves` test leaves in ascending order of their keys.
+fn sorted_test_leaves(num_leaves: usize) -> Vec<(TestKey, TestValue)> {
+    let mut leaves: Vec<(TestKey, TestValue)> = TEST_LEAVES[..num_leaves]
+        .iter()
+        .map(|leaf| match leaf {
+            Trie::Leaf { key, value } => (*key, *value),
+            _ => panic!("should be a leaf"),
+        })
+        .collect();
+    leaves.sort_by_key(|(key, _)| *key);
+    leaves
+}
+
+/// Builds the trie of each generator from its leaves in ascending order of their keys, and checks
+/// that exactly the generator's tries were written.
+fn build_writes_only_final_tries<'a, R, S, E>(environments: Vec<(&'a R, &S)>) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    for ((environment, store), (num_leaves, generator)) in environments
+        .into_iter()
+        .zip(TEST_TRIE_GENERATORS.iter().enumerate())
+    {
+        let (expected_root, expected_tries) = generator()?;
+        let mut txn = environment.create_read_write_txn()?;
+        let mut builder = TrieBuilder::new();
+        for (key, value) in sorted_test_leaves(num_leaves) {
+            let result = builder.append::<_, _, E>(&mut txn, store, key, value)?;
+            assert_eq!(result, AppendLeafResult::Appended);
+        }
+        let root = builder.finish::<_, _, E>(&mut txn, store)?;
+        txn.commit()?;
+
+        assert_eq!(root, expected_root);
+        let expected: HashSet<Blake2bHash> = expected_tries.iter().map(|trie| trie.hash).collect();
+        assert_eq!(
+            stored_hashes::<TestKey, TestValue, _, _, E>(environment, store)?,
+            expected
+        );
+    }
+    Ok(())
+}
+
+/// Appends a leaf whose key is not greater than the previous one, and checks that it is refused
+/// without affecting the trie.
+fn build_rejects_leaves_out_of_order<'a, R, S, E>(environment: &'a R, store: &S) -> Result<(), E>
+where
+    R: TransactionSource<'a, Handle = S::Handle>,
+    S: TrieStore<TestKey, TestValue>,
+    S::Error: From<R::Error>,
+    E: From<R::Error> + From<S::Error> + From<types::bytesrepr::Error>,
+{
+    let (expected_root, _) = TEST_TRIE_GENERATORS[2]()?;
+    let leaves = sorted_test_leaves(2);
+
+    let mut txn = environment.create_read_write_txn()?;
+    let mut builder = TrieBuilder::new();
+    for (key, value) in &leaves {
+        let result = builder.append::<_, _, E>(&mut txn, store, *key, *value)?;
+        assert_eq!(result, AppendLeafResult::Appended);
+    }
+    for (key, value) in &leaves {
+        let result = builder.append::<_, _, E>(&mut txn, store, *key, *value)?;
+        assert_eq!(result, AppendLeafResult::NotAscending);
+    }
+    let root = builder.finish::<_, _, E>(&mut txn, store)?;
+    txn.commit()?;
+
+    assert_eq!(root, expected_root);
+    Ok(())
+}
+
+#[test]
+fn lmdb_build_writes_only_final_tries() {
+    let contexts: Vec<LmdbTestContext> = (0..TEST_TRIE_GENERATORS_LENGTH)
+        .map(|_| LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap())
+        .collect();
+    let environments = contexts
+        .iter()
+        .map(|context| (&context.environment, &context.store))
+        .collect();
+    build_writes_only_final_tries::<_, _, error::Error>(environments).unwrap();
+}
+
+#[test]
+fn in_memory_build_writes_only_final_tries() {
+    let contexts: Vec<InMemoryTestContext> = (0..TEST_TRIE_GENERATORS_LENGTH)
+        .map(|_| InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap())
+        .collect();
+    let environments = contexts
+        .iter()
+        .map(|context| (&context.environment, &context.store))
+        .collect();
+    build_writes_only_final_tries::<_, _, in_memory::Error>(environments).unwrap();
+}
+
+#[test]
+fn lmdb_build_rejects_leaves_out_of_order() {
+    let context = LmdbTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    build_rejects_leaves_out_of_order::<_, _, error::Error>(&context.environment, &context.store)
+        .unwrap();
+}
+
+#[test]
+fn in_memory_build_rejects_leaves_out_of_order() {
+    let context = InMemoryTestContext::new(EMPTY_HASHED_TEST_TRIES).unwrap();
+    build_rejects_leaves_out_of_order::<_, _, in_memory::Error>(
+        &context.environment,
+        &context.store,
+    )
+    .unwrap();
+}
@@ -0,0 +1,38 @@
+//! A store for marking the trie elements which are reachable from the roots kept by a prune.
+//!
+//! The marks live in their own database in the same environment as the trie store, so the set of
+//! reachable elements does not have to fit in memory.
+use std::marker::PhantomData;
+
+use engine_shared::newtypes::Blake2bHash;
+use types::bytesrepr;
+
+use crate::store::Store;
+
+/// The name of the database holding the marks.
+pub const NAME: &str = "TRIE_STORE_PRUNE_MARKS";
+
+/// A set of trie element hashes, stored under the given handle.
+pub struct PruneMarkStore<H, E> {
+    handle: H,
+    _error: PhantomData<E>,
+}
+
+impl<H, E> PruneMarkStore<H, E> {
+    pub fn new(handle: H) -> Self {
+        PruneMarkStore {
+            handle,
+            _error: PhantomData,
+        }
+    }
+}
+
+impl<H: Clone, E: From<bytesrepr::Error>> Store<Blake2bHash, ()> for PruneMarkStore<H, E> {
+    type Error = E;
+
+    type Handle = H;
+
+    fn handle(&self) -> Self::Handle {
+        self.handle.clone()
+    }
+}
@@ -1,5 +1,7 @@
 use std::{collections::BTreeSet, path::Path};
 
+use rand::Rng;
+
 use engine_core::{
     engine_state::{deploy_item::DeployItem, executable_deploy_item::ExecutableDeployItem},
     DeployHash,
@@ -18,7 +20,9 @@ struct DeployItemData {
     pub session_code: Option<ExecutableDeployItem>,
     pub gas_price: u64,
     pub authorization_keys: BTreeSet<AccountHash>,
-    pub deploy_hash: DeployHash,
+    pub deploy_hash: Option<DeployHash>,
+    pub timestamp: u64,
+    pub ttl_millis: u32,
 }
 
 pub struct DeployItemBuilder {
@@ -211,7 +215,17 @@ impl DeployItemBuilder {
     }
 
     pub fn with_deploy_hash(mut self, hash: [u8; 32]) -> Self {
-        self.deploy_item.deploy_hash = hash;
+        self.deploy_item.deploy_hash = Some(hash);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.deploy_item.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_ttl_millis(mut self, ttl_millis: u32) -> Self {
+        self.deploy_item.ttl_millis = ttl_millis;
         self
     }
 
@@ -231,7 +245,13 @@ impl DeployItemBuilder {
                 .expect("should have payment code"),
             gas_price: self.deploy_item.gas_price,
             authorization_keys: self.deploy_item.authorization_keys,
-            deploy_hash: self.deploy_item.deploy_hash,
+            // Deploys can't be executed twice, so each one gets a distinct hash unless specified
+            deploy_hash: self
+                .deploy_item
+                .deploy_hash
+                .unwrap_or_else(|| rand::thread_rng().gen()),
+            timestamp: self.deploy_item.timestamp,
+            ttl_millis: self.deploy_item.ttl_millis,
         }
     }
 
@@ -46,6 +46,11 @@ impl ExecuteRequestBuilder {
         self
     }
 
+    pub fn with_chained(mut self, chained: bool) -> Self {
+        self.execute_request.chained = chained;
+        self
+    }
+
     pub fn build(self) -> ExecuteRequest {
         self.execute_request
     }
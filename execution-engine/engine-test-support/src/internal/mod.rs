@@ -13,7 +13,9 @@ use engine_core::engine_state::{
     genesis::{ExecConfig, GenesisAccount, GenesisConfig},
     run_genesis_request::RunGenesisRequest,
 };
-use engine_shared::{motes::Motes, newtypes::Blake2bHash, test_utils};
+use engine_shared::{
+    host_function_costs::HostFunctionCosts, motes::Motes, newtypes::Blake2bHash, test_utils,
+};
 use engine_wasm_prep::wasm_costs::WasmCosts;
 use types::{account::AccountHash, ProtocolVersion, U512};
 
@@ -54,6 +56,7 @@ lazy_static! {
     pub static ref DEFAULT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::V1_0_0;
     pub static ref DEFAULT_PAYMENT: U512 = 100_000_000.into();
     pub static ref DEFAULT_WASM_COSTS: WasmCosts = test_utils::wasm_costs_mock();
+    pub static ref DEFAULT_HOST_FUNCTION_COSTS: HostFunctionCosts = HostFunctionCosts::default();
     pub static ref DEFAULT_EXEC_CONFIG: ExecConfig = {
         let mint_installer_bytes;
         let pos_installer_bytes;
@@ -69,6 +72,7 @@ lazy_static! {
             standard_payment_installer_bytes,
             DEFAULT_ACCOUNTS.clone(),
             *DEFAULT_WASM_COSTS,
+            *DEFAULT_HOST_FUNCTION_COSTS,
         )
     };
     pub static ref DEFAULT_GENESIS_CONFIG: GenesisConfig = {
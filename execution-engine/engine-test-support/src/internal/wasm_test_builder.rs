@@ -15,14 +15,15 @@ use log::LevelFilter;
 use engine_core::{
     engine_state::{
         execute_request::ExecuteRequest, execution_result::ExecutionResult,
-        run_genesis_request::RunGenesisRequest, EngineConfig, EngineState, SYSTEM_ACCOUNT_ADDR,
+        execution_trace::ExecutionTrace, run_genesis_request::RunGenesisRequest, EngineConfig,
+        EngineState, SYSTEM_ACCOUNT_ADDR,
     },
     execution,
 };
 use engine_grpc_server::engine_server::{
     ipc::{
-        CommitRequest, CommitResponse, GenesisResponse, QueryRequest, UpgradeRequest,
-        UpgradeResponse,
+        BidStateRequest, BidStateResponse, CommitRequest, CommitResponse, GenesisResponse,
+        QueryRequest, UpgradeRequest, UpgradeResponse,
     },
     ipc_grpc::ExecutionEngineService,
     mappings::{MappingError, TransformMap},
@@ -50,7 +51,7 @@ use types::{
     CLValue, Contract, ContractHash, ContractWasm, Key, URef, U512,
 };
 
-use crate::internal::utils;
+use crate::internal::{utils, DEFAULT_PROTOCOL_VERSION};
 
 /// LMDB initial map size is calculated based on DEFAULT_LMDB_PAGES and systems page size.
 ///
@@ -286,7 +287,7 @@ impl LmdbWasmTestBuilder {
 
 impl<S> WasmTestBuilder<S>
 where
-    S: StateProvider,
+    S: StateProvider + Sync,
     S::Error: Into<execution::Error>,
     EngineState<S>: ExecutionEngineService,
 {
@@ -380,6 +381,21 @@ where
         bytesrepr::deserialize(query_response.take_success()).map_err(|err| format!("{}", err))
     }
 
+    /// Reads the validator bids through the `bid_state` endpoint at `maybe_post_state`, or at the
+    /// latest post-state hash if `None`.
+    pub fn get_bid_state(&self, maybe_post_state: Option<Vec<u8>>) -> BidStateResponse {
+        let post_state = maybe_post_state
+            .or_else(|| self.post_state_hash.clone())
+            .expect("builder must have a post-state hash");
+
+        let bid_state_request = create_bid_state_request(post_state);
+
+        self.engine_state
+            .bid_state(RequestOptions::new(), bid_state_request)
+            .wait_drop_metadata()
+            .expect("should get bid state response")
+    }
+
     pub fn exec(&mut self, mut exec_request: ExecuteRequest) -> &mut Self {
         let exec_request = {
             let hash = self
@@ -667,6 +683,17 @@ where
         exec_result.cost()
     }
 
+    /// Returns the host calls made by the first deploy of the last exec request.  This is empty
+    /// unless the builder's engine config enables tracing.
+    pub fn last_exec_trace(&self) -> ExecutionTrace {
+        let exec_response = self
+            .exec_responses
+            .last()
+            .expect("Expected to be called after run()");
+        let exec_result = exec_response.get(0).expect("should have result");
+        exec_result.trace().clone()
+    }
+
     pub fn exec_error_message(&self, index: usize) -> Option<String> {
         let response = self.get_exec_response(index)?;
         Some(utils::get_error_message(response))
@@ -690,6 +717,15 @@ fn create_query_request(post_state: Vec<u8>, base_key: Key, path: Vec<String>) -
     query_request
 }
 
+fn create_bid_state_request(post_state: Vec<u8>) -> BidStateRequest {
+    let mut bid_state_request = BidStateRequest::new();
+
+    bid_state_request.set_parent_state_hash(post_state);
+    bid_state_request.set_protocol_version((*DEFAULT_PROTOCOL_VERSION).into());
+
+    bid_state_request
+}
+
 #[allow(clippy::implicit_hasher)]
 fn create_commit_request(
     prestate_hash: &[u8],
@@ -0,0 +1,100 @@
+use engine_core::engine_state::{
+    deploy_item::DeployItem, execute_request::ExecuteRequest, execution_effect::ExecutionEffect,
+};
+use engine_test_support::{
+    internal::{
+        DeployItemBuilder, ExecuteRequestBuilder, InMemoryWasmTestBuilder,
+        DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{account::AccountHash, runtime_args, RuntimeArgs, U512};
+
+const ACCOUNT_1_ADDR: AccountHash = AccountHash::new([1u8; 32]);
+const ACCOUNT_2_ADDR: AccountHash = AccountHash::new([2u8; 32]);
+const ARG_TARGET: &str = "target";
+const ARG_AMOUNT: &str = "amount";
+const ACCOUNT_1_FUNDING: u64 = 1_000_000;
+const ACCOUNT_2_FUNDING: u64 = 1000;
+
+fn transfer(source: AccountHash, target: AccountHash, amount: u64, index: u8) -> DeployItem {
+    DeployItemBuilder::new()
+        .with_address(source)
+        .with_empty_payment_bytes(runtime_args! {})
+        .with_transfer_args(runtime_args! {
+            ARG_TARGET => target,
+            ARG_AMOUNT => U512::from(amount),
+        })
+        .with_authorization_keys(&[source])
+        .with_deploy_hash([index; 32])
+        .build()
+}
+
+/// An exec request which creates account 1, and then transfers from it to account 2.
+fn dependent_transfers(chained: bool) -> ExecuteRequest {
+    ExecuteRequestBuilder::new()
+        .push_deploy(transfer(
+            DEFAULT_ACCOUNT_ADDR,
+            ACCOUNT_1_ADDR,
+            ACCOUNT_1_FUNDING,
+            0,
+        ))
+        .push_deploy(transfer(
+            ACCOUNT_1_ADDR,
+            ACCOUNT_2_ADDR,
+            ACCOUNT_2_FUNDING,
+            1,
+        ))
+        .with_chained(chained)
+        .build()
+}
+
+#[ignore]
+#[test]
+fn should_not_see_effects_of_previous_deploys_when_not_chained() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder
+        .run_genesis(&DEFAULT_RUN_GENESIS_REQUEST)
+        .exec(dependent_transfers(false));
+
+    let results = builder.get_exec_response(0).expect("should have results");
+    assert_eq!(results.len(), 2);
+    assert!(!results[0].is_failure());
+    assert!(results[1].is_failure());
+}
+
+#[ignore]
+#[test]
+fn should_see_effects_of_previous_deploys_when_chained() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder
+        .run_genesis(&DEFAULT_RUN_GENESIS_REQUEST)
+        .exec(dependent_transfers(true));
+
+    let results = builder.get_exec_response(0).expect("should have results");
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| !result.is_failure()));
+
+    let cumulative_effect = results
+        .iter()
+        .map(|result| result.effect().clone())
+        .fold(ExecutionEffect::default(), ExecutionEffect::then);
+
+    let prestate_hash = builder.get_post_state_hash();
+    builder.commit_effects(prestate_hash, cumulative_effect.transforms);
+
+    let account_1 = builder
+        .get_account(ACCOUNT_1_ADDR)
+        .expect("should have account 1");
+    let account_2 = builder
+        .get_account(ACCOUNT_2_ADDR)
+        .expect("should have account 2");
+    assert_eq!(
+        builder.get_purse_balance(account_1.main_purse()),
+        U512::from(ACCOUNT_1_FUNDING - ACCOUNT_2_FUNDING)
+    );
+    assert_eq!(
+        builder.get_purse_balance(account_2.main_purse()),
+        U512::from(ACCOUNT_2_FUNDING)
+    );
+}
@@ -0,0 +1,163 @@
+use engine_core::engine_state::execution_effect::Event;
+use engine_test_support::{
+    internal::{ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_RUN_GENESIS_REQUEST},
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, CLValue, RuntimeArgs};
+
+const CONTRACT_CRYPTO: &str = "crypto.wasm";
+const ARG_ALGORITHM: &str = "algorithm";
+const ARG_PUBLIC_KEY: &str = "public_key";
+const ARG_MESSAGE: &str = "message";
+const ARG_SIGNATURE: &str = "signature";
+const ALGORITHM_ED25519: &str = "ed25519";
+const ALGORITHM_SECP256K1: &str = "secp256k1";
+const EVENT_BLAKE2B: &str = "blake2b";
+const EVENT_SHA256: &str = "sha256";
+const EVENT_VALID: &str = "valid";
+
+const MESSAGE: &[u8] = b"Hello, world!";
+const MESSAGE_BLAKE2B: &str = "b5da441cfe72ae042ef4d2b17742907f675de4da57462d4c3609c2e2ed755970";
+const MESSAGE_SHA256: &str = "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3";
+
+// Test 1 from RFC 8032, section 7.1, which signs the empty message
+const ED25519_PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+const ED25519_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+// Compressed public key for the secret key `[1; 32]`, and its compact signature of `MESSAGE`
+const SECP256K1_PUBLIC_KEY: &str =
+    "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f";
+const SECP256K1_SIGNATURE: &str = "b00dc313ff492d2d6c404245fa4486a0b3faed7f1ec565026bd18e0db8eb8a387867249e21c6971a1e38fa2549ca21a61afd72c0a3fcdf3830960f301be0063f";
+
+fn decode(hex: &str) -> Vec<u8> {
+    base16::decode(hex).expect("should decode hex")
+}
+
+/// Runs the crypto contract, and returns the events it emitted.
+fn run_crypto(
+    algorithm: &str,
+    public_key: Vec<u8>,
+    message: Vec<u8>,
+    signature: Vec<u8>,
+) -> Vec<Event> {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_CRYPTO,
+        runtime_args! {
+            ARG_ALGORITHM => algorithm.to_string(),
+            ARG_PUBLIC_KEY => public_key,
+            ARG_MESSAGE => message,
+            ARG_SIGNATURE => signature,
+        },
+    )
+    .build();
+    builder
+        .run_genesis(&DEFAULT_RUN_GENESIS_REQUEST)
+        .exec(exec_request)
+        .expect_success()
+        .commit();
+
+    let response = builder.get_exec_response(0).expect("should have response");
+    response[0].effect().events.clone()
+}
+
+fn event_value(events: &[Event], name: &str) -> CLValue {
+    events
+        .iter()
+        .find(|event| event.name == name)
+        .unwrap_or_else(|| panic!("should have emitted {}", name))
+        .value
+        .clone()
+}
+
+fn is_valid(events: &[Event]) -> bool {
+    event_value(events, EVENT_VALID)
+        .into_t()
+        .expect("should be bool")
+}
+
+#[ignore]
+#[test]
+fn should_hash_with_blake2b_and_sha256() {
+    let events = run_crypto(
+        ALGORITHM_SECP256K1,
+        decode(SECP256K1_PUBLIC_KEY),
+        MESSAGE.to_vec(),
+        decode(SECP256K1_SIGNATURE),
+    );
+
+    let blake2b: [u8; 32] = event_value(&events, EVENT_BLAKE2B)
+        .into_t()
+        .expect("should be digest");
+    assert_eq!(blake2b.to_vec(), decode(MESSAGE_BLAKE2B));
+
+    let sha256: [u8; 32] = event_value(&events, EVENT_SHA256)
+        .into_t()
+        .expect("should be digest");
+    assert_eq!(sha256.to_vec(), decode(MESSAGE_SHA256));
+}
+
+#[ignore]
+#[test]
+fn should_verify_ed25519_signature() {
+    let events = run_crypto(
+        ALGORITHM_ED25519,
+        decode(ED25519_PUBLIC_KEY),
+        Vec::new(),
+        decode(ED25519_SIGNATURE),
+    );
+    assert!(is_valid(&events));
+}
+
+#[ignore]
+#[test]
+fn should_reject_invalid_ed25519_signature() {
+    let events = run_crypto(
+        ALGORITHM_ED25519,
+        decode(ED25519_PUBLIC_KEY),
+        MESSAGE.to_vec(),
+        decode(ED25519_SIGNATURE),
+    );
+    assert!(!is_valid(&events));
+
+    let events = run_crypto(
+        ALGORITHM_ED25519,
+        decode(ED25519_PUBLIC_KEY),
+        Vec::new(),
+        vec![0; 3],
+    );
+    assert!(!is_valid(&events));
+}
+
+#[ignore]
+#[test]
+fn should_verify_secp256k1_signature() {
+    let events = run_crypto(
+        ALGORITHM_SECP256K1,
+        decode(SECP256K1_PUBLIC_KEY),
+        MESSAGE.to_vec(),
+        decode(SECP256K1_SIGNATURE),
+    );
+    assert!(is_valid(&events));
+}
+
+#[ignore]
+#[test]
+fn should_reject_invalid_secp256k1_signature() {
+    let events = run_crypto(
+        ALGORITHM_SECP256K1,
+        decode(SECP256K1_PUBLIC_KEY),
+        Vec::new(),
+        decode(SECP256K1_SIGNATURE),
+    );
+    assert!(!is_valid(&events));
+
+    let events = run_crypto(
+        ALGORITHM_SECP256K1,
+        decode(ED25519_PUBLIC_KEY),
+        MESSAGE.to_vec(),
+        decode(SECP256K1_SIGNATURE),
+    );
+    assert!(!is_valid(&events));
+}
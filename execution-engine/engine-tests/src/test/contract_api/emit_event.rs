@@ -0,0 +1,76 @@
+use engine_core::engine_state::execution_effect::Event;
+use engine_test_support::{
+    internal::{
+        ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_HOST_FUNCTION_COSTS,
+        DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, CLValue, RuntimeArgs};
+
+const CONTRACT_EMIT_EVENT: &str = "emit_event.wasm";
+const ARG_VALUE: &str = "value";
+const ARG_REVERT: &str = "revert";
+const EVENT_STARTED: &str = "started";
+const EVENT_VALUE: &str = "value";
+
+fn emit_events(builder: &mut InMemoryWasmTestBuilder, value: &str, revert: bool) {
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_EMIT_EVENT,
+        runtime_args! { ARG_VALUE => value.to_string(), ARG_REVERT => revert },
+    )
+    .build();
+    builder.exec(exec_request);
+}
+
+#[ignore]
+#[test]
+fn should_return_emitted_events_in_order() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+    emit_events(&mut builder, "Hello, world!", false);
+    builder.expect_success();
+
+    let response = builder.get_exec_response(0).expect("should have response");
+    let expected_events = vec![
+        Event::new(EVENT_STARTED.to_string(), CLValue::from_t(()).unwrap()),
+        Event::new(
+            EVENT_VALUE.to_string(),
+            CLValue::from_t("Hello, world!".to_string()).unwrap(),
+        ),
+    ];
+    assert_eq!(response[0].effect().events, expected_events);
+}
+
+#[ignore]
+#[test]
+fn should_discard_events_of_reverted_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+    emit_events(&mut builder, "Hello, world!", true);
+
+    let response = builder.get_exec_response(0).expect("should have response");
+    assert!(response[0].is_failure());
+    assert!(response[0].effect().events.is_empty());
+}
+
+#[ignore]
+#[test]
+fn should_charge_for_event_size() {
+    let short_value = "a";
+    let long_value = "a".repeat(1000);
+
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+    emit_events(&mut builder, short_value, false);
+    builder.expect_success();
+    let short_cost = builder.last_exec_gas_cost();
+    emit_events(&mut builder, &long_value, false);
+    builder.expect_success();
+    let long_cost = builder.last_exec_gas_cost();
+
+    let extra_bytes = (long_value.len() - short_value.len()) as u64;
+    let event_gas_per_byte = u64::from(DEFAULT_HOST_FUNCTION_COSTS.emit_event.per_byte);
+    assert!(long_cost.value() >= short_cost.value() + extra_bytes * event_gas_per_byte);
+}
@@ -1,5 +1,7 @@
 mod account;
 mod create_purse;
+mod crypto;
+mod emit_event;
 mod get_arg;
 mod get_blocktime;
 mod get_caller;
@@ -8,6 +10,7 @@ mod list_named_keys;
 mod main_purse;
 mod mint_purse;
 mod revert;
+mod storage;
 mod subcall;
 mod transfer;
 mod transfer_purse_to_account;
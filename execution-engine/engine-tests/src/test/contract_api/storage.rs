@@ -0,0 +1,80 @@
+use engine_test_support::{
+    internal::{
+        ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_RUN_GENESIS_REQUEST,
+        DEFAULT_WASM_COSTS,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, RuntimeArgs};
+
+const CONTRACT_WRITE_BYTES: &str = "write_bytes.wasm";
+const CONTRACT_ADD_NAMED_KEY: &str = "add_named_key.wasm";
+const ARG_VALUE: &str = "value";
+const ARG_NAME: &str = "name";
+
+fn write_bytes(builder: &mut InMemoryWasmTestBuilder, value: Vec<u8>) {
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_WRITE_BYTES,
+        runtime_args! { ARG_VALUE => value },
+    )
+    .build();
+    builder.exec(exec_request).commit();
+}
+
+fn add_named_key(builder: &mut InMemoryWasmTestBuilder, name: String) {
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_ADD_NAMED_KEY,
+        runtime_args! { ARG_NAME => name },
+    )
+    .build();
+    builder.exec(exec_request).commit();
+}
+
+#[ignore]
+#[test]
+fn should_charge_for_size_of_written_values() {
+    let short_value = vec![1u8];
+    let long_value = vec![1u8; 10_000];
+
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+    write_bytes(&mut builder, short_value.clone());
+    builder.expect_success();
+    let short_cost = builder.last_exec_gas_cost();
+    write_bytes(&mut builder, long_value.clone());
+    builder.expect_success();
+    let long_cost = builder.last_exec_gas_cost();
+
+    // The value is written twice, once by `new_uref` and once by `write`, but only the first write
+    // grows global state
+    let extra_bytes = (long_value.len() - short_value.len()) as u64;
+    let storage_gas_per_byte = u64::from(DEFAULT_WASM_COSTS.storage_per_byte);
+    assert!(long_cost.value() >= short_cost.value() + extra_bytes * storage_gas_per_byte);
+}
+
+#[ignore]
+#[test]
+fn should_charge_for_named_keys_added_to_an_account() {
+    let short_name = "a".to_string();
+    let long_name = "b".repeat(10_000);
+
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+    add_named_key(&mut builder, short_name.clone());
+    builder.expect_success();
+    let short_cost = builder.last_exec_gas_cost();
+    add_named_key(&mut builder, long_name.clone());
+    builder.expect_success();
+    let long_cost = builder.last_exec_gas_cost();
+
+    let account = builder
+        .get_account(DEFAULT_ACCOUNT_ADDR)
+        .expect("should have account");
+    assert!(account.named_keys().contains_key(&long_name));
+
+    let extra_bytes = (long_name.len() - short_name.len()) as u64;
+    let storage_gas_per_byte = u64::from(DEFAULT_WASM_COSTS.storage_per_byte);
+    assert!(long_cost.value() >= short_cost.value() + extra_bytes * storage_gas_per_byte);
+}
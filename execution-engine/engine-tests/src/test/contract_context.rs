@@ -62,7 +62,7 @@ fn should_enforce_intended_execution_contexts() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -80,7 +80,7 @@ fn should_enforce_intended_execution_contexts() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([4; 32])
+            .with_deploy_hash([5; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -164,7 +164,7 @@ fn should_enforce_intended_execution_context_direct_by_name() {
             .with_stored_session_named_key(CONTRACT_HASH_KEY, CONTRACT_CODE_TEST, args)
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -177,7 +177,7 @@ fn should_enforce_intended_execution_context_direct_by_name() {
             .with_stored_session_named_key(CONTRACT_HASH_KEY, ADD_NEW_KEY_AS_SESSION, args)
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([4; 32])
+            .with_deploy_hash([5; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -267,7 +267,7 @@ fn should_enforce_intended_execution_context_direct_by_hash() {
             .with_stored_session_hash(contract_hash, CONTRACT_CODE_TEST, args)
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -280,7 +280,7 @@ fn should_enforce_intended_execution_context_direct_by_hash() {
             .with_stored_session_hash(contract_hash, ADD_NEW_KEY_AS_SESSION, args)
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([4; 32])
+            .with_deploy_hash([5; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
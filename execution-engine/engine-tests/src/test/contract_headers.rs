@@ -56,7 +56,7 @@ fn should_enforce_intended_execution_contexts() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -74,7 +74,7 @@ fn should_enforce_intended_execution_contexts() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT, })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([4; 32])
+            .with_deploy_hash([5; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
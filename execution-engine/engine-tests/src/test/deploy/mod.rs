@@ -1,3 +1,4 @@
 mod non_standard_payment;
 mod preconditions;
+mod replay_protection;
 mod stored_contracts;
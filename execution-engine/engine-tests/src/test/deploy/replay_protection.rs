@@ -0,0 +1,220 @@
+use assert_matches::assert_matches;
+
+use engine_core::engine_state::{
+    deploy_item::DeployItem, engine_config::DEFAULT_MAX_TTL_MILLIS, Error,
+};
+use engine_test_support::{
+    internal::{
+        utils, DeployItemBuilder, ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_PAYMENT,
+        DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{account::AccountHash, runtime_args, RuntimeArgs, U512};
+
+const ACCOUNT_1_ADDR: AccountHash = AccountHash::new([42u8; 32]);
+const DO_NOTHING_WASM: &str = "do_nothing.wasm";
+const REVERT_WASM: &str = "revert.wasm";
+const ARG_AMOUNT: &str = "amount";
+const ARG_TARGET: &str = "target";
+const DEPLOY_HASH: [u8; 32] = [1; 32];
+const TIMESTAMP: u64 = 1_000;
+const TTL_MILLIS: u32 = 500;
+
+fn session_deploy(session_file: &str, deploy_hash: [u8; 32]) -> DeployItemBuilder {
+    DeployItemBuilder::new()
+        .with_address(DEFAULT_ACCOUNT_ADDR)
+        .with_session_code(session_file, RuntimeArgs::default())
+        .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT })
+        .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
+        .with_deploy_hash(deploy_hash)
+}
+
+fn transfer_deploy(deploy_hash: [u8; 32]) -> DeployItemBuilder {
+    DeployItemBuilder::new()
+        .with_address(DEFAULT_ACCOUNT_ADDR)
+        .with_empty_payment_bytes(runtime_args! {})
+        .with_transfer_args(runtime_args! {
+            ARG_TARGET => ACCOUNT_1_ADDR,
+            ARG_AMOUNT => U512::from(1_000_000),
+        })
+        .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
+        .with_deploy_hash(deploy_hash)
+}
+
+fn exec(builder: &mut InMemoryWasmTestBuilder, deploy: DeployItem, block_time: u64) {
+    let exec_request = ExecuteRequestBuilder::new()
+        .push_deploy(deploy)
+        .with_block_time(block_time)
+        .build();
+    builder.exec(exec_request).commit();
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_replayed_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    exec(
+        &mut builder,
+        session_deploy(DO_NOTHING_WASM, DEPLOY_HASH).build(),
+        0,
+    );
+    builder.expect_success();
+
+    exec(
+        &mut builder,
+        session_deploy(DO_NOTHING_WASM, DEPLOY_HASH).build(),
+        0,
+    );
+    let response = builder.get_exec_response(1).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployReplay);
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_replay_within_one_request() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let exec_request = ExecuteRequestBuilder::new()
+        .push_deploy(session_deploy(DO_NOTHING_WASM, DEPLOY_HASH).build())
+        .push_deploy(session_deploy(DO_NOTHING_WASM, DEPLOY_HASH).build())
+        .build();
+    builder.exec(exec_request).commit();
+
+    let response = builder.get_exec_response(0).expect("should have response");
+    assert_eq!(response.len(), 2);
+    assert!(response[0].as_error().is_none());
+    let precondition_failure = utils::get_precondition_failure(&response[1..]);
+    assert_matches!(precondition_failure, Error::DeployReplay);
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_replay_of_failed_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    // The deploy was paid for, so it can't be executed again even though its session failed
+    exec(
+        &mut builder,
+        session_deploy(REVERT_WASM, DEPLOY_HASH).build(),
+        0,
+    );
+    let response = builder.get_exec_response(0).expect("should have response");
+    assert!(response[0].is_failure());
+    assert!(!response[0].has_precondition_failure());
+
+    exec(
+        &mut builder,
+        session_deploy(REVERT_WASM, DEPLOY_HASH).build(),
+        0,
+    );
+    let response = builder.get_exec_response(1).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployReplay);
+}
+
+#[ignore]
+#[test]
+fn should_execute_deploys_with_distinct_hashes() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    exec(
+        &mut builder,
+        session_deploy(DO_NOTHING_WASM, [1; 32]).build(),
+        0,
+    );
+    builder.expect_success();
+
+    exec(
+        &mut builder,
+        session_deploy(DO_NOTHING_WASM, [2; 32]).build(),
+        0,
+    );
+    builder.expect_success();
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_replayed_transfer() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    exec(&mut builder, transfer_deploy(DEPLOY_HASH).build(), 0);
+    builder.expect_success();
+
+    exec(&mut builder, transfer_deploy(DEPLOY_HASH).build(), 0);
+    let response = builder.get_exec_response(1).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployReplay);
+}
+
+#[ignore]
+#[test]
+fn should_execute_deploy_within_ttl() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let deploy = session_deploy(DO_NOTHING_WASM, DEPLOY_HASH)
+        .with_timestamp(TIMESTAMP)
+        .with_ttl_millis(TTL_MILLIS)
+        .build();
+    exec(&mut builder, deploy, TIMESTAMP + u64::from(TTL_MILLIS));
+    builder.expect_success();
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_expired_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let deploy = session_deploy(DO_NOTHING_WASM, DEPLOY_HASH)
+        .with_timestamp(TIMESTAMP)
+        .with_ttl_millis(TTL_MILLIS)
+        .build();
+    exec(&mut builder, deploy, TIMESTAMP + u64::from(TTL_MILLIS) + 1);
+    let response = builder.get_exec_response(0).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployExpired);
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_expired_transfer() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let deploy = transfer_deploy(DEPLOY_HASH)
+        .with_timestamp(TIMESTAMP)
+        .with_ttl_millis(TTL_MILLIS)
+        .build();
+    exec(&mut builder, deploy, TIMESTAMP + u64::from(TTL_MILLIS) + 1);
+    let response = builder.get_exec_response(0).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployExpired);
+}
+
+#[ignore]
+#[test]
+fn should_raise_precondition_failure_for_deploy_without_ttl_after_max_ttl() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let deploy = session_deploy(DO_NOTHING_WASM, DEPLOY_HASH)
+        .with_timestamp(TIMESTAMP)
+        .build();
+    exec(
+        &mut builder,
+        deploy,
+        TIMESTAMP + u64::from(DEFAULT_MAX_TTL_MILLIS) + 1,
+    );
+    let response = builder.get_exec_response(0).expect("should have response");
+    let precondition_failure = utils::get_precondition_failure(response);
+    assert_matches!(precondition_failure, Error::DeployExpired);
+}
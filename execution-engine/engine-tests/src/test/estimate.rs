@@ -0,0 +1,164 @@
+use std::convert::TryFrom;
+
+use engine_core::{
+    engine_state::{
+        deploy_item::DeployItem,
+        estimate::{EstimateRequest, EstimateResult},
+        EngineConfig, Error,
+    },
+    execution,
+};
+use engine_shared::{
+    gas::Gas,
+    newtypes::{Blake2bHash, CorrelationId},
+};
+use engine_storage::global_state::in_memory::InMemoryGlobalState;
+use engine_test_support::{
+    internal::{
+        DeployItemBuilder, ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_BLOCK_TIME,
+        DEFAULT_PAYMENT, DEFAULT_PROTOCOL_VERSION, DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, RuntimeArgs, U512};
+
+const CONTRACT_DO_NOTHING: &str = "do_nothing.wasm";
+const CONTRACT_ENDLESS_LOOP: &str = "endless_loop.wasm";
+const CONTRACT_REVERT: &str = "revert.wasm";
+const ARG_AMOUNT: &str = "amount";
+
+fn deploy(session_code: &str, payment: U512, index: u8) -> DeployItem {
+    DeployItemBuilder::new()
+        .with_address(DEFAULT_ACCOUNT_ADDR)
+        .with_session_code(session_code, runtime_args! {})
+        .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => payment })
+        .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
+        .with_deploy_hash([index; 32])
+        .build()
+}
+
+fn do_nothing_deploy(payment: U512, index: u8) -> DeployItem {
+    deploy(CONTRACT_DO_NOTHING, payment, index)
+}
+
+fn estimate(builder: &InMemoryWasmTestBuilder, deploy_item: DeployItem) -> EstimateResult {
+    let post_state_hash = Blake2bHash::try_from(builder.get_post_state_hash().as_slice())
+        .expect("should have post state hash");
+    let estimate_request = EstimateRequest::new(
+        post_state_hash,
+        DEFAULT_BLOCK_TIME,
+        deploy_item,
+        *DEFAULT_PROTOCOL_VERSION,
+    );
+    builder
+        .get_engine_state()
+        .run_estimate(CorrelationId::new(), estimate_request)
+        .expect("should find the post state")
+}
+
+fn estimate_do_nothing(builder: &InMemoryWasmTestBuilder, payment: U512) -> EstimateResult {
+    estimate(builder, do_nothing_deploy(payment, 0))
+}
+
+#[ignore]
+#[test]
+fn should_estimate_the_minimum_payment_of_a_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    // A payment far too small to run the session code is not taken into account.
+    let first_estimate = estimate_do_nothing(&builder, U512::one());
+    assert!(first_estimate.execution_result().is_success());
+    assert!(first_estimate.payment_cost() > Gas::default());
+    assert!(first_estimate.session_cost() > Gas::default());
+    assert!(first_estimate.finalize_cost() > Gas::default());
+
+    // The estimate does not depend on the amount the deploy happens to pay.
+    let payment = first_estimate
+        .minimum_payment()
+        .expect("should have minimum payment")
+        .value();
+    let estimate = estimate_do_nothing(&builder, payment * 1000);
+    assert!(estimate.execution_result().is_success());
+    assert_eq!(
+        estimate.minimum_payment().map(|motes| motes.value()),
+        Some(payment)
+    );
+
+    // Paying the minimum payment is enough, and costs what was estimated.
+    let exec_request = ExecuteRequestBuilder::new()
+        .push_deploy(do_nothing_deploy(payment, 1))
+        .build();
+    builder.exec(exec_request).expect_success();
+    assert_eq!(
+        builder.last_exec_gas_cost(),
+        estimate.payment_cost() + estimate.session_cost()
+    );
+
+    // Paying any less is not.
+    let exec_request = ExecuteRequestBuilder::new()
+        .push_deploy(do_nothing_deploy(payment - U512::one(), 2))
+        .build();
+    builder.exec(exec_request);
+    let response = builder.get_exec_response(1).expect("should have response");
+    match response[0].as_error() {
+        Some(Error::Exec(execution::Error::GasLimit)) => (),
+        other => panic!("should run out of gas, but got {:?}", other),
+    }
+}
+
+#[ignore]
+#[test]
+fn should_not_report_a_minimum_payment_for_a_failing_deploy() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let estimate = estimate(&builder, deploy(CONTRACT_REVERT, *DEFAULT_PAYMENT, 0));
+
+    assert!(estimate.execution_result().is_failure());
+    assert!(estimate.session_cost() > Gas::default());
+    assert_eq!(estimate.minimum_payment(), None);
+}
+
+#[ignore]
+#[test]
+fn should_stop_a_looping_session_at_the_estimate_gas_limit() {
+    let estimate_gas_limit = 10_000_000;
+    let engine_config = EngineConfig::new()
+        .with_use_system_contracts(cfg!(feature = "use-system-contracts"))
+        .with_enable_bonding(cfg!(feature = "enable-bonding"))
+        .with_estimate_gas_limit(estimate_gas_limit);
+    let global_state = InMemoryGlobalState::empty().expect("should create global state");
+    let empty_root_hash = global_state.empty_root_hash.to_vec();
+    let mut builder = InMemoryWasmTestBuilder::new(global_state, engine_config, empty_root_hash);
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let estimate = estimate(&builder, deploy(CONTRACT_ENDLESS_LOOP, *DEFAULT_PAYMENT, 0));
+
+    match estimate.execution_result().as_error() {
+        Some(Error::Exec(execution::Error::GasLimit)) => (),
+        other => panic!("should run out of gas, but got {:?}", other),
+    }
+    assert!(estimate.session_cost() <= Gas::new(estimate_gas_limit.into()));
+    assert_eq!(estimate.minimum_payment(), None);
+}
+
+#[ignore]
+#[test]
+fn should_not_estimate_against_a_missing_state() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let missing_state_hash = Blake2bHash::new(&[42u8]);
+    let estimate_request = EstimateRequest::new(
+        missing_state_hash,
+        DEFAULT_BLOCK_TIME,
+        do_nothing_deploy(U512::one(), 0),
+        *DEFAULT_PROTOCOL_VERSION,
+    );
+    let result = builder
+        .get_engine_state()
+        .run_estimate(CorrelationId::new(), estimate_request);
+
+    assert!(result.is_err());
+}
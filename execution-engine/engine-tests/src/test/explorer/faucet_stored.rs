@@ -123,7 +123,7 @@ fn should_fail_if_already_funded() {
             )
             .with_empty_payment_bytes(runtime_args! { "amount" => U512::from(10_000_000) })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_KEY])
-            .with_deploy_hash([2; 32])
+            .with_deploy_hash([3; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
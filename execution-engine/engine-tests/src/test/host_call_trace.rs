@@ -0,0 +1,87 @@
+use tempfile::TempDir;
+
+use engine_core::engine_state::EngineConfig;
+use engine_test_support::{
+    internal::{
+        ExecuteRequestBuilder, InMemoryWasmTestBuilder, LmdbWasmTestBuilder,
+        DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, RuntimeArgs};
+
+const CONTRACT_GET_CALLER_SUBCALL: &str = "get_caller_subcall.wasm";
+const ARG_ACCOUNT: &str = "account";
+const HOST_FUNCTION_CALL_CONTRACT: &str = "host_function_call_contract";
+const HOST_FUNCTION_GET_CALLER: &str = "host_function_get_caller";
+
+#[ignore]
+#[test]
+fn should_not_trace_host_calls_by_default() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder
+        .run_genesis(&DEFAULT_RUN_GENESIS_REQUEST)
+        .exec(
+            ExecuteRequestBuilder::standard(
+                DEFAULT_ACCOUNT_ADDR,
+                CONTRACT_GET_CALLER_SUBCALL,
+                runtime_args! { ARG_ACCOUNT => DEFAULT_ACCOUNT_ADDR },
+            )
+            .build(),
+        )
+        .expect_success();
+
+    assert!(builder.last_exec_trace().is_empty());
+}
+
+#[ignore]
+#[test]
+fn should_trace_host_calls_of_session_and_called_contracts() {
+    let data_dir = TempDir::new().expect("should create temp dir");
+    let engine_config = EngineConfig::new()
+        .with_use_system_contracts(cfg!(feature = "use-system-contracts"))
+        .with_enable_bonding(cfg!(feature = "enable-bonding"))
+        .with_trace_host_calls(true);
+    let mut builder = LmdbWasmTestBuilder::new_with_config(data_dir.path(), engine_config);
+    builder
+        .run_genesis(&DEFAULT_RUN_GENESIS_REQUEST)
+        .exec(
+            ExecuteRequestBuilder::standard(
+                DEFAULT_ACCOUNT_ADDR,
+                CONTRACT_GET_CALLER_SUBCALL,
+                runtime_args! { ARG_ACCOUNT => DEFAULT_ACCOUNT_ADDR },
+            )
+            .build(),
+        )
+        .expect_success();
+
+    let trace = builder.last_exec_trace();
+    assert!(trace
+        .iter()
+        .all(|host_call| host_call.gas_before <= host_call.gas_after));
+
+    // The session code asks for its caller itself before calling the stored contract, which asks
+    // again one level deeper.
+    let call_contract_index = trace
+        .iter()
+        .position(|host_call| host_call.name == HOST_FUNCTION_CALL_CONTRACT)
+        .expect("should have traced the contract call");
+    let call_contract = &trace[call_contract_index];
+    assert_eq!(call_contract.call_depth, 0);
+    assert!(trace[..call_contract_index].iter().any(|host_call| {
+        host_call.name == HOST_FUNCTION_GET_CALLER && host_call.call_depth == 0
+    }));
+
+    let nested_calls: Vec<_> = trace[call_contract_index + 1..]
+        .iter()
+        .take_while(|host_call| host_call.call_depth > 0)
+        .collect();
+    assert!(nested_calls
+        .iter()
+        .any(|host_call| host_call.name == HOST_FUNCTION_GET_CALLER));
+    assert!(nested_calls.iter().all(|host_call| {
+        host_call.call_depth == 1
+            && host_call.gas_before >= call_contract.gas_before
+            && host_call.gas_after <= call_contract.gas_after
+    }));
+}
@@ -0,0 +1,83 @@
+use engine_core::engine_state::{genesis::ExecConfig, run_genesis_request::RunGenesisRequest};
+use engine_shared::{gas::Gas, host_function_costs::HostFunctionCosts};
+use engine_test_support::{
+    internal::{
+        utils, ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_ACCOUNTS,
+        DEFAULT_GENESIS_CONFIG_HASH, DEFAULT_HOST_FUNCTION_COSTS, DEFAULT_PROTOCOL_VERSION,
+        DEFAULT_WASM_COSTS, MINT_INSTALL_CONTRACT, POS_INSTALL_CONTRACT,
+        STANDARD_PAYMENT_INSTALL_CONTRACT,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{runtime_args, RuntimeArgs, U512};
+
+const CONTRACT_GET_BLOCKTIME: &str = "get_blocktime.wasm";
+const ARG_KNOWN_BLOCK_TIME: &str = "known_block_time";
+const BLOCK_TIME: u64 = 42;
+const EXTRA_COST: u32 = 1_000_000;
+
+fn run_genesis(host_function_costs: HostFunctionCosts) -> InMemoryWasmTestBuilder {
+    let exec_config = ExecConfig::new(
+        utils::read_wasm_file_bytes(MINT_INSTALL_CONTRACT),
+        utils::read_wasm_file_bytes(POS_INSTALL_CONTRACT),
+        utils::read_wasm_file_bytes(STANDARD_PAYMENT_INSTALL_CONTRACT),
+        DEFAULT_ACCOUNTS.clone(),
+        *DEFAULT_WASM_COSTS,
+        host_function_costs,
+    );
+    let run_genesis_request = RunGenesisRequest::new(
+        *DEFAULT_GENESIS_CONFIG_HASH,
+        *DEFAULT_PROTOCOL_VERSION,
+        exec_config,
+    );
+
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&run_genesis_request);
+    builder
+}
+
+fn get_blocktime_gas_cost(builder: &mut InMemoryWasmTestBuilder) -> Gas {
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_GET_BLOCKTIME,
+        runtime_args! { ARG_KNOWN_BLOCK_TIME => BLOCK_TIME },
+    )
+    .with_block_time(BLOCK_TIME)
+    .build();
+    builder.exec(exec_request).commit().expect_success();
+    builder.last_exec_gas_cost()
+}
+
+#[ignore]
+#[test]
+fn should_charge_base_cost_of_host_function() {
+    let mut default_builder = run_genesis(*DEFAULT_HOST_FUNCTION_COSTS);
+    let default_cost = get_blocktime_gas_cost(&mut default_builder);
+
+    let mut host_function_costs = *DEFAULT_HOST_FUNCTION_COSTS;
+    host_function_costs.get_blocktime.base += EXTRA_COST;
+    let mut expensive_builder = run_genesis(host_function_costs);
+    let expensive_cost = get_blocktime_gas_cost(&mut expensive_builder);
+
+    // The contract calls `get_blocktime` once
+    assert_eq!(
+        expensive_cost,
+        default_cost + Gas::new(U512::from(EXTRA_COST))
+    );
+}
+
+#[ignore]
+#[test]
+fn should_charge_per_byte_cost_of_host_function() {
+    let mut default_builder = run_genesis(*DEFAULT_HOST_FUNCTION_COSTS);
+    let default_cost = get_blocktime_gas_cost(&mut default_builder);
+
+    let mut host_function_costs = *DEFAULT_HOST_FUNCTION_COSTS;
+    host_function_costs.get_named_arg_size.per_byte += EXTRA_COST;
+    let mut expensive_builder = run_genesis(host_function_costs);
+    let expensive_cost = get_blocktime_gas_cost(&mut expensive_builder);
+
+    // The contract reads one named argument, so pays at least for the bytes of its name
+    let extra_cost = Gas::new(U512::from(EXTRA_COST) * U512::from(ARG_KNOWN_BLOCK_TIME.len()));
+    assert!(expensive_cost >= default_cost + extra_cost);
+}
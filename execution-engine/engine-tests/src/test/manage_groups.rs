@@ -121,7 +121,7 @@ fn should_create_and_remove_group() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -341,7 +341,7 @@ fn should_create_and_remove_urefs_from_group() {
             )
             .with_empty_payment_bytes(runtime_args! { ARG_AMOUNT => *DEFAULT_PAYMENT })
             .with_authorization_keys(&[DEFAULT_ACCOUNT_ADDR])
-            .with_deploy_hash([3; 32])
+            .with_deploy_hash([4; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
@@ -1,12 +1,18 @@
+mod chained_execution;
 mod check_transfer_success;
 mod contract_api;
 mod contract_context;
 mod counter;
 mod deploy;
+mod estimate;
 mod explorer;
 mod groups;
+mod host_call_trace;
+mod host_function_costs;
 mod manage_groups;
+mod parallel_execution;
 mod regression;
+mod scan;
 mod system_contracts;
 mod upgrade;
 mod wasmless_transfer;
@@ -0,0 +1,74 @@
+use tempfile::TempDir;
+
+use engine_core::engine_state::{execution_effect::ExecutionEffect, EngineConfig};
+use engine_shared::gas::Gas;
+use engine_test_support::{
+    internal::{
+        DeployItemBuilder, ExecuteRequestBuilder, LmdbWasmTestBuilder, DEFAULT_RUN_GENESIS_REQUEST,
+    },
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{account::AccountHash, runtime_args, RuntimeArgs, U512};
+
+const ARG_TARGET: &str = "target";
+const ARG_AMOUNT: &str = "amount";
+const TRANSFER_COUNT: u8 = 8;
+const UNKNOWN_ADDR: AccountHash = AccountHash::new([255u8; 32]);
+
+/// Executes a batch of transfers in a single exec request, and returns the parts of each result
+/// which can be compared.
+fn execute_transfers(execution_threads: usize) -> Vec<(bool, Gas, ExecutionEffect)> {
+    let data_dir = TempDir::new().expect("should create temp dir");
+    let engine_config = EngineConfig::new()
+        .with_use_system_contracts(cfg!(feature = "use-system-contracts"))
+        .with_enable_bonding(cfg!(feature = "enable-bonding"))
+        .with_execution_threads(execution_threads);
+    let mut builder = LmdbWasmTestBuilder::new_with_config(data_dir.path(), engine_config);
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let mut exec_request_builder = ExecuteRequestBuilder::new();
+    for index in 0..TRANSFER_COUNT {
+        // The last transfer is from an account which does not exist, so should fail.
+        let source = if index == TRANSFER_COUNT - 1 {
+            UNKNOWN_ADDR
+        } else {
+            DEFAULT_ACCOUNT_ADDR
+        };
+        let deploy_item = DeployItemBuilder::new()
+            .with_address(source)
+            .with_empty_payment_bytes(runtime_args! {})
+            .with_transfer_args(runtime_args! {
+                ARG_TARGET => AccountHash::new([index + 1; 32]),
+                ARG_AMOUNT => U512::from(1000 + u64::from(index)),
+            })
+            .with_authorization_keys(&[source])
+            .with_deploy_hash([index; 32])
+            .build();
+        exec_request_builder = exec_request_builder.push_deploy(deploy_item);
+    }
+
+    builder.exec(exec_request_builder.build());
+
+    builder
+        .get_exec_responses()
+        .last()
+        .expect("should have exec response")
+        .iter()
+        .map(|result| (result.is_failure(), result.cost(), result.effect().clone()))
+        .collect()
+}
+
+#[ignore]
+#[test]
+fn should_execute_deploys_in_parallel_with_the_same_results() {
+    let sequential_results = execute_transfers(1);
+    assert_eq!(sequential_results.len(), usize::from(TRANSFER_COUNT));
+    assert!(sequential_results[..usize::from(TRANSFER_COUNT) - 1]
+        .iter()
+        .all(|(is_failure, _, _)| !is_failure));
+    assert!(sequential_results.last().unwrap().0);
+
+    for execution_threads in &[2, 4, 16] {
+        assert_eq!(execute_transfers(*execution_threads), sequential_results);
+    }
+}
@@ -0,0 +1,76 @@
+use std::convert::TryFrom;
+
+use engine_core::engine_state::scan::{ScanRequest, ScanResult};
+use engine_shared::{
+    newtypes::{Blake2bHash, CorrelationId},
+    stored_value::StoredValue,
+};
+use engine_test_support::{
+    internal::{InMemoryWasmTestBuilder, DEFAULT_RUN_GENESIS_REQUEST},
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{bytesrepr::ToBytes, Key};
+
+fn scan_all(builder: &InMemoryWasmTestBuilder, request: ScanRequest) -> Vec<(Key, StoredValue)> {
+    let mut entries = Vec::new();
+    let mut maybe_request = Some(request);
+    while let Some(request) = maybe_request {
+        match builder
+            .get_engine_state()
+            .run_scan(CorrelationId::new(), request)
+            .expect("should scan")
+        {
+            ScanResult::Success {
+                entries: page,
+                next_request,
+            } => {
+                entries.extend(page);
+                maybe_request = next_request;
+            }
+            ScanResult::RootNotFound => panic!("should find the post state"),
+        }
+    }
+    entries
+}
+
+#[ignore]
+#[test]
+fn should_scan_accounts_in_pages() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let post_state_hash = Blake2bHash::try_from(builder.get_post_state_hash().as_slice())
+        .expect("should have post state hash");
+    let account_tag = &Key::Account(DEFAULT_ACCOUNT_ADDR).to_bytes().unwrap()[..1];
+
+    let accounts = scan_all(
+        &builder,
+        ScanRequest::with_prefix(post_state_hash, account_tag, usize::max_value()),
+    );
+    assert!(accounts
+        .iter()
+        .all(|(key, value)| key.into_account().is_some() && value.as_account().is_some()));
+    assert!(accounts
+        .iter()
+        .any(|(key, _)| *key == Key::Account(DEFAULT_ACCOUNT_ADDR)));
+
+    let paged_accounts = scan_all(
+        &builder,
+        ScanRequest::with_prefix(post_state_hash, account_tag, 1),
+    );
+    assert_eq!(paged_accounts, accounts);
+}
+
+#[ignore]
+#[test]
+fn should_not_scan_missing_state() {
+    let builder = InMemoryWasmTestBuilder::default();
+    let result = builder
+        .get_engine_state()
+        .run_scan(
+            CorrelationId::new(),
+            ScanRequest::with_prefix([1u8; 32].into(), &[], 1),
+        )
+        .expect("should scan");
+    assert_eq!(result, ScanResult::RootNotFound);
+}
@@ -0,0 +1,202 @@
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use engine_core::engine_state::{
+    bid_state::{Queue, QueueEntry},
+    genesis::GenesisAccount,
+};
+use engine_grpc_server::engine_server::ipc::{BidStateResponse, BidState_QueueEntry};
+use engine_shared::{
+    additive_map::AdditiveMap, motes::Motes, stored_value::StoredValue, transform::Transform,
+};
+use engine_test_support::{
+    internal::{utils, ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_ACCOUNTS},
+    DEFAULT_ACCOUNT_ADDR,
+};
+use types::{account::AccountHash, runtime_args, BlockTime, CLValue, Key, RuntimeArgs, U512};
+
+const CONTRACT_POS_BONDING: &str = "pos_bonding.wasm";
+
+const VALIDATOR_1_ADDR: AccountHash = AccountHash::new([42; 32]);
+const VALIDATOR_1_STAKE: u64 = 50_000;
+const VALIDATOR_2_ADDR: AccountHash = AccountHash::new([43; 32]);
+const VALIDATOR_2_STAKE: u64 = 75_000;
+const DEFAULT_ACCOUNT_STAKE: u64 = 100_000;
+
+const TEST_BOND: &str = "bond";
+
+const ARG_AMOUNT: &str = "amount";
+const ARG_ENTRY_POINT: &str = "entry_point";
+
+/// The keys under which the proof-of-stake contract stores its bonding and unbonding queues.
+const BONDING_KEY: [u8; 32] = {
+    let mut result = [0; 32];
+    result[31] = 1;
+    result
+};
+const UNBONDING_KEY: [u8; 32] = {
+    let mut result = [0; 32];
+    result[31] = 2;
+    result
+};
+
+fn genesis_builder() -> InMemoryWasmTestBuilder {
+    let accounts = {
+        let mut tmp: Vec<GenesisAccount> = DEFAULT_ACCOUNTS.clone();
+        for (account_hash, stake) in &[
+            (VALIDATOR_1_ADDR, VALIDATOR_1_STAKE),
+            (VALIDATOR_2_ADDR, VALIDATOR_2_STAKE),
+        ] {
+            tmp.push(GenesisAccount::new(
+                *account_hash,
+                Motes::new((*stake).into()) * Motes::new(2.into()),
+                Motes::new((*stake).into()),
+            ));
+        }
+        tmp
+    };
+
+    let run_genesis_request = utils::create_run_genesis_request(accounts);
+
+    let mut builder = InMemoryWasmTestBuilder::default();
+    builder.run_genesis(&run_genesis_request);
+    builder
+}
+
+fn get_bids(response: &BidStateResponse) -> BTreeMap<AccountHash, U512> {
+    assert!(response.has_success(), "expected success: {:?}", response);
+    response
+        .get_success()
+        .get_bids()
+        .iter()
+        .cloned()
+        .map(|bid| <(AccountHash, U512)>::try_from(bid).expect("should map bid"))
+        .collect()
+}
+
+#[ignore]
+#[test]
+fn should_get_genesis_bid_state() {
+    let builder = genesis_builder();
+
+    let response = builder.get_bid_state(None);
+
+    let mut expected = BTreeMap::new();
+    expected.insert(VALIDATOR_1_ADDR, U512::from(VALIDATOR_1_STAKE));
+    expected.insert(VALIDATOR_2_ADDR, U512::from(VALIDATOR_2_STAKE));
+    assert_eq!(get_bids(&response), expected);
+
+    let bid_state = response.get_success();
+    assert!(bid_state.get_bonding_queue().is_empty());
+    assert!(bid_state.get_unbonding_queue().is_empty());
+}
+
+fn get_queue(entries: &[BidState_QueueEntry]) -> Vec<QueueEntry> {
+    entries
+        .iter()
+        .cloned()
+        .map(|entry| QueueEntry::try_from(entry).expect("should map queue entry"))
+        .collect()
+}
+
+#[ignore]
+#[test]
+fn should_get_bid_state_with_queued_entries() {
+    let mut builder = genesis_builder();
+
+    let bonding_queue = vec![
+        QueueEntry {
+            validator: DEFAULT_ACCOUNT_ADDR,
+            amount: U512::from(DEFAULT_ACCOUNT_STAKE),
+            timestamp: BlockTime::new(1),
+        },
+        QueueEntry {
+            validator: VALIDATOR_2_ADDR,
+            amount: U512::from(VALIDATOR_2_STAKE),
+            timestamp: BlockTime::new(2),
+        },
+    ];
+    let unbonding_queue = vec![QueueEntry {
+        validator: VALIDATOR_1_ADDR,
+        amount: U512::from(VALIDATOR_1_STAKE),
+        timestamp: BlockTime::new(3),
+    }];
+
+    let mut effects = AdditiveMap::new();
+    for (key, entries) in &[
+        (BONDING_KEY, &bonding_queue),
+        (UNBONDING_KEY, &unbonding_queue),
+    ] {
+        let cl_value = CLValue::from_t(Queue(entries.to_vec())).expect("should create CLValue");
+        effects.insert(
+            Key::Hash(*key),
+            Transform::Write(StoredValue::CLValue(cl_value)),
+        );
+    }
+    let prestate_hash = builder.get_post_state_hash();
+    builder.commit_effects(prestate_hash, effects);
+
+    let response = builder.get_bid_state(None);
+    assert!(response.has_success(), "expected success: {:?}", response);
+    let bid_state = response.get_success();
+    assert_eq!(get_queue(bid_state.get_bonding_queue()), bonding_queue);
+    assert_eq!(get_queue(bid_state.get_unbonding_queue()), unbonding_queue);
+}
+
+#[ignore]
+#[test]
+fn should_get_bid_state_after_bonding() {
+    if !cfg!(feature = "enable-bonding") {
+        return;
+    }
+
+    let mut builder = genesis_builder();
+
+    let exec_request = ExecuteRequestBuilder::standard(
+        DEFAULT_ACCOUNT_ADDR,
+        CONTRACT_POS_BONDING,
+        runtime_args! {
+            ARG_ENTRY_POINT => String::from(TEST_BOND),
+            ARG_AMOUNT => U512::from(DEFAULT_ACCOUNT_STAKE)
+        },
+    )
+    .build();
+
+    builder.exec(exec_request).expect_success().commit();
+
+    let mut expected = BTreeMap::new();
+    expected.insert(VALIDATOR_1_ADDR, U512::from(VALIDATOR_1_STAKE));
+    expected.insert(VALIDATOR_2_ADDR, U512::from(VALIDATOR_2_STAKE));
+
+    // The bid state under the genesis hash is unaffected by the bond.
+    let genesis_response = builder.get_bid_state(Some(builder.get_genesis_hash()));
+    assert_eq!(get_bids(&genesis_response), expected);
+
+    expected.insert(DEFAULT_ACCOUNT_ADDR, U512::from(DEFAULT_ACCOUNT_STAKE));
+    let response = builder.get_bid_state(None);
+    assert_eq!(get_bids(&response), expected);
+}
+
+#[ignore]
+#[test]
+fn should_report_missing_parent_for_unknown_state_hash() {
+    let builder = genesis_builder();
+
+    let unknown_state_hash = vec![1u8; 32];
+    let response = builder.get_bid_state(Some(unknown_state_hash.clone()));
+
+    assert!(response.has_missing_parent());
+    assert_eq!(
+        response.get_missing_parent().get_hash(),
+        &unknown_state_hash[..]
+    );
+}
+
+#[ignore]
+#[test]
+fn should_fail_for_invalid_state_hash_length() {
+    let builder = genesis_builder();
+
+    let response = builder.get_bid_state(Some(vec![1u8; 31]));
+
+    assert!(response.has_failure());
+}
@@ -1,3 +1,4 @@
+mod bid_state;
 mod bonding;
 mod commit_validators;
 mod finalize_payment;
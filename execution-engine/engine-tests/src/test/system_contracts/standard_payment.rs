@@ -678,7 +678,7 @@ fn independent_standard_payments_should_not_write_the_same_keys() {
                 runtime_args! { ARG_AMOUNT => U512::from(payment_purse_amount)},
             )
             .with_authorization_keys(&[account_1_account_hash])
-            .with_deploy_hash([1; 32])
+            .with_deploy_hash([3; 32])
             .build();
 
         ExecuteRequestBuilder::new().push_deploy(deploy).build()
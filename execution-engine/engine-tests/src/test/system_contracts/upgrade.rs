@@ -1,10 +1,11 @@
 use engine_core::engine_state::{upgrade::ActivationPoint, Error};
 use engine_grpc_server::engine_server::ipc::DeployCode;
+use engine_shared::host_function_costs::{HostFunctionCost, HostFunctionCosts};
 #[cfg(feature = "use-system-contracts")]
 use engine_shared::{stored_value::StoredValue, transform::Transform};
 use engine_test_support::internal::{
-    utils, InMemoryWasmTestBuilder, UpgradeRequestBuilder, DEFAULT_RUN_GENESIS_REQUEST,
-    DEFAULT_WASM_COSTS,
+    utils, InMemoryWasmTestBuilder, UpgradeRequestBuilder, DEFAULT_HOST_FUNCTION_COSTS,
+    DEFAULT_RUN_GENESIS_REQUEST, DEFAULT_WASM_COSTS,
 };
 #[cfg(feature = "use-system-contracts")]
 use engine_test_support::{internal::ExecuteRequestBuilder, DEFAULT_ACCOUNT_ADDR};
@@ -35,6 +36,15 @@ fn get_upgraded_wasm_costs() -> WasmCosts {
         max_stack_height: 64 * 1024,
         opcodes_mul: 3,
         opcodes_div: 8,
+        storage_per_byte: 1,
+    }
+}
+
+fn get_upgraded_host_function_costs() -> HostFunctionCosts {
+    HostFunctionCosts {
+        transfer_to_account: HostFunctionCost::new(100_000, 0),
+        call_contract: HostFunctionCost::new(20_000, 2),
+        ..*DEFAULT_HOST_FUNCTION_COSTS
     }
 }
 
@@ -399,6 +409,99 @@ fn should_allow_only_wasm_costs_minor_version() {
     );
 }
 
+#[ignore]
+#[test]
+fn should_allow_only_host_function_costs_minor_version() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let sem_ver = PROTOCOL_VERSION.value();
+    let new_protocol_version =
+        ProtocolVersion::from_parts(sem_ver.major, sem_ver.minor + 1, sem_ver.patch);
+
+    let new_host_function_costs = get_upgraded_host_function_costs();
+
+    let mut upgrade_request = {
+        UpgradeRequestBuilder::new()
+            .with_current_protocol_version(PROTOCOL_VERSION)
+            .with_new_protocol_version(new_protocol_version)
+            .with_activation_point(DEFAULT_ACTIVATION_POINT)
+            .with_new_host_function_costs(new_host_function_costs)
+            .build()
+    };
+
+    builder.upgrade_with_upgrade_request(&mut upgrade_request);
+
+    let upgrade_response = builder
+        .get_upgrade_response(0)
+        .expect("should have response");
+
+    assert!(upgrade_response.has_success(), "expected success");
+
+    let upgraded_host_function_costs = builder
+        .get_engine_state()
+        .host_function_costs(new_protocol_version)
+        .expect("should have result")
+        .expect("should have upgraded costs");
+
+    assert_eq!(
+        new_host_function_costs, upgraded_host_function_costs,
+        "upgraded costs should equal new costs"
+    );
+
+    let upgraded_wasm_costs = builder
+        .get_engine_state()
+        .wasm_costs(new_protocol_version)
+        .expect("should have result")
+        .expect("should have wasm costs");
+
+    assert_eq!(
+        *DEFAULT_WASM_COSTS, upgraded_wasm_costs,
+        "wasm costs should be unchanged"
+    );
+}
+
+#[ignore]
+#[test]
+fn should_keep_host_function_costs_when_not_upgraded() {
+    let mut builder = InMemoryWasmTestBuilder::default();
+
+    builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST);
+
+    let sem_ver = PROTOCOL_VERSION.value();
+    let new_protocol_version =
+        ProtocolVersion::from_parts(sem_ver.major, sem_ver.minor, sem_ver.patch + 1);
+
+    let mut upgrade_request = {
+        UpgradeRequestBuilder::new()
+            .with_current_protocol_version(PROTOCOL_VERSION)
+            .with_new_protocol_version(new_protocol_version)
+            .with_activation_point(DEFAULT_ACTIVATION_POINT)
+            .with_new_costs(get_upgraded_wasm_costs())
+            .build()
+    };
+
+    builder.upgrade_with_upgrade_request(&mut upgrade_request);
+
+    let upgrade_response = builder
+        .get_upgrade_response(0)
+        .expect("should have response");
+
+    assert!(upgrade_response.has_success(), "expected success");
+
+    let upgraded_host_function_costs = builder
+        .get_engine_state()
+        .host_function_costs(new_protocol_version)
+        .expect("should have result")
+        .expect("should have host function costs");
+
+    assert_eq!(
+        *DEFAULT_HOST_FUNCTION_COSTS, upgraded_host_function_costs,
+        "host function costs should be unchanged"
+    );
+}
+
 #[cfg(feature = "use-system-contracts")]
 #[ignore]
 #[test]
@@ -47,6 +47,11 @@ impl Preprocessor {
         }
     }
 
+    /// The costs with which modules are instrumented.
+    pub fn wasm_costs(&self) -> &WasmCosts {
+        &self.wasm_costs
+    }
+
     pub fn preprocess(&self, module_bytes: &[u8]) -> Result<Module, PreprocessingError> {
         let module = deserialize(module_bytes)?;
         let module = pwasm_utils::externalize_mem(module, None, self.mem_pages);
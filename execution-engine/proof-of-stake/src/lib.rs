@@ -18,8 +18,12 @@ use types::{
 };
 
 pub use crate::{
-    mint_provider::MintProvider, queue::Queue, queue_provider::QueueProvider,
-    runtime_provider::RuntimeProvider, stakes::Stakes, stakes_provider::StakesProvider,
+    mint_provider::MintProvider,
+    queue::{Queue, QueueEntry},
+    queue_provider::QueueProvider,
+    runtime_provider::RuntimeProvider,
+    stakes::Stakes,
+    stakes_provider::StakesProvider,
 };
 
 pub trait ProofOfStake:
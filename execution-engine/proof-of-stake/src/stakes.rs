@@ -56,6 +56,35 @@ impl Stakes {
         })
     }
 
+    /// Parses the stakes from the names of the proof-of-stake contract's named keys, as encoded by
+    /// [`Stakes::strings`].  Names which do not start with `v_` are ignored.
+    pub fn from_strings<'a, I: IntoIterator<Item = &'a str>>(names: I) -> Result<Stakes> {
+        let mut stakes = BTreeMap::new();
+        for name in names {
+            let mut split_name = name.split('_');
+            if Some("v") != split_name.next() {
+                continue;
+            }
+            let hex_key = split_name
+                .next()
+                .ok_or(Error::StakesKeyDeserializationFailed)?;
+            if hex_key.len() != 64 {
+                return Err(Error::StakesKeyDeserializationFailed);
+            }
+            let mut key_bytes = [0u8; 32];
+            let _bytes_written = base16::decode_slice(hex_key, &mut key_bytes)
+                .map_err(|_| Error::StakesKeyDeserializationFailed)?;
+            debug_assert!(_bytes_written == key_bytes.len());
+            let pub_key = AccountHash::new(key_bytes);
+            let balance = split_name
+                .next()
+                .and_then(|b| U512::from_dec_str(b).ok())
+                .ok_or(Error::StakesDeserializationFailed)?;
+            stakes.insert(pub_key, balance);
+        }
+        Ok(Stakes(stakes))
+    }
+
     pub fn total_bonds(&self) -> U512 {
         self.values().fold(U512::zero(), |x, y| x + y)
     }
@@ -170,6 +199,9 @@ impl Stakes {
 
 #[cfg(test)]
 mod tests {
+    use alloc::{format, string::String, vec, vec::Vec};
+    use core::iter;
+
     use types::{account::AccountHash, system_contract_errors::pos::Error, U512};
 
     use super::Stakes;
@@ -186,6 +218,30 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_strings_round_trip() {
+        let stakes = new_stakes(&[(KEY1, 5), (KEY2, 100)]);
+        let strings: Vec<String> = stakes.strings().collect();
+        let names = strings
+            .iter()
+            .map(String::as_str)
+            .chain(iter::once("pos_bonding_purse"));
+        assert_eq!(Ok(stakes), Stakes::from_strings(names));
+    }
+
+    #[test]
+    fn test_from_invalid_strings() {
+        assert_eq!(
+            Err(Error::StakesKeyDeserializationFailed),
+            Stakes::from_strings(vec!["v_0102_5"])
+        );
+        let name = format!("v_{}_five", base16::encode_lower(&KEY1));
+        assert_eq!(
+            Err(Error::StakesDeserializationFailed),
+            Stakes::from_strings(vec![name.as_str()])
+        );
+    }
+
     #[test]
     fn test_bond() {
         let mut stakes = new_stakes(&[(KEY2, 100)]);
@@ -35,6 +35,7 @@ pub mod contracts;
 #[cfg(any(feature = "gens", test))]
 pub mod gens;
 mod key;
+pub mod merkle_proof;
 mod phase;
 mod protocol_version;
 pub mod runtime_args;
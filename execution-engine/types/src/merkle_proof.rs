@@ -0,0 +1,479 @@
+//! Verification of Merkle proofs over the global state trie.
+//!
+//! A proof is the ordered list of serialized trie elements (`Node`s, `Extension`s and `Leaf`s)
+//! visited while walking from a state root along the path of a given key.  An inclusion proof ends
+//! at the leaf holding the key, while an absence proof ends where the path of the key leaves the
+//! trie.  Only the state root hash needs to be trusted: verification recomputes the Blake2b hash of
+//! every element and checks it against the pointer held by the element's parent.
+
+use alloc::vec::Vec;
+
+use blake2::{
+    digest::{Input, VariableOutput},
+    VarBlake2b,
+};
+use failure::Fail;
+
+use crate::{
+    bytesrepr::{self, FromBytes, ToBytes},
+    BLAKE2B_DIGEST_LENGTH,
+};
+
+const RADIX: usize = 256;
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+const EXTENSION_TAG: u8 = 2;
+
+const LEAF_POINTER_TAG: u8 = 0;
+const NODE_POINTER_TAG: u8 = 1;
+
+/// Errors which can occur while verifying a Merkle proof.
+#[derive(Fail, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The proof contains no trie elements.
+    #[fail(display = "Proof is empty")]
+    EmptyProof,
+    /// The first element of the proof does not hash to the given state root.
+    #[fail(display = "Proof does not start at the given state root")]
+    StateRootMismatch,
+    /// The element at the given position does not hash to the pointer held by its parent.
+    #[fail(
+        display = "Proof step {} does not match the hash held by its parent",
+        _0
+    )]
+    HashMismatch(usize),
+    /// The element at the given position is not of the kind pointed to by its parent.
+    #[fail(
+        display = "Proof step {} does not match the pointer kind held by its parent",
+        _0
+    )]
+    PointerKindMismatch(usize),
+    /// The element at the given position does not continue along the path of the given key.
+    #[fail(display = "Proof step {} diverges from the path of the given key", _0)]
+    PathMismatch(usize),
+    /// The proof ends before reaching a leaf.
+    #[fail(display = "Proof ends before reaching a leaf")]
+    IncompleteProof,
+    /// The proof contains elements after its leaf.
+    #[fail(display = "Proof contains steps after its leaf")]
+    TrailingSteps,
+    /// The leaf of the proof holds a different key.
+    #[fail(display = "Proof leaf holds a different key")]
+    KeyMismatch,
+    /// The proof shows that the given key is present, so cannot prove its absence.
+    #[fail(display = "Proof leaf holds the given key")]
+    KeyPresent,
+    /// The element at the given position could not be parsed.
+    #[fail(display = "Proof step {} is malformed: {}", _0, _1)]
+    Malformed(usize, bytesrepr::Error),
+    /// The given key could not be serialized.
+    #[fail(display = "Key could not be serialized: {}", _0)]
+    KeySerialization(bytesrepr::Error),
+}
+
+struct Pointer {
+    tag: u8,
+    hash: [u8; BLAKE2B_DIGEST_LENGTH],
+}
+
+fn hash(data: &[u8]) -> [u8; BLAKE2B_DIGEST_LENGTH] {
+    let mut ret = [0u8; BLAKE2B_DIGEST_LENGTH];
+    // Safe to unwrap here because our digest length is constant and valid
+    let mut hasher = VarBlake2b::new(BLAKE2B_DIGEST_LENGTH).unwrap();
+    hasher.input(data);
+    hasher.variable_result(|hash| ret.clone_from_slice(hash));
+    ret
+}
+
+fn parse_pointer(bytes: &[u8]) -> Result<(Pointer, &[u8]), bytesrepr::Error> {
+    let (tag, remainder) = u8::from_bytes(bytes)?;
+    if tag != LEAF_POINTER_TAG && tag != NODE_POINTER_TAG {
+        return Err(bytesrepr::Error::Formatting);
+    }
+    let (hash, remainder) = FromBytes::from_bytes(remainder)?;
+    Ok((Pointer { tag, hash }, remainder))
+}
+
+/// Parses the pointer block of a serialized `Node` and returns the pointer at `index`, if any.
+fn parse_node_child(pointer_block: &[u8], index: u8) -> Result<Option<Pointer>, bytesrepr::Error> {
+    let mut remainder = pointer_block;
+    let mut ret = None;
+    for current_index in 0..RADIX {
+        let (option_tag, rem) = u8::from_bytes(remainder)?;
+        remainder = match option_tag {
+            0 => rem,
+            1 => {
+                let (pointer, rem) = parse_pointer(rem)?;
+                if current_index == usize::from(index) {
+                    ret = Some(pointer);
+                }
+                rem
+            }
+            _ => return Err(bytesrepr::Error::Formatting),
+        };
+    }
+    if !remainder.is_empty() {
+        return Err(bytesrepr::Error::LeftOverBytes);
+    }
+    Ok(ret)
+}
+
+/// Parses a serialized `Extension` and returns its affix and pointer.
+fn parse_extension(bytes: &[u8]) -> Result<(&[u8], Pointer), bytesrepr::Error> {
+    let (affix_length, remainder) = u32::from_bytes(bytes)?;
+    let (affix, remainder) = bytesrepr::safe_split_at(remainder, affix_length as usize)?;
+    let (pointer, remainder) = parse_pointer(remainder)?;
+    if !remainder.is_empty() {
+        return Err(bytesrepr::Error::LeftOverBytes);
+    }
+    Ok((affix, pointer))
+}
+
+/// The trie element at which a proof ends.
+enum EndPoint<'a> {
+    /// A leaf at the given position, holding the given serialized key and value.
+    Leaf(usize, &'a [u8]),
+    /// A node with no pointer at the index given by the key's path.
+    EmptySlot(usize),
+    /// An extension whose affix differs from the key's path.
+    DivergentExtension(usize),
+}
+
+/// Walks `proof_steps` from `state_root` along the path of `key_bytes`, checking the hash chain,
+/// and returns the element at which the path ends.
+fn walk<'a>(
+    state_root: &[u8; BLAKE2B_DIGEST_LENGTH],
+    key_bytes: &[u8],
+    proof_steps: &'a [Vec<u8>],
+) -> Result<EndPoint<'a>, Error> {
+    let root = proof_steps.first().ok_or(Error::EmptyProof)?;
+    if hash(root) != *state_root {
+        return Err(Error::StateRootMismatch);
+    }
+
+    let mut depth: usize = 0;
+    for (position, step) in proof_steps.iter().enumerate() {
+        let malformed = |error| Error::Malformed(position, error);
+        let is_last = position + 1 == proof_steps.len();
+        let (tag, remainder) = u8::from_bytes(step).map_err(malformed)?;
+        let maybe_pointer = match tag {
+            LEAF_TAG => {
+                if !is_last {
+                    return Err(Error::TrailingSteps);
+                }
+                return Ok(EndPoint::Leaf(position, remainder));
+            }
+            NODE_TAG => {
+                let index = *key_bytes.get(depth).ok_or(Error::PathMismatch(position))?;
+                depth += 1;
+                parse_node_child(remainder, index).map_err(malformed)?
+            }
+            EXTENSION_TAG => {
+                let (affix, pointer) = parse_extension(remainder).map_err(malformed)?;
+                let sub_path = key_bytes
+                    .get(depth..depth + affix.len())
+                    .ok_or(Error::PathMismatch(position))?;
+                depth += affix.len();
+                if sub_path == affix {
+                    Some(pointer)
+                } else if is_last {
+                    return Ok(EndPoint::DivergentExtension(position));
+                } else {
+                    return Err(Error::PathMismatch(position));
+                }
+            }
+            _ => return Err(malformed(bytesrepr::Error::Formatting)),
+        };
+        let pointer = match maybe_pointer {
+            Some(pointer) => pointer,
+            None if is_last => return Ok(EndPoint::EmptySlot(position)),
+            None => return Err(Error::PathMismatch(position)),
+        };
+
+        let child_position = position + 1;
+        let child = proof_steps
+            .get(child_position)
+            .ok_or(Error::IncompleteProof)?;
+        if hash(child) != pointer.hash {
+            return Err(Error::HashMismatch(child_position));
+        }
+        let child_is_leaf = child.first() == Some(&LEAF_TAG);
+        if child_is_leaf != (pointer.tag == LEAF_POINTER_TAG) {
+            return Err(Error::PointerKindMismatch(child_position));
+        }
+    }
+
+    Err(Error::IncompleteProof)
+}
+
+/// Parses the key held by the leaf at `position`, and returns whether it is `key` along with the
+/// serialized value held by the leaf.
+fn leaf_holds_key<'a, K>(
+    key: &K,
+    position: usize,
+    leaf: &'a [u8],
+) -> Result<(bool, &'a [u8]), Error>
+where
+    K: FromBytes + PartialEq,
+{
+    let (leaf_key, value) =
+        K::from_bytes(leaf).map_err(|error| Error::Malformed(position, error))?;
+    Ok((leaf_key == *key, value))
+}
+
+/// Verifies that `proof_steps` prove the inclusion of `key` in the global state identified by
+/// `state_root`.
+///
+/// `proof_steps` are the serialized trie elements ordered from the root down to the leaf.  The key
+/// held by the leaf is parsed as a `K` and must equal `key`.  On success, returns the serialized
+/// value held by the leaf.
+pub fn verify_inclusion_proof<'a, K>(
+    state_root: &[u8; BLAKE2B_DIGEST_LENGTH],
+    key: &K,
+    proof_steps: &'a [Vec<u8>],
+) -> Result<&'a [u8], Error>
+where
+    K: ToBytes + FromBytes + PartialEq,
+{
+    let key_bytes = key.to_bytes().map_err(Error::KeySerialization)?;
+    match walk(state_root, &key_bytes, proof_steps)? {
+        EndPoint::Leaf(position, leaf) => match leaf_holds_key(key, position, leaf)? {
+            (true, value) => Ok(value),
+            (false, _) => Err(Error::KeyMismatch),
+        },
+        EndPoint::EmptySlot(position) | EndPoint::DivergentExtension(position) => {
+            Err(Error::PathMismatch(position))
+        }
+    }
+}
+
+/// Verifies that `proof_steps` prove the absence of `key` from the global state identified by
+/// `state_root`.
+///
+/// `proof_steps` are the serialized trie elements ordered from the root down to the element at
+/// which the path of the key ends: a node with no pointer at the key's next index, an extension
+/// whose affix differs from the key's path, or a leaf holding a different key.  The key held by
+/// such a leaf is parsed as a `K` and compared with `key`.
+pub fn verify_absence_proof<K>(
+    state_root: &[u8; BLAKE2B_DIGEST_LENGTH],
+    key: &K,
+    proof_steps: &[Vec<u8>],
+) -> Result<(), Error>
+where
+    K: ToBytes + FromBytes + PartialEq,
+{
+    let key_bytes = key.to_bytes().map_err(Error::KeySerialization)?;
+    match walk(state_root, &key_bytes, proof_steps)? {
+        EndPoint::Leaf(position, leaf) => match leaf_holds_key(key, position, leaf)? {
+            (true, _) => Err(Error::KeyPresent),
+            (false, _) => Ok(()),
+        },
+        EndPoint::EmptySlot(_) | EndPoint::DivergentExtension(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    const KEY: [u8; 3] = [1, 2, 3];
+    const VALUE: [u8; 2] = [9, 9];
+
+    fn leaf() -> Vec<u8> {
+        let mut ret = vec![LEAF_TAG];
+        ret.extend_from_slice(&KEY);
+        ret.extend_from_slice(&VALUE);
+        ret
+    }
+
+    fn node(index: u8, tag: u8, child: &[u8]) -> Vec<u8> {
+        let mut ret = vec![NODE_TAG];
+        for current_index in 0..RADIX {
+            if current_index == usize::from(index) {
+                ret.push(1);
+                ret.push(tag);
+                ret.extend_from_slice(&hash(child));
+            } else {
+                ret.push(0);
+            }
+        }
+        ret
+    }
+
+    fn extension(affix: &[u8], child: &[u8]) -> Vec<u8> {
+        let mut ret = vec![EXTENSION_TAG];
+        ret.extend_from_slice(&(affix.len() as u32).to_le_bytes());
+        ret.extend_from_slice(affix);
+        ret.push(NODE_POINTER_TAG);
+        ret.extend_from_slice(&hash(child));
+        ret
+    }
+
+    /// Builds root Node -> Extension -> Node -> Leaf along the path of `KEY`.
+    fn valid_proof() -> Vec<Vec<u8>> {
+        let leaf = leaf();
+        let lower_node = node(KEY[2], LEAF_POINTER_TAG, &leaf);
+        let extension = extension(&KEY[1..2], &lower_node);
+        let root = node(KEY[0], NODE_POINTER_TAG, &extension);
+        vec![root, extension, lower_node, leaf]
+    }
+
+    #[test]
+    fn should_verify_valid_proof() {
+        let proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &KEY, &proof),
+            Ok(&VALUE[..])
+        );
+    }
+
+    #[test]
+    fn should_reject_wrong_state_root() {
+        let proof = valid_proof();
+        assert_eq!(
+            verify_inclusion_proof(&[0u8; 32], &KEY, &proof),
+            Err(Error::StateRootMismatch)
+        );
+        assert_eq!(
+            verify_inclusion_proof(&[0u8; 32], &KEY, &[]),
+            Err(Error::EmptyProof)
+        );
+    }
+
+    #[test]
+    fn should_reject_tampered_step() {
+        let mut proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        let last = proof.len() - 1;
+        *proof[last].last_mut().unwrap() = 0;
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &KEY, &proof),
+            Err(Error::HashMismatch(last))
+        );
+    }
+
+    #[test]
+    fn should_reject_other_key() {
+        let proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &[1u8, 3, 3], &proof),
+            Err(Error::PathMismatch(1))
+        );
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &[1u8, 2, 4], &proof),
+            Err(Error::PathMismatch(2))
+        );
+    }
+
+    #[test]
+    fn should_reject_truncated_proof() {
+        let mut proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        proof.pop();
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &KEY, &proof),
+            Err(Error::IncompleteProof)
+        );
+    }
+
+    #[test]
+    fn should_verify_absence_at_empty_slot() {
+        let mut proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        proof.truncate(3);
+        assert_eq!(
+            verify_absence_proof(&state_root, &[1u8, 2, 4], &proof),
+            Ok(())
+        );
+        assert_eq!(
+            verify_absence_proof(&state_root, &KEY, &proof),
+            Err(Error::IncompleteProof)
+        );
+    }
+
+    #[test]
+    fn should_verify_absence_at_divergent_extension() {
+        let mut proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        proof.truncate(2);
+        assert_eq!(
+            verify_absence_proof(&state_root, &[1u8, 3, 3], &proof),
+            Ok(())
+        );
+    }
+
+    /// Builds root Node -> Leaf, so that the leaf holding `KEY` is reached by every key starting
+    /// with `KEY[0]`.
+    fn short_proof() -> Vec<Vec<u8>> {
+        let leaf = leaf();
+        let root = node(KEY[0], LEAF_POINTER_TAG, &leaf);
+        vec![root, leaf]
+    }
+
+    #[test]
+    fn should_verify_absence_at_other_leaf() {
+        let proof = short_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_absence_proof(&state_root, &[1u8, 2, 4], &proof),
+            Ok(())
+        );
+        assert_eq!(
+            verify_absence_proof(&state_root, &[1u8, 5, 5], &proof),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_compare_the_whole_leaf_key() {
+        let proof = short_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &KEY, &proof),
+            Ok(&VALUE[..])
+        );
+        // The leaf holds `KEY`, which shares all but its last byte with this key.
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &[1u8, 2, 4], &proof),
+            Err(Error::KeyMismatch)
+        );
+        // A leaf too short to hold a whole key is rejected rather than matched against a prefix.
+        let truncated_leaf = vec![LEAF_TAG, KEY[0], KEY[1]];
+        let root = node(KEY[0], LEAF_POINTER_TAG, &truncated_leaf);
+        let state_root = hash(&root);
+        let proof = vec![root, truncated_leaf];
+        assert_eq!(
+            verify_inclusion_proof(&state_root, &KEY, &proof),
+            Err(Error::Malformed(1, bytesrepr::Error::EarlyEndOfStream))
+        );
+        assert_eq!(
+            verify_absence_proof(&state_root, &KEY, &proof),
+            Err(Error::Malformed(1, bytesrepr::Error::EarlyEndOfStream))
+        );
+    }
+
+    #[test]
+    fn should_reject_absence_proof_of_present_key() {
+        let proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_absence_proof(&state_root, &KEY, &proof),
+            Err(Error::KeyPresent)
+        );
+    }
+
+    #[test]
+    fn should_reject_absence_proof_with_trailing_steps() {
+        let proof = valid_proof();
+        let state_root = hash(&proof[0]);
+        assert_eq!(
+            verify_absence_proof(&state_root, &[1u8, 2, 4], &proof),
+            Err(Error::PathMismatch(2))
+        );
+    }
+}
@@ -139,7 +139,8 @@ object ChainSpec extends ParserImplicits {
       memCopyPerByte: Int Refined NonNegative,
       maxStackHeight: Int Refined NonNegative,
       opcodesMultiplier: Int Refined NonNegative,
-      opcodesDivisor: Int Refined Positive
+      opcodesDivisor: Int Refined Positive,
+      storagePerByte: Int Refined NonNegative
   ) extends SubConfig
 
   final case class Account(
@@ -425,6 +426,7 @@ object ChainSpecReader {
           .withMaxStackHeight(wasmCosts.maxStackHeight.value)
           .withOpcodesMul(wasmCosts.opcodesMultiplier.value)
           .withOpcodesDiv(wasmCosts.opcodesDivisor.value)
+          .withStoragePerByte(wasmCosts.storagePerByte.value)
       )
 
   private def toDeployConfig(deployConfig: Deploy): ipc.ChainSpec.DeployConfig =
@@ -29,6 +29,7 @@ class ChainSpecTest extends WordSpecLike with Matchers with Inspectors {
         conf.wasmCosts.regular.value shouldBe 1
         conf.wasmCosts.memInitialPages.value shouldBe 5
         conf.wasmCosts.opcodesDivisor.value shouldBe 10
+        conf.wasmCosts.storagePerByte.value shouldBe 11
       }
     }
 
@@ -58,6 +59,7 @@ class ChainSpecTest extends WordSpecLike with Matchers with Inspectors {
         conf.wasmCosts.get.regular.value shouldBe 21
         conf.wasmCosts.get.memInitialPages.value shouldBe 25
         conf.wasmCosts.get.opcodesDivisor.value shouldBe 210
+        conf.wasmCosts.get.storagePerByte.value shouldBe 211
       }
     }
 
@@ -150,6 +152,7 @@ class ChainSpecTest extends WordSpecLike with Matchers with Inspectors {
           wasmCosts.maxStackHeight shouldBe 8
           wasmCosts.opcodesMul shouldBe 9
           wasmCosts.opcodesDiv shouldBe 10
+          wasmCosts.storagePerByte shouldBe 11
         }
       }
 
@@ -174,6 +177,7 @@ class ChainSpecTest extends WordSpecLike with Matchers with Inspectors {
           wasmCosts.maxStackHeight shouldBe 28
           wasmCosts.opcodesMul shouldBe 29
           wasmCosts.opcodesDiv shouldBe 210
+          wasmCosts.storagePerByte shouldBe 211
         }
       }
 